@@ -0,0 +1,201 @@
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::domain::json;
+use crate::templates::{OriginLoadModeOwned, ProxyOptions};
+use crate::ui_events::{generate_cli, OutputTarget};
+
+pub struct BatchOptions {
+    pub targets: Vec<OutputTarget>,
+    pub recursive: bool,
+    // Wildcard matched against file names (`*` and `?`, case-insensitive).
+    pub pattern: String,
+    pub proxy: ProxyOptions,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            targets: vec![OutputTarget::Source],
+            recursive: false,
+            pattern: "*.dll".to_string(),
+            proxy: ProxyOptions::default(),
+        }
+    }
+}
+
+// File names are case-insensitive on Windows, unlike the export patterns sharing the matcher.
+pub fn wildcard_match(pattern: &str, name: &str) -> bool {
+    crate::export_policy::wildcard_match(&pattern.to_lowercase(), &name.to_lowercase())
+}
+
+#[derive(Debug, Clone)]
+pub struct BatchEntry {
+    pub dll: PathBuf,
+    pub target: OutputTarget,
+    pub output_dir: PathBuf,
+    pub result: std::result::Result<usize, String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BatchReport {
+    pub entries: Vec<BatchEntry>,
+}
+
+impl BatchReport {
+    pub fn succeeded(&self) -> usize {
+        self.entries.iter().filter(|e| e.result.is_ok()).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.entries.len() - self.succeeded()
+    }
+
+    pub fn to_table(&self) -> String {
+        let dll_width = self
+            .entries
+            .iter()
+            .map(|e| e.dll.display().to_string().len())
+            .max()
+            .unwrap_or(3)
+            .max(3);
+
+        let mut out = String::new();
+        let _ = writeln!(out, "{:<dll_width$}  {:<7}  {:<6}  DETAIL", "DLL", "TARGET", "STATUS");
+        for e in &self.entries {
+            let (status, detail) = match &e.result {
+                Ok(n) => ("ok", format!("{} file(s) -> {}", n, e.output_dir.display())),
+                Err(err) => ("FAILED", err.replace('\n', " ")),
+            };
+            let _ = writeln!(
+                out,
+                "{:<dll_width$}  {:<7}  {:<6}  {}",
+                e.dll.display(),
+                e.target.name(),
+                status,
+                detail
+            );
+        }
+        let _ = writeln!(
+            out,
+            "Total: {}, succeeded: {}, failed: {}",
+            self.entries.len(),
+            self.succeeded(),
+            self.failed()
+        );
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        let _ = write!(
+            out,
+            "{{\n  \"total\": {},\n  \"succeeded\": {},\n  \"failed\": {},\n  \"results\": [",
+            self.entries.len(),
+            self.succeeded(),
+            self.failed()
+        );
+        for (idx, e) in self.entries.iter().enumerate() {
+            out.push_str(if idx == 0 { "\n    " } else { ",\n    " });
+            let _ = write!(
+                out,
+                "{{\"dll\": {}, \"target\": {}, \"output_dir\": {}, ",
+                json::quote(&e.dll.display().to_string()),
+                json::quote(e.target.name()),
+                json::quote(&e.output_dir.display().to_string())
+            );
+            let _ = match &e.result {
+                Ok(n) => write!(out, "\"ok\": true, \"files\": {}}}", n),
+                Err(err) => write!(out, "\"ok\": false, \"error\": {}}}", json::quote(err)),
+            };
+        }
+        if !self.entries.is_empty() {
+            out.push_str("\n  ");
+        }
+        out.push_str("]\n}\n");
+        out
+    }
+}
+
+pub fn collect_dlls(input_dir: &Path, options: &BatchOptions) -> Result<Vec<PathBuf>> {
+    let mut found = Vec::new();
+    let mut pending = vec![input_dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = fs::read_dir(&dir)
+            .map_err(|e| anyhow::anyhow!("Failed to read directory {}: {e}", dir.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            // Links and junctions are skipped rather than followed, so a link loop can't recurse forever.
+            let file_type = fs::symlink_metadata(&path)?.file_type();
+            if file_type.is_symlink() {
+                continue;
+            }
+            if file_type.is_dir() {
+                if options.recursive {
+                    pending.push(path);
+                }
+                continue;
+            }
+            let name = path
+                .file_name()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            if wildcard_match(&options.pattern, &name) {
+                found.push(path);
+            }
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
+pub fn run_batch(
+    input_dir: &Path,
+    output_dir: &Path,
+    options: &BatchOptions,
+    origin_for: impl Fn(&Path) -> Result<OriginLoadModeOwned>,
+) -> Result<BatchReport> {
+    let dlls = collect_dlls(input_dir, options)?;
+    if dlls.is_empty() {
+        anyhow::bail!(
+            "No files matching '{}' found in {}",
+            options.pattern,
+            input_dir.display()
+        );
+    }
+
+    let mut report = BatchReport::default();
+    for dll_path in dlls {
+        // Mirror the input layout so same-named DLLs from different folders don't collide.
+        let relative = dll_path
+            .strip_prefix(input_dir)
+            .unwrap_or(&dll_path)
+            .with_extension("");
+        let dll_out = output_dir.join(relative);
+
+        for &target in &options.targets {
+            let target_out = if options.targets.len() > 1 {
+                dll_out.join(target.name())
+            } else {
+                dll_out.clone()
+            };
+            let result = origin_for(&dll_path)
+                .and_then(|origin| {
+                    generate_cli(target, &dll_path, &target_out, origin, &options.proxy)
+                })
+                .map(|written| written.len())
+                .map_err(|e| format!("{e:#}"));
+            report.entries.push(BatchEntry {
+                dll: dll_path.clone(),
+                target,
+                output_dir: target_out,
+                result,
+            });
+        }
+    }
+
+    Ok(report)
+}
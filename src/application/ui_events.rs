@@ -1,11 +1,19 @@
-use std::{env, path::Path};
+use std::{env, io::Write, path::Path};
 
 use crate::dll;
+use crate::export_diff;
+use crate::export_policy;
+use crate::host_filter;
+use crate::prototype;
 use crate::templates::{
     render_asm_x64, render_asm_x64_gas, render_asm_x86, render_asm_x86_gas, render_c, render_c_x64,
-    render_cmake_lists, render_def, render_filters, render_filters_2026, render_solution,
-    render_slnx_2026, render_user, render_user_2026, render_vcxproj, render_vcxproj_2026,
-    OriginLoadMode, OriginLoadModeOwned, VsGuids, VsTemplateContext,
+    render_cmake_lists, render_def, render_meson_build, render_meson_cross_file, meson_cross_file_name, render_filters, render_payload_c, render_proxy_h,
+    render_origin_config_sample, render_filters_2026, render_solution,
+    render_rust_build_rs, render_rust_cargo_toml, render_rust_jump, render_rust_lib, render_rust_origin,
+    render_rust_payload, render_rust_sys, render_slnx_2026, render_user, render_user_2026, render_vcxproj,
+    render_vcxproj_2026, check_origin_forwarders, check_rust_crate, needs_rust_payload_file,
+    needs_jump_table, needs_payload_file, payload_file_name, proxy_header_name, proxy_source_name, FailurePolicy, OriginLoadMode, OriginLoadModeOwned, PayloadEntry, PluginLoader,
+    ProxyOptions, VsGuids, VsTemplateContext,
 };
 use eframe::egui;
 use rfd;
@@ -15,12 +23,39 @@ use uuid::Uuid;
 const DEFAULT_LOG: &str =
     "AheadLibEx (Rust)\nAuthor: i1tao\nGitHub: https://github.com/i1tao/AheadLibEx\n------------------------------------------------------";
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum OutputTarget {
     Source,
     Vs2022,
     Vs2026,
     CMake,
+    Meson,
+    Rust,
+}
+
+impl OutputTarget {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "source" | "src" | "c" => Some(Self::Source),
+            "vs2022" | "2022" => Some(Self::Vs2022),
+            "vs2026" | "2026" => Some(Self::Vs2026),
+            "cmake" | "cml" => Some(Self::CMake),
+            "meson" => Some(Self::Meson),
+            "rust" | "rs" | "cargo" => Some(Self::Rust),
+            _ => None,
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Source => "source",
+            Self::Vs2022 => "vs2022",
+            Self::Vs2026 => "vs2026",
+            Self::CMake => "cmake",
+            Self::Meson => "meson",
+            Self::Rust => "rust",
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -28,6 +63,29 @@ pub enum OriginModeChoice {
     SystemDir,
     SameDir,
     CustomPath,
+    Chain,
+}
+
+// GUI side of `PayloadEntry`; the first-call export comes from a separate text field.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PayloadChoice {
+    Thread,
+    Sync,
+    FirstCall,
+    HostReady,
+    None,
+    Demo,
+}
+
+impl PayloadChoice {
+    pub const ALL: [Self; 6] = [
+        Self::Thread,
+        Self::Sync,
+        Self::FirstCall,
+        Self::HostReady,
+        Self::None,
+        Self::Demo,
+    ];
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -54,10 +112,34 @@ pub struct UiState {
     pub output_vs2022: bool,
     pub output_vs2026: bool,
     pub output_cmake: bool,
+    pub output_meson: bool,
+    pub output_rust: bool,
     pub ui_language: UiLanguageChoice,
     pub origin_mode: OriginModeChoice,
     pub origin_same_dir_name: String,
     pub origin_custom_path: String,
+    // One `system` / `samedir[:<name>]` / `custom:<path>` per line, tried in order.
+    pub origin_chain: String,
+    pub forward_only: bool,
+    pub trace: bool,
+    pub lazy: bool,
+    pub deferred_init: bool,
+    pub minimal_deps: bool,
+    pub crash_guard: bool,
+    pub cpp: bool,
+    pub failure_policy: FailurePolicy,
+    pub payload: PayloadChoice,
+    // Name or @ordinal for PayloadChoice::FirstCall.
+    pub payload_export: String,
+    // Same syntax as a `--host-list` file.
+    pub host_rules: String,
+    // `<dir>[\\<pattern>]`; empty means no plugin loader.
+    pub plugin_dir: String,
+    pub plugin_init: String,
+    // Same syntax as an `--export-rules` file.
+    pub export_rules: String,
+    // C declarations for hooked exports, parsed like a header.
+    pub hook_prototypes: String,
     pub log: String,
     pub dragging: bool,
     pub success: Option<bool>,
@@ -72,10 +154,28 @@ impl UiState {
             output_vs2022: false,
             output_vs2026: false,
             output_cmake: false,
+            output_meson: false,
+            output_rust: false,
             ui_language: detect_default_ui_language(),
             origin_mode: OriginModeChoice::SystemDir,
             origin_same_dir_name: String::new(),
             origin_custom_path: String::new(),
+            origin_chain: String::new(),
+            forward_only: false,
+            trace: false,
+            lazy: false,
+            deferred_init: false,
+            minimal_deps: false,
+            crash_guard: false,
+            cpp: false,
+            failure_policy: FailurePolicy::Abort,
+            payload: PayloadChoice::Thread,
+            payload_export: String::new(),
+            host_rules: String::new(),
+            plugin_dir: String::new(),
+            plugin_init: String::new(),
+            export_rules: String::new(),
+            hook_prototypes: String::new(),
             log: default_log(),
             dragging: false,
             success: None,
@@ -161,8 +261,10 @@ pub fn generate(state: &mut UiState) {
         && !state.output_vs2022
         && !state.output_vs2026
         && !state.output_cmake
+        && !state.output_meson
+        && !state.output_rust
     {
-        state.log = "Please select at least one output target (Source/VS2022/VS2026/CMake)".into();
+        state.log = "Please select at least one output target (Source/VS2022/VS2026/CMake/Meson/Rust)".into();
         state.success = Some(false);
         return;
     }
@@ -171,7 +273,9 @@ pub fn generate(state: &mut UiState) {
     let selected_count = state.output_source as u8
         + state.output_vs2022 as u8
         + state.output_vs2026 as u8
-        + state.output_cmake as u8;
+        + state.output_cmake as u8
+        + state.output_meson as u8
+        + state.output_rust as u8;
     if selected_count > 1 {
         state.log = "Please select only one output target".into();
         state.success = Some(false);
@@ -224,6 +328,23 @@ pub fn generate(state: &mut UiState) {
                 }
             };
             let origin = origin_load_mode.as_borrowed();
+            let proxy_options = match build_proxy_options(state) {
+                Ok(v) => v,
+                Err(err) => {
+                    state
+                        .log
+                        .push_str(&format!("\n-- Proxy options invalid --\n{err}"));
+                    state.success = Some(false);
+                    return;
+                }
+            };
+            if let Err(err) = proxy_options.check_exports(&exports_for_write) {
+                state
+                    .log
+                    .push_str(&format!("\n-- Payload entry invalid --\n{err}"));
+                state.success = Some(false);
+                return;
+            }
 
             if state.output_source {
                 match write_source_files(
@@ -232,6 +353,7 @@ pub fn generate(state: &mut UiState) {
                     is_x64,
                     origin,
                     &exports_for_write,
+                    &proxy_options,
                 ) {
                     Ok(_) => state.log.push_str("\n-- Source files written successfully --"),
                     Err(err) => {
@@ -249,6 +371,7 @@ pub fn generate(state: &mut UiState) {
                     is_x64,
                     origin,
                     &exports_for_write,
+                    &proxy_options,
                 ) {
                     Ok(_) => state
                         .log
@@ -269,6 +392,7 @@ pub fn generate(state: &mut UiState) {
                     is_x64,
                     origin,
                     &exports_for_write,
+                    &proxy_options,
                 ) {
                     Ok(_) => state
                         .log
@@ -289,6 +413,7 @@ pub fn generate(state: &mut UiState) {
                     is_x64,
                     origin,
                     &exports_for_write,
+                    &proxy_options,
                 ) {
                     Ok(_) => state
                         .log
@@ -301,6 +426,48 @@ pub fn generate(state: &mut UiState) {
                     }
                 }
             }
+
+            if state.output_meson {
+                match write_meson_project(
+                    dll_path,
+                    Path::new(state.project_dir.trim()),
+                    is_x64,
+                    origin,
+                    &exports_for_write,
+                    &proxy_options,
+                ) {
+                    Ok(_) => state
+                        .log
+                        .push_str("\n-- Meson project written successfully --"),
+                    Err(err) => {
+                        state
+                            .log
+                            .push_str(&format!("\n-- Meson project write failed --\n{err}"));
+                        state.success = Some(false);
+                    }
+                }
+            }
+
+            if state.output_rust {
+                match write_rust_crate(
+                    dll_path,
+                    Path::new(state.project_dir.trim()),
+                    is_x64,
+                    origin,
+                    &exports_for_write,
+                    &proxy_options,
+                ) {
+                    Ok(_) => state
+                        .log
+                        .push_str("\n-- Rust crate written successfully --"),
+                    Err(err) => {
+                        state
+                            .log
+                            .push_str(&format!("\n-- Rust crate write failed --\n{err}"));
+                        state.success = Some(false);
+                    }
+                }
+            }
         }
         Err(err) => {
             state.log = format!("Failed to parse DLL: {err}");
@@ -309,11 +476,51 @@ pub fn generate(state: &mut UiState) {
     }
 }
 
+pub fn compare(state: &mut UiState) {
+    if state.dll_path.trim().is_empty() {
+        state.log = "Please select a DLL file first".into();
+        state.success = Some(false);
+        return;
+    }
+
+    let Some(baseline) = rfd::FileDialog::new()
+        .set_title("Select the previous DLL or saved export list")
+        .add_filter("DLL / export list", &["dll", "txt"])
+        .add_filter("All files", &["*"])
+        .pick_file()
+    else {
+        return;
+    };
+
+    let current = Path::new(state.dll_path.trim());
+    let result = dll::load_exports(&baseline).and_then(|old| {
+        let new = dll::read_exports(current)?;
+        Ok((old, new))
+    });
+    match result {
+        Ok((old, new)) => {
+            let diff = export_diff::diff_exports(&old.exports, &new.exports);
+            let mut out = String::new();
+            out.push_str(&format!("Old: {}\n", baseline.display()));
+            out.push_str(&format!("New: {}\n", current.display()));
+            out.push_str("-- Export Diff --\n");
+            out.push_str(&diff.to_text());
+            state.log = out;
+            state.success = Some(diff.is_empty());
+        }
+        Err(err) => {
+            state.log = format!("Failed to compare exports: {err}");
+            state.success = Some(false);
+        }
+    }
+}
+
 pub fn generate_cli(
     target: OutputTarget,
     dll_path: &Path,
     output_dir: &Path,
     origin_load_mode: OriginLoadModeOwned,
+    options: &ProxyOptions,
 ) -> anyhow::Result<Vec<String>> {
     if !dll_path.exists() {
         return Err(anyhow::anyhow!("DLL path not found: {}", dll_path.display()));
@@ -323,13 +530,28 @@ pub fn generate_cli(
     let mut exports = info.exports.clone();
     exports.sort_by_key(|e| e.ordinal);
     let is_x64 = info.arch.eq_ignore_ascii_case("x64");
+    options.check_exports(&exports)?;
 
     let origin = origin_load_mode.as_borrowed();
     match target {
-        OutputTarget::Source => write_source_files(dll_path, output_dir, is_x64, origin, &exports),
-        OutputTarget::Vs2022 => write_vs2022_project(dll_path, output_dir, is_x64, origin, &exports),
-        OutputTarget::Vs2026 => write_vs2026_project(dll_path, output_dir, is_x64, origin, &exports),
-        OutputTarget::CMake => write_cmake_project(dll_path, output_dir, is_x64, origin, &exports),
+        OutputTarget::Source => {
+            write_source_files(dll_path, output_dir, is_x64, origin, &exports, options)
+        }
+        OutputTarget::Vs2022 => {
+            write_vs2022_project(dll_path, output_dir, is_x64, origin, &exports, options)
+        }
+        OutputTarget::Vs2026 => {
+            write_vs2026_project(dll_path, output_dir, is_x64, origin, &exports, options)
+        }
+        OutputTarget::CMake => {
+            write_cmake_project(dll_path, output_dir, is_x64, origin, &exports, options)
+        }
+        OutputTarget::Meson => {
+            write_meson_project(dll_path, output_dir, is_x64, origin, &exports, options)
+        }
+        OutputTarget::Rust => {
+            write_rust_crate(dll_path, output_dir, is_x64, origin, &exports, options)
+        }
     }
 }
 
@@ -340,9 +562,27 @@ pub fn reset(state: &mut UiState) {
     state.output_vs2022 = false;
     state.output_vs2026 = false;
     state.output_cmake = false;
+    state.output_meson = false;
+    state.output_rust = false;
     state.origin_mode = OriginModeChoice::SystemDir;
     state.origin_same_dir_name.clear();
     state.origin_custom_path.clear();
+    state.origin_chain.clear();
+    state.forward_only = false;
+    state.trace = false;
+    state.lazy = false;
+    state.deferred_init = false;
+    state.minimal_deps = false;
+    state.crash_guard = false;
+    state.cpp = false;
+    state.failure_policy = FailurePolicy::Abort;
+    state.payload = PayloadChoice::Thread;
+    state.payload_export.clear();
+    state.host_rules.clear();
+    state.plugin_dir.clear();
+    state.plugin_init.clear();
+    state.export_rules.clear();
+    state.hook_prototypes.clear();
     state.log = default_log();
     state.success = None;
 }
@@ -353,7 +593,7 @@ pub fn handle_drop(state: &mut UiState, ctx: &egui::Context) {
         if let Some(f) = i.raw.dropped_files.first() {
             if let Some(p) = &f.path {
                 if p.extension()
-                    .map_or(false, |e| e.eq_ignore_ascii_case("dll"))
+                    .is_some_and(|e| e.eq_ignore_ascii_case("dll"))
                 {
                     state.dll_path = p.display().to_string();
                     state.project_dir = default_project_dir(p)
@@ -402,6 +642,74 @@ fn build_origin_load_mode(state: &UiState, dll_stem: &str) -> anyhow::Result<Ori
             }
             Ok(OriginLoadModeOwned::custom_path(p.to_string()))
         }
+        OriginModeChoice::Chain => {
+            let default_name = format!("{dll_stem}_orig.dll");
+            let candidates = state
+                .origin_chain
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(|line| OriginLoadModeOwned::parse(line, &default_name))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            if candidates.is_empty() {
+                anyhow::bail!("Please list at least one origin DLL location");
+            }
+            Ok(OriginLoadModeOwned::chain(candidates))
+        }
+    }
+}
+
+fn build_proxy_options(state: &UiState) -> anyhow::Result<ProxyOptions> {
+    let mut options = if state.forward_only {
+        ProxyOptions::forward_only()
+    } else {
+        ProxyOptions::default()
+    };
+    options.trace = state.trace;
+    options.lazy = state.lazy;
+    options.deferred_init = state.deferred_init;
+    options.minimal_deps = state.minimal_deps;
+    options.crash_guard = state.crash_guard;
+    options.cpp = state.cpp;
+    options.failure_policy = state.failure_policy;
+    options.payload = match state.payload {
+        PayloadChoice::Thread => PayloadEntry::Thread,
+        PayloadChoice::Sync => PayloadEntry::Sync,
+        PayloadChoice::FirstCall => {
+            PayloadEntry::parse(&format!("first-call:{}", state.payload_export))?
+        }
+        PayloadChoice::HostReady => PayloadEntry::HostReady,
+        PayloadChoice::None => PayloadEntry::None,
+        PayloadChoice::Demo => PayloadEntry::Demo,
+    };
+    options.host_filter = host_filter::parse_host_list(&state.host_rules)?;
+    if !state.plugin_dir.trim().is_empty() {
+        let plugins = PluginLoader::parse(&state.plugin_dir)?;
+        options.plugins = Some(if state.plugin_init.trim().is_empty() {
+            plugins
+        } else {
+            plugins.with_init_export(&state.plugin_init)?
+        });
+    }
+    options.export_rules = export_policy::parse_rules(&state.export_rules)?;
+    options.hook_prototypes = prototype::prototypes_from_header(&state.hook_prototypes);
+    Ok(options)
+}
+
+pub fn load_export_rules(state: &mut UiState) {
+    let Some(p) = rfd::FileDialog::new()
+        .add_filter("Export rules", &["txt", "rules", "ini"])
+        .add_filter("All files", &["*"])
+        .pick_file()
+    else {
+        return;
+    };
+    match fs::read_to_string(&p) {
+        Ok(text) => state.export_rules = text,
+        Err(err) => {
+            state.log = format!("Failed to read export rules {}: {err}", p.display());
+            state.success = Some(false);
+        }
     }
 }
 
@@ -443,15 +751,70 @@ fn selected_targets(state: &UiState) -> Vec<String> {
     if state.output_cmake {
         v.push("CMake".to_string());
     }
+    if state.output_meson {
+        v.push("Meson".to_string());
+    }
+    if state.output_rust {
+        v.push("Rust".to_string());
+    }
     v
 }
 
-fn write_source_files(
+// For files the user edits after generation: created when missing, never overwritten. `create_new`
+// makes the check and the create one step. False when the file was already there.
+fn write_file_once(path: &Path, content: &str) -> anyhow::Result<bool> {
+    match fs::OpenOptions::new().write(true).create_new(true).open(path) {
+        Ok(mut file) => {
+            file.write_all(content.as_bytes())?;
+            Ok(true)
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+// The payload source holds the user's code: created when missing, never overwritten, and listed in
+// `written` only when this run created it.
+fn write_payload_once(path: &Path, content: &str, written: &mut Vec<String>) -> anyhow::Result<()> {
+    if write_file_once(path, content)? {
+        written.push(path.display().to_string());
+    }
+    Ok(())
+}
+
+// The proxy sources every C target builds: the C source, header, jump tables and .def. `gas` adds
+// the GNU assembler flavour next to MASM for toolchains other than MSVC.
+fn proxy_source_files(ctx: &VsTemplateContext, is_x64: bool, gas: bool) -> Vec<(String, String)> {
+    let arch = if is_x64 { "x64" } else { "x86" };
+    let mut files = vec![
+        (
+            proxy_source_name(ctx, is_x64),
+            if is_x64 { render_c_x64(ctx) } else { render_c(ctx) },
+        ),
+        (proxy_header_name(ctx), render_proxy_h(ctx)),
+    ];
+    if needs_jump_table(ctx) {
+        let masm = if is_x64 { render_asm_x64(ctx) } else { render_asm_x86(ctx) };
+        files.push((format!("{}_{arch}_jump.asm", ctx.base_name), masm));
+        if gas {
+            let gas = if is_x64 { render_asm_x64_gas(ctx) } else { render_asm_x86_gas(ctx) };
+            files.push((format!("{}_{arch}_jump.S", ctx.base_name), gas));
+        }
+    }
+    files.push((format!("{}.def", ctx.base_name), render_def(ctx, is_x64)));
+    files
+}
+
+// Writes a C target: the files `files` lists (its build files plus proxy_source_files), then the
+// write-once payload and origin config every C target shares.
+fn write_c_target(
     dll_path: &Path,
     output_dir: &Path,
     is_x64: bool,
     origin_load_mode: OriginLoadMode<'_>,
     exports: &[dll::ExportEntry],
+    options: &ProxyOptions,
+    files: impl FnOnce(&VsTemplateContext) -> Vec<(String, String)>,
 ) -> anyhow::Result<Vec<String>> {
     let dll_stem = dll_path
         .file_stem()
@@ -486,67 +849,88 @@ fn write_source_files(
         origin_load_mode,
         exports,
         guids,
+        options: options.clone(),
     };
-
-    let c_src_x86 = if is_x64 { None } else { Some(render_c(&ctx)) };
-    let c_src_x64 = if is_x64 {
-        Some(render_c_x64(&ctx))
-    } else {
-        None
-    };
-    let asm_src_x86 = if is_x64 { None } else { Some(render_asm_x86(&ctx)) };
-    let asm_src_x86_gas = if is_x64 {
-        None
-    } else {
-        Some(render_asm_x86_gas(&ctx))
-    };
-    let asm_src_x64 = if is_x64 { Some(render_asm_x64(&ctx)) } else { None };
-    let asm_src_x64_gas = if is_x64 {
-        Some(render_asm_x64_gas(&ctx))
-    } else {
-        None
-    };
-    let def_src = render_def(&ctx, is_x64);
+    check_origin_forwarders(&ctx)?;
+    let files = files(&ctx);
 
     fs::create_dir_all(output_dir)?;
-
     let mut written = Vec::new();
-    let mut write_file = |name: &str, content: &str| -> anyhow::Result<()> {
+    for (name, content) in files {
         let path = output_dir.join(name);
         fs::write(&path, content)?;
         written.push(path.display().to_string());
-        Ok(())
-    };
-
-    if let Some(content) = c_src_x86 {
-        write_file(&format!("{}_x86.c", base_name), &content)?;
-    }
-    if let Some(content) = c_src_x64 {
-        write_file(&format!("{}_x64.c", base_name), &content)?;
-    }
-    if let Some(content) = asm_src_x86 {
-        write_file(&format!("{}_x86_jump.asm", base_name), &content)?;
-    }
-    if let Some(content) = asm_src_x86_gas {
-        write_file(&format!("{}_x86_jump.S", base_name), &content)?;
     }
-    if let Some(content) = asm_src_x64 {
-        write_file(&format!("{}_x64_jump.asm", base_name), &content)?;
+
+    if needs_payload_file(&ctx) {
+        write_payload_once(
+            &output_dir.join(payload_file_name(&ctx)),
+            &render_payload_c(&ctx, is_x64),
+            &mut written,
+        )?;
     }
-    if let Some(content) = asm_src_x64_gas {
-        write_file(&format!("{}_x64_jump.S", base_name), &content)?;
+    if let Some((name, content)) = render_origin_config_sample(&ctx) {
+        let path = output_dir.join(name);
+        if write_file_once(&path, &content)? {
+            written.push(path.display().to_string());
+        }
     }
-    write_file(&format!("{}.def", base_name), &def_src)?;
-
     Ok(written)
 }
 
+fn write_source_files(
+    dll_path: &Path,
+    output_dir: &Path,
+    is_x64: bool,
+    origin_load_mode: OriginLoadMode<'_>,
+    exports: &[dll::ExportEntry],
+    options: &ProxyOptions,
+) -> anyhow::Result<Vec<String>> {
+    write_c_target(dll_path, output_dir, is_x64, origin_load_mode, exports, options, |ctx| {
+        proxy_source_files(ctx, is_x64, true)
+    })
+}
+
 fn write_cmake_project(
     dll_path: &Path,
     output_dir: &Path,
     is_x64: bool,
     origin_load_mode: OriginLoadMode<'_>,
     exports: &[dll::ExportEntry],
+    options: &ProxyOptions,
+) -> anyhow::Result<Vec<String>> {
+    write_c_target(dll_path, output_dir, is_x64, origin_load_mode, exports, options, |ctx| {
+        let mut files = vec![("CMakeLists.txt".to_string(), render_cmake_lists(ctx, is_x64))];
+        files.extend(proxy_source_files(ctx, is_x64, true));
+        files
+    })
+}
+
+fn write_meson_project(
+    dll_path: &Path,
+    output_dir: &Path,
+    is_x64: bool,
+    origin_load_mode: OriginLoadMode<'_>,
+    exports: &[dll::ExportEntry],
+    options: &ProxyOptions,
+) -> anyhow::Result<Vec<String>> {
+    write_c_target(dll_path, output_dir, is_x64, origin_load_mode, exports, options, |ctx| {
+        let mut files = vec![
+            ("meson.build".to_string(), render_meson_build(ctx, is_x64)),
+            (meson_cross_file_name(is_x64).to_string(), render_meson_cross_file(is_x64)),
+        ];
+        files.extend(proxy_source_files(ctx, is_x64, true));
+        files
+    })
+}
+
+fn write_rust_crate(
+    dll_path: &Path,
+    output_dir: &Path,
+    is_x64: bool,
+    origin_load_mode: OriginLoadMode<'_>,
+    exports: &[dll::ExportEntry],
+    options: &ProxyOptions,
 ) -> anyhow::Result<Vec<String>> {
     let dll_stem = dll_path
         .file_stem()
@@ -560,18 +944,13 @@ fn write_cmake_project(
 
     let base_name = dll_stem;
 
-    let guid_solution = new_guid_braced();
-    let guid_project = new_guid_braced();
-    let guid_source = new_guid_braced();
-    let guid_header = new_guid_braced();
-    let guid_resource = new_guid_braced();
-
+    // A Cargo crate has no solution or filters; the GUIDs are only there to fill the context.
     let guids = VsGuids {
-        solution: &guid_solution,
-        project: &guid_project,
-        filter_source: &guid_source,
-        filter_header: &guid_header,
-        filter_resource: &guid_resource,
+        solution: "",
+        project: "",
+        filter_source: "",
+        filter_header: "",
+        filter_resource: "",
     };
 
     let ctx = VsTemplateContext {
@@ -581,31 +960,13 @@ fn write_cmake_project(
         origin_load_mode,
         exports,
         guids,
+        options: options.clone(),
     };
+    check_rust_crate(&ctx)?;
+    check_origin_forwarders(&ctx)?;
+    let origin_config = render_origin_config_sample(&ctx);
 
-    let cmake_lists = render_cmake_lists(&ctx, is_x64);
-
-    let c_src_x86 = if is_x64 { None } else { Some(render_c(&ctx)) };
-    let c_src_x64 = if is_x64 {
-        Some(render_c_x64(&ctx))
-    } else {
-        None
-    };
-    let asm_src_x86 = if is_x64 { None } else { Some(render_asm_x86(&ctx)) };
-    let asm_src_x86_gas = if is_x64 {
-        None
-    } else {
-        Some(render_asm_x86_gas(&ctx))
-    };
-    let asm_src_x64 = if is_x64 { Some(render_asm_x64(&ctx)) } else { None };
-    let asm_src_x64_gas = if is_x64 {
-        Some(render_asm_x64_gas(&ctx))
-    } else {
-        None
-    };
-    let def_src = render_def(&ctx, is_x64);
-
-    fs::create_dir_all(output_dir)?;
+    fs::create_dir_all(output_dir.join("src"))?;
 
     let mut written = Vec::new();
     let mut write_file = |name: &str, content: &str| -> anyhow::Result<()> {
@@ -615,27 +976,28 @@ fn write_cmake_project(
         Ok(())
     };
 
-    write_file("CMakeLists.txt", &cmake_lists)?;
-
-    if let Some(content) = c_src_x86 {
-        write_file(&format!("{}_x86.c", base_name), &content)?;
-    }
-    if let Some(content) = c_src_x64 {
-        write_file(&format!("{}_x64.c", base_name), &content)?;
+    write_file("Cargo.toml", &render_rust_cargo_toml(&ctx))?;
+    write_file("build.rs", &render_rust_build_rs(&ctx, is_x64))?;
+    write_file(&format!("{}.def", base_name), &render_def(&ctx, is_x64))?;
+    write_file("src/lib.rs", &render_rust_lib(&ctx, is_x64))?;
+    write_file("src/jump.rs", &render_rust_jump(&ctx, is_x64))?;
+    if needs_jump_table(&ctx) {
+        write_file("src/origin.rs", &render_rust_origin(&ctx))?;
     }
-    if let Some(content) = asm_src_x86 {
-        write_file(&format!("{}_x86_jump.asm", base_name), &content)?;
+    write_file("src/sys.rs", &render_rust_sys())?;
+    if needs_rust_payload_file(&ctx) {
+        write_payload_once(
+            &output_dir.join("src/payload.rs"),
+            &render_rust_payload(&ctx),
+            &mut written,
+        )?;
     }
-    if let Some(content) = asm_src_x86_gas {
-        write_file(&format!("{}_x86_jump.S", base_name), &content)?;
-    }
-    if let Some(content) = asm_src_x64 {
-        write_file(&format!("{}_x64_jump.asm", base_name), &content)?;
-    }
-    if let Some(content) = asm_src_x64_gas {
-        write_file(&format!("{}_x64_jump.S", base_name), &content)?;
+    if let Some((name, content)) = origin_config {
+        let path = output_dir.join(name);
+        if write_file_once(&path, &content)? {
+            written.push(path.display().to_string());
+        }
     }
-    write_file(&format!("{}.def", base_name), &def_src)?;
 
     Ok(written)
 }
@@ -646,91 +1008,18 @@ fn write_vs2022_project(
     is_x64: bool,
     origin_load_mode: OriginLoadMode<'_>,
     exports: &[dll::ExportEntry],
+    options: &ProxyOptions,
 ) -> anyhow::Result<Vec<String>> {
-    let dll_stem = dll_path
-        .file_stem()
-        .map(|s| s.to_string_lossy().to_string())
-        .filter(|s| !s.is_empty())
-        .ok_or_else(|| anyhow::anyhow!("Invalid DLL filename"))?;
-    let dll_name = dll_path
-        .file_name()
-        .map(|s| s.to_string_lossy().to_string())
-        .unwrap_or_else(|| format!("{dll_stem}.dll"));
-
-    let solution_name = format!("AheadlibEx_{}", dll_stem);
-    let project_name = dll_stem;
-    let base_name = project_name.clone();
-
-    let guid_solution = new_guid_braced();
-    let guid_project = new_guid_braced();
-    let guid_source = new_guid_braced();
-    let guid_header = new_guid_braced();
-    let guid_resource = new_guid_braced();
-
-    let guids = VsGuids {
-        solution: &guid_solution,
-        project: &guid_project,
-        filter_source: &guid_source,
-        filter_header: &guid_header,
-        filter_resource: &guid_resource,
-    };
-
-    let ctx = VsTemplateContext {
-        project_name: &project_name,
-        dll_name: &dll_name,
-        base_name: &base_name,
-        origin_load_mode,
-        exports,
-        guids,
-    };
-
-    let sln = render_solution(&ctx, is_x64);
-    let vcxproj = render_vcxproj(&ctx, is_x64);
-    let filters = render_filters(&ctx, is_x64);
-    let user = render_user();
-    let c_src_x86 = if is_x64 { None } else { Some(render_c(&ctx)) };
-    let c_src_x64 = if is_x64 {
-        Some(render_c_x64(&ctx))
-    } else {
-        None
-    };
-    let asm_src_x86 = if is_x64 { None } else { Some(render_asm_x86(&ctx)) };
-    let asm_src_x64 = if is_x64 {
-        Some(render_asm_x64(&ctx))
-    } else {
-        None
-    };
-    let def_src = render_def(&ctx, is_x64);
-
-    fs::create_dir_all(output_dir)?;
-
-    let mut written = Vec::new();
-    let mut write_file = |name: &str, content: &str| -> anyhow::Result<()> {
-        let path = output_dir.join(name);
-        fs::write(&path, content)?;
-        written.push(path.display().to_string());
-        Ok(())
-    };
-
-    write_file(&format!("{}.sln", solution_name), &sln)?;
-    write_file(&format!("{}.vcxproj", project_name), &vcxproj)?;
-    write_file(&format!("{}.vcxproj.filters", project_name), &filters)?;
-    write_file(&format!("{}.vcxproj.user", project_name), &user)?;
-    if let Some(content) = c_src_x86 {
-        write_file(&format!("{}_x86.c", base_name), &content)?;
-    }
-    if let Some(content) = c_src_x64 {
-        write_file(&format!("{}_x64.c", base_name), &content)?;
-    }
-    if let Some(content) = asm_src_x86 {
-        write_file(&format!("{}_x86_jump.asm", base_name), &content)?;
-    }
-    if let Some(content) = asm_src_x64 {
-        write_file(&format!("{}_x64_jump.asm", base_name), &content)?;
-    }
-    write_file(&format!("{}.def", base_name), &def_src)?;
-
-    Ok(written)
+    write_c_target(dll_path, output_dir, is_x64, origin_load_mode, exports, options, |ctx| {
+        let mut files = vec![
+            (format!("AheadlibEx_{}.sln", ctx.base_name), render_solution(ctx, is_x64)),
+            (format!("{}.vcxproj", ctx.project_name), render_vcxproj(ctx, is_x64)),
+            (format!("{}.vcxproj.filters", ctx.project_name), render_filters(ctx, is_x64)),
+            (format!("{}.vcxproj.user", ctx.project_name), render_user()),
+        ];
+        files.extend(proxy_source_files(ctx, is_x64, false));
+        files
+    })
 }
 
 fn write_vs2026_project(
@@ -739,89 +1028,16 @@ fn write_vs2026_project(
     is_x64: bool,
     origin_load_mode: OriginLoadMode<'_>,
     exports: &[dll::ExportEntry],
+    options: &ProxyOptions,
 ) -> anyhow::Result<Vec<String>> {
-    let dll_stem = dll_path
-        .file_stem()
-        .map(|s| s.to_string_lossy().to_string())
-        .filter(|s| !s.is_empty())
-        .ok_or_else(|| anyhow::anyhow!("Invalid DLL filename"))?;
-    let dll_name = dll_path
-        .file_name()
-        .map(|s| s.to_string_lossy().to_string())
-        .unwrap_or_else(|| format!("{dll_stem}.dll"));
-
-    let solution_name = format!("AheadlibEx_{}", dll_stem);
-    let project_name = dll_stem;
-    let base_name = project_name.clone();
-
-    let guid_solution = new_guid_braced();
-    let guid_project = new_guid_braced();
-    let guid_source = new_guid_braced();
-    let guid_header = new_guid_braced();
-    let guid_resource = new_guid_braced();
-
-    let guids = VsGuids {
-        solution: &guid_solution,
-        project: &guid_project,
-        filter_source: &guid_source,
-        filter_header: &guid_header,
-        filter_resource: &guid_resource,
-    };
-
-    let ctx = VsTemplateContext {
-        project_name: &project_name,
-        dll_name: &dll_name,
-        base_name: &base_name,
-        origin_load_mode,
-        exports,
-        guids,
-    };
-
-    let slnx = render_slnx_2026(&ctx, is_x64);
-    let vcxproj = render_vcxproj_2026(&ctx, is_x64);
-    let filters = render_filters_2026(&ctx, is_x64);
-    let user = render_user_2026();
-    let c_src_x86 = if is_x64 { None } else { Some(render_c(&ctx)) };
-    let c_src_x64 = if is_x64 {
-        Some(render_c_x64(&ctx))
-    } else {
-        None
-    };
-    let asm_src_x86 = if is_x64 { None } else { Some(render_asm_x86(&ctx)) };
-    let asm_src_x64 = if is_x64 {
-        Some(render_asm_x64(&ctx))
-    } else {
-        None
-    };
-    let def_src = render_def(&ctx, is_x64);
-
-    fs::create_dir_all(output_dir)?;
-
-    let mut written = Vec::new();
-    let mut write_file = |name: &str, content: &str| -> anyhow::Result<()> {
-        let path = output_dir.join(name);
-        fs::write(&path, content)?;
-        written.push(path.display().to_string());
-        Ok(())
-    };
-
-    write_file(&format!("{}.slnx", solution_name), &slnx)?;
-    write_file(&format!("{}.vcxproj", project_name), &vcxproj)?;
-    write_file(&format!("{}.vcxproj.filters", project_name), &filters)?;
-    write_file(&format!("{}.vcxproj.user", project_name), &user)?;
-    if let Some(content) = c_src_x86 {
-        write_file(&format!("{}_x86.c", base_name), &content)?;
-    }
-    if let Some(content) = c_src_x64 {
-        write_file(&format!("{}_x64.c", base_name), &content)?;
-    }
-    if let Some(content) = asm_src_x86 {
-        write_file(&format!("{}_x86_jump.asm", base_name), &content)?;
-    }
-    if let Some(content) = asm_src_x64 {
-        write_file(&format!("{}_x64_jump.asm", base_name), &content)?;
-    }
-    write_file(&format!("{}.def", base_name), &def_src)?;
-
-    Ok(written)
+    write_c_target(dll_path, output_dir, is_x64, origin_load_mode, exports, options, |ctx| {
+        let mut files = vec![
+            (format!("AheadlibEx_{}.slnx", ctx.base_name), render_slnx_2026(ctx, is_x64)),
+            (format!("{}.vcxproj", ctx.project_name), render_vcxproj_2026(ctx, is_x64)),
+            (format!("{}.vcxproj.filters", ctx.project_name), render_filters_2026(ctx, is_x64)),
+            (format!("{}.vcxproj.user", ctx.project_name), render_user_2026()),
+        ];
+        files.extend(proxy_source_files(ctx, is_x64, false));
+        files
+    })
 }
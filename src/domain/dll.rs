@@ -1,7 +1,7 @@
 use std::{fs::File, path::Path};
 
-use anyhow::{Context, Result};
-use goblin::pe::{export::Reexport, PE};
+use anyhow::{bail, Context, Result};
+use goblin::pe::{export::Reexport, section_table::IMAGE_SCN_MEM_EXECUTE, PE};
 use memmap2::MmapOptions;
 
 #[derive(Debug, Clone)]
@@ -9,12 +9,14 @@ pub struct ExportEntry {
     pub name: String,
     pub ordinal: u16,
     pub forwarder: Option<String>,
+    pub is_data: bool,
 }
 
 #[derive(Debug, Clone)]
 pub struct DllExports {
     pub arch: String,
     pub exports: Vec<ExportEntry>,
+    pub imports: Vec<String>,
 }
 
 pub fn read_exports(path: &Path) -> Result<DllExports> {
@@ -59,13 +61,110 @@ pub fn read_exports(path: &Path) -> Result<DllExports> {
                 None => None,
             };
 
+            // Exports living in a non-executable section are data; forwarders have no address of their own.
+            let is_data = forwarder.is_none()
+                && pe
+                    .sections
+                    .iter()
+                    .find(|s| {
+                        let start = s.virtual_address as usize;
+                        let size = s.virtual_size.max(s.size_of_raw_data) as usize;
+                        e.rva >= start && e.rva < start + size
+                    })
+                    .is_some_and(|s| s.characteristics & IMAGE_SCN_MEM_EXECUTE == 0);
+
             ExportEntry {
                 name,
                 ordinal: ordinal.min(u16::MAX as u32) as u16,
                 forwarder,
+                is_data,
             }
         })
         .collect();
 
-    Ok(DllExports { arch, exports })
+    let imports = pe.libraries.iter().map(|l| l.to_string()).collect();
+
+    Ok(DllExports {
+        arch,
+        exports,
+        imports,
+    })
+}
+
+// Accepts either a PE image or an export list written by `format_export_list`.
+pub fn load_exports(path: &Path) -> Result<DllExports> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("Failed to read: {}", path.display()))?;
+    if bytes.starts_with(b"MZ") {
+        return read_exports(path);
+    }
+    let text = String::from_utf8(bytes)
+        .with_context(|| format!("Export list is not valid UTF-8: {}", path.display()))?;
+    parse_export_list(&text).with_context(|| format!("Invalid export list: {}", path.display()))
+}
+
+// One export per line, tab separated: ordinal, name, code|data, forwarder (or `-`).
+pub fn format_export_list(info: &DllExports) -> String {
+    let mut out = String::new();
+    out.push_str("# AheadLibEx export list\n");
+    out.push_str(&format!("# arch: {}\n", info.arch));
+    for e in &info.exports {
+        out.push_str(&format!(
+            "{}\t{}\t{}\t{}\n",
+            e.ordinal,
+            e.name,
+            if e.is_data { "data" } else { "code" },
+            e.forwarder.as_deref().unwrap_or("-")
+        ));
+    }
+    out
+}
+
+pub fn parse_export_list(text: &str) -> Result<DllExports> {
+    let mut arch = String::new();
+    let mut exports = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(comment) = line.strip_prefix('#') {
+            if let Some(v) = comment.trim().strip_prefix("arch:") {
+                arch = v.trim().to_string();
+            }
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 2 {
+            bail!("line {}: expected at least <ordinal> and <name>", idx + 1);
+        }
+        let ordinal = fields[0]
+            .trim()
+            .parse::<u16>()
+            .with_context(|| format!("line {}: invalid ordinal '{}'", idx + 1, fields[0]))?;
+        let is_data = match fields.get(2).map(|s| s.trim()) {
+            None | Some("code") => false,
+            Some("data") => true,
+            Some(other) => bail!("line {}: unknown export kind '{}'", idx + 1, other),
+        };
+        let forwarder = fields
+            .get(3)
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty() && *s != "-")
+            .map(|s| s.to_string());
+
+        exports.push(ExportEntry {
+            name: fields[1].to_string(),
+            ordinal,
+            forwarder,
+            is_data,
+        });
+    }
+
+    Ok(DllExports {
+        arch,
+        exports,
+        imports: Vec::new(),
+    })
 }
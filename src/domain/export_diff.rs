@@ -0,0 +1,292 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+
+use crate::dll::ExportEntry;
+use crate::domain::json;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExportChange {
+    Added {
+        name: String,
+        ordinal: u16,
+    },
+    Removed {
+        name: String,
+        ordinal: u16,
+    },
+    Renamed {
+        ordinal: u16,
+        old_name: String,
+        new_name: String,
+    },
+    OrdinalChanged {
+        name: String,
+        old_ordinal: u16,
+        new_ordinal: u16,
+    },
+    ForwarderChanged {
+        name: String,
+        old_forwarder: Option<String>,
+        new_forwarder: Option<String>,
+    },
+    KindChanged {
+        name: String,
+        old_is_data: bool,
+        new_is_data: bool,
+    },
+}
+
+impl ExportChange {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Added { .. } => "added",
+            Self::Removed { .. } => "removed",
+            Self::Renamed { .. } => "renamed",
+            Self::OrdinalChanged { .. } => "ordinal",
+            Self::ForwarderChanged { .. } => "forwarder",
+            Self::KindChanged { .. } => "kind",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExportDiff {
+    pub changes: Vec<ExportChange>,
+}
+
+impl ExportDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn count(&self, kind: &str) -> usize {
+        self.changes.iter().filter(|c| c.kind() == kind).count()
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        if self.changes.is_empty() {
+            out.push_str("Export tables are identical\n");
+            return out;
+        }
+
+        for change in &self.changes {
+            let _ = match change {
+                ExportChange::Added { name, ordinal } => {
+                    writeln!(out, "+ added      #{:<5} {}", ordinal, name)
+                }
+                ExportChange::Removed { name, ordinal } => {
+                    writeln!(out, "- removed    #{:<5} {}", ordinal, name)
+                }
+                ExportChange::Renamed {
+                    ordinal,
+                    old_name,
+                    new_name,
+                } => writeln!(out, "~ renamed    #{:<5} {} -> {}", ordinal, old_name, new_name),
+                ExportChange::OrdinalChanged {
+                    name,
+                    old_ordinal,
+                    new_ordinal,
+                } => writeln!(out, "~ ordinal    {} #{} -> #{}", name, old_ordinal, new_ordinal),
+                ExportChange::ForwarderChanged {
+                    name,
+                    old_forwarder,
+                    new_forwarder,
+                } => writeln!(
+                    out,
+                    "~ forwarder  {} {} -> {}",
+                    name,
+                    old_forwarder.as_deref().unwrap_or("(none)"),
+                    new_forwarder.as_deref().unwrap_or("(none)")
+                ),
+                ExportChange::KindChanged {
+                    name,
+                    old_is_data,
+                    new_is_data,
+                } => writeln!(
+                    out,
+                    "~ kind       {} {} -> {}",
+                    name,
+                    kind_name(*old_is_data),
+                    kind_name(*new_is_data)
+                ),
+            };
+        }
+
+        let _ = writeln!(
+            out,
+            "Summary: {} added, {} removed, {} renamed, {} ordinal, {} forwarder, {} kind",
+            self.count("added"),
+            self.count("removed"),
+            self.count("renamed"),
+            self.count("ordinal"),
+            self.count("forwarder"),
+            self.count("kind")
+        );
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        out.push_str("{\n  \"identical\": ");
+        out.push_str(if self.changes.is_empty() { "true" } else { "false" });
+        out.push_str(",\n  \"changes\": [");
+        for (idx, change) in self.changes.iter().enumerate() {
+            out.push_str(if idx == 0 { "\n    " } else { ",\n    " });
+            let _ = write!(out, "{{\"kind\": {}, ", json::quote(change.kind()));
+            let _ = match change {
+                ExportChange::Added { name, ordinal } | ExportChange::Removed { name, ordinal } => {
+                    write!(out, "\"name\": {}, \"ordinal\": {}}}", json::quote(name), ordinal)
+                }
+                ExportChange::Renamed {
+                    ordinal,
+                    old_name,
+                    new_name,
+                } => write!(
+                    out,
+                    "\"ordinal\": {}, \"old_name\": {}, \"new_name\": {}}}",
+                    ordinal,
+                    json::quote(old_name),
+                    json::quote(new_name)
+                ),
+                ExportChange::OrdinalChanged {
+                    name,
+                    old_ordinal,
+                    new_ordinal,
+                } => write!(
+                    out,
+                    "\"name\": {}, \"old_ordinal\": {}, \"new_ordinal\": {}}}",
+                    json::quote(name),
+                    old_ordinal,
+                    new_ordinal
+                ),
+                ExportChange::ForwarderChanged {
+                    name,
+                    old_forwarder,
+                    new_forwarder,
+                } => write!(
+                    out,
+                    "\"name\": {}, \"old_forwarder\": {}, \"new_forwarder\": {}}}",
+                    json::quote(name),
+                    json::quote_opt(old_forwarder.as_deref()),
+                    json::quote_opt(new_forwarder.as_deref())
+                ),
+                ExportChange::KindChanged {
+                    name,
+                    old_is_data,
+                    new_is_data,
+                } => write!(
+                    out,
+                    "\"name\": {}, \"old_kind\": {}, \"new_kind\": {}}}",
+                    json::quote(name),
+                    json::quote(kind_name(*old_is_data)),
+                    json::quote(kind_name(*new_is_data))
+                ),
+            };
+        }
+        if !self.changes.is_empty() {
+            out.push_str("\n  ");
+        }
+        out.push_str("]\n}\n");
+        out
+    }
+}
+
+fn kind_name(is_data: bool) -> &'static str {
+    if is_data {
+        "data"
+    } else {
+        "code"
+    }
+}
+
+pub fn diff_exports(old: &[ExportEntry], new: &[ExportEntry]) -> ExportDiff {
+    // Unnamed exports are keyed by their "#<ordinal>" name, so they match on ordinal.
+    let old_by_name: BTreeMap<&str, &ExportEntry> =
+        old.iter().map(|e| (e.name.as_str(), e)).collect();
+    let new_by_name: BTreeMap<&str, &ExportEntry> =
+        new.iter().map(|e| (e.name.as_str(), e)).collect();
+
+    let mut changes = Vec::new();
+
+    for (name, old_entry) in &old_by_name {
+        let Some(new_entry) = new_by_name.get(name) else {
+            continue;
+        };
+        if old_entry.ordinal != new_entry.ordinal {
+            changes.push(ExportChange::OrdinalChanged {
+                name: name.to_string(),
+                old_ordinal: old_entry.ordinal,
+                new_ordinal: new_entry.ordinal,
+            });
+        }
+        if old_entry.forwarder != new_entry.forwarder {
+            changes.push(ExportChange::ForwarderChanged {
+                name: name.to_string(),
+                old_forwarder: old_entry.forwarder.clone(),
+                new_forwarder: new_entry.forwarder.clone(),
+            });
+        }
+        if old_entry.is_data != new_entry.is_data {
+            changes.push(ExportChange::KindChanged {
+                name: name.to_string(),
+                old_is_data: old_entry.is_data,
+                new_is_data: new_entry.is_data,
+            });
+        }
+    }
+
+    let removed: Vec<&ExportEntry> = old_by_name
+        .iter()
+        .filter(|(name, _)| !new_by_name.contains_key(*name))
+        .map(|(_, e)| *e)
+        .collect();
+    let added: Vec<&ExportEntry> = new_by_name
+        .iter()
+        .filter(|(name, _)| !old_by_name.contains_key(*name))
+        .map(|(_, e)| *e)
+        .collect();
+
+    // A name that disappeared while another appeared at the same ordinal is a rename.
+    let mut renamed_old = HashSet::new();
+    let mut renamed_new = HashSet::new();
+    for old_entry in &removed {
+        if let Some(new_entry) = added
+            .iter()
+            .find(|e| e.ordinal == old_entry.ordinal && !renamed_new.contains(&e.name))
+        {
+            renamed_old.insert(old_entry.name.clone());
+            renamed_new.insert(new_entry.name.clone());
+            changes.push(ExportChange::Renamed {
+                ordinal: old_entry.ordinal,
+                old_name: old_entry.name.clone(),
+                new_name: new_entry.name.clone(),
+            });
+        }
+    }
+
+    for e in removed.iter().filter(|e| !renamed_old.contains(&e.name)) {
+        changes.push(ExportChange::Removed {
+            name: e.name.clone(),
+            ordinal: e.ordinal,
+        });
+    }
+    for e in added.iter().filter(|e| !renamed_new.contains(&e.name)) {
+        changes.push(ExportChange::Added {
+            name: e.name.clone(),
+            ordinal: e.ordinal,
+        });
+    }
+
+    changes.sort_by_key(|c| match c {
+        ExportChange::Added { ordinal, .. }
+        | ExportChange::Removed { ordinal, .. }
+        | ExportChange::Renamed { ordinal, .. } => *ordinal,
+        ExportChange::OrdinalChanged { old_ordinal, .. } => *old_ordinal,
+        ExportChange::ForwarderChanged { name, .. } | ExportChange::KindChanged { name, .. } => {
+            old_by_name.get(name.as_str()).map_or(0, |e| e.ordinal)
+        }
+    });
+
+    ExportDiff { changes }
+}
@@ -0,0 +1,305 @@
+use anyhow::{bail, Result};
+
+use crate::dll::ExportEntry;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ExportStrategy {
+    // Jump stub through a pointer resolved with GetProcAddress at runtime.
+    #[default]
+    Stub,
+    // Linker forwarder (`Foo=foo_orig.Foo`), resolved by the Windows loader.
+    Forward,
+    // Jump stub whose pointer can be redirected to a user detour in `install_hooks`.
+    Hook,
+    // Not exported by the proxy at all.
+    Drop,
+}
+
+impl ExportStrategy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "stub" | "jump" => Some(Self::Stub),
+            "forward" | "fwd" => Some(Self::Forward),
+            "hook" => Some(Self::Hook),
+            "drop" | "omit" | "none" => Some(Self::Drop),
+            _ => None,
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Stub => "stub",
+            Self::Forward => "forward",
+            Self::Hook => "hook",
+            Self::Drop => "drop",
+        }
+    }
+}
+
+// What the generated code does when the original DLL or one of its exports is missing.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum FailurePolicy {
+    // MessageBox, then ExitProcess (missing export) or a failed DllMain (missing DLL).
+    #[default]
+    Abort,
+    // No UI: ExitProcess with ERROR_MOD_NOT_FOUND / ERROR_PROC_NOT_FOUND.
+    Exit,
+    // OutputDebugString plus `<proxy>.dll.log`; missing exports set ERROR_PROC_NOT_FOUND and return 0.
+    Log,
+    // No UI: DllMain returns FALSE so the host's LoadLibrary fails.
+    FailLoad,
+}
+
+impl FailurePolicy {
+    pub const ALL: [Self; 4] = [Self::Abort, Self::Exit, Self::Log, Self::FailLoad];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "abort" | "message" | "messagebox" => Some(Self::Abort),
+            "exit" => Some(Self::Exit),
+            "log" | "continue" => Some(Self::Log),
+            "fail-load" | "failload" | "fail" => Some(Self::FailLoad),
+            _ => None,
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Abort => "abort",
+            Self::Exit => "exit",
+            Self::Log => "log",
+            Self::FailLoad => "fail-load",
+        }
+    }
+}
+
+// How DllMain hands control to the user's payload.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum PayloadEntry {
+    // CreateThread from DLL_PROCESS_ATTACH.
+    #[default]
+    Thread,
+    // Called straight from DLL_PROCESS_ATTACH, under loader lock.
+    Sync,
+    // Runs once, from the first call of the selected export (name or ordinal).
+    FirstCall(ExportSelector),
+    // APC on the loading thread, run once process start-up has finished loading every static import.
+    HostReady,
+    // No payload scaffolding at all.
+    None,
+    // Thread plus the test MessageBox.
+    Demo,
+}
+
+impl PayloadEntry {
+    // `thread|sync|first-call:<export>|host-ready|none|demo`
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim();
+        let (kind, export) = match text.split_once(':') {
+            Some((kind, export)) => (kind, Some(export)),
+            None => (text, None),
+        };
+        let entry = match (kind.trim().to_ascii_lowercase().as_str(), export) {
+            ("thread", None) => Self::Thread,
+            ("sync" | "attach", None) => Self::Sync,
+            ("first-call" | "firstcall", Some(export)) => {
+                let selector = ExportSelector::parse(export)?;
+                if let ExportSelector::Pattern(pattern) = &selector {
+                    bail!("first-call needs a single export, not the pattern '{}'", pattern);
+                }
+                Self::FirstCall(selector)
+            }
+            ("first-call" | "firstcall", None) => {
+                bail!("first-call needs an export: first-call:<name|@ordinal>")
+            }
+            ("host-ready" | "hostready" | "apc", None) => Self::HostReady,
+            ("none", None) => Self::None,
+            ("demo", None) => Self::Demo,
+            _ => bail!(
+                "Unknown payload entry '{}'. Use thread|sync|first-call:<export>|host-ready|none|demo.",
+                text
+            ),
+        };
+        Ok(entry)
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::Thread => "thread".to_string(),
+            Self::Sync => "sync".to_string(),
+            Self::FirstCall(selector) => format!("first-call:{}", selector.text()),
+            Self::HostReady => "host-ready".to_string(),
+            Self::None => "none".to_string(),
+            Self::Demo => "demo".to_string(),
+        }
+    }
+}
+
+// DLLs the payload loads from a directory before it runs user code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PluginLoader {
+    // Relative to the proxy DLL directory unless absolute; no trailing separator.
+    pub dir: String,
+    pub pattern: String,
+    // Called with an AHEADLIBEX_PLUGIN_CONTEXT; a plugin whose init returns FALSE is unloaded.
+    pub init_export: Option<String>,
+}
+
+impl PluginLoader {
+    pub const DEFAULT_PATTERN: &'static str = "*.dll";
+
+    // `<dir>` or `<dir>\<pattern>`, e.g. `plugins` or `plugins\*.asi`.
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim().trim_end_matches(['\\', '/']);
+        let (dir, pattern) = match text.rfind(['\\', '/']) {
+            Some(pos) if text[pos + 1..].contains(['*', '?']) => (&text[..pos], &text[pos + 1..]),
+            None if text.contains(['*', '?']) => ("", text),
+            _ => (text, Self::DEFAULT_PATTERN),
+        };
+        if dir.contains(['*', '?']) {
+            bail!("Plugin directory '{}' can't contain wildcards; only the file name can", dir);
+        }
+        Ok(Self {
+            dir: if dir.is_empty() { ".".to_string() } else { dir.to_string() },
+            pattern: pattern.to_string(),
+            init_export: None,
+        })
+    }
+
+    pub fn with_init_export(mut self, export: &str) -> Result<Self> {
+        let export = export.trim();
+        if export.is_empty() || export.starts_with(['@', '#']) {
+            bail!("Plugin init export needs a name, got '{}'", export);
+        }
+        self.init_export = Some(export.to_string());
+        Ok(self)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExportSelector {
+    Name(String),
+    Ordinal(u16),
+    // Wildcard over export names (`*` and `?`); unnamed exports never match.
+    Pattern(String),
+}
+
+impl ExportSelector {
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim();
+        if text.is_empty() {
+            bail!("Empty export selector");
+        }
+        if let Some(num) = text.strip_prefix('@').or_else(|| text.strip_prefix('#')) {
+            let Ok(ordinal) = num.trim().parse::<u16>() else {
+                bail!("Invalid ordinal selector '{}'", text);
+            };
+            return Ok(Self::Ordinal(ordinal));
+        }
+        if text.contains('*') || text.contains('?') {
+            return Ok(Self::Pattern(text.to_string()));
+        }
+        Ok(Self::Name(text.to_string()))
+    }
+
+    pub fn text(&self) -> String {
+        match self {
+            Self::Name(name) | Self::Pattern(name) => name.clone(),
+            Self::Ordinal(ordinal) => format!("@{ordinal}"),
+        }
+    }
+
+    pub fn matches(&self, entry: &ExportEntry) -> bool {
+        match self {
+            Self::Name(name) => entry.name == *name,
+            Self::Ordinal(ordinal) => entry.ordinal == *ordinal,
+            Self::Pattern(pattern) => {
+                !entry.name.starts_with('#') && wildcard_match(pattern, &entry.name)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExportRule {
+    pub selector: ExportSelector,
+    pub strategy: ExportStrategy,
+}
+
+impl ExportRule {
+    // `<name|@ordinal|pattern>=<forward|stub|hook|drop>`
+    pub fn parse(text: &str) -> Result<Self> {
+        let Some((selector, strategy)) = text.rsplit_once('=') else {
+            bail!("Invalid export rule '{}', expected <selector>=<strategy>", text.trim());
+        };
+        let Some(strategy) = ExportStrategy::from_name(strategy) else {
+            bail!(
+                "Unknown export strategy '{}'. Use forward|stub|hook|drop.",
+                strategy.trim()
+            );
+        };
+        Ok(Self {
+            selector: ExportSelector::parse(selector)?,
+            strategy,
+        })
+    }
+}
+
+// One rule per line; blank lines and lines starting with `;` are ignored. `#` starts a comment
+// when followed by whitespace or on a line without `=`, so `#7=drop` stays an ordinal rule.
+pub fn parse_rules(text: &str) -> Result<Vec<ExportRule>> {
+    let mut rules = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        let is_comment = match line.strip_prefix('#') {
+            Some(rest) => rest.starts_with(char::is_whitespace) || !rest.contains('='),
+            None => line.starts_with(';'),
+        };
+        if line.is_empty() || is_comment {
+            continue;
+        }
+        let rule = ExportRule::parse(line)
+            .map_err(|e| anyhow::anyhow!("line {}: {e}", idx + 1))?;
+        rules.push(rule);
+    }
+    Ok(rules)
+}
+
+// Later rules win, so a config file can be refined with extra `--export` flags.
+pub fn strategy_for(
+    rules: &[ExportRule],
+    default: ExportStrategy,
+    entry: &ExportEntry,
+) -> ExportStrategy {
+    rules
+        .iter()
+        .rev()
+        .find(|r| r.selector.matches(entry))
+        .map_or(default, |r| r.strategy)
+}
+
+// Case-sensitive, like export names and exact Name selectors.
+pub fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let n: Vec<char> = name.chars().collect();
+
+    // Iterative matcher with single-star backtracking.
+    let (mut pi, mut ni) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ni < n.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == n[ni]) {
+            pi += 1;
+            ni += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ni));
+            pi += 1;
+        } else if let Some((sp, sn)) = star {
+            pi = sp + 1;
+            ni = sn + 1;
+            star = Some((sp, sn + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
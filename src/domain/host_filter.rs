@@ -0,0 +1,133 @@
+use anyhow::{bail, Result};
+
+// Inclusive FILEVERSION range, packed like VS_FIXEDFILEINFO (MS << 32 | LS).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct VersionRange {
+    pub min: u64,
+    pub max: u64,
+}
+
+impl VersionRange {
+    // `1.2` (any 1.2.x.y), `1.2-1.4.5`, `1.2-` or `-2.0`. Missing parts are 0 on the low end and
+    // 65535 on the high end.
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim();
+        let (min, max) = match text.split_once('-') {
+            Some((min, max)) => (min.trim(), max.trim()),
+            None => (text, text),
+        };
+        if min.is_empty() && max.is_empty() {
+            bail!("Empty version range");
+        }
+        let min = if min.is_empty() { 0 } else { pack_version(min, 0)? };
+        let max = if max.is_empty() { u64::MAX } else { pack_version(max, 0xFFFF)? };
+        if min > max {
+            bail!("Version range '{}' is empty (low end above high end)", text);
+        }
+        Ok(Self { min, max })
+    }
+}
+
+fn pack_version(text: &str, fill: u16) -> Result<u64> {
+    let parts = text.split('.').collect::<Vec<_>>();
+    if parts.len() > 4 {
+        bail!("Invalid version '{}', expected up to 4 parts", text);
+    }
+    let mut packed = 0u64;
+    for idx in 0..4 {
+        let part = match parts.get(idx) {
+            Some(p) => p
+                .trim()
+                .parse::<u16>()
+                .map_err(|_| anyhow::anyhow!("Invalid version '{}'", text))?,
+            None => fill,
+        };
+        packed = (packed << 16) | u64::from(part);
+    }
+    Ok(packed)
+}
+
+pub fn parse_sha256(text: &str) -> Result<[u8; 32]> {
+    let hex = text.trim();
+    if hex.len() != 64 || !hex.is_ascii() {
+        bail!("Invalid SHA-256 '{}', expected 64 hex digits", hex);
+    }
+    let mut digest = [0u8; 32];
+    for (idx, byte) in digest.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[idx * 2..idx * 2 + 2], 16)
+            .map_err(|_| anyhow::anyhow!("Invalid SHA-256 '{}'", hex))?;
+    }
+    Ok(digest)
+}
+
+// Processes the payload may run in. Each non-empty list must match (any entry of it);
+// an empty filter allows every process.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HostFilter {
+    // Executable file names, case-insensitive.
+    pub names: Vec<String>,
+    // Full executable paths, case-insensitive.
+    pub paths: Vec<String>,
+    pub versions: Vec<VersionRange>,
+    // SHA-256 of the executable file.
+    pub sha256: Vec<[u8; 32]>,
+}
+
+impl HostFilter {
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+            && self.paths.is_empty()
+            && self.versions.is_empty()
+            && self.sha256.is_empty()
+    }
+
+    pub fn merge(&mut self, other: HostFilter) {
+        self.names.extend(other.names);
+        self.paths.extend(other.paths);
+        self.versions.extend(other.versions);
+        self.sha256.extend(other.sha256);
+    }
+
+    // `name:game.exe`, `path:C:\Games\game.exe`, `version:1.2-1.4`, `sha256:<hex>`.
+    // Without a prefix, anything containing a slash is a path and the rest is a name.
+    pub fn add(&mut self, text: &str) -> Result<()> {
+        let text = text.trim();
+        let (kind, value) = match text.split_once(':') {
+            Some((kind, value))
+                if matches!(
+                    kind.trim().to_ascii_lowercase().as_str(),
+                    "name" | "path" | "version" | "sha256"
+                ) =>
+            {
+                (kind.trim().to_ascii_lowercase(), value.trim())
+            }
+            _ if text.contains('\\') || text.contains('/') => ("path".to_string(), text),
+            _ => ("name".to_string(), text),
+        };
+        if value.is_empty() {
+            bail!("Empty host rule '{}'", text);
+        }
+        match kind.as_str() {
+            "name" => self.names.push(value.to_string()),
+            "path" => self.paths.push(value.to_string()),
+            "version" => self.versions.push(VersionRange::parse(value)?),
+            _ => self.sha256.push(parse_sha256(value)?),
+        }
+        Ok(())
+    }
+}
+
+// One host rule per line; blank lines and lines starting with `#` or `;` are ignored.
+pub fn parse_host_list(text: &str) -> Result<HostFilter> {
+    let mut filter = HostFilter::default();
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        filter
+            .add(line)
+            .map_err(|e| anyhow::anyhow!("line {}: {e}", idx + 1))?;
+    }
+    Ok(filter)
+}
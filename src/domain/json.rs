@@ -0,0 +1,24 @@
+// Minimal JSON helpers for the machine readable reports; the data we emit is flat
+// enough that pulling in a serializer isn't worth the binary size.
+
+pub fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+pub fn quote_opt(s: Option<&str>) -> String {
+    s.map(quote).unwrap_or_else(|| "null".to_string())
+}
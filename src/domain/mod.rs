@@ -1 +1,7 @@
 pub mod dll;
+pub mod export_diff;
+pub mod export_policy;
+pub mod host_filter;
+pub mod json;
+pub mod prototype;
+pub mod verify;
@@ -0,0 +1,489 @@
+use anyhow::{bail, Result};
+
+// Enough of a C function signature to emit a typed hook that calls through to the original.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FunctionPrototype {
+    pub name: String,
+    pub return_type: String,
+    // Empty means the compiler default (__cdecl on x86).
+    pub calling_convention: String,
+    pub params: Vec<Param>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Param {
+    pub ty: String,
+    pub name: String,
+}
+
+const CALLING_CONVENTIONS: &[&str] = &[
+    "WINAPI",
+    "APIENTRY",
+    "CALLBACK",
+    "NTAPI",
+    "WINAPIV",
+    "STDMETHODCALLTYPE",
+    "__stdcall",
+    "__cdecl",
+    "__fastcall",
+    "__vectorcall",
+    "_stdcall",
+    "_cdecl",
+];
+
+// Linkage/attribute noise that may precede a declaration in SDK-style headers.
+const DECL_NOISE: &[&str] = &[
+    "extern",
+    "static",
+    "inline",
+    "__inline",
+    "__forceinline",
+    "\"C\"",
+    "WINBASEAPI",
+    "WINUSERAPI",
+    "WINADVAPI",
+    "NTSYSAPI",
+    "DECLSPEC_IMPORT",
+    "DECLSPEC_NORETURN",
+];
+
+impl FunctionPrototype {
+    pub fn is_void(&self) -> bool {
+        self.return_type == "void" || self.return_type == "VOID"
+    }
+
+    // `HANDLE WINAPI CreateFileW(LPCWSTR lpFileName, DWORD dwDesiredAccess, ...);`
+    pub fn parse_declaration(text: &str) -> Result<Self> {
+        let text = strip_sal(text.trim().trim_end_matches(';').trim());
+        let (Some(open), Some(close)) = (text.find('('), text.rfind(')')) else {
+            bail!("Not a function declaration: '{}'", text);
+        };
+        if close < open {
+            bail!("Not a function declaration: '{}'", text);
+        }
+
+        let head = remove_declspecs(&text[..open]);
+        let mut tokens = split_type_tokens(&head);
+        let Some(name) = tokens.pop().filter(|t| is_identifier(t)) else {
+            bail!("Missing function name in '{}'", text);
+        };
+
+        let mut calling_convention = String::new();
+        tokens.retain(|t| {
+            if CALLING_CONVENTIONS.contains(&t.as_str()) {
+                calling_convention = t.clone();
+                false
+            } else {
+                !DECL_NOISE.contains(&t.as_str())
+            }
+        });
+        if tokens.is_empty() {
+            bail!("Missing return type for '{}'", name);
+        }
+        let return_type = join_type_tokens(&tokens);
+
+        let params = parse_params(&text[open + 1..close])
+            .map_err(|e| anyhow::anyhow!("{}: {e}", name))?;
+
+        Ok(Self {
+            name,
+            return_type,
+            calling_convention,
+            params,
+        })
+    }
+
+    // Best effort from the export name alone: MSVC C++ mangling or x86 `_Name@N` stdcall.
+    pub fn from_decorated(export_name: &str) -> Option<Self> {
+        if export_name.starts_with('?') {
+            return demangle_msvc(export_name);
+        }
+
+        let (base, bytes) = export_name.rsplit_once('@')?;
+        let bytes: usize = bytes.parse().ok()?;
+        let base = base.strip_prefix('_').unwrap_or(base);
+        if base.is_empty() || !is_identifier(base) || !bytes.is_multiple_of(4) {
+            return None;
+        }
+        // Argument types are unknown; pass the raw stack slots through.
+        let params = (0..bytes / 4)
+            .map(|i| Param {
+                ty: "ULONG_PTR".to_string(),
+                name: format!("a{}", i + 1),
+            })
+            .collect();
+        // ULONGLONG keeps EDX:EAX intact for callers expecting a 64-bit result.
+        Some(Self {
+            name: export_name.to_string(),
+            return_type: "ULONGLONG".to_string(),
+            calling_convention: "__stdcall".to_string(),
+            params,
+        })
+    }
+}
+
+// Every parseable function declaration in a header (or a GUI text box); first one wins.
+pub fn prototypes_from_header(text: &str) -> Vec<FunctionPrototype> {
+    let mut cleaned = String::with_capacity(text.len());
+    let mut in_block = false;
+    for line in text.lines() {
+        let mut line = line;
+        if in_block {
+            match line.find("*/") {
+                Some(end) => {
+                    line = &line[end + 2..];
+                    in_block = false;
+                }
+                None => continue,
+            }
+        }
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+        let mut rest = line;
+        loop {
+            let line_comment = rest.find("//");
+            let block_comment = rest.find("/*");
+            match (line_comment, block_comment) {
+                (Some(l), Some(b)) if l < b => {
+                    cleaned.push_str(&rest[..l]);
+                    break;
+                }
+                (Some(l), None) => {
+                    cleaned.push_str(&rest[..l]);
+                    break;
+                }
+                (_, Some(b)) => {
+                    cleaned.push_str(&rest[..b]);
+                    match rest[b + 2..].find("*/") {
+                        Some(end) => rest = &rest[b + 2 + end + 2..],
+                        None => {
+                            in_block = true;
+                            break;
+                        }
+                    }
+                }
+                (None, None) => {
+                    cleaned.push_str(rest);
+                    break;
+                }
+            }
+        }
+        cleaned.push('\n');
+    }
+
+    let mut found = Vec::new();
+    for stmt in cleaned.split([';', '{', '}']) {
+        let stmt = stmt.trim();
+        if !stmt.contains('(') {
+            continue;
+        }
+        let Ok(proto) = FunctionPrototype::parse_declaration(stmt) else {
+            continue;
+        };
+        if !found.iter().any(|p: &FunctionPrototype| p.name == proto.name) {
+            found.push(proto);
+        }
+    }
+    found
+}
+
+fn parse_params(text: &str) -> Result<Vec<Param>> {
+    let text = text.trim();
+    if text.is_empty() || text == "void" || text == "VOID" {
+        return Ok(Vec::new());
+    }
+
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    for (idx, ch) in text.char_indices() {
+        match ch {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&text[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+
+    let mut params = Vec::with_capacity(parts.len());
+    for (idx, part) in parts.iter().enumerate() {
+        let part = part.trim();
+        if part == "..." {
+            bail!("variadic functions cannot be hooked with a typed stub");
+        }
+        let fallback_name = format!("a{}", idx + 1);
+
+        // Function pointers are pointer sized; keep the hook simple and pass them as PVOID.
+        if part.contains('(') {
+            let name = part
+                .split_once("(*")
+                .and_then(|(_, rest)| rest.split(')').next())
+                .map(|n| n.trim().to_string())
+                .filter(|n| is_identifier(n))
+                .unwrap_or(fallback_name);
+            params.push(Param {
+                ty: "PVOID".to_string(),
+                name,
+            });
+            continue;
+        }
+
+        // Arrays decay to pointers.
+        let (part, is_array) = match part.find('[') {
+            Some(idx) => (part[..idx].trim(), true),
+            None => (part, false),
+        };
+        let mut tokens = split_type_tokens(part);
+        let has_name = tokens.len() >= 2
+            && tokens.last().is_some_and(|t| is_identifier(t) && !is_type_keyword(t));
+        let name = if has_name {
+            tokens.pop().unwrap_or_default()
+        } else {
+            fallback_name
+        };
+        if tokens.is_empty() {
+            bail!("missing type for parameter {}", idx + 1);
+        }
+        let mut ty = join_type_tokens(&tokens);
+        if is_array {
+            ty.push_str(" *");
+        }
+        params.push(Param { ty, name });
+    }
+    Ok(params)
+}
+
+// Splits `const char*name` into `const`, `char`, `*`, `name`.
+fn split_type_tokens(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    for word in text.split_whitespace() {
+        let mut current = String::new();
+        for ch in word.chars() {
+            if ch == '*' || ch == '&' {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+                tokens.push(ch.to_string());
+            } else {
+                current.push(ch);
+            }
+        }
+        if !current.is_empty() {
+            tokens.push(current);
+        }
+    }
+    tokens
+}
+
+fn join_type_tokens(tokens: &[String]) -> String {
+    let mut out = String::new();
+    for token in tokens {
+        // Keep consecutive stars together: `char **`.
+        let stacked_star = token == "*" && out.ends_with('*');
+        if !out.is_empty() && !stacked_star {
+            out.push(' ');
+        }
+        out.push_str(token);
+    }
+    out
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn is_type_keyword(s: &str) -> bool {
+    matches!(
+        s,
+        "int" | "char" | "short" | "long" | "float" | "double" | "void" | "unsigned" | "signed"
+            | "const" | "volatile" | "struct" | "enum" | "union" | "bool"
+    )
+}
+
+// Drops SAL annotations such as `_In_`, `_Out_writes_(n)` and `__in_opt`.
+fn strip_sal(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let at_word_start = i == 0 || !(chars[i - 1].is_ascii_alphanumeric() || chars[i - 1] == '_');
+        if at_word_start && chars[i] == '_' {
+            let mut j = i;
+            while j < chars.len() && (chars[j].is_ascii_alphanumeric() || chars[j] == '_') {
+                j += 1;
+            }
+            let word: String = chars[i..j].iter().collect();
+            let is_sal = (word.len() > 2
+                && word.ends_with('_')
+                && word[1..2].chars().all(|c| c.is_ascii_uppercase()))
+                || (word.starts_with("__in") && !word.starts_with("__int"))
+                || word.starts_with("__out")
+                || word.starts_with("__deref");
+            if is_sal {
+                i = j;
+                // Skip a balanced argument list: `_In_reads_(count)`.
+                if i < chars.len() && chars[i] == '(' {
+                    let mut depth = 0;
+                    while i < chars.len() {
+                        match chars[i] {
+                            '(' => depth += 1,
+                            ')' => {
+                                depth -= 1;
+                                if depth == 0 {
+                                    i += 1;
+                                    break;
+                                }
+                            }
+                            _ => {}
+                        }
+                        i += 1;
+                    }
+                }
+                continue;
+            }
+        }
+        out.push(chars[i]);
+        i += 1;
+    }
+    out
+}
+
+fn remove_declspecs(text: &str) -> String {
+    let mut out = text.to_string();
+    while let Some(start) = out.find("__declspec(") {
+        let Some(len) = out[start..].find(')') else {
+            break;
+        };
+        out.replace_range(start..start + len + 1, " ");
+    }
+    out
+}
+
+// Global functions only (`?Name@@Y<cc><ret><params>Z`); anything exotic yields None.
+fn demangle_msvc(symbol: &str) -> Option<FunctionPrototype> {
+    let rest = symbol.strip_prefix('?')?;
+    let (name, sig) = rest.split_once("@@")?;
+    if !is_identifier(name) {
+        return None;
+    }
+    let sig = sig.strip_prefix('Y')?;
+    let mut chars = sig.chars().peekable();
+    let calling_convention = match chars.next()? {
+        'A' => "__cdecl",
+        'G' => "__stdcall",
+        'I' => "__fastcall",
+        'Q' => "__vectorcall",
+        _ => return None,
+    };
+
+    let return_type = demangle_type(&mut chars, &[])?;
+
+    // Digits refer back to earlier parameter types that took more than one character.
+    let mut backrefs: Vec<String> = Vec::new();
+    let mut params = Vec::new();
+    if chars.peek() == Some(&'X') {
+        chars.next();
+    } else {
+        loop {
+            match chars.peek() {
+                Some('@') | Some('Z') => break,
+                None => return None,
+                _ => {}
+            }
+            let before = chars.clone().count();
+            let ty = demangle_type(&mut chars, &backrefs)?;
+            if before - chars.clone().count() > 1 && backrefs.len() < 10 {
+                backrefs.push(ty.clone());
+            }
+            params.push(Param {
+                ty,
+                name: format!("a{}", params.len() + 1),
+            });
+        }
+    }
+
+    Some(FunctionPrototype {
+        name: symbol.to_string(),
+        return_type,
+        calling_convention: calling_convention.to_string(),
+        params,
+    })
+}
+
+fn demangle_type(
+    chars: &mut std::iter::Peekable<std::str::Chars>,
+    backrefs: &[String],
+) -> Option<String> {
+    let c = chars.next()?;
+    if let Some(idx) = c.to_digit(10) {
+        return backrefs.get(idx as usize).cloned();
+    }
+    let ty = match c {
+        'X' => "void".to_string(),
+        'D' => "char".to_string(),
+        'C' => "signed char".to_string(),
+        'E' => "unsigned char".to_string(),
+        'F' => "short".to_string(),
+        'G' => "unsigned short".to_string(),
+        'H' => "int".to_string(),
+        'I' => "unsigned int".to_string(),
+        'J' => "long".to_string(),
+        'K' => "unsigned long".to_string(),
+        'M' => "float".to_string(),
+        'N' => "double".to_string(),
+        '_' => match chars.next()? {
+            'J' => "__int64".to_string(),
+            'K' => "unsigned __int64".to_string(),
+            'N' => "bool".to_string(),
+            'W' => "wchar_t".to_string(),
+            _ => return None,
+        },
+        // Pointers and references: P/Q/A + optional E (64-bit) + cv qualifier.
+        'P' | 'Q' | 'A' | 'R' => {
+            if chars.peek() == Some(&'E') {
+                chars.next();
+            }
+            let is_const = match chars.next()? {
+                'A' => false,
+                'B' => true,
+                'C' | 'D' => true,
+                _ => return None,
+            };
+            let pointee = match chars.peek().copied() {
+                // Class/struct/union/enum pointees collapse to void.
+                Some(kind @ ('V' | 'U' | 'T' | 'W')) => {
+                    chars.next();
+                    if kind == 'W' {
+                        // Enums carry an underlying-type digit.
+                        chars.next();
+                    }
+                    // Qualified name, terminated by `@@`.
+                    while let Some(ch) = chars.next() {
+                        if ch == '@' && chars.peek() == Some(&'@') {
+                            chars.next();
+                            break;
+                        }
+                    }
+                    "void".to_string()
+                }
+                _ => demangle_type(chars, backrefs)?,
+            };
+            if is_const {
+                format!("const {pointee} *")
+            } else {
+                format!("{pointee} *")
+            }
+        }
+        _ => return None,
+    };
+    Some(ty)
+}
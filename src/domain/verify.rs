@@ -0,0 +1,223 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use crate::dll::{DllExports, ExportEntry};
+use crate::domain::json;
+
+#[derive(Debug, Clone, Default)]
+pub struct VerifyOptions {
+    // A proxy built against the dynamic CRT imports vcruntime/ucrt; opt in when that's expected.
+    pub allow_crt: bool,
+    // DLL names the proxy must never import statically (the original it forwards to).
+    pub origin_names: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyIssue {
+    pub kind: &'static str,
+    pub export: Option<String>,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct VerifyReport {
+    pub checked: usize,
+    pub issues: Vec<VerifyIssue>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    fn push(&mut self, kind: &'static str, export: Option<&str>, detail: String) {
+        self.issues.push(VerifyIssue {
+            kind,
+            export: export.map(|s| s.to_string()),
+            detail,
+        });
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        for issue in &self.issues {
+            match &issue.export {
+                Some(name) => {
+                    let _ = writeln!(out, "[{}] {}: {}", issue.kind, name, issue.detail);
+                }
+                None => {
+                    let _ = writeln!(out, "[{}] {}", issue.kind, issue.detail);
+                }
+            }
+        }
+        if self.issues.is_empty() {
+            let _ = writeln!(out, "OK: {} export(s) match the original", self.checked);
+        } else {
+            let _ = writeln!(
+                out,
+                "FAILED: {} issue(s) across {} checked export(s)",
+                self.issues.len(),
+                self.checked
+            );
+        }
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        let _ = write!(
+            out,
+            "{{\n  \"ok\": {},\n  \"checked\": {},\n  \"issues\": [",
+            self.is_ok(),
+            self.checked
+        );
+        for (idx, issue) in self.issues.iter().enumerate() {
+            out.push_str(if idx == 0 { "\n    " } else { ",\n    " });
+            let _ = write!(
+                out,
+                "{{\"kind\": {}, \"export\": {}, \"detail\": {}}}",
+                json::quote(issue.kind),
+                json::quote_opt(issue.export.as_deref()),
+                json::quote(&issue.detail)
+            );
+        }
+        if !self.issues.is_empty() {
+            out.push_str("\n  ");
+        }
+        out.push_str("]\n}\n");
+        out
+    }
+}
+
+fn is_noname(e: &ExportEntry) -> bool {
+    e.name.starts_with('#')
+}
+
+fn is_crt_import(lib: &str) -> bool {
+    let lib = lib.to_ascii_lowercase();
+    [
+        "msvcr",
+        "msvcp",
+        "vcruntime",
+        "ucrtbase",
+        "api-ms-win-crt-",
+        "libgcc_s",
+        "libstdc++",
+        "libwinpthread",
+    ]
+    .iter()
+    .any(|prefix| lib.starts_with(prefix))
+}
+
+pub fn verify_proxy(
+    proxy: &DllExports,
+    original: &DllExports,
+    options: &VerifyOptions,
+) -> VerifyReport {
+    let mut report = VerifyReport {
+        checked: original.exports.len(),
+        ..Default::default()
+    };
+
+    if !proxy.arch.is_empty() && !original.arch.is_empty() && proxy.arch != original.arch {
+        report.push(
+            "arch",
+            None,
+            format!("proxy is {} but the original is {}", proxy.arch, original.arch),
+        );
+    }
+
+    let proxy_by_name: HashMap<&str, &ExportEntry> = proxy
+        .exports
+        .iter()
+        .filter(|e| !is_noname(e))
+        .map(|e| (e.name.as_str(), e))
+        .collect();
+    let proxy_by_ordinal: HashMap<u16, &ExportEntry> =
+        proxy.exports.iter().map(|e| (e.ordinal, e)).collect();
+
+    // Proxy ordinals already matched to an original export, so they aren't reported as extra too.
+    let mut matched = HashSet::new();
+    for orig in &original.exports {
+        let by_ordinal = proxy_by_ordinal.get(&orig.ordinal).copied();
+        let found = if is_noname(orig) {
+            by_ordinal
+        } else {
+            // A NONAME proxy export at the same ordinal still stands in for a named original.
+            proxy_by_name
+                .get(orig.name.as_str())
+                .copied()
+                .or(by_ordinal.filter(|p| is_noname(p)))
+        };
+        let Some(p) = found else {
+            report.push(
+                "missing",
+                Some(&orig.name),
+                format!("not exported by the proxy (ordinal {})", orig.ordinal),
+            );
+            continue;
+        };
+        matched.insert(p.ordinal);
+
+        if p.ordinal != orig.ordinal {
+            report.push(
+                "ordinal",
+                Some(&orig.name),
+                format!("proxy ordinal {} != original ordinal {}", p.ordinal, orig.ordinal),
+            );
+        }
+        if is_noname(orig) != is_noname(p) {
+            let detail = if is_noname(orig) {
+                format!("original is NONAME but the proxy exports it as '{}'", p.name)
+            } else {
+                "original is named but the proxy exports it NONAME".to_string()
+            };
+            report.push("noname", Some(&orig.name), detail);
+        }
+        // A forwarded proxy export resolves to the original's symbol, so it inherits the DATA kind.
+        if p.forwarder.is_none() && orig.forwarder.is_none() && p.is_data != orig.is_data {
+            let detail = if orig.is_data {
+                "original is a DATA export but the proxy exports code".to_string()
+            } else {
+                "original exports code but the proxy export is DATA".to_string()
+            };
+            report.push("data", Some(&orig.name), detail);
+        }
+    }
+
+    let orig_names: HashMap<&str, &ExportEntry> = original
+        .exports
+        .iter()
+        .filter(|e| !is_noname(e))
+        .map(|e| (e.name.as_str(), e))
+        .collect();
+    for p in proxy.exports.iter().filter(|p| !matched.contains(&p.ordinal)) {
+        let known = if is_noname(p) {
+            original.exports.iter().any(|e| e.ordinal == p.ordinal)
+        } else {
+            orig_names.contains_key(p.name.as_str())
+        };
+        if !known {
+            report.push(
+                "extra",
+                Some(&p.name),
+                format!("exported by the proxy (ordinal {}) but not by the original", p.ordinal),
+            );
+        }
+    }
+
+    for lib in &proxy.imports {
+        if !options.allow_crt && is_crt_import(lib) {
+            report.push("import", None, format!("unexpected C runtime dependency: {lib}"));
+        }
+        if options
+            .origin_names
+            .iter()
+            .any(|n| n.eq_ignore_ascii_case(lib))
+        {
+            report.push("import", None, format!("origin DLL is imported statically: {lib}"));
+        }
+    }
+
+    report
+}
@@ -1,14 +1,29 @@
 #![allow(dead_code)]
 
 use crate::dll::ExportEntry;
-use std::collections::HashSet;
+use crate::export_policy::{self, ExportRule};
+use crate::host_filter::HostFilter;
+use crate::domain::json;
+use crate::prototype::FunctionPrototype;
+pub use crate::export_policy::{ExportStrategy, FailurePolicy, PayloadEntry, PluginLoader};
+use std::collections::{BTreeSet, HashSet};
 use std::fmt::Write;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum OriginLoadModeOwned {
     SystemDir,
     SameDir { original_name: String },
     CustomPath { path: String },
+    // Path read at load time from an environment variable (default `<STEM>_ORIGIN`).
+    Env { var: Option<String> },
+    // Path read at load time from an INI/JSON file beside the proxy (default `<stem>.origin.ini`).
+    Config { file: Option<String> },
+    // Bare-name search over System32, the Windows directory and PATH, never the proxy's own directory.
+    Search { name: Option<String> },
+    // LoadLibraryEx with LOAD_LIBRARY_SEARCH_* flags, after registering `dirs` with AddDllDirectory.
+    LoadEx { flags: u32, dirs: Vec<String> },
+    // Candidates tried in order until one loads; never nested.
+    Chain(Vec<OriginLoadModeOwned>),
 }
 
 impl OriginLoadModeOwned {
@@ -24,6 +39,88 @@ impl OriginLoadModeOwned {
         Self::CustomPath { path }
     }
 
+    pub fn env(var: Option<String>) -> Self {
+        Self::Env { var }
+    }
+
+    pub fn config(file: Option<String>) -> Self {
+        Self::Config { file }
+    }
+
+    pub fn search(name: Option<String>) -> Self {
+        Self::Search { name }
+    }
+
+    // `flags` are LOAD_LIBRARY_SEARCH_* bits; directories imply `user-dirs`.
+    pub fn load_ex(flags: u32, dirs: Vec<String>) -> Self {
+        let flags = if dirs.is_empty() {
+            flags
+        } else {
+            flags | LOAD_LIBRARY_SEARCH_USER_DIRS
+        };
+        Self::LoadEx { flags, dirs }
+    }
+
+    pub fn is_runtime(&self) -> bool {
+        matches!(self, Self::Env { .. } | Self::Config { .. })
+    }
+
+    // Runtime sources (env/config) always get a compiled-in fallback; the system directory if none was given.
+    pub fn chain(candidates: Vec<OriginLoadModeOwned>) -> Self {
+        let mut flat = Vec::with_capacity(candidates.len());
+        for candidate in candidates {
+            match candidate {
+                Self::Chain(inner) => flat.extend(inner),
+                other => flat.push(other),
+            }
+        }
+        if !flat.is_empty() && flat.iter().all(Self::is_runtime) {
+            flat.push(Self::SystemDir);
+        }
+        if flat.len() == 1 {
+            flat.remove(0)
+        } else {
+            Self::Chain(flat)
+        }
+    }
+
+    // `system`, `samedir[:<name>]`, `custom:<path>`, `env[:<VAR>]`, `config[:<file>]`, `search[:<name>]`
+    // or `loadex[:<flag>,<flag>][;<dir>;<dir>]`; `samedir` alone uses `default_original_name`.
+    pub fn parse(spec: &str, default_original_name: &str) -> anyhow::Result<Self> {
+        let spec = spec.trim();
+        let (kind, value) = match spec.split_once(':') {
+            Some((kind, value)) => (kind, Some(value.trim()).filter(|v| !v.is_empty())),
+            None => (spec, None),
+        };
+        match (kind.trim().to_ascii_lowercase().as_str(), value) {
+            ("system" | "systemdir" | "sys", None) => Ok(Self::system_dir()),
+            ("samedir" | "same" | "local", name) => Ok(Self::same_dir(
+                name.unwrap_or(default_original_name).to_string(),
+            )),
+            ("custom" | "path", Some(path)) => Ok(Self::custom_path(path.to_string())),
+            ("custom" | "path", None) => {
+                anyhow::bail!("Origin mode custom requires a path (custom:<path>)")
+            }
+            ("env", var) => Ok(Self::env(var.map(str::to_string))),
+            ("config" | "ini" | "json", file) => Ok(Self::config(file.map(str::to_string))),
+            ("search", name) => Ok(Self::search(name.map(str::to_string))),
+            ("loadex", value) => {
+                let mut parts = value.unwrap_or("").split(';');
+                let flags = parse_search_flags(parts.next().unwrap_or(""))?;
+                let dirs = parts
+                    .map(str::trim)
+                    .filter(|d| !d.is_empty())
+                    .map(str::to_string)
+                    .collect::<Vec<_>>();
+                Ok(Self::load_ex(flags, dirs))
+            }
+            _ => anyhow::bail!(
+                "Unknown origin mode '{}'. Use system|samedir[:<name>]|custom:<path>|env[:<VAR>]|config[:<file>]|search[:<name>]|loadex[:<flags>][;<dir>].",
+                spec
+            ),
+        }
+    }
+
     pub fn as_borrowed(&self) -> OriginLoadMode<'_> {
         match self {
             Self::SystemDir => OriginLoadMode::SystemDir,
@@ -31,6 +128,11 @@ impl OriginLoadModeOwned {
                 original_name: original_name.as_str(),
             },
             Self::CustomPath { path } => OriginLoadMode::CustomPath { path: path.as_str() },
+            Self::Env { var } => OriginLoadMode::Env { var: var.as_deref() },
+            Self::Config { file } => OriginLoadMode::Config { file: file.as_deref() },
+            Self::Search { name } => OriginLoadMode::Search { name: name.as_deref() },
+            Self::LoadEx { flags, dirs } => OriginLoadMode::LoadEx { flags: *flags, dirs },
+            Self::Chain(candidates) => OriginLoadMode::Chain(candidates),
         }
     }
 }
@@ -40,6 +142,182 @@ pub enum OriginLoadMode<'a> {
     SystemDir,
     SameDir { original_name: &'a str },
     CustomPath { path: &'a str },
+    Env { var: Option<&'a str> },
+    Config { file: Option<&'a str> },
+    Search { name: Option<&'a str> },
+    LoadEx { flags: u32, dirs: &'a [String] },
+    Chain(&'a [OriginLoadModeOwned]),
+}
+
+impl OriginLoadMode<'_> {
+    pub fn to_owned_mode(&self) -> OriginLoadModeOwned {
+        match *self {
+            Self::SystemDir => OriginLoadModeOwned::SystemDir,
+            Self::SameDir { original_name } => OriginLoadModeOwned::same_dir(original_name.to_string()),
+            Self::CustomPath { path } => OriginLoadModeOwned::custom_path(path.to_string()),
+            Self::Env { var } => OriginLoadModeOwned::env(var.map(str::to_string)),
+            Self::Config { file } => OriginLoadModeOwned::config(file.map(str::to_string)),
+            Self::Search { name } => OriginLoadModeOwned::search(name.map(str::to_string)),
+            Self::LoadEx { flags, dirs } => OriginLoadModeOwned::load_ex(flags, dirs.to_vec()),
+            Self::Chain(candidates) => OriginLoadModeOwned::Chain(candidates.to_vec()),
+        }
+    }
+
+    // The locations `load_original_module` tries, in order, one `origin_candidate_<n>` each.
+    fn candidate_list(&self) -> Vec<OriginLoadModeOwned> {
+        match *self {
+            Self::Chain(candidates) => candidates.to_vec(),
+            Self::Env { .. } | Self::Config { .. } => {
+                match OriginLoadModeOwned::chain(vec![self.to_owned_mode()]) {
+                    OriginLoadModeOwned::Chain(candidates) => candidates,
+                    other => vec![other],
+                }
+            }
+            _ => vec![self.to_owned_mode()],
+        }
+    }
+}
+
+pub const LOAD_LIBRARY_SEARCH_DLL_LOAD_DIR: u32 = 0x0000_0100;
+pub const LOAD_LIBRARY_SEARCH_APPLICATION_DIR: u32 = 0x0000_0200;
+pub const LOAD_LIBRARY_SEARCH_USER_DIRS: u32 = 0x0000_0400;
+pub const LOAD_LIBRARY_SEARCH_SYSTEM32: u32 = 0x0000_0800;
+pub const LOAD_LIBRARY_SEARCH_DEFAULT_DIRS: u32 = 0x0000_1000;
+
+// (CLI name, bit, C macro)
+const SEARCH_FLAGS: &[(&str, u32, &str)] = &[
+    ("dll-load-dir", LOAD_LIBRARY_SEARCH_DLL_LOAD_DIR, "LOAD_LIBRARY_SEARCH_DLL_LOAD_DIR"),
+    ("application-dir", LOAD_LIBRARY_SEARCH_APPLICATION_DIR, "LOAD_LIBRARY_SEARCH_APPLICATION_DIR"),
+    ("user-dirs", LOAD_LIBRARY_SEARCH_USER_DIRS, "LOAD_LIBRARY_SEARCH_USER_DIRS"),
+    ("system32", LOAD_LIBRARY_SEARCH_SYSTEM32, "LOAD_LIBRARY_SEARCH_SYSTEM32"),
+    ("default-dirs", LOAD_LIBRARY_SEARCH_DEFAULT_DIRS, "LOAD_LIBRARY_SEARCH_DEFAULT_DIRS"),
+];
+
+// Comma-separated flag names; empty means `dll-load-dir,default-dirs`.
+pub fn parse_search_flags(text: &str) -> anyhow::Result<u32> {
+    let mut flags = 0;
+    for name in text.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        let Some((_, bit, _)) = SEARCH_FLAGS
+            .iter()
+            .find(|(flag, _, _)| flag.eq_ignore_ascii_case(name))
+        else {
+            anyhow::bail!(
+                "Unknown search flag '{}'. Use dll-load-dir|application-dir|user-dirs|system32|default-dirs.",
+                name
+            );
+        };
+        flags |= bit;
+    }
+    if flags == 0 {
+        flags = LOAD_LIBRARY_SEARCH_DLL_LOAD_DIR | LOAD_LIBRARY_SEARCH_DEFAULT_DIRS;
+    }
+    Ok(flags)
+}
+
+fn search_flags_expr(flags: u32) -> String {
+    SEARCH_FLAGS
+        .iter()
+        .filter(|(_, bit, _)| flags & bit != 0)
+        .map(|(_, _, name)| *name)
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+pub fn default_origin_env_var(base_name: &str) -> String {
+    format!("{}_ORIGIN", sanitize_identifier(base_name).to_ascii_uppercase())
+}
+
+pub fn default_origin_config_file(base_name: &str) -> String {
+    format!("{base_name}.origin.ini")
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ProxyOptions {
+    pub default_strategy: ExportStrategy,
+    // Route every stub through a thunk that logs the call before jumping to the original.
+    pub trace: bool,
+    // Resolve each export on its first call instead of all of them in DllMain.
+    pub lazy: bool,
+    // Keep DllMain to bookkeeping; load the original and start the payload on first export use.
+    pub deferred_init: bool,
+    pub failure_policy: FailurePolicy,
+    pub payload: PayloadEntry,
+    // Processes the payload and hooks are active in; empty means every process.
+    pub host_filter: HostFilter,
+    // Loaded by payload_main before the user's code.
+    pub plugins: Option<PluginLoader>,
+    // Catch faults in the payload and typed-hook callbacks, log them and switch both off.
+    pub crash_guard: bool,
+    // Emit .cpp sources: explicit extern "C" linkage, constexpr tables, a namespaced header API.
+    pub cpp: bool,
+    // kernel32 only: no C runtime, no user32 import, DllMain as the entry point.
+    pub minimal_deps: bool,
+    pub export_rules: Vec<ExportRule>,
+    // Signatures for hooked exports (manual entry or a header); decorations are the fallback.
+    pub hook_prototypes: Vec<FunctionPrototype>,
+}
+
+impl ProxyOptions {
+    pub fn forward_only() -> Self {
+        Self {
+            default_strategy: ExportStrategy::Forward,
+            ..Default::default()
+        }
+    }
+
+    // Pointers start at resolver entries, which either resolve one export (lazy) or run the
+    // deferred init first.
+    pub fn uses_resolvers(&self) -> bool {
+        self.lazy || self.deferred_init
+    }
+
+    // Options that only make sense against a particular export table or payload entry.
+    pub fn check_exports(&self, exports: &[ExportEntry]) -> anyhow::Result<()> {
+        if self.cpp && self.minimal_deps {
+            anyhow::bail!("C++ sources rely on the C runtime for static initialization; they can't be combined with minimal dependencies");
+        }
+        if self.crash_guard && self.minimal_deps {
+            anyhow::bail!("The crash guard needs the C runtime's SEH support under MSVC; it can't be combined with minimal dependencies");
+        }
+        if self.plugins.is_some() {
+            match self.payload {
+                PayloadEntry::None => {
+                    anyhow::bail!("The plugin loader runs from the payload; it needs a payload entry other than none")
+                }
+                PayloadEntry::Sync if !self.deferred_init => anyhow::bail!(
+                    "A sync payload runs under loader lock, where loading plugins can deadlock; use another payload entry or deferred init"
+                ),
+                _ => {}
+            }
+        }
+        if let PayloadEntry::FirstCall(selector) = &self.payload {
+            let Some(entry) = exports.iter().find(|e| selector.matches(e)) else {
+                anyhow::bail!("Payload export '{}' is not exported by the DLL", selector.text());
+            };
+            if entry.is_data {
+                anyhow::bail!("Payload export '{}' is a DATA export; it is never called", selector.text());
+            }
+            if !matches!(self.strategy_for(entry), ExportStrategy::Stub | ExportStrategy::Hook) {
+                anyhow::bail!(
+                    "Payload export '{}' must be a stub or hook; forwarded and dropped exports never reach the proxy",
+                    selector.text()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn strategy_for(&self, entry: &ExportEntry) -> ExportStrategy {
+        export_policy::strategy_for(&self.export_rules, self.default_strategy, entry)
+    }
+
+    pub fn prototype_for(&self, entry: &ExportEntry) -> Option<FunctionPrototype> {
+        self.hook_prototypes
+            .iter()
+            .find(|p| p.name == entry.name)
+            .cloned()
+            .or_else(|| FunctionPrototype::from_decorated(&entry.name))
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -59,6 +337,7 @@ pub struct VsTemplateContext<'a> {
     pub origin_load_mode: OriginLoadMode<'a>,
     pub exports: &'a [ExportEntry],
     pub guids: VsGuids<'a>,
+    pub options: ProxyOptions,
 }
 
 #[derive(Clone, Debug)]
@@ -68,6 +347,161 @@ struct PreparedExport<'a> {
     forwarder: Option<&'a str>,
     label: String,
     stub: String,
+    strategy: ExportStrategy,
+    forward_target: Option<ForwardTarget>,
+    // Set for hooks with a known signature; those are exported from C instead of the jump table.
+    prototype: Option<FunctionPrototype>,
+    table_index: Option<usize>,
+    // First call starts the payload (`PayloadEntry::FirstCall`).
+    payload_trigger: bool,
+    // Pointer starts at AheadLibEx_Resolve_<stub> instead of being filled by init_forwarders.
+    resolver: bool,
+    x86_cleanup: X86Cleanup,
+}
+
+// Where a linker-forwarded export points. The original's module name depends on the architecture,
+// so it is only filled in when rendering.
+#[derive(Clone, Debug)]
+enum ForwardTarget {
+    // `<export>` or `#<ordinal>` in the original DLL.
+    Origin(String),
+    // `<module>.<export>` in some other DLL the original forwards to.
+    Other(String),
+}
+
+// Who pops an x86 export's arguments, so a missing export's fallback returns with the stack the
+// caller expects.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum X86Cleanup {
+    // cdecl, or nothing on the stack.
+    Caller,
+    // stdcall: the callee pops this many bytes.
+    Stdcall(u32),
+    // fastcall: the callee pops this many bytes, the ones that didn't go in ECX/EDX.
+    Fastcall(u32),
+    // Undecorated and no prototype.
+    Unknown,
+}
+
+impl X86Cleanup {
+    // `@Name@N` is fastcall with N argument bytes; otherwise the prototype (given, demangled or
+    // from `_Name@N`) says.
+    fn of(entry: &ExportEntry, options: &ProxyOptions) -> Self {
+        if let Some((base, bytes)) = entry.name.strip_prefix('@').and_then(|n| n.rsplit_once('@')) {
+            if let (false, Ok(bytes)) = (base.is_empty(), bytes.parse::<u32>()) {
+                // Assumes the first two arguments fit in registers.
+                return Self::callee(Self::Fastcall, bytes.saturating_sub(8));
+            }
+        }
+        let Some(proto) = options.prototype_for(entry) else {
+            return Self::Unknown;
+        };
+        let sizes = proto.params.iter().map(|p| x86_arg_size(&p.ty));
+        match proto.calling_convention.as_str() {
+            "" | "__cdecl" | "_cdecl" | "WINAPIV" => Self::Caller,
+            "__fastcall" => {
+                let mut registers = 2;
+                let bytes = sizes
+                    .filter(|&size| {
+                        let in_register = size == 4 && registers > 0;
+                        if in_register {
+                            registers -= 1;
+                        }
+                        !in_register
+                    })
+                    .sum();
+                Self::callee(Self::Fastcall, bytes)
+            }
+            // Vector arguments travel in XMM registers; the stack share isn't worth guessing.
+            "__vectorcall" => Self::Unknown,
+            _ => Self::callee(Self::Stdcall, sizes.sum()),
+        }
+    }
+
+    fn callee(kind: fn(u32) -> Self, bytes: u32) -> Self {
+        if bytes == 0 {
+            Self::Caller
+        } else {
+            kind(bytes)
+        }
+    }
+
+    // The C fallback with this cleanup; x64 has only the one.
+    fn fallback(self, is_x64: bool) -> String {
+        match (is_x64, self) {
+            (true, _) | (false, Self::Caller) => "missing_export".to_string(),
+            (false, Self::Stdcall(bytes)) => format!("missing_export_stdcall_{bytes}"),
+            (false, Self::Fastcall(bytes)) => format!("missing_export_fastcall_{bytes}"),
+            (false, Self::Unknown) => "missing_export_unknown".to_string(),
+        }
+    }
+}
+
+// Stack bytes an x86 argument takes: 8 for 64-bit scalars, 4 for pointers and everything narrower.
+fn x86_arg_size(ty: &str) -> u32 {
+    if ty.contains('*') || ty.contains('&') {
+        return 4;
+    }
+    let base = ty
+        .split_whitespace()
+        .filter(|t| *t != "const" && *t != "volatile")
+        .collect::<Vec<_>>()
+        .join(" ");
+    match base.as_str() {
+        "double" | "long double" | "__int64" | "signed __int64" | "unsigned __int64" | "long long"
+        | "unsigned long long" | "int64_t" | "uint64_t" | "LONGLONG" | "ULONGLONG" | "DWORDLONG"
+        | "DWORD64" | "INT64" | "UINT64" | "LONG64" | "ULONG64" | "LARGE_INTEGER" | "ULARGE_INTEGER"
+        | "FILETIME" => 8,
+        _ => 4,
+    }
+}
+
+impl PreparedExport<'_> {
+    // `<module>.<export>` for linker-forwarded exports, given origin_forward_module.
+    fn forward_to(&self, origin_module: Option<&str>) -> Option<String> {
+        match self.forward_target.as_ref()? {
+            ForwardTarget::Origin(export) => {
+                let module = origin_module.expect("rejected by check_origin_forwarders");
+                Some(format!("{module}.{export}"))
+            }
+            ForwardTarget::Other(target) => Some(target.clone()),
+        }
+    }
+
+    fn is_noname(&self) -> bool {
+        self.label.starts_with("Noname")
+    }
+
+    // Anything that goes through the jump table (untyped hooks included).
+    fn is_stub(&self) -> bool {
+        match self.strategy {
+            ExportStrategy::Stub => true,
+            ExportStrategy::Hook => self.prototype.is_none(),
+            _ => false,
+        }
+    }
+
+    // Needs pfnAheadLibEx_<stub> resolved from the original at runtime.
+    fn needs_pointer(&self) -> bool {
+        matches!(self.strategy, ExportStrategy::Stub | ExportStrategy::Hook)
+    }
+
+    fn is_hook(&self) -> bool {
+        self.strategy == ExportStrategy::Hook && self.prototype.is_none()
+    }
+
+    fn is_typed_hook(&self) -> bool {
+        self.strategy == ExportStrategy::Hook && self.prototype.is_some()
+    }
+
+    // Symbol the export entry points at (pragma and .def), ignoring x86 underscore decoration.
+    fn internal_symbol(&self) -> String {
+        if self.is_typed_hook() {
+            format!("AheadLibEx_Hook_{}", self.stub)
+        } else {
+            format!("AheadLibEx_{}", self.stub)
+        }
+    }
 }
 
 const TPL_SOLUTION: &str = include_str!(concat!(
@@ -94,6 +528,106 @@ const TPL_C_X64: &str = include_str!(concat!(
     env!("CARGO_MANIFEST_DIR"),
     "/templates/common/proxy_x64.c.tpl"
 ));
+const TPL_ORIGIN_RUNTIME: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/common/origin_runtime.c.tpl"
+));
+const TPL_ORIGIN_PATH: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/common/origin_path.c.tpl"
+));
+const TPL_ORIGIN_LOAD: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/common/origin_load.c.tpl"
+));
+const TPL_ORIGIN_INI: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/common/origin_ini.c.tpl"
+));
+const TPL_ORIGIN_PROBE: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/common/origin_probe.c.tpl"
+));
+const TPL_ORIGIN_RESOLVE: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/common/origin_resolve.c.tpl"
+));
+const TPL_ORIGIN_JSON: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/common/origin_json.c.tpl"
+));
+const TPL_ORIGIN_SEARCH: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/common/origin_search.c.tpl"
+));
+const TPL_ORIGIN_DLL_DIRS: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/common/origin_dll_dirs.c.tpl"
+));
+const TPL_MINIMAL_RUNTIME: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/common/minimal_runtime.c.tpl"
+));
+const TPL_FAILURE_LOG: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/common/failure_log.c.tpl"
+));
+const TPL_HOST_FILTER: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/common/host_filter.c.tpl"
+));
+const TPL_CRASH_GUARD: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/common/crash_guard.c.tpl"
+));
+const TPL_PROXY_H: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/common/proxy.h.tpl"
+));
+const TPL_PAYLOAD_C: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/common/payload.c.tpl"
+));
+const TPL_PLUGIN_LOADER: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/common/plugin_loader.c.tpl"
+));
+const TPL_HOST_VERSION: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/common/host_version.c.tpl"
+));
+const TPL_HOST_SHA256: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/common/host_sha256.c.tpl"
+));
+const TPL_DEFERRED_INIT: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/common/deferred_init.c.tpl"
+));
+const TPL_LAZY_RUNTIME: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/common/lazy_runtime.c.tpl"
+));
+const TPL_TRACE_RUNTIME: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/common/trace_runtime.c.tpl"
+));
+const TPL_CALL_THUNK_X86: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/common/call_thunk_x86.asm.tpl"
+));
+const TPL_CALL_THUNK_X86_GAS: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/common/call_thunk_x86.S.tpl"
+));
+const TPL_CALL_THUNK_X64: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/common/call_thunk_x64.asm.tpl"
+));
+const TPL_CALL_THUNK_X64_GAS: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/common/call_thunk_x64.S.tpl"
+));
 const TPL_ASM_X86: &str = include_str!(concat!(
     env!("CARGO_MANIFEST_DIR"),
     "/templates/common/proxy_x86_jump.asm.tpl"
@@ -130,6 +664,42 @@ const TPL_SLNX_2026: &str = include_str!(concat!(
     env!("CARGO_MANIFEST_DIR"),
     "/templates/vs2026/vs2026_solution.slnx.tpl"
 ));
+const TPL_MESON_BUILD: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/meson/meson.build.tpl"
+));
+const TPL_MESON_CROSS: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/meson/mingw_cross.ini.tpl"
+));
+const TPL_RUST_CARGO_TOML: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/rust/Cargo.toml.tpl"
+));
+const TPL_RUST_BUILD_RS: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/rust/build.rs.tpl"
+));
+const TPL_RUST_LIB: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/rust/lib.rs.tpl"
+));
+const TPL_RUST_JUMP: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/rust/jump.rs.tpl"
+));
+const TPL_RUST_ORIGIN: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/rust/origin.rs.tpl"
+));
+const TPL_RUST_SYS: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/rust/sys.rs.tpl"
+));
+const TPL_RUST_PAYLOAD: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/rust/payload.rs.tpl"
+));
 
 fn fill(template: &str, pairs: &[(&str, String)]) -> String {
     let mut out = template.to_string();
@@ -159,170 +729,771 @@ fn escape_c_text_literal(s: &str) -> String {
             '"' => out.push_str("\\\""),
             '\r' => {}
             '\n' => out.push_str("\\n"),
+            // Sources may be compiled as ANSI (MSVC without /utf-8), so anything outside
+            // printable ASCII is spelled as an escape.
+            c if (c as u32) < 0x20 || (0x7F..0xA0).contains(&(c as u32)) => {
+                let _ = write!(out, "\\{:03o}", c as u32);
+            }
+            c if (c as u32) > 0xFFFF => {
+                let _ = write!(out, "\\U{:08X}", c as u32);
+            }
+            c if !c.is_ascii() => {
+                let _ = write!(out, "\\u{:04X}", c as u32);
+            }
             other => out.push(other),
         }
     }
     out
 }
 
-fn render_load_origin_module(ctx: &VsTemplateContext) -> String {
+// `report_failure` plus the fallback stubs, and what get_address does after reporting.
+fn render_failure_policy(policy: FailurePolicy, exports: &[PreparedExport], is_x64: bool) -> (String, String) {
+    let mut out = String::from("static LONG g_missing_export_count;\n\n");
+    let report = |body: &str| {
+        format!("static VOID WINAPI report_failure(LPCTSTR message, DWORD error)\n{{\n{body}}}\n")
+    };
+    let (report_failure, missing_export) = match policy {
+        FailurePolicy::Abort => (
+            report("    UNREFERENCED_PARAMETER(error);\n    MessageBox(NULL, message, TEXT(\"AheadLibEx\"), MB_ICONSTOP);\n"),
+            "\n        ExitProcess(0);\n",
+        ),
+        FailurePolicy::Exit => (
+            report("    OutputDebugString(message);\n    ExitProcess(error);\n"),
+            "",
+        ),
+        FailurePolicy::Log => (TPL_FAILURE_LOG.to_string(), ""),
+        FailurePolicy::FailLoad => (
+            report("    UNREFERENCED_PARAMETER(error);\n    OutputDebugString(message);\n"),
+            "        InterlockedIncrement(&g_missing_export_count);\n",
+        ),
+    };
+    out.push_str(&report_failure);
+    if matches!(policy, FailurePolicy::Log | FailurePolicy::FailLoad) {
+        out.push_str(&render_missing_exports(exports, is_x64));
+    }
+    (out, missing_export.to_string())
+}
+
+// aheadlibex_ensure_init's body: what a failed deferred init does, per policy.
+fn render_init_failure(policy: FailurePolicy) -> String {
+    let why = match policy {
+        FailurePolicy::Log => {
+            return "    // With `log` init can't fail: missing exports already point at their fallbacks.\n    InitOnceExecuteOnce(&g_aheadlibex_init_once, aheadlibex_init_once_proc, NULL, NULL);\n".to_string();
+        }
+        FailurePolicy::Abort => "report_failure has shown why; nothing to forward to, so exit",
+        FailurePolicy::Exit => "report_failure normally exits first; keep its error code if it didn't",
+        FailurePolicy::FailLoad => "Too late to fail the load; exit with the reason instead",
+    };
+    format!(
+        "    if (!InitOnceExecuteOnce(&g_aheadlibex_init_once, aheadlibex_init_once_proc, NULL, NULL))\n    {{\n        // {why}.\n        ExitProcess(g_aheadlibex_init_error);\n    }}\n"
+    )
+}
+
+// Stand-ins for exports the original DLL does not provide. x86 callers of stdcall and fastcall
+// exports expect the callee to pop the arguments, so those get one per size, with dummy
+// arguments that make the compiler emit the matching `ret N`.
+fn render_missing_exports(exports: &[PreparedExport], is_x64: bool) -> String {
     let mut out = String::new();
-    match ctx.origin_load_mode {
-        OriginLoadMode::SystemDir => {
-            let dll = escape_c_text_literal(ctx.dll_name);
-            let _ = writeln!(out, "    TCHAR module_path[MAX_PATH] = {{ 0 }};");
-            let _ = writeln!(out, "    TCHAR message[MAX_PATH] = {{ 0 }};");
-            let _ = writeln!(out, "");
-            let _ = writeln!(out, "    UNREFERENCED_PARAMETER(module);");
-            let _ = writeln!(out, "");
-            let _ = writeln!(out, "    GetSystemDirectory(module_path, MAX_PATH);");
-            let _ = writeln!(out, "    lstrcat(module_path, TEXT(\"\\\\\"));");
-            let _ = writeln!(out, "    lstrcat(module_path, TEXT(\"{}\"));", dll);
-            let _ = writeln!(out, "");
-            let _ = writeln!(out, "    g_origin_module_handle = LoadLibrary(module_path);");
-            let _ = writeln!(out, "    if (!g_origin_module_handle)");
-            let _ = writeln!(out, "    {{");
-            let _ = writeln!(out, "        wsprintf(message, TEXT(\"Cannot locate %s, AheadLibEx cannot continue.\\\\nerror code:0x%08X\"), module_path, GetLastError());");
-            let _ = writeln!(out, "        MessageBox(NULL, message, TEXT(\"AheadLibEx\"), MB_ICONSTOP);");
-            let _ = writeln!(out, "    }}");
-            let _ = writeln!(out, "");
-            let _ = writeln!(out, "    return g_origin_module_handle != NULL;");
-        }
-        OriginLoadMode::SameDir { original_name } => {
-            let original = escape_c_text_literal(original_name);
-            let _ = writeln!(out, "    TCHAR module_path[MAX_PATH] = {{ 0 }};");
-            let _ = writeln!(out, "    TCHAR message[MAX_PATH] = {{ 0 }};");
-            let _ = writeln!(out, "");
-            let _ = writeln!(out, "    DWORD n = GetModuleFileName(module, module_path, MAX_PATH);");
-            let _ = writeln!(out, "    if (n == 0 || n >= MAX_PATH)");
-            let _ = writeln!(out, "    {{");
-            let _ = writeln!(out, "        wsprintf(message, TEXT(\"GetModuleFileName failed, AheadLibEx cannot continue.\\\\nerror code:0x%08X\"), GetLastError());");
-            let _ = writeln!(out, "        MessageBox(NULL, message, TEXT(\"AheadLibEx\"), MB_ICONSTOP);");
-            let _ = writeln!(out, "        return FALSE;");
-            let _ = writeln!(out, "    }}");
-            let _ = writeln!(out, "");
-            let _ = writeln!(out, "    TCHAR* last = NULL;");
-            let _ = writeln!(out, "    for (TCHAR* p = module_path; *p; ++p)");
-            let _ = writeln!(out, "    {{");
-            let _ = writeln!(out, "        if (*p == TEXT('\\\\') || *p == TEXT('/'))");
-            let _ = writeln!(out, "        {{");
-            let _ = writeln!(out, "            last = p;");
-            let _ = writeln!(out, "        }}");
-            let _ = writeln!(out, "    }}");
-            let _ = writeln!(out, "    if (last)");
-            let _ = writeln!(out, "    {{");
-            let _ = writeln!(out, "        *(last + 1) = TEXT('\\0');");
-            let _ = writeln!(out, "    }}");
-            let _ = writeln!(out, "    else");
-            let _ = writeln!(out, "    {{");
-            let _ = writeln!(out, "        module_path[0] = TEXT('\\0');");
-            let _ = writeln!(out, "    }}");
-            let _ = writeln!(out, "");
-            let _ = writeln!(out, "    lstrcat(module_path, TEXT(\"{}\"));", original);
-            let _ = writeln!(out, "");
-            let _ = writeln!(out, "    g_origin_module_handle = LoadLibrary(module_path);");
-            let _ = writeln!(out, "    if (!g_origin_module_handle)");
-            let _ = writeln!(out, "    {{");
-            let _ = writeln!(out, "        wsprintf(message, TEXT(\"Cannot locate %s, AheadLibEx cannot continue.\\\\nerror code:0x%08X\"), module_path, GetLastError());");
-            let _ = writeln!(out, "        MessageBox(NULL, message, TEXT(\"AheadLibEx\"), MB_ICONSTOP);");
-            let _ = writeln!(out, "    }}");
-            let _ = writeln!(out, "");
-            let _ = writeln!(out, "    return g_origin_module_handle != NULL;");
-        }
-        OriginLoadMode::CustomPath { path } => {
-            let origin_cfg = escape_c_text_literal(path);
-            let _ = writeln!(out, "    TCHAR module_path[MAX_PATH] = {{ 0 }};");
-            let _ = writeln!(out, "    TCHAR message[MAX_PATH] = {{ 0 }};");
-            let _ = writeln!(out, "    const TCHAR origin_cfg[] = TEXT(\"{}\");", origin_cfg);
-            let _ = writeln!(out, "");
-            let _ = writeln!(out, "    if ((origin_cfg[0] && origin_cfg[1] == TEXT(':')) || origin_cfg[0] == TEXT('\\\\') || origin_cfg[0] == TEXT('/'))");
-            let _ = writeln!(out, "    {{");
-            let _ = writeln!(out, "        lstrcpyn(module_path, origin_cfg, MAX_PATH);");
-            let _ = writeln!(out, "    }}");
-            let _ = writeln!(out, "    else");
-            let _ = writeln!(out, "    {{");
-            let _ = writeln!(out, "        DWORD n = GetModuleFileName(module, module_path, MAX_PATH);");
-            let _ = writeln!(out, "        if (n == 0 || n >= MAX_PATH)");
-            let _ = writeln!(out, "        {{");
-            let _ = writeln!(out, "            wsprintf(message, TEXT(\"GetModuleFileName failed, AheadLibEx cannot continue.\\\\nerror code:0x%08X\"), GetLastError());");
-            let _ = writeln!(out, "            MessageBox(NULL, message, TEXT(\"AheadLibEx\"), MB_ICONSTOP);");
-            let _ = writeln!(out, "            return FALSE;");
-            let _ = writeln!(out, "        }}");
-            let _ = writeln!(out, "");
-            let _ = writeln!(out, "        TCHAR* last = NULL;");
-            let _ = writeln!(out, "        for (TCHAR* p = module_path; *p; ++p)");
-            let _ = writeln!(out, "        {{");
-            let _ = writeln!(out, "            if (*p == TEXT('\\\\') || *p == TEXT('/'))");
-            let _ = writeln!(out, "            {{");
-            let _ = writeln!(out, "                last = p;");
-            let _ = writeln!(out, "            }}");
-            let _ = writeln!(out, "        }}");
-            let _ = writeln!(out, "        if (last)");
-            let _ = writeln!(out, "        {{");
-            let _ = writeln!(out, "            *(last + 1) = TEXT('\\0');");
-            let _ = writeln!(out, "        }}");
-            let _ = writeln!(out, "        else");
-            let _ = writeln!(out, "        {{");
-            let _ = writeln!(out, "            module_path[0] = TEXT('\\0');");
-            let _ = writeln!(out, "        }}");
-            let _ = writeln!(out, "");
-            let _ = writeln!(out, "        lstrcat(module_path, origin_cfg);");
-            let _ = writeln!(out, "    }}");
-            let _ = writeln!(out, "");
-            let _ = writeln!(out, "    g_origin_module_handle = LoadLibrary(module_path);");
-            let _ = writeln!(out, "    if (!g_origin_module_handle)");
-            let _ = writeln!(out, "    {{");
-            let _ = writeln!(out, "        wsprintf(message, TEXT(\"Cannot locate %s, AheadLibEx cannot continue.\\\\nerror code:0x%08X\"), module_path, GetLastError());");
-            let _ = writeln!(out, "        MessageBox(NULL, message, TEXT(\"AheadLibEx\"), MB_ICONSTOP);");
-            let _ = writeln!(out, "    }}");
-            let _ = writeln!(out, "");
-            let _ = writeln!(out, "    return g_origin_module_handle != NULL;");
+    for cleanup in fallback_cleanups(exports, is_x64) {
+        let (convention, registers, bytes) = match cleanup {
+            X86Cleanup::Caller => {
+                out.push_str("\n// Stands in for exports the original DLL does not provide.\n");
+                out.push_str("static ULONG_PTR WINAPI missing_export(void)\n{\n");
+                out.push_str("    SetLastError(ERROR_PROC_NOT_FOUND);\n    return 0;\n}\n");
+                continue;
+            }
+            X86Cleanup::Stdcall(bytes) => ("WINAPI", 0, bytes),
+            X86Cleanup::Fastcall(bytes) => ("__fastcall", 2, bytes),
+            X86Cleanup::Unknown => {
+                out.push_str("\n// Undecorated exports without a prototype: nothing says how many bytes their callers pushed,\n");
+                out.push_str("// so returning could unbalance the stack. A prototype gives the export a fallback that returns.\n");
+                out.push_str("static ULONG_PTR WINAPI missing_export_unknown(void)\n{\n");
+                out.push_str("    report_failure(TEXT(\"Called a missing export whose argument size is unknown.\"), ERROR_PROC_NOT_FOUND);\n");
+                out.push_str("    ExitProcess(ERROR_PROC_NOT_FOUND);\n}\n");
+                continue;
+            }
+        };
+        let count = registers + bytes / 4;
+        let params = (1..=count)
+            .map(|i| format!("ULONG_PTR a{i}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let _ = write!(
+            out,
+            "\n// Pops {bytes} bytes.\nstatic ULONG_PTR {convention} {}({params})\n{{\n",
+            cleanup.fallback(false)
+        );
+        for i in 1..=count {
+            let _ = writeln!(out, "    UNREFERENCED_PARAMETER(a{i});");
         }
+        out.push_str("    SetLastError(ERROR_PROC_NOT_FOUND);\n    return 0;\n}\n");
     }
     out
 }
 
-pub fn sanitize_identifier(raw: &str) -> String {
-    let mut out = String::with_capacity(raw.len());
-    for ch in raw.chars() {
-        let valid = ch.is_ascii_alphanumeric() || ch == '_';
-        if valid {
-            out.push(ch);
-        } else {
-            out.push('_');
-        }
-    }
-    if out.is_empty() {
-        "_".to_string()
+// One fallback per cleanup the resolved exports need; x64 has only the one.
+fn fallback_cleanups(exports: &[PreparedExport], is_x64: bool) -> BTreeSet<X86Cleanup> {
+    if is_x64 {
+        BTreeSet::from([X86Cleanup::Caller])
     } else {
-        out
+        exports
+            .iter()
+            .filter(|e| e.needs_pointer())
+            .map(|e| e.x86_cleanup)
+            .collect()
     }
 }
 
-fn prepare_exports(entries: &[ExportEntry]) -> Vec<PreparedExport<'_>> {
-    let mut exports = entries.iter().collect::<Vec<_>>();
-    exports.sort_by_key(|e| (e.ordinal, e.name.clone()));
+const HOST_GATE: &str = "    if (!aheadlibex_host_allowed())\n    {\n        return;\n    }\n";
+
+// Helpers plus aheadlibex_host_allowed(); each list that is set has to match.
+fn render_host_filter(filter: &HostFilter) -> String {
+    let mut checks = String::new();
+    let compare_any = |what: &str, var: &str, values: &[String]| {
+        let cond = values
+            .iter()
+            .map(|v| format!("lstrcmpiW({var}, L\"{}\") != 0", escape_c_text_literal(v)))
+            .collect::<Vec<_>>()
+            .join(" &&\n        ");
+        format!("    // {what}\n    if ({cond})\n    {{\n        return FALSE;\n    }}\n")
+    };
+    if !filter.names.is_empty() {
+        checks.push_str("    const WCHAR* name = path;\n");
+        checks.push_str("    for (const WCHAR* p = path; *p; ++p)\n    {\n");
+        checks.push_str("        if (*p == L'\\\\' || *p == L'/')\n        {\n            name = p + 1;\n        }\n    }\n");
+        checks.push_str(&compare_any("Executable name", "name", &filter.names));
+    }
+    if !filter.paths.is_empty() {
+        checks.push_str(&compare_any("Executable path", "path", &filter.paths));
+    }
 
-    let mut used_stubs = HashSet::new();
-    let mut prepared = Vec::with_capacity(exports.len());
-    for entry in exports {
-        let is_noname = entry.name.starts_with('#');
-        let label = if is_noname {
-            format!("Noname{}", entry.ordinal)
-        } else {
-            entry.name.clone()
-        };
+    let mut out = String::new();
+    if !filter.versions.is_empty() {
+        let cond = filter
+            .versions
+            .iter()
+            .map(|r| format!("(version >= 0x{:016X}ULL && version <= 0x{:016X}ULL)", r.min, r.max))
+            .collect::<Vec<_>>()
+            .join(" ||\n          ");
+        let _ = writeln!(
+            checks,
+            "    // File version\n    ULONGLONG version = aheadlibex_host_version();\n    if (!({cond}))\n    {{\n        return FALSE;\n    }}"
+        );
+        out.push_str(TPL_HOST_VERSION);
+    }
+    if !filter.sha256.is_empty() {
+        checks.push_str("    // SHA-256 of the executable\n");
+        checks.push_str("    static const BYTE allowed_sha256[][32] =\n    {\n");
+        for digest in &filter.sha256 {
+            let bytes = digest
+                .iter()
+                .map(|b| format!("0x{b:02x}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let _ = writeln!(checks, "        {{ {bytes} }},");
+        }
+        checks.push_str("    };\n    BYTE digest[32];\n    BOOL hash_matches = FALSE;\n    int i;\n");
+        checks.push_str("    if (aheadlibex_host_sha256(path, digest))\n    {\n");
+        checks.push_str("        for (i = 0; i < (int)(sizeof(allowed_sha256) / sizeof(allowed_sha256[0])); ++i)\n        {\n");
+        checks.push_str("            hash_matches |= aheadlibex_same_digest(digest, allowed_sha256[i]);\n        }\n    }\n");
+        checks.push_str("    if (!hash_matches)\n    {\n        return FALSE;\n    }\n");
+        out.push_str(TPL_HOST_SHA256);
+    }
+    out.push_str(&fill(TPL_HOST_FILTER, &[("HOST_CHECKS", checks)]));
+    out
+}
 
-        let mut stub = if is_noname {
-            format!("Unnamed{}", entry.ordinal)
-        } else {
-            sanitize_identifier(&entry.name)
-        };
+const PAYLOAD_THREAD: &str = "static DWORD WINAPI payload_thread_proc(LPVOID context)
+{
+    UNREFERENCED_PARAMETER(context);
+    {{RUN_PAYLOAD}}
+    return 0;
+}
+";
+
+// payload_main plus whatever starts it; then the DllMain and deferred-init call sites.
+fn render_payload(
+    exports: &[PreparedExport],
+    payload: &PayloadEntry,
+    deferred: bool,
+    host_gate: &str,
+    plugin_loader: &str,
+    guard: bool,
+) -> (String, String, String) {
+    if *payload == PayloadEntry::None {
+        return (String::new(), String::new(), String::new());
+    }
 
-        if used_stubs.contains(&stub) {
-            stub = format!("{}_{}", stub, entry.ordinal);
+    let mut out = plugin_loader.to_string();
+    // Plugins and the demo MessageBox are generated, so they wrap the user's payload_main.
+    let wrapped = !plugin_loader.is_empty() || *payload == PayloadEntry::Demo;
+    if wrapped {
+        out.push_str("static VOID WINAPI payload_entry(void)\n{\n");
+        if !plugin_loader.is_empty() {
+            out.push_str("    load_plugins();\n");
         }
-        used_stubs.insert(stub.clone());
+        out.push_str("    payload_main();\n");
+        if *payload == PayloadEntry::Demo {
+            out.push_str("    MessageBox(NULL, TEXT(\"AheadLibExTest!\"), TEXT(\"AheadLibEx\"), MB_OK);\n");
+        }
+        out.push_str("}\n\n");
+    }
+    let run = match (guard, wrapped) {
+        (true, true) => "AHEADLIBEX_GUARDED(\"payload_main\", payload_entry());",
+        (true, false) => "AHEADLIBEX_GUARDED(\"payload_main\", payload_main());",
+        (false, true) => "payload_entry();",
+        (false, false) => "payload_main();",
+    };
+
+    let start = "        start_payload();\n";
+    let deferred_note = "        // Deferred: the payload starts from the first export call, outside loader lock.\n";
+    let (attach, deferred_call) = match payload {
+        PayloadEntry::Thread | PayloadEntry::Demo => {
+            out.push_str(&PAYLOAD_THREAD.replace("{{RUN_PAYLOAD}}", run));
+            out.push_str("\nstatic VOID start_payload(void)\n{\n    HANDLE thread;\n\n");
+            out.push_str(host_gate);
+            out.push_str("    thread = CreateThread(NULL, 0, payload_thread_proc, NULL, 0, NULL);\n");
+            out.push_str("    if (thread)\n    {\n        CloseHandle(thread);\n    }\n}\n");
+            if deferred {
+                (deferred_note.to_string(), "    start_payload();\n")
+            } else {
+                (start.to_string(), "")
+            }
+        }
+        PayloadEntry::Sync => {
+            if deferred {
+                out.push_str("// Runs inside the deferred init, outside loader lock.\n");
+            } else {
+                out.push_str("// Runs inside DllMain under loader lock: no LoadLibrary, COM or waiting on other threads.\n");
+            }
+            out.push_str("static VOID start_payload(void)\n{\n");
+            out.push_str(host_gate);
+            let _ = writeln!(out, "    {run}\n}}");
+            if deferred {
+                (deferred_note.to_string(), "    start_payload();\n")
+            } else {
+                (start.to_string(), "")
+            }
+        }
+        PayloadEntry::FirstCall(_) => {
+            let label = exports
+                .iter()
+                .find(|e| e.payload_trigger)
+                .map_or("the payload export", |e| e.label.as_str());
+            out.push_str("static LONG g_payload_started;\n\n");
+            let _ = writeln!(out, "// Called from the resolver of {label}; only the first call runs the payload.");
+            out.push_str("static VOID start_payload(void)\n{\n");
+            out.push_str(host_gate);
+            let _ = writeln!(
+                out,
+                "    if (InterlockedCompareExchange(&g_payload_started, 1, 0) == 0)\n    {{\n        {run}\n    }}\n}}"
+            );
+            (
+                format!("        // The payload starts from the first call of {label}.\n"),
+                "",
+            )
+        }
+        PayloadEntry::HostReady => {
+            out.push_str("static VOID NTAPI payload_apc_proc(ULONG_PTR context)\n{\n");
+            let _ = writeln!(out, "    UNREFERENCED_PARAMETER(context);\n    {run}\n}}\n");
+            out.push_str(&PAYLOAD_THREAD.replace("{{RUN_PAYLOAD}}", run));
+            out.push_str("\n// Static imports (reserved != NULL) load while the process starts; an APC queued to the\n");
+            out.push_str("// loading thread runs once every static import is initialized, before the host's entry point.\n");
+            out.push_str("// A later LoadLibrary has no such point to wait for, so the payload gets its own thread.\n");
+            out.push_str("static VOID schedule_payload(PVOID reserved)\n{\n    HANDLE thread;\n\n");
+            out.push_str(host_gate);
+            out.push_str("    if (reserved && QueueUserAPC(payload_apc_proc, GetCurrentThread(), 0))\n    {\n        return;\n    }\n");
+            out.push_str("    thread = CreateThread(NULL, 0, payload_thread_proc, NULL, 0, NULL);\n");
+            out.push_str("    if (thread)\n    {\n        CloseHandle(thread);\n    }\n}\n");
+            ("        schedule_payload(reserved);\n".to_string(), "")
+        }
+        PayloadEntry::None => unreachable!(),
+    };
+    out.push('\n');
+    (out, attach, deferred_call.to_string())
+}
+
+fn render_plugin_loader(plugins: &PluginLoader) -> String {
+    let (context, init) = match &plugins.init_export {
+        Some(export) => {
+            let mut context = String::new();
+            let _ = writeln!(context, "// Passed to each plugin's {export} export; new fields are only ever appended.");
+            context.push_str("typedef struct AHEADLIBEX_PLUGIN_CONTEXT\n{\n");
+            context.push_str("    DWORD size; // sizeof(AHEADLIBEX_PLUGIN_CONTEXT)\n");
+            context.push_str("    HMODULE proxy_module;\n");
+            context.push_str("    HMODULE origin_module;\n");
+            context.push_str("    LPCWSTR plugin_path;\n");
+            context.push_str("} AHEADLIBEX_PLUGIN_CONTEXT;\n\n");
+            context.push_str("typedef BOOL (WINAPI* aheadlibex_plugin_init_fn)(const AHEADLIBEX_PLUGIN_CONTEXT* context);\n\n");
+
+            let mut init = String::new();
+            let _ = writeln!(
+                init,
+                "            aheadlibex_plugin_init_fn init = (aheadlibex_plugin_init_fn)(ULONG_PTR)GetProcAddress(plugin, \"{}\");",
+                escape_c_text_literal(export)
+            );
+            init.push_str("            AHEADLIBEX_PLUGIN_CONTEXT context;\n");
+            init.push_str("            context.size = sizeof(context);\n");
+            init.push_str("            context.proxy_module = self;\n");
+            // The loader sits ahead of the origin runtime; the header declares the accessor for both
+            // stub and forward-only proxies.
+            init.push_str("            context.origin_module = AheadLibEx_OriginModule();\n");
+            init.push_str("            context.plugin_path = path;\n");
+            init.push_str("            if (init && !init(&context))\n            {\n");
+            init.push_str("                plugin_report(L\"AheadLibEx: plugin init failed, unloading: \", path, GetLastError());\n");
+            init.push_str("                FreeLibrary(plugin);\n");
+            init.push_str("            }\n            else\n            {\n");
+            init.push_str("                plugin_report(L\"AheadLibEx: plugin loaded: \", path, ERROR_SUCCESS);\n");
+            init.push_str("            }\n");
+            (context, init)
+        }
+        None => (
+            String::new(),
+            "            plugin_report(L\"AheadLibEx: plugin loaded: \", path, ERROR_SUCCESS);\n".to_string(),
+        ),
+    };
+    fill(
+        TPL_PLUGIN_LOADER,
+        &[
+            ("PLUGIN_CONTEXT", context),
+            ("PLUGIN_DIR", escape_c_text_literal(&plugins.dir)),
+            ("PLUGIN_PATTERN", escape_c_text_literal(&plugins.pattern)),
+            ("PLUGIN_INIT", init),
+        ],
+    )
+}
+
+fn render_load_origin_module(ctx: &VsTemplateContext) -> String {
+    let mut out = String::new();
+    // With `log`, a missing original is not fatal: every export falls back to the failure stub.
+    let loaded = if ctx.options.failure_policy == FailurePolicy::Log {
+        "TRUE"
+    } else {
+        "g_origin_module_handle != NULL"
+    };
+    let candidates = ctx.origin_load_mode.candidate_list();
+    let dll = escape_c_text_literal(ctx.dll_name);
+    let _ = writeln!(out, "    origin_path module_path = {{ 0 }};");
+    let _ = writeln!(out, "    DWORD error = ERROR_MOD_NOT_FOUND;");
+    let _ = writeln!(out);
+    for (idx, candidate) in candidates.iter().enumerate() {
+        let idx = idx + 1;
+        let flags = match candidate {
+            OriginLoadModeOwned::LoadEx { flags, .. } => search_flags_expr(*flags),
+            _ => "0".to_string(),
+        };
+        let _ = writeln!(out, "    if (!g_origin_module_handle && origin_candidate_{idx}(module, &module_path))");
+        let _ = writeln!(out, "    {{");
+        let _ = writeln!(out, "        g_origin_module_handle = origin_load(module, &module_path, {flags});");
+        let _ = writeln!(out, "        error = GetLastError();");
+        let _ = writeln!(out, "    }}");
+        if matches!(candidate, OriginLoadModeOwned::LoadEx { dirs, .. } if !dirs.is_empty()) {
+            let _ = writeln!(out, "    origin_remove_dll_directories();");
+        }
+    }
+    let _ = writeln!(out, "    if (!g_origin_module_handle)");
+    let _ = writeln!(out, "    {{");
+    if candidates.len() == 1 {
+        let _ = writeln!(out, "        origin_report(L\"Cannot locate \", module_path.text ? module_path.text : L\"{}\", error);", dll);
+    } else {
+        let _ = writeln!(out, "        origin_report(L\"Cannot locate {} in any of {} locations\", L\"\", error);", dll, candidates.len());
+    }
+    let _ = writeln!(out, "    }}");
+    let _ = writeln!(out, "    else");
+    let _ = writeln!(out, "    {{");
+    let _ = writeln!(out, "        origin_trace_loaded(&module_path);");
+    let _ = writeln!(out, "    }}");
+    let _ = writeln!(out, "    origin_path_free(&module_path);");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "    return {loaded};");
+    out
+}
+
+// Load/report helpers plus one `origin_candidate_<n>` per location: each fills module_path and
+// returns FALSE if the location can't be built.
+fn render_origin_candidates(ctx: &VsTemplateContext) -> String {
+    let candidates = ctx.origin_load_mode.candidate_list();
+    let mut out = String::from(TPL_ORIGIN_LOAD);
+    let needs_probe = candidates.iter().any(|c| {
+        matches!(
+            c,
+            OriginLoadModeOwned::Search { .. } | OriginLoadModeOwned::LoadEx { .. }
+        )
+    });
+    let needs_search = candidates
+        .iter()
+        .any(|c| matches!(c, OriginLoadModeOwned::Search { .. }));
+    let needs_dll_dirs = candidates
+        .iter()
+        .any(|c| matches!(c, OriginLoadModeOwned::LoadEx { dirs, .. } if !dirs.is_empty()));
+    let needs_resolve = needs_search
+        || needs_dll_dirs
+        || candidates.iter().any(|c| {
+            matches!(
+                c,
+                OriginLoadModeOwned::CustomPath { .. }
+                    | OriginLoadModeOwned::Env { .. }
+                    | OriginLoadModeOwned::Config { .. }
+            )
+        });
+    let config_files = candidates
+        .iter()
+        .filter_map(|c| match c {
+            OriginLoadModeOwned::Config { file } => Some(
+                file.clone()
+                    .unwrap_or_else(|| default_origin_config_file(ctx.base_name)),
+            ),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if needs_resolve {
+        out.push_str(TPL_ORIGIN_RESOLVE);
+    }
+    if config_files.iter().any(|f| !is_json_config(f)) {
+        out.push_str(TPL_ORIGIN_INI);
+    }
+    if config_files.iter().any(|f| is_json_config(f)) {
+        out.push_str(TPL_ORIGIN_JSON);
+    }
+    if needs_probe {
+        out.push_str(TPL_ORIGIN_PROBE);
+    }
+    if needs_search {
+        out.push_str(TPL_ORIGIN_SEARCH);
+    }
+    if needs_dll_dirs {
+        // Cookies are dropped after each candidate, so the largest list sizes the array.
+        let most = candidates
+            .iter()
+            .map(|c| match c {
+                OriginLoadModeOwned::LoadEx { dirs, .. } => dirs.len(),
+                _ => 0,
+            })
+            .max()
+            .unwrap_or_default();
+        out.push_str(&fill(TPL_ORIGIN_DLL_DIRS, &[("DLL_DIR_COUNT", most.to_string())]));
+    }
+    let dll = escape_c_text_literal(ctx.dll_name);
+    for (idx, candidate) in candidates.iter().enumerate() {
+        let idx = idx + 1;
+        let _ = writeln!(out, "static BOOL WINAPI origin_candidate_{idx}(HMODULE module, origin_path* module_path)");
+        out.push_str("{\n");
+        match candidate.as_borrowed() {
+            OriginLoadMode::SystemDir => {
+                let _ = writeln!(out, "    // System directory");
+                let _ = writeln!(out, "    UNREFERENCED_PARAMETER(module);");
+                let _ = writeln!(out, "    return origin_known_dir(GetSystemDirectoryW, module_path) && origin_path_join(module_path, L\"{}\");", dll);
+            }
+            OriginLoadMode::SameDir { original_name } => {
+                let _ = writeln!(out, "    // Proxy directory");
+                let _ = writeln!(out, "    return origin_module_dir(module, module_path) && origin_path_join(module_path, L\"{}\");", escape_c_text_literal(original_name));
+            }
+            OriginLoadMode::CustomPath { path } => {
+                let _ = writeln!(out, "    static const WCHAR origin_cfg[] = L\"{}\";", escape_c_text_literal(path));
+                let _ = writeln!(out, "    return origin_resolve_path(module, origin_cfg, module_path);");
+            }
+            OriginLoadMode::Env { var } => {
+                let var = var
+                    .map(str::to_string)
+                    .unwrap_or_else(|| default_origin_env_var(ctx.base_name));
+                let _ = writeln!(out, "    origin_path value = {{ 0 }};");
+                let _ = writeln!(out, "    BOOL found = origin_env(L\"{}\", &value) && origin_resolve_path(module, value.text, module_path);", escape_c_text_literal(&var));
+                let _ = writeln!(out, "    origin_path_free(&value);");
+                let _ = writeln!(out, "    return found;");
+            }
+            OriginLoadMode::Config { file } => {
+                let file = file
+                    .map(str::to_string)
+                    .unwrap_or_else(|| default_origin_config_file(ctx.base_name));
+                let reader = if is_json_config(&file) {
+                    "origin_read_json_path"
+                } else {
+                    "origin_read_ini_path"
+                };
+                let _ = writeln!(out, "    origin_path config_path = {{ 0 }};");
+                let _ = writeln!(out, "    origin_path value = {{ 0 }};");
+                let _ = writeln!(out, "    BOOL found = origin_resolve_path(module, L\"{}\", &config_path)", escape_c_text_literal(&file));
+                let _ = writeln!(out, "        && {reader}(&config_path, &value)");
+                let _ = writeln!(out, "        && origin_resolve_path(module, value.text, module_path);");
+                let _ = writeln!(out, "    origin_path_free(&config_path);");
+                let _ = writeln!(out, "    origin_path_free(&value);");
+                let _ = writeln!(out, "    return found;");
+            }
+            OriginLoadMode::Search { name } => {
+                let name = name.map(escape_c_text_literal).unwrap_or_else(|| dll.clone());
+                let _ = writeln!(out, "    return origin_search_path(module, L\"{}\", module_path);", name);
+            }
+            OriginLoadMode::LoadEx { flags, dirs } => {
+                let _ = writeln!(out, "    // {}", search_flags_expr(flags));
+                let _ = writeln!(out, "    origin_path dir = {{ 0 }};");
+                let _ = writeln!(out, "    BOOL found = FALSE;");
+                let _ = writeln!(out);
+                // Every directory is registered first so the original's own dependencies resolve too.
+                for dir in dirs {
+                    let _ = writeln!(out, "    origin_add_dll_directory(module, L\"{}\");", escape_c_text_literal(dir));
+                }
+                let probe = |out: &mut String, fetch: &str| {
+                    let _ = writeln!(out, "    if (!found && {fetch})");
+                    let _ = writeln!(out, "    {{");
+                    let _ = writeln!(out, "        found = origin_try_dir(dir.text, dir.length, L\"{}\", module, module_path);", dll);
+                    let _ = writeln!(out, "    }}");
+                };
+                if flags & (LOAD_LIBRARY_SEARCH_APPLICATION_DIR | LOAD_LIBRARY_SEARCH_DEFAULT_DIRS) != 0 {
+                    probe(&mut out, "origin_module_dir(NULL, &dir)");
+                }
+                for dir in dirs {
+                    probe(&mut out, &format!("origin_resolve_path(module, L\"{}\", &dir)", escape_c_text_literal(dir)));
+                }
+                if flags & (LOAD_LIBRARY_SEARCH_SYSTEM32 | LOAD_LIBRARY_SEARCH_DEFAULT_DIRS) != 0 {
+                    probe(&mut out, "origin_known_dir(GetSystemDirectoryW, &dir)");
+                }
+                // Only DLL_LOAD_DIR / USER_DIRS with no directories: nothing to probe.
+                if dirs.is_empty()
+                    && flags & (LOAD_LIBRARY_SEARCH_APPLICATION_DIR | LOAD_LIBRARY_SEARCH_SYSTEM32 | LOAD_LIBRARY_SEARCH_DEFAULT_DIRS) == 0
+                {
+                    let _ = writeln!(out, "    UNREFERENCED_PARAMETER(module);");
+                    let _ = writeln!(out, "    UNREFERENCED_PARAMETER(module_path);");
+                }
+                let _ = writeln!(out, "    origin_path_free(&dir);");
+                let _ = writeln!(out, "    return found;");
+            }
+            OriginLoadMode::Chain(_) => {
+                let _ = writeln!(out, "    UNREFERENCED_PARAMETER(module);");
+                let _ = writeln!(out, "    UNREFERENCED_PARAMETER(module_path);");
+                let _ = writeln!(out, "    return FALSE;");
+            }
+        }
+        out.push_str("}\n\n");
+    }
+    out
+}
+
+fn is_json_config(file: &str) -> bool {
+    file.to_ascii_lowercase().ends_with(".json")
+}
+
+// Sample for the first `config` candidate, pointing at the compiled-in fallback: (file name, content).
+pub fn render_origin_config_sample(ctx: &VsTemplateContext) -> Option<(String, String)> {
+    let candidates = match ctx.origin_load_mode {
+        OriginLoadMode::Chain(candidates) => candidates.to_vec(),
+        mode => vec![mode.to_owned_mode()],
+    };
+    let file = candidates.iter().find_map(|c| match c {
+        OriginLoadModeOwned::Config { file } => Some(
+            file.clone()
+                .unwrap_or_else(|| default_origin_config_file(ctx.base_name)),
+        ),
+        _ => None,
+    })?;
+    let fallback = candidates
+        .iter()
+        .find_map(|c| match c {
+            OriginLoadModeOwned::SameDir { original_name } => Some(original_name.clone()),
+            OriginLoadModeOwned::CustomPath { path } => Some(path.clone()),
+            OriginLoadModeOwned::SystemDir => {
+                Some(format!("C:\\Windows\\System32\\{}", ctx.dll_name))
+            }
+            _ => None,
+        })
+        .unwrap_or_else(|| format!("C:\\Windows\\System32\\{}", ctx.dll_name));
+    // Only the file name is used for the sample; the proxy resolves relative names itself.
+    let name = file
+        .rsplit(['\\', '/'])
+        .next()
+        .unwrap_or(file.as_str())
+        .to_string();
+    let content = if is_json_config(&file) {
+        format!("{{\n  \"path\": {}\n}}\n", json::quote(&fallback))
+    } else {
+        format!(
+            "; Original DLL for the {} proxy: absolute, UNC, or relative to the proxy DLL directory.\r\n\
+             ; Delete this file (or the path line) to use the compiled-in default.\r\n\
+             [origin]\r\npath={}\r\n",
+            ctx.dll_name, fallback
+        )
+    };
+    Some((name, content))
+}
+
+pub fn sanitize_identifier(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    for ch in raw.chars() {
+        let valid = ch.is_ascii_alphanumeric() || ch == '_';
+        if valid {
+            out.push(ch);
+        } else {
+            out.push('_');
+        }
+    }
+    if out.is_empty() {
+        "_".to_string()
+    } else {
+        out
+    }
+}
+
+// Module part of a linker forwarder (`<module>.<export>`) pointing at the original DLL. The
+// documented form is a bare module name resolved by the loader, so only a first candidate that
+// names the original's file has one: `system` would name the proxy itself, and the runtime modes
+// aren't known until load time. None for those; check_origin_forwarders rejects them.
+fn origin_forward_module(ctx: &VsTemplateContext) -> Option<String> {
+    let path = match ctx.origin_load_mode {
+        OriginLoadMode::SameDir { original_name } => original_name,
+        OriginLoadMode::CustomPath { path } => path,
+        OriginLoadMode::Chain(candidates) => {
+            return origin_forward_module(&VsTemplateContext {
+                origin_load_mode: candidates.first()?.as_borrowed(),
+                ..ctx.clone()
+            });
+        }
+        _ => return None,
+    };
+    Some(match path.rsplit_once('.') {
+        Some((stem, ext)) if ext.eq_ignore_ascii_case("dll") => stem.to_string(),
+        _ => path.to_string(),
+    })
+}
+
+fn origin_mode_name(mode: OriginLoadMode) -> &'static str {
+    match mode {
+        OriginLoadMode::SystemDir => "system",
+        OriginLoadMode::SameDir { .. } => "samedir",
+        OriginLoadMode::CustomPath { .. } => "custom",
+        OriginLoadMode::Env { .. } => "env",
+        OriginLoadMode::Config { .. } => "config",
+        OriginLoadMode::Search { .. } => "search",
+        OriginLoadMode::LoadEx { .. } => "loadex",
+        OriginLoadMode::Chain(candidates) => candidates.first().map_or("system", |c| origin_mode_name(c.as_borrowed())),
+    }
+}
+
+// Exports forwarded into the original (forward-only, `forward` rules, DATA exports) need a module
+// name fixed at build time; the loader splits the forwarder at its first '.', so the name can't
+// have one either.
+pub fn check_origin_forwarders(ctx: &VsTemplateContext) -> anyhow::Result<()> {
+    let exports = prepare_exports(ctx);
+    let Some(exp) = exports.iter().find(|e| matches!(e.forward_target, Some(ForwardTarget::Origin(_)))) else {
+        return Ok(());
+    };
+    let Some(module) = origin_forward_module(ctx) else {
+        anyhow::bail!(
+            "'{}' is forwarded to the original, which needs samedir or custom as the first origin mode: a linker forwarder names the original's file, and {} doesn't",
+            exp.raw_name,
+            origin_mode_name(ctx.origin_load_mode)
+        );
+    };
+    if module.contains('.') {
+        anyhow::bail!("Forwarders can't name '{module}': the loader would split it at its first '.'");
+    }
+    Ok(())
+}
+
+// Linker forwarder for an export the original itself forwards (`lib!export`, `lib!#N`), so the
+// proxy points straight at the final target. A forward back into the original's own name would
+// land on the proxy, so that one goes to the original's location instead.
+fn reexport_target(forwarder: &str, ctx: &VsTemplateContext) -> ForwardTarget {
+    let Some((lib, export)) = forwarder.split_once('!') else {
+        return ForwardTarget::Other(forwarder.to_string());
+    };
+    let own_stem = ctx
+        .dll_name
+        .rsplit_once('.')
+        .map_or(ctx.dll_name, |(stem, _)| stem);
+    if lib.eq_ignore_ascii_case(own_stem) {
+        ForwardTarget::Origin(export.to_string())
+    } else {
+        ForwardTarget::Other(format!("{lib}.{export}"))
+    }
+}
+
+// Body of AheadLibEx_OriginModule for forward-only proxies. The loader mapped the original while
+// resolving the forwarders, so the original is whatever module a forwarded export resolves into;
+// no path has to match.
+fn forwarded_origin_module(exports: &[PreparedExport]) -> String {
+    let Some(exp) = exports
+        .iter()
+        .find(|e| matches!(e.forward_target, Some(ForwardTarget::Origin(_))))
+    else {
+        return "    // No export forwards to the original, so the loader never maps it.\n    return NULL;\n".to_string();
+    };
+    format!(
+        "    HMODULE self = NULL;
+    HMODULE origin = NULL;
+    FARPROC address = NULL;
+
+    // @{ordinal} ({label}) is forwarded, so resolving it through the proxy lands in the original.
+    if (GetModuleHandleExW(GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS | GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+                           (LPCWSTR)(ULONG_PTR)AheadLibEx_OriginModule, &self))
+    {{
+        address = GetProcAddress(self, MAKEINTRESOURCEA({ordinal}));
+    }}
+    if (address)
+    {{
+        GetModuleHandleExW(GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS | GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+                           (LPCWSTR)(ULONG_PTR)address, &origin);
+    }}
+    return origin;
+",
+        ordinal = exp.ordinal,
+        label = exp.label
+    )
+}
+
+fn prepare_exports<'a>(ctx: &VsTemplateContext<'a>) -> Vec<PreparedExport<'a>> {
+    let mut exports = ctx.exports.iter().collect::<Vec<_>>();
+    exports.sort_by_key(|e| (e.ordinal, e.name.clone()));
+
+    let mut used_stubs = HashSet::new();
+    let mut prepared = Vec::with_capacity(exports.len());
+    for entry in exports {
+        let mut strategy = ctx.options.strategy_for(entry);
+        if strategy == ExportStrategy::Drop {
+            continue;
+        }
+        // Importers read a DATA export's variable rather than calling it, and a jump stub has no
+        // variable behind it: only the original's symbol will do.
+        if entry.is_data {
+            strategy = ExportStrategy::Forward;
+        }
+        let payload_trigger = !entry.is_data
+            && matches!(&ctx.options.payload, PayloadEntry::FirstCall(selector) if selector.matches(entry));
+        // A stub would only follow the original's own forward at runtime; the first-call payload
+        // export keeps its stub so it still reaches the proxy.
+        if strategy == ExportStrategy::Stub && entry.forwarder.is_some() && !payload_trigger {
+            strategy = ExportStrategy::Forward;
+        }
+
+        let is_noname = entry.name.starts_with('#');
+        let label = if is_noname {
+            format!("Noname{}", entry.ordinal)
+        } else {
+            entry.name.clone()
+        };
+
+        let mut stub = if is_noname {
+            format!("Unnamed{}", entry.ordinal)
+        } else {
+            sanitize_identifier(&entry.name)
+        };
+
+        if used_stubs.contains(&stub) {
+            stub = format!("{}_{}", stub, entry.ordinal);
+        }
+        used_stubs.insert(stub.clone());
+
+        let forward_target = match (strategy, entry.forwarder.as_deref()) {
+            (ExportStrategy::Forward, Some(forwarder)) => Some(reexport_target(forwarder, ctx)),
+            (ExportStrategy::Forward, None) if is_noname => {
+                Some(ForwardTarget::Origin(format!("#{}", entry.ordinal)))
+            }
+            (ExportStrategy::Forward, None) => Some(ForwardTarget::Origin(entry.name.clone())),
+            _ => None,
+        };
+        let prototype = if strategy == ExportStrategy::Hook {
+            ctx.options.prototype_for(entry)
+        } else {
+            None
+        };
 
         prepared.push(PreparedExport {
             raw_name: &entry.name,
@@ -330,12 +1501,33 @@ fn prepare_exports(entries: &[ExportEntry]) -> Vec<PreparedExport<'_>> {
             forwarder: entry.forwarder.as_deref(),
             label,
             stub,
+            strategy,
+            forward_target,
+            prototype,
+            table_index: None,
+            payload_trigger,
+            resolver: false,
+            x86_cleanup: X86Cleanup::of(entry, &ctx.options),
         });
     }
 
+    // Runtime exports get a slot in g_aheadlibex_exports, in jump table order.
+    for (idx, exp) in prepared.iter_mut().filter(|e| e.needs_pointer()).enumerate() {
+        exp.table_index = Some(idx);
+        exp.resolver = ctx.options.uses_resolvers() || exp.payload_trigger;
+    }
+
     prepared
 }
 
+// False when every export is forwarded by the linker: no jump table, no origin runtime.
+pub fn needs_jump_table(ctx: &VsTemplateContext) -> bool {
+    // Resolver entries live in the asm file, typed hooks included.
+    prepare_exports(ctx)
+        .iter()
+        .any(|e| e.is_stub() || e.resolver)
+}
+
 fn solution_configs(is_x64: bool, project_guid: &str) -> (String, String) {
     if is_x64 {
         (
@@ -382,40 +1574,35 @@ fn project_config_entries(is_x64: bool) -> String {
     }
 }
 
-fn cl_item_group(base: &str, is_x64: bool) -> String {
-    if is_x64 {
-        format!(
-            r#"  <ItemGroup>
-    <ClCompile Include="{base}_x64.c" />
-  </ItemGroup>
-"#
-        )
+fn cl_item_group(base: &str, is_x64: bool, ext: &str, with_payload: bool) -> String {
+    let arch = if is_x64 { "x64" } else { "x86" };
+    let payload = if with_payload {
+        format!("    <ClCompile Include=\"{base}_payload.{ext}\" />\n")
     } else {
-        format!(
-            r#"  <ItemGroup>
-    <ClCompile Include="{base}_x86.c" />
+        String::new()
+    };
+    format!(
+        r#"  <ItemGroup>
+    <ClCompile Include="{base}_{arch}.{ext}" />
+{payload}  </ItemGroup>
+  <ItemGroup>
+    <ClInclude Include="{base}_proxy.h" />
   </ItemGroup>
 "#
-        )
-    }
+    )
 }
 
-fn asm_item_group(base: &str, is_x64: bool) -> String {
-    if is_x64 {
-        format!(
-            r#"  <ItemGroup>
-     <MASM Include="{base}_x64_jump.asm" />
-   </ItemGroup>
-"#
-        )
-    } else {
-        format!(
-            r#"  <ItemGroup>
-    <MASM Include="{base}_x86_jump.asm" />
+fn asm_item_group(base: &str, is_x64: bool, with_jump_table: bool) -> String {
+    if !with_jump_table {
+        return String::new();
+    }
+    let arch = if is_x64 { "x64" } else { "x86" };
+    format!(
+        r#"  <ItemGroup>
+    <MASM Include="{base}_{arch}_jump.asm" />
   </ItemGroup>
 "#
-        )
-    }
+    )
 }
 
 fn config_groups(toolset: &str, is_x64: bool) -> String {
@@ -488,57 +1675,71 @@ fn exports_macro(project_name: &str) -> String {
     }
 }
 
-fn item_definitions(exports_macro: &str, is_x64: bool) -> String {
+fn item_definitions(exports_macro: &str, is_x64: bool, minimal_deps: bool) -> String {
+    // No CRT: drop the runtime checks that call into it and make DllMain the entry point.
+    let (sdl, minimal_cl, minimal_link) = if minimal_deps {
+        (
+            "false",
+            "      <BufferSecurityCheck>false</BufferSecurityCheck>\n      <BasicRuntimeChecks>Default</BasicRuntimeChecks>\n      <SupportJustMyCode>false</SupportJustMyCode>\n      <OmitDefaultLibName>true</OmitDefaultLibName>\n",
+            "      <IgnoreAllDefaultLibraries>true</IgnoreAllDefaultLibraries>\n      <AdditionalDependencies>kernel32.lib</AdditionalDependencies>\n      <EntryPointSymbol>DllMain</EntryPointSymbol>\n",
+        )
+    } else {
+        ("true", "", "")
+    };
+
     if is_x64 {
         format!(
             r#"  <ItemDefinitionGroup Condition="'$(Configuration)|$(Platform)'=='Debug|x64'">
     <ClCompile>
       <WarningLevel>Level3</WarningLevel>
-      <SDLCheck>true</SDLCheck>
+      <SDLCheck>{SDL}</SDLCheck>
       <PreprocessorDefinitions>_DEBUG;{EXPORTS_MACRO};_WINDOWS;_USRDLL;%(PreprocessorDefinitions)</PreprocessorDefinitions>
       <ConformanceMode>true</ConformanceMode>
       <PrecompiledHeader>NotUsing</PrecompiledHeader>
       <PrecompiledHeaderFile>pch.h</PrecompiledHeaderFile>
-    </ClCompile>
+{MINIMAL_CL}    </ClCompile>
     <Link>
       <SubSystem>Windows</SubSystem>
       <GenerateDebugInformation>true</GenerateDebugInformation>
       <EnableUAC>false</EnableUAC>
-    </Link>
+{MINIMAL_LINK}    </Link>
   </ItemDefinitionGroup>
   <ItemDefinitionGroup Condition="'$(Configuration)|$(Platform)'=='Release|x64'">
     <ClCompile>
       <WarningLevel>Level3</WarningLevel>
       <FunctionLevelLinking>true</FunctionLevelLinking>
       <IntrinsicFunctions>true</IntrinsicFunctions>
-      <SDLCheck>true</SDLCheck>
+      <SDLCheck>{SDL}</SDLCheck>
       <PreprocessorDefinitions>NDEBUG;{EXPORTS_MACRO};_WINDOWS;_USRDLL;%(PreprocessorDefinitions)</PreprocessorDefinitions>
       <ConformanceMode>true</ConformanceMode>
       <PrecompiledHeader>NotUsing</PrecompiledHeader>
       <PrecompiledHeaderFile>pch.h</PrecompiledHeaderFile>
-    </ClCompile>
+{MINIMAL_CL}    </ClCompile>
     <Link>
       <SubSystem>Windows</SubSystem>
       <EnableCOMDATFolding>true</EnableCOMDATFolding>
       <OptimizeReferences>true</OptimizeReferences>
       <GenerateDebugInformation>true</GenerateDebugInformation>
       <EnableUAC>false</EnableUAC>
-    </Link>
+{MINIMAL_LINK}    </Link>
   </ItemDefinitionGroup>
 "#,
-            EXPORTS_MACRO = exports_macro
+            EXPORTS_MACRO = exports_macro,
+            SDL = sdl,
+            MINIMAL_CL = minimal_cl,
+            MINIMAL_LINK = minimal_link
         )
     } else {
         format!(
             r#"  <ItemDefinitionGroup Condition="'$(Configuration)|$(Platform)'=='Debug|Win32'">
     <ClCompile>
       <WarningLevel>Level3</WarningLevel>
-      <SDLCheck>true</SDLCheck>
+      <SDLCheck>{SDL}</SDLCheck>
       <PreprocessorDefinitions>WIN32;_DEBUG;{EXPORTS_MACRO};_WINDOWS;_USRDLL;%(PreprocessorDefinitions)</PreprocessorDefinitions>
       <ConformanceMode>true</ConformanceMode>
       <PrecompiledHeader>NotUsing</PrecompiledHeader>
       <PrecompiledHeaderFile>pch.h</PrecompiledHeaderFile>
-    </ClCompile>
+{MINIMAL_CL}    </ClCompile>
     <MASM>
       <UseSafeExceptionHandlers>true</UseSafeExceptionHandlers>
     </MASM>
@@ -546,19 +1747,19 @@ fn item_definitions(exports_macro: &str, is_x64: bool) -> String {
       <SubSystem>Windows</SubSystem>
       <GenerateDebugInformation>true</GenerateDebugInformation>
       <EnableUAC>false</EnableUAC>
-    </Link>
+{MINIMAL_LINK}    </Link>
   </ItemDefinitionGroup>
   <ItemDefinitionGroup Condition="'$(Configuration)|$(Platform)'=='Release|Win32'">
     <ClCompile>
       <WarningLevel>Level3</WarningLevel>
       <FunctionLevelLinking>true</FunctionLevelLinking>
       <IntrinsicFunctions>true</IntrinsicFunctions>
-      <SDLCheck>true</SDLCheck>
+      <SDLCheck>{SDL}</SDLCheck>
       <PreprocessorDefinitions>WIN32;NDEBUG;{EXPORTS_MACRO};_WINDOWS;_USRDLL;%(PreprocessorDefinitions)</PreprocessorDefinitions>
       <ConformanceMode>true</ConformanceMode>
       <PrecompiledHeader>NotUsing</PrecompiledHeader>
       <PrecompiledHeaderFile>pch.h</PrecompiledHeaderFile>
-    </ClCompile>
+{MINIMAL_CL}    </ClCompile>
     <MASM>
       <UseSafeExceptionHandlers>true</UseSafeExceptionHandlers>
     </MASM>
@@ -568,20 +1769,19 @@ fn item_definitions(exports_macro: &str, is_x64: bool) -> String {
       <OptimizeReferences>true</OptimizeReferences>
       <GenerateDebugInformation>true</GenerateDebugInformation>
       <EnableUAC>false</EnableUAC>
-    </Link>
+{MINIMAL_LINK}    </Link>
   </ItemDefinitionGroup>
 "#,
-            EXPORTS_MACRO = exports_macro
+            EXPORTS_MACRO = exports_macro,
+            SDL = sdl,
+            MINIMAL_CL = minimal_cl,
+            MINIMAL_LINK = minimal_link
         )
     }
 }
 
-fn extension_settings(is_x64: bool) -> String {
-    if is_x64 {
-        "    <Import Project=\"$(VCTargetsPath)\\BuildCustomizations\\masm.props\" />\n".to_string()
-    } else {
-        "    <Import Project=\"$(VCTargetsPath)\\BuildCustomizations\\masm.props\" />\n".to_string()
-    }
+fn extension_settings() -> String {
+    "    <Import Project=\"$(VCTargetsPath)\\BuildCustomizations\\masm.props\" />\n".to_string()
 }
 
 fn sanitize_build_target_name(raw: &str) -> String {
@@ -589,51 +1789,49 @@ fn sanitize_build_target_name(raw: &str) -> String {
     if out
         .chars()
         .next()
-        .map_or(true, |c| !(c.is_ascii_alphabetic() || c == '_'))
+        .is_none_or(|c| !(c.is_ascii_alphabetic() || c == '_'))
     {
         out = format!("AheadLibEx_{out}");
     }
     out
 }
 
-fn extension_targets(is_x64: bool) -> String {
-    if is_x64 {
-        "    <Import Project=\"$(VCTargetsPath)\\BuildCustomizations\\masm.targets\" />\n".to_string()
-    } else {
-        "    <Import Project=\"$(VCTargetsPath)\\BuildCustomizations\\masm.targets\" />\n".to_string()
-    }
+fn extension_targets() -> String {
+    "    <Import Project=\"$(VCTargetsPath)\\BuildCustomizations\\masm.targets\" />\n".to_string()
 }
 
-fn filter_itemgroups(base: &str, is_x64: bool) -> String {
-    if is_x64 {
-        format!(
-            r#"  <ItemGroup>
-     <ClCompile Include="{base}_x64.c">
-       <Filter>Source Files</Filter>
-     </ClCompile>
-   </ItemGroup>
-   <ItemGroup>
-     <MASM Include="{base}_x64_jump.asm">
-       <Filter>Source Files</Filter>
-     </MASM>
-   </ItemGroup>
-"#
-        )
+fn filter_itemgroups(base: &str, is_x64: bool, ext: &str, with_jump_table: bool, with_payload: bool) -> String {
+    let arch = if is_x64 { "x64" } else { "x86" };
+    let payload = if with_payload {
+        format!("    <ClCompile Include=\"{base}_payload.{ext}\">\n      <Filter>Source Files</Filter>\n    </ClCompile>\n")
     } else {
-        format!(
-            r#"  <ItemGroup>
-     <ClCompile Include="{base}_x86.c">
-       <Filter>Source Files</Filter>
-     </ClCompile>
-  </ItemGroup>
+        String::new()
+    };
+    let mut out = format!(
+        r#"  <ItemGroup>
+    <ClCompile Include="{base}_{arch}.{ext}">
+      <Filter>Source Files</Filter>
+    </ClCompile>
+{payload}  </ItemGroup>
   <ItemGroup>
-    <MASM Include="{base}_x86_jump.asm">
+    <ClInclude Include="{base}_proxy.h">
+      <Filter>Header Files</Filter>
+    </ClInclude>
+  </ItemGroup>
+"#
+    );
+    if with_jump_table {
+        let _ = write!(
+            out,
+            r#"  <ItemGroup>
+    <MASM Include="{base}_{arch}_jump.asm">
       <Filter>Source Files</Filter>
     </MASM>
-   </ItemGroup>
+  </ItemGroup>
 "#
-        )
+        );
     }
+    out
 }
 
 fn slnx_platforms(is_x64: bool) -> String {
@@ -666,13 +1864,22 @@ pub fn render_vcxproj(ctx: &VsTemplateContext, is_x64: bool) -> String {
             ("BASE", ctx.base_name.to_string()),
             ("PROJECT_GUID", ctx.guids.project.to_string()),
             ("PROJECT_CONFIGS", project_config_entries(is_x64)),
-            ("CL_ITEM_GROUP", cl_item_group(ctx.base_name, is_x64)),
-            ("ASM_ITEM_GROUP", asm_item_group(ctx.base_name, is_x64)),
+            (
+                "CL_ITEM_GROUP",
+                cl_item_group(ctx.base_name, is_x64, source_extension(&ctx.options), needs_payload_file(ctx)),
+            ),
+            (
+                "ASM_ITEM_GROUP",
+                asm_item_group(ctx.base_name, is_x64, needs_jump_table(ctx)),
+            ),
             ("CONFIG_GROUPS", config_groups("v143", is_x64)),
             ("PROPERTY_SHEETS", property_sheets(is_x64)),
-            ("ITEM_DEFINITIONS", item_definitions(&exports_macro, is_x64)),
-            ("EXTENSION_SETTINGS", extension_settings(is_x64)),
-            ("EXTENSION_TARGETS", extension_targets(is_x64)),
+            (
+                "ITEM_DEFINITIONS",
+                item_definitions(&exports_macro, is_x64, ctx.options.minimal_deps),
+            ),
+            ("EXTENSION_SETTINGS", extension_settings()),
+            ("EXTENSION_TARGETS", extension_targets()),
         ],
     )
 }
@@ -690,13 +1897,22 @@ pub fn render_vcxproj_2026(ctx: &VsTemplateContext, is_x64: bool) -> String {
             ("PROJECT_GUID", ctx.guids.project.to_string()),
             ("BASE", ctx.base_name.to_string()),
             ("PROJECT_CONFIGS", project_config_entries(is_x64)),
-            ("CL_ITEM_GROUP", cl_item_group(ctx.base_name, is_x64)),
-            ("ASM_ITEM_GROUP", asm_item_group(ctx.base_name, is_x64)),
+            (
+                "CL_ITEM_GROUP",
+                cl_item_group(ctx.base_name, is_x64, source_extension(&ctx.options), needs_payload_file(ctx)),
+            ),
+            (
+                "ASM_ITEM_GROUP",
+                asm_item_group(ctx.base_name, is_x64, needs_jump_table(ctx)),
+            ),
             ("CONFIG_GROUPS", config_groups("v145", is_x64)),
             ("PROPERTY_SHEETS", property_sheets(is_x64)),
-            ("ITEM_DEFINITIONS", item_definitions(&exports_macro, is_x64)),
-            ("EXTENSION_SETTINGS", extension_settings(is_x64)),
-            ("EXTENSION_TARGETS", extension_targets(is_x64)),
+            (
+                "ITEM_DEFINITIONS",
+                item_definitions(&exports_macro, is_x64, ctx.options.minimal_deps),
+            ),
+            ("EXTENSION_SETTINGS", extension_settings()),
+            ("EXTENSION_TARGETS", extension_targets()),
         ],
     )
 }
@@ -709,7 +1925,16 @@ pub fn render_filters(ctx: &VsTemplateContext, is_x64: bool) -> String {
             ("GUID_SOURCE", ctx.guids.filter_source.to_string()),
             ("GUID_HEADER", ctx.guids.filter_header.to_string()),
             ("GUID_RESOURCE", ctx.guids.filter_resource.to_string()),
-            ("FILTER_ITEMGROUPS", filter_itemgroups(ctx.base_name, is_x64)),
+            (
+                "FILTER_ITEMGROUPS",
+                filter_itemgroups(
+                    ctx.base_name,
+                    is_x64,
+                    source_extension(&ctx.options),
+                    needs_jump_table(ctx),
+                    needs_payload_file(ctx),
+                ),
+            ),
         ],
     )
 }
@@ -722,7 +1947,16 @@ pub fn render_filters_2026(ctx: &VsTemplateContext, is_x64: bool) -> String {
             ("GUID_SOURCE", ctx.guids.filter_source.to_string()),
             ("GUID_HEADER", ctx.guids.filter_header.to_string()),
             ("GUID_RESOURCE", ctx.guids.filter_resource.to_string()),
-            ("FILTER_ITEMGROUPS", filter_itemgroups(ctx.base_name, is_x64)),
+            (
+                "FILTER_ITEMGROUPS",
+                filter_itemgroups(
+                    ctx.base_name,
+                    is_x64,
+                    source_extension(&ctx.options),
+                    needs_jump_table(ctx),
+                    needs_payload_file(ctx),
+                ),
+            ),
         ],
     )
 }
@@ -745,148 +1979,803 @@ pub fn render_slnx_2026(ctx: &VsTemplateContext, is_x64: bool) -> String {
     )
 }
 
-pub fn render_c(ctx: &VsTemplateContext) -> String {
-    let exports = prepare_exports(ctx.exports);
+fn render_proxy_c(ctx: &VsTemplateContext, template: &str, is_x64: bool) -> String {
+    let exports = prepare_exports(ctx);
+    let has_stubs = exports.iter().any(|e| e.needs_pointer());
+    let has_resolvers = exports.iter().any(|e| e.resolver);
+    let origin_module = origin_forward_module(ctx);
 
     let mut export_pragmas = String::new();
     for exp in &exports {
-        let noname = if exp.label.starts_with("Noname") {
-            ",NONAME"
-        } else {
-            ""
-        };
-        let entry = format!("{}=AheadLibEx_{},@{}{}", exp.label, exp.stub, exp.ordinal, noname);
+        let noname = if exp.is_noname() { ",NONAME" } else { "" };
+        if let Some(target) = exp.forward_to(origin_module.as_deref()) {
+            let entry = format!(
+                "{}={},@{}{}",
+                exp.label,
+                escape_c_text_literal(&target),
+                exp.ordinal,
+                noname
+            );
+            let _ = writeln!(
+                export_pragmas,
+                "#pragma comment(linker, \"/EXPORT:\\\"{}\\\"\")",
+                entry
+            );
+            continue;
+        }
+
+        let entry = format!("{}={},@{}{}", exp.label, exp.internal_symbol(), exp.ordinal, noname);
         let _ = writeln!(
             export_pragmas,
             "#pragma comment(linker, \"/EXPORT:\\\"{}\\\"\")",
             entry
         );
-        let _ = writeln!(
-            export_pragmas,
-            "#pragma comment(linker, \"/alternatename:AheadLibEx_{}=_AheadLibEx_{}\")",
-            exp.stub,
-            exp.stub
-        );
+        // Typed hooks are C functions; the linker matches their decorated x86 names itself.
+        if !is_x64 && !exp.is_typed_hook() {
+            let _ = writeln!(
+                export_pragmas,
+                "#pragma comment(linker, \"/alternatename:AheadLibEx_{}=_AheadLibEx_{}\")",
+                exp.stub,
+                exp.stub
+            );
+        }
     }
 
+    let cpp = ctx.options.cpp;
     let mut forward_decls = String::new();
-    forward_decls.push_str("#ifdef __cplusplus\nextern \"C\" {\n#endif\n");
-    for exp in &exports {
-        let _ = writeln!(
-            forward_decls,
-            "PVOID pfnAheadLibEx_{} = NULL;",
-            exp.stub
-        );
-    }
-    forward_decls.push_str("#ifdef __cplusplus\n}\n#endif\n");
-
-    let trampolines = String::new();
-    // x86 uses a separate jump table assembly file for toolchain compatibility.
-
     let mut init_forwarders = String::new();
-    for exp in &exports {
-        if exp.label.starts_with("Noname") {
+    if has_stubs {
+        // The asm thunks reference these by their undecorated C names.
+        forward_decls.push_str(if cpp { "extern \"C\"\n{\n" } else { "#ifdef __cplusplus\nextern \"C\" {\n#endif\n" });
+        for exp in exports.iter().filter(|e| e.needs_pointer()) {
+            if exp.resolver {
+                let _ = writeln!(
+                    forward_decls,
+                    "void AheadLibEx_Resolve_{name}(void);\nPVOID pfnAheadLibEx_{name} = (PVOID)AheadLibEx_Resolve_{name};",
+                    name = exp.stub
+                );
+            } else {
+                let _ = writeln!(
+                    forward_decls,
+                    "PVOID pfnAheadLibEx_{} = NULL;",
+                    exp.stub
+                );
+            }
+        }
+        for exp in exports.iter().filter(|e| e.is_hook()) {
             let _ = writeln!(
-                init_forwarders,
-                "    pfnAheadLibEx_{} = get_address(MAKEINTRESOURCEA({}));",
-                exp.stub, exp.ordinal
+                forward_decls,
+                "PVOID pfnAheadLibEx_Orig_{} = NULL;",
+                exp.stub
             );
-        } else {
+        }
+        forward_decls.push_str(if cpp { "}\n" } else { "#ifdef __cplusplus\n}\n#endif\n" });
+
+        if ctx.options.lazy {
+            init_forwarders.push_str("    // Lazy mode: each export is resolved by its first call.\n");
+        } else if let Some(exp) = exports.iter().find(|e| e.payload_trigger) {
             let _ = writeln!(
                 init_forwarders,
-                "    pfnAheadLibEx_{} = get_address(\"{}\");",
-                exp.stub, exp.raw_name
+                "    // {} is resolved by its first call, which also starts the payload.",
+                exp.label
             );
         }
+        // C++ won't convert FARPROC to PVOID implicitly.
+        let cast = if cpp { "(PVOID)" } else { "" };
+        for exp in exports
+            .iter()
+            .filter(|e| e.needs_pointer() && !ctx.options.lazy && !e.payload_trigger)
+        {
+            let fallback = missing_export_fallback(exp, ctx.options.failure_policy, is_x64);
+            if exp.is_noname() {
+                let _ = writeln!(
+                    init_forwarders,
+                    "    pfnAheadLibEx_{} = {cast}get_address(MAKEINTRESOURCEA({}), {fallback});",
+                    exp.stub, exp.ordinal
+                );
+            } else {
+                let _ = writeln!(
+                    init_forwarders,
+                    "    pfnAheadLibEx_{} = {cast}get_address(\"{}\", {fallback});",
+                    exp.stub, exp.raw_name
+                );
+            }
+        }
     }
 
-    fill(
-        TPL_C_X86,
-        &[
-            ("DLL_NAME", ctx.dll_name.to_string()),
-            ("LOAD_ORIGIN_MODULE", render_load_origin_module(ctx)),
-            ("EXPORT_PRAGMAS", export_pragmas),
+    let hooks = exports.iter().filter(|e| e.is_hook()).collect::<Vec<_>>();
+    // The filter only has something to gate when there is a payload or a hook.
+    let host_filter = !ctx.options.host_filter.is_empty()
+        && (ctx.options.payload != PayloadEntry::None || exports.iter().any(|e| e.strategy == ExportStrategy::Hook));
+    let host_gate = if host_filter { HOST_GATE } else { "" };
+    let mut start_hooks = String::new();
+    let mut attach_hooks = "";
+    if !hooks.is_empty() {
+        // The detours are the user's code, so they are set in the payload file, which is never rewritten.
+        start_hooks.push_str("\n// Saves the original of every hooked export, then lets install_hooks point them at detours.\n");
+        start_hooks.push_str("static VOID WINAPI start_hooks(void)\n{\n");
+        start_hooks.push_str(host_gate);
+        for exp in &hooks {
+            let _ = writeln!(
+                start_hooks,
+                "    pfnAheadLibEx_Orig_{name} = pfnAheadLibEx_{name};",
+                name = exp.stub
+            );
+        }
+        start_hooks.push_str("    install_hooks();\n}\n");
+        attach_hooks = "        start_hooks();\n";
+    }
+
+    let deferred = has_stubs && ctx.options.deferred_init;
+    // Only worth emitting when there is user code to guard.
+    let crash_guard = ctx.options.crash_guard
+        && (ctx.options.payload != PayloadEntry::None || exports.iter().any(|e| e.is_typed_hook()));
+    let mut detach = String::new();
+    if has_stubs && ctx.options.trace {
+        detach.push_str("        AheadLibEx_TraceFlush();\n");
+    }
+    if crash_guard {
+        detach.push_str("        aheadlibex_guard_shutdown();\n");
+    }
+    let (origin_runtime, attach_init) = if has_stubs {
+        let attach_init = if deferred {
+            "        g_aheadlibex_self = module;\n".to_string()
+        } else {
+            let init = if ctx.options.failure_policy == FailurePolicy::FailLoad {
+                "        if (!init_forwarders())\n        {\n            free_origin_module();\n            return FALSE;\n        }\n"
+            } else {
+                "        init_forwarders();\n"
+            };
+            format!("        if (!load_original_module(module))\n        {{\n            return FALSE;\n        }}\n{init}{attach_hooks}")
+        };
+        detach.push_str("        free_origin_module();\n");
+        (
+            format!("{TPL_ORIGIN_RUNTIME}\nHMODULE WINAPI AheadLibEx_OriginModule(void)\n{{\n    return g_origin_module_handle;\n}}\n\n"),
+            attach_init,
+        )
+    } else {
+        // Every export is a linker forwarder; the loader maps the original for us.
+        (
+            format!(
+                "HMODULE WINAPI AheadLibEx_OriginModule(void)\n{{\n{}}}\n\n",
+                forwarded_origin_module(&exports)
+            ),
+            String::new(),
+        )
+    };
+    let detach_cleanup = if detach.is_empty() {
+        String::new()
+    } else {
+        format!("    else if (reason == DLL_PROCESS_DETACH)\n    {{\n{detach}    }}\n")
+    };
+
+    let mut export_runtime = String::new();
+    if has_stubs && (ctx.options.trace || has_resolvers) {
+        export_runtime.push_str(&render_export_table(&exports, cpp));
+    }
+    if has_stubs && ctx.options.trace {
+        export_runtime.push_str(TPL_TRACE_RUNTIME);
+    }
+    if has_stubs && ctx.options.deferred_init {
+        export_runtime.push_str(TPL_DEFERRED_INIT);
+    }
+    let uses_fallbacks = matches!(ctx.options.failure_policy, FailurePolicy::Log | FailurePolicy::FailLoad);
+    let resolve_fallback = if !uses_fallbacks || !has_resolvers {
+        "NULL"
+    } else if is_x64 {
+        "(FARPROC)missing_export"
+    } else {
+        // Fallbacks differ per export on x86, so the resolver looks its one up by table index.
+        export_runtime.push_str("\n// get_address fallback for each g_aheadlibex_exports entry.\n");
+        export_runtime.push_str("static const FARPROC g_aheadlibex_fallbacks[] =\n{\n");
+        for exp in exports.iter().filter(|e| e.table_index.is_some()) {
+            let _ = writeln!(
+                export_runtime,
+                "    {},",
+                missing_export_fallback(exp, ctx.options.failure_policy, is_x64)
+            );
+        }
+        export_runtime.push_str("};\n");
+        "g_aheadlibex_fallbacks[index]"
+    };
+    if has_resolvers {
+        export_runtime.push_str(TPL_LAZY_RUNTIME);
+    }
+
+    let plugin_loader = ctx
+        .options
+        .plugins
+        .as_ref()
+        .map(render_plugin_loader)
+        .unwrap_or_default();
+    let (payload, attach_payload, deferred_payload) = render_payload(
+        &exports,
+        &ctx.options.payload,
+        deferred,
+        host_gate,
+        &plugin_loader,
+        crash_guard,
+    );
+    let mut resolve_init = String::new();
+    if deferred {
+        resolve_init.push_str("    aheadlibex_ensure_init();\n");
+    }
+    if let Some(index) = exports.iter().find(|e| e.payload_trigger).and_then(|e| e.table_index) {
+        let _ = writeln!(resolve_init, "    if (index == {index})\n    {{\n        start_payload();\n    }}");
+    }
+
+    let (failure_policy, missing_export) = render_failure_policy(ctx.options.failure_policy, &exports, is_x64);
+
+    // x86 uses a separate jump table assembly file for toolchain compatibility.
+    let trampolines = String::new();
+
+    fill(
+        template,
+        &[
+            ("DLL_NAME", ctx.dll_name.to_string()),
+            ("PROXY_HEADER", proxy_header_name(ctx)),
+            ("EXTERN_C", if cpp { "extern \"C\" " } else { "" }.to_string()),
+            ("EXPORT_PRAGMAS", export_pragmas),
             ("FORWARD_DECLS", forward_decls),
-            ("INIT_FORWARDERS", init_forwarders),
             ("X86_TRAMPOLINES", trampolines),
+            ("ORIGIN_RUNTIME", origin_runtime),
+            ("ATTACH_INIT", attach_init),
+            ("DETACH_CLEANUP", detach_cleanup),
+            ("LOAD_ORIGIN_MODULE", render_load_origin_module(ctx)),
+            ("ORIGIN_CANDIDATES", render_origin_candidates(ctx)),
+            ("INIT_FORWARDERS", init_forwarders),
+            ("FAILURE_POLICY", failure_policy),
+            (
+                "MINIMAL_RUNTIME",
+                if ctx.options.minimal_deps { TPL_MINIMAL_RUNTIME } else { "" }.to_string(),
+            ),
+            ("MISSING_EXPORT", missing_export),
+            ("INSTALL_HOOKS", start_hooks),
+            ("EXPORT_RUNTIME", export_runtime),
+            (
+                "DEFERRED_HOOKS",
+                if hooks.is_empty() { "" } else { "    start_hooks();\n" }.to_string(),
+            ),
+            ("RESOLVE_INIT", resolve_init),
+            (
+                "PATH_HELPERS",
+                // The origin loader, the host filter and the plugin loader share the wide path helpers.
+                if has_stubs || host_filter || ctx.options.plugins.is_some() { TPL_ORIGIN_PATH } else { "" }
+                    .to_string(),
+            ),
+            ("RESOLVE_FALLBACK", resolve_fallback.to_string()),
+            ("INIT_FAILURE", render_init_failure(ctx.options.failure_policy)),
+            (
+                "CRASH_GUARD",
+                if crash_guard { TPL_CRASH_GUARD } else { "" }.to_string(),
+            ),
+            (
+                "HOST_FILTER",
+                if host_filter { render_host_filter(&ctx.options.host_filter) } else { String::new() },
+            ),
+            ("PAYLOAD", payload),
+            ("ATTACH_PAYLOAD", attach_payload),
+            ("DEFERRED_PAYLOAD", deferred_payload),
+            (
+                "HOOK_FUNCTIONS",
+                render_hook_functions(&exports, ctx.options.trace, host_filter, crash_guard, cpp),
+            ),
         ],
     )
 }
 
-pub fn render_c_x64(ctx: &VsTemplateContext) -> String {
-    let exports = prepare_exports(ctx.exports);
+// What get_address hands back for `exp` when the original lacks it; only `log` and `fail-load`
+// carry on past a missing export.
+fn missing_export_fallback(exp: &PreparedExport, policy: FailurePolicy, is_x64: bool) -> String {
+    if matches!(policy, FailurePolicy::Log | FailurePolicy::FailLoad) {
+        format!("(FARPROC){}", exp.x86_cleanup.fallback(is_x64))
+    } else {
+        "NULL".to_string()
+    }
+}
 
-    let mut export_pragmas = String::new();
-    for exp in &exports {
-        let noname = if exp.label.starts_with("Noname") {
-            ",NONAME"
+// Name/ordinal/slot for every runtime-resolved export, indexed by the asm thunks.
+// C++ builds it at compile time, so the resolver is kept as a function pointer there.
+fn render_export_table(exports: &[PreparedExport], cpp: bool) -> String {
+    let mut out = String::new();
+    out.push_str("\ntypedef struct AHEADLIBEX_EXPORT\n{\n");
+    out.push_str("    PCSTR name; // NULL for NONAME exports\n");
+    out.push_str("    WORD ordinal;\n");
+    out.push_str("    PVOID* slot;\n");
+    if cpp {
+        out.push_str("    void (*resolver)(void); // initial slot value when resolved on first call\n");
+    } else {
+        out.push_str("    PVOID resolver; // initial slot value when resolved on first call\n");
+    }
+    out.push_str("} AHEADLIBEX_EXPORT;\n\n");
+    if cpp {
+        out.push_str("static constexpr AHEADLIBEX_EXPORT g_aheadlibex_exports[] =\n{\n");
+    } else {
+        out.push_str("static const AHEADLIBEX_EXPORT g_aheadlibex_exports[] =\n{\n");
+    }
+    let null = if cpp { "nullptr" } else { "NULL" };
+    for exp in exports.iter().filter(|e| e.table_index.is_some()) {
+        let name = if exp.is_noname() {
+            null.to_string()
         } else {
-            ""
+            // `\?` keeps decorated C++ names clear of trigraphs.
+            format!("\"{}\"", escape_c_text_literal(exp.raw_name).replace('?', "\\?"))
+        };
+        let resolver = match (exp.resolver, cpp) {
+            (true, true) => format!("AheadLibEx_Resolve_{}", exp.stub),
+            (true, false) => format!("(PVOID)AheadLibEx_Resolve_{}", exp.stub),
+            (false, _) => null.to_string(),
         };
-        let entry = format!("{}=AheadLibEx_{},@{}{}", exp.label, exp.stub, exp.ordinal, noname);
         let _ = writeln!(
-            export_pragmas,
-            "#pragma comment(linker, \"/EXPORT:\\\"{}\\\"\")",
-            entry
+            out,
+            "    {{ {}, {}, &pfnAheadLibEx_{}, {} }},",
+            name, exp.ordinal, exp.stub, resolver
         );
     }
+    out.push_str("};\n");
+    out
+}
 
-    let mut forward_decls = String::new();
-    forward_decls.push_str("#ifdef __cplusplus\nextern \"C\" {\n#endif\n");
-    for exp in &exports {
-        let _ = writeln!(
-            forward_decls,
-            "PVOID pfnAheadLibEx_{} = NULL;",
-            exp.stub
-        );
-    }
-    forward_decls.push_str("#ifdef __cplusplus\n}\n#endif\n");
+// `before_<name>` / `after_<name>` declarators; the proxy calls them, the payload source defines them.
+fn hook_callback_signatures(name: &str, proto: &FunctionPrototype) -> (String, String) {
+    let is_void = proto.is_void();
+    let ret = &proto.return_type;
+    let join = |f: &dyn Fn(&crate::prototype::Param) -> String| -> String {
+        proto.params.iter().map(f).collect::<Vec<_>>().join(", ")
+    };
+    let params = join(&|p| format!("{} {}", p.ty, p.name));
+    let ptr_params = if proto.params.is_empty() {
+        "void".to_string()
+    } else {
+        join(&|p| format!("{} *{}", p.ty, p.name))
+    };
+    let after_params = match (is_void, proto.params.is_empty()) {
+        (true, true) => "void".to_string(),
+        (true, false) => params,
+        (false, true) => format!("{ret} result"),
+        (false, false) => format!("{ret} result, {params}"),
+    };
+    let after_ret = if is_void { "void" } else { ret.as_str() };
+    (
+        format!("void before_{name}({ptr_params})"),
+        format!("{after_ret} after_{name}({after_params})"),
+    )
+}
 
-    let mut init_forwarders = String::new();
-    for exp in &exports {
-        if exp.label.starts_with("Noname") {
+// Typed hooks: before_<name>(&args...), original call, after_<name>(result, args...).
+fn render_hook_functions(exports: &[PreparedExport], trace: bool, host_filter: bool, guard: bool, cpp: bool) -> String {
+    let mut out = String::new();
+    for exp in exports.iter().filter(|e| e.is_typed_hook()) {
+        let Some(proto) = &exp.prototype else {
+            continue;
+        };
+        let name = &exp.stub;
+        let cc = if proto.calling_convention.is_empty() {
+            String::new()
+        } else {
+            format!("{} ", proto.calling_convention)
+        };
+        let is_void = proto.is_void();
+        let ret = &proto.return_type;
+
+        let join = |f: &dyn Fn(&crate::prototype::Param) -> String| -> String {
+            proto.params.iter().map(f).collect::<Vec<_>>().join(", ")
+        };
+        let params = if proto.params.is_empty() {
+            "void".to_string()
+        } else {
+            join(&|p| format!("{} {}", p.ty, p.name))
+        };
+        let args = join(&|p| p.name.clone());
+        let arg_addrs = join(&|p| format!("&{}", p.name));
+        let after_args = match (is_void, proto.params.is_empty()) {
+            (true, _) => args.clone(),
+            (false, true) => "result".to_string(),
+            (false, false) => format!("result, {args}"),
+        };
+
+        let _ = writeln!(out);
+        let _ = writeln!(out, "// Hook for {}; AheadLibEx_{name}_t and the callbacks come from the proxy header.", exp.raw_name);
+        // Exported under the undecorated name, so C++ needs C linkage here.
+        let linkage = if cpp { "extern \"C\" " } else { "" };
+        let _ = writeln!(out, "{linkage}{ret} {cc}AheadLibEx_Hook_{name}({params})");
+        let _ = writeln!(out, "{{");
+        let call = format!("((AheadLibEx_{name}_t)pfnAheadLibEx_{name})({args})");
+        if trace {
             let _ = writeln!(
-                init_forwarders,
-                "    pfnAheadLibEx_{} = get_address(MAKEINTRESOURCEA({}));",
-                exp.stub, exp.ordinal
+                out,
+                "    AheadLibEx_TraceEnter({}, AHEADLIBEX_RETURN_ADDRESS());",
+                exp.table_index.unwrap_or_default()
             );
+        }
+        if host_filter {
+            let _ = writeln!(out, "    if (!aheadlibex_host_allowed())\n    {{");
+            if is_void {
+                let _ = writeln!(out, "        {call};\n        return;");
+            } else {
+                let _ = writeln!(out, "        return {call};");
+            }
+            let _ = writeln!(out, "    }}");
+        }
+        if guard {
+            // A faulting callback is skipped from then on; the original still runs.
+            let _ = writeln!(out, "    AHEADLIBEX_GUARDED(\"before_{name}\", before_{name}({arg_addrs}));");
+            if is_void {
+                let _ = writeln!(out, "    {call};");
+                let _ = writeln!(out, "    AHEADLIBEX_GUARDED(\"after_{name}\", after_{name}({after_args}));");
+            } else {
+                let _ = writeln!(out, "    {ret} result = {call};");
+                let _ = writeln!(out, "    AHEADLIBEX_GUARDED(\"after_{name}\", result = after_{name}({after_args}));");
+                let _ = writeln!(out, "    return result;");
+            }
         } else {
+            let _ = writeln!(out, "    before_{name}({arg_addrs});");
+            if is_void {
+                let _ = writeln!(out, "    {call};");
+                let _ = writeln!(out, "    after_{name}({after_args});");
+            } else {
+                let _ = writeln!(out, "    {ret} result = {call};");
+                let _ = writeln!(out, "    return after_{name}({after_args});");
+            }
+        }
+        let _ = writeln!(out, "}}");
+    }
+    out
+}
+
+pub fn render_c(ctx: &VsTemplateContext) -> String {
+    render_proxy_c(ctx, TPL_C_X86, false)
+}
+
+pub fn render_c_x64(ctx: &VsTemplateContext) -> String {
+    render_proxy_c(ctx, TPL_C_X64, true)
+}
+
+// False when there is no user code to keep: no payload and no typed hooks.
+pub fn needs_payload_file(ctx: &VsTemplateContext) -> bool {
+    ctx.options.payload != PayloadEntry::None
+        || prepare_exports(ctx).iter().any(|e| e.strategy == ExportStrategy::Hook)
+}
+
+pub fn payload_file_name(ctx: &VsTemplateContext) -> String {
+    format!("{}_payload.{}", ctx.base_name, source_extension(&ctx.options))
+}
+
+pub fn source_extension(options: &ProxyOptions) -> &'static str {
+    if options.cpp {
+        "cpp"
+    } else {
+        "c"
+    }
+}
+
+// <stem>_x64.c / <stem>_x86.c, or .cpp for the C++ flavor.
+pub fn proxy_source_name(ctx: &VsTemplateContext, is_x64: bool) -> String {
+    let arch = if is_x64 { "x64" } else { "x86" };
+    format!("{}_{arch}.{}", ctx.base_name, source_extension(&ctx.options))
+}
+
+pub fn proxy_header_name(ctx: &VsTemplateContext) -> String {
+    format!("{}_proxy.h", ctx.base_name)
+}
+
+// <stem>_proxy.h: what payload code may use from the generated C, plus the entry points the
+// generated C expects from the payload source.
+pub fn render_proxy_h(ctx: &VsTemplateContext) -> String {
+    let exports = prepare_exports(ctx);
+
+    let mut pointers = String::new();
+    if exports.iter().any(|e| e.needs_pointer()) {
+        pointers.push_str("\n// Original exports, by sanitized name. With lazy resolution or loader-lock-safe init a pointer\n");
+        pointers.push_str("// may still lead to its resolver, which is just as callable.\n");
+        for exp in exports.iter().filter(|e| e.needs_pointer()) {
+            let _ = writeln!(pointers, "extern PVOID pfnAheadLibEx_{}; // {} @{}", exp.stub, exp.label, exp.ordinal);
+        }
+    }
+    let hooks: Vec<_> = exports.iter().filter(|e| e.is_hook()).collect();
+    if !hooks.is_empty() {
+        pointers.push_str("\n// Hooked exports jump through pfnAheadLibEx_<name>; these keep the original.\n");
+        for exp in hooks {
+            let _ = writeln!(pointers, "extern PVOID pfnAheadLibEx_Orig_{};", exp.stub);
+        }
+    }
+
+    let mut prototypes = String::new();
+    let mut typed = Vec::new();
+    for exp in exports.iter().filter(|e| e.needs_pointer()) {
+        let proto = exp.prototype.clone().or_else(|| {
+            ctx.exports
+                .iter()
+                .find(|e| e.ordinal == exp.ordinal)
+                .and_then(|e| ctx.options.prototype_for(e))
+        });
+        let Some(proto) = proto else {
+            continue;
+        };
+        if prototypes.is_empty() {
+            prototypes.push_str("\n// Known signatures: call the original as ((AheadLibEx_<name>_t)pfnAheadLibEx_<name>)(...).\n");
+        }
+        let cc = if proto.calling_convention.is_empty() {
+            String::new()
+        } else {
+            format!("{} ", proto.calling_convention)
+        };
+        let params = if proto.params.is_empty() {
+            "void".to_string()
+        } else {
+            proto.params.iter().map(|p| format!("{} {}", p.ty, p.name)).collect::<Vec<_>>().join(", ")
+        };
+        let _ = writeln!(prototypes, "typedef {} ({cc}*AheadLibEx_{}_t)({params});", proto.return_type, exp.stub);
+        typed.push(exp.ordinal);
+    }
+
+    let mut entry_points = String::new();
+    if needs_payload_file(ctx) {
+        let _ = writeln!(entry_points, "\n// Defined in {}.", payload_file_name(ctx));
+        if ctx.options.payload != PayloadEntry::None {
+            entry_points.push_str("VOID WINAPI payload_main(void);\n");
+        }
+        if exports.iter().any(|e| e.is_hook()) {
+            entry_points.push_str("VOID WINAPI install_hooks(void);\n");
+        }
+        for exp in exports.iter().filter(|e| e.is_typed_hook()) {
+            if let Some(proto) = &exp.prototype {
+                let (before, after) = hook_callback_signatures(&exp.stub, proto);
+                let _ = writeln!(entry_points, "{before};\n{after};");
+            }
+        }
+    }
+
+    fill(
+        TPL_PROXY_H,
+        &[
+            ("DLL_NAME", ctx.dll_name.to_string()),
+            ("BASE", ctx.base_name.to_string()),
+            ("GUARD", sanitize_identifier(ctx.base_name).to_ascii_uppercase()),
+            ("POINTERS", pointers),
+            ("PROTOTYPES", prototypes),
+            ("ENTRY_POINTS", entry_points),
+            (
+                "CPP_API",
+                if ctx.options.cpp { render_cpp_api(ctx, &exports, &typed) } else { String::new() },
+            ),
+        ],
+    )
+}
+
+// Sanitized names may still be C++ keywords; those get a trailing underscore in the namespace.
+fn cpp_identifier(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "alignas", "alignof", "and", "and_eq", "asm", "auto", "bitand", "bitor", "bool", "break", "case",
+        "catch", "char", "char8_t", "char16_t", "char32_t", "class", "compl", "concept", "const",
+        "consteval", "constexpr", "constinit", "const_cast", "continue", "co_await", "co_return",
+        "co_yield", "decltype", "default", "delete", "do", "double", "dynamic_cast", "else", "enum",
+        "explicit", "export", "extern", "false", "float", "for", "friend", "goto", "if", "inline", "int",
+        "long", "mutable", "namespace", "new", "noexcept", "not", "not_eq", "nullptr", "operator", "or",
+        "or_eq", "private", "protected", "public", "register", "reinterpret_cast", "requires", "return",
+        "short", "signed", "sizeof", "static", "static_assert", "static_cast", "struct", "switch",
+        "template", "this", "thread_local", "throw", "true", "try", "typedef", "typeid", "typename",
+        "union", "unsigned", "using", "virtual", "void", "volatile", "wchar_t", "while", "xor", "xor_eq",
+    ];
+    if KEYWORDS.contains(&name) {
+        format!("{name}_")
+    } else {
+        name.to_string()
+    }
+}
+
+// namespace aheadlibex: typed accessors for the originals and a constexpr export table.
+fn render_cpp_api(ctx: &VsTemplateContext, exports: &[PreparedExport], typed: &[u16]) -> String {
+    let origin_module = origin_forward_module(ctx);
+    let mut out = String::new();
+    out.push_str("\n#ifdef __cplusplus\nnamespace aheadlibex\n{\n");
+    out.push_str("inline HMODULE origin_module() { return AheadLibEx_OriginModule(); }\n");
+
+    let accessors: Vec<_> = exports.iter().filter(|e| e.needs_pointer()).collect();
+    if !accessors.is_empty() {
+        out.push_str("\n// The original of every runtime-resolved export, typed where the signature is known.\n");
+        out.push_str("namespace original\n{\n");
+        for exp in accessors {
+            let name = cpp_identifier(&exp.stub);
+            // Untyped hooks repoint pfnAheadLibEx_<name> at the detour; the original is kept aside.
+            let pointer = if exp.is_hook() && !exp.is_typed_hook() {
+                format!("pfnAheadLibEx_Orig_{}", exp.stub)
+            } else {
+                format!("pfnAheadLibEx_{}", exp.stub)
+            };
+            if typed.contains(&exp.ordinal) {
+                let _ = writeln!(
+                    out,
+                    "inline AheadLibEx_{stub}_t {name}() {{ return reinterpret_cast<AheadLibEx_{stub}_t>({pointer}); }}",
+                    stub = exp.stub
+                );
+            } else {
+                let _ = writeln!(out, "inline PVOID {name}() {{ return {pointer}; }}");
+            }
+        }
+        out.push_str("}\n");
+    }
+
+    // An empty array would be ill-formed; DLLs with no exports get no table.
+    if !exports.is_empty() {
+        out.push_str("\nstruct export_info\n{\n");
+        out.push_str("    const char* name; // nullptr for NONAME exports\n");
+        out.push_str("    WORD ordinal;\n");
+        out.push_str("    const char* forward; // linker forward target, nullptr when resolved at runtime\n");
+        out.push_str("};\n\n");
+        let _ = writeln!(out, "// Every export of {}, as the proxy exports it.", ctx.dll_name);
+        out.push_str("constexpr export_info exports[] =\n{\n");
+        for exp in exports {
+            let name = if exp.is_noname() {
+                "nullptr".to_string()
+            } else {
+                format!("\"{}\"", escape_c_text_literal(exp.raw_name).replace('?', "\\?"))
+            };
+            let forward = match exp.forward_to(origin_module.as_deref()) {
+                Some(target) => format!("\"{}\"", escape_c_text_literal(&target).replace('?', "\\?")),
+                None => "nullptr".to_string(),
+            };
+            let _ = writeln!(out, "    {{ {name}, {}, {forward} }},", exp.ordinal);
+        }
+        out.push_str("};\n");
+    }
+    out.push_str("}\n#endif\n");
+    out
+}
+
+// <stem>_payload.c: the entry points the proxy calls into. Written only when missing, so it
+// survives regeneration.
+pub fn render_payload_c(ctx: &VsTemplateContext, is_x64: bool) -> String {
+    let notes = if ctx.options.minimal_deps {
+        "\n// Minimal dependencies: no C runtime and no user32 import; call kernel32 only.\n"
+    } else if ctx.options.cpp {
+        "\n// C++: aheadlibex::origin_module(), aheadlibex::original::<name>() and aheadlibex::exports\n// come from the proxy header. Keep the entry points below as they are declared there.\n"
+    } else {
+        ""
+    };
+
+    let mut payload_main = String::new();
+    if ctx.options.payload != PayloadEntry::None {
+        payload_main.push_str("\nVOID WINAPI payload_main(void)\n{\n");
+        payload_main.push_str("    // TODO: your patch process begins here.\n");
+        payload_main.push_str("}\n");
+    }
+
+    let exports = prepare_exports(ctx);
+    let hooks = exports.iter().filter(|e| e.is_hook()).collect::<Vec<_>>();
+    let mut install_hooks = String::new();
+    if !hooks.is_empty() {
+        install_hooks.push_str("\n// Runs once the exports are resolved. Hooked exports jump through pfnAheadLibEx_<name>: point\n");
+        install_hooks.push_str("// it at your detour here, and call pfnAheadLibEx_Orig_<name> from the detour to reach the original.\n");
+        install_hooks.push_str("VOID WINAPI install_hooks(void)\n{\n");
+        for exp in &hooks {
             let _ = writeln!(
-                init_forwarders,
-                "    pfnAheadLibEx_{} = get_address(\"{}\");",
-                exp.stub, exp.raw_name
+                install_hooks,
+                "    // TODO: pfnAheadLibEx_{name} = (PVOID)Detour_{name};",
+                name = exp.stub
             );
         }
+        install_hooks.push_str("}\n");
+    }
+
+    let mut callbacks = String::new();
+    for exp in exports.iter().filter(|e| e.is_typed_hook()) {
+        let Some(proto) = &exp.prototype else {
+            continue;
+        };
+        let (before, after) = hook_callback_signatures(&exp.stub, proto);
+        let unreferenced: String = proto
+            .params
+            .iter()
+            .map(|p| format!("    UNREFERENCED_PARAMETER({});\n", p.name))
+            .collect();
+        let _ = writeln!(callbacks);
+        let _ = writeln!(callbacks, "// Hook for {}", exp.raw_name);
+        let _ = writeln!(callbacks, "{before}\n{{");
+        callbacks.push_str(&unreferenced);
+        let _ = writeln!(callbacks, "    // TODO: inspect or change the arguments before the original runs.\n}}\n");
+        let _ = writeln!(callbacks, "{after}\n{{");
+        callbacks.push_str(&unreferenced);
+        callbacks.push_str("    // TODO: inspect the result after the original returns.\n");
+        if !proto.is_void() {
+            callbacks.push_str("    return result;\n");
+        }
+        callbacks.push_str("}\n");
     }
 
     fill(
-        TPL_C_X64,
+        TPL_PAYLOAD_C,
         &[
             ("DLL_NAME", ctx.dll_name.to_string()),
-            ("LOAD_ORIGIN_MODULE", render_load_origin_module(ctx)),
-            ("EXPORT_PRAGMAS", export_pragmas),
-            ("FORWARD_DECLS", forward_decls),
-            ("INIT_FORWARDERS", init_forwarders),
+            ("BASE", ctx.base_name.to_string()),
+            ("GENERATED_SRC", proxy_source_name(ctx, is_x64)),
+            ("PROXY_HEADER", proxy_header_name(ctx)),
+            ("PAYLOAD_NOTES", notes.to_string()),
+            ("PAYLOAD_MAIN", payload_main),
+            ("INSTALL_HOOKS", install_hooks),
+            ("HOOK_CALLBACKS", callbacks),
         ],
     )
 }
 
+// Exports that start out pointing at a resolver entry (lazy, deferred init or payload trigger).
+fn lazy_exports<'a>(exports: &[PreparedExport<'a>]) -> Vec<PreparedExport<'a>> {
+    exports.iter().filter(|e| e.resolver).cloned().collect()
+}
+
+// C handlers behind the shared call thunks, in the order their thunks are emitted.
+fn thunk_handlers(options: &ProxyOptions, resolvers: bool) -> Vec<(&'static str, &'static str)> {
+    let mut handlers = Vec::new();
+    if options.trace {
+        handlers.push(("AheadLibEx_TraceThunk", "AheadLibEx_TraceEnter"));
+    }
+    if resolvers {
+        handlers.push(("AheadLibEx_ResolveThunk", "AheadLibEx_Resolve"));
+    }
+    handlers
+}
+
+fn render_call_thunks(template: &str, options: &ProxyOptions, resolvers: bool) -> String {
+    let mut out = String::new();
+    for (thunk, handler) in thunk_handlers(options, resolvers) {
+        out.push_str(&fill(
+            template,
+            &[
+                ("THUNK_NAME", thunk.to_string()),
+                ("THUNK_HANDLER", handler.to_string()),
+            ],
+        ));
+        out.push('\n');
+    }
+    out
+}
+
 pub fn render_asm_x64(ctx: &VsTemplateContext) -> String {
-    let exports = prepare_exports(ctx.exports);
+    let all = prepare_exports(ctx);
+    let resolvers = lazy_exports(&all);
+    let exports: Vec<_> = all.into_iter().filter(|e| e.is_stub()).collect();
+    let trace = ctx.options.trace;
 
     let mut externs = String::new();
-    for exp in &exports {
-        let _ = writeln!(externs, "EXTERN pfnAheadLibEx_{}:dq;", exp.stub);
+    for (_, handler) in thunk_handlers(&ctx.options, !resolvers.is_empty()) {
+        let _ = writeln!(externs, "EXTERN {handler}:PROC");
+    }
+    if !trace {
+        for exp in &exports {
+            let _ = writeln!(externs, "EXTERN pfnAheadLibEx_{}:dq;", exp.stub);
+        }
     }
 
     let mut jumps = String::new();
     for exp in &exports {
+        if trace {
+            let _ = writeln!(
+                jumps,
+                "AheadLibEx_{name} PROC\n    push {index}\n    jmp AheadLibEx_TraceThunk\nAheadLibEx_{name} ENDP\n",
+                name = exp.stub,
+                index = exp.table_index.unwrap_or_default()
+            );
+        } else {
+            let _ = writeln!(
+                jumps,
+                "AheadLibEx_{name} PROC\n    jmp pfnAheadLibEx_{name}\nAheadLibEx_{name} ENDP\n",
+                name = exp.stub
+            );
+        }
+    }
+    for exp in &resolvers {
         let _ = writeln!(
             jumps,
-            "AheadLibEx_{name} PROC\n    jmp pfnAheadLibEx_{name}\nAheadLibEx_{name} ENDP\n",
-            name = exp.stub
+            "AheadLibEx_Resolve_{name} PROC\n    push {index}\n    jmp AheadLibEx_ResolveThunk\nAheadLibEx_Resolve_{name} ENDP\n",
+            name = exp.stub,
+            index = exp.table_index.unwrap_or_default()
         );
     }
+    jumps.push_str(&render_call_thunks(
+        TPL_CALL_THUNK_X64,
+        &ctx.options,
+        !resolvers.is_empty(),
+    ));
 
     fill(
         TPL_ASM_X64,
@@ -895,32 +2784,58 @@ pub fn render_asm_x64(ctx: &VsTemplateContext) -> String {
 }
 
 pub fn render_asm_x86(ctx: &VsTemplateContext) -> String {
-    let exports = prepare_exports(ctx.exports);
+    let all = prepare_exports(ctx);
+    let resolvers = lazy_exports(&all);
+    let exports: Vec<_> = all.into_iter().filter(|e| e.is_stub()).collect();
+    let trace = ctx.options.trace;
 
     let mut jumps = String::new();
     for exp in &exports {
-        let _ = writeln!(jumps, "EXTERN _pfnAheadLibEx_{name}:DWORD", name = exp.stub);
+        if !trace {
+            let _ = writeln!(jumps, "EXTERN _pfnAheadLibEx_{name}:DWORD", name = exp.stub);
+        }
         let _ = writeln!(jumps, "PUBLIC AheadLibEx_{name}", name = exp.stub);
         let _ = writeln!(jumps, "PUBLIC _AheadLibEx_{name}", name = exp.stub);
         let _ = writeln!(jumps, "AheadLibEx_{name}:", name = exp.stub);
         let _ = writeln!(jumps, "_AheadLibEx_{name}:", name = exp.stub);
-        let _ = writeln!(
-            jumps,
-            "    jmp DWORD PTR [_pfnAheadLibEx_{name}]",
-            name = exp.stub
-        );
+        if trace {
+            let _ = writeln!(jumps, "    push {}", exp.table_index.unwrap_or_default());
+            let _ = writeln!(jumps, "    jmp AheadLibEx_TraceThunk");
+        } else {
+            let _ = writeln!(
+                jumps,
+                "    jmp DWORD PTR [_pfnAheadLibEx_{name}]",
+                name = exp.stub
+            );
+        }
         jumps.push('\n');
     }
+    for exp in &resolvers {
+        let _ = writeln!(jumps, "PUBLIC _AheadLibEx_Resolve_{}", exp.stub);
+        let _ = writeln!(jumps, "_AheadLibEx_Resolve_{}:", exp.stub);
+        let _ = writeln!(jumps, "    push {}", exp.table_index.unwrap_or_default());
+        let _ = writeln!(jumps, "    jmp AheadLibEx_ResolveThunk\n");
+    }
+    jumps.push_str(&render_call_thunks(
+        TPL_CALL_THUNK_X86,
+        &ctx.options,
+        !resolvers.is_empty(),
+    ));
 
     fill(TPL_ASM_X86, &[("ASM_JUMPS", jumps)])
 }
 
 pub fn render_asm_x86_gas(ctx: &VsTemplateContext) -> String {
-    let exports = prepare_exports(ctx.exports);
+    let all = prepare_exports(ctx);
+    let resolvers = lazy_exports(&all);
+    let exports: Vec<_> = all.into_iter().filter(|e| e.is_stub()).collect();
+    let trace = ctx.options.trace;
 
     let mut externs = String::new();
-    for exp in &exports {
-        let _ = writeln!(externs, "    .extern _pfnAheadLibEx_{}", exp.stub);
+    if !trace {
+        for exp in &exports {
+            let _ = writeln!(externs, "    .extern _pfnAheadLibEx_{}", exp.stub);
+        }
     }
 
     let mut jumps = String::new();
@@ -929,8 +2844,24 @@ pub fn render_asm_x86_gas(ctx: &VsTemplateContext) -> String {
         let _ = writeln!(jumps, "    .globl _AheadLibEx_{}", exp.stub);
         let _ = writeln!(jumps, "AheadLibEx_{}:", exp.stub);
         let _ = writeln!(jumps, "_AheadLibEx_{}:", exp.stub);
-        let _ = writeln!(jumps, "    jmp DWORD PTR [_pfnAheadLibEx_{}]\n", exp.stub);
+        if trace {
+            let _ = writeln!(jumps, "    push {}", exp.table_index.unwrap_or_default());
+            let _ = writeln!(jumps, "    jmp AheadLibEx_TraceThunk\n");
+        } else {
+            let _ = writeln!(jumps, "    jmp DWORD PTR [_pfnAheadLibEx_{}]\n", exp.stub);
+        }
     }
+    for exp in &resolvers {
+        let _ = writeln!(jumps, "    .globl _AheadLibEx_Resolve_{}", exp.stub);
+        let _ = writeln!(jumps, "_AheadLibEx_Resolve_{}:", exp.stub);
+        let _ = writeln!(jumps, "    push {}", exp.table_index.unwrap_or_default());
+        let _ = writeln!(jumps, "    jmp AheadLibEx_ResolveThunk\n");
+    }
+    jumps.push_str(&render_call_thunks(
+        TPL_CALL_THUNK_X86_GAS,
+        &ctx.options,
+        !resolvers.is_empty(),
+    ));
 
     fill(
         TPL_ASM_X86_GAS,
@@ -939,21 +2870,51 @@ pub fn render_asm_x86_gas(ctx: &VsTemplateContext) -> String {
 }
 
 pub fn render_asm_x64_gas(ctx: &VsTemplateContext) -> String {
-    let exports = prepare_exports(ctx.exports);
+    let all = prepare_exports(ctx);
+    let resolvers = lazy_exports(&all);
+    let exports: Vec<_> = all.into_iter().filter(|e| e.is_stub()).collect();
+    let trace = ctx.options.trace;
 
     let mut externs = String::new();
-    for exp in &exports {
-        let _ = writeln!(externs, "    .extern pfnAheadLibEx_{}", exp.stub);
+    for (_, handler) in thunk_handlers(&ctx.options, !resolvers.is_empty()) {
+        let _ = writeln!(externs, "    .extern {handler}");
+    }
+    if !trace {
+        for exp in &exports {
+            let _ = writeln!(externs, "    .extern pfnAheadLibEx_{}", exp.stub);
+        }
     }
 
     let mut jumps = String::new();
     for exp in &exports {
+        if trace {
+            let _ = writeln!(
+                jumps,
+                "    .globl AheadLibEx_{name}\nAheadLibEx_{name}:\n    push {index}\n    jmp AheadLibEx_TraceThunk\n",
+                name = exp.stub,
+                index = exp.table_index.unwrap_or_default()
+            );
+        } else {
+            let _ = writeln!(
+                jumps,
+                "    .globl AheadLibEx_{name}\nAheadLibEx_{name}:\n    jmp QWORD PTR [rip + pfnAheadLibEx_{name}]\n",
+                name = exp.stub
+            );
+        }
+    }
+    for exp in &resolvers {
         let _ = writeln!(
             jumps,
-            "    .globl AheadLibEx_{name}\nAheadLibEx_{name}:\n    jmp QWORD PTR [rip + pfnAheadLibEx_{name}]\n",
-            name = exp.stub
+            "    .globl AheadLibEx_Resolve_{name}\nAheadLibEx_Resolve_{name}:\n    push {index}\n    jmp AheadLibEx_ResolveThunk\n",
+            name = exp.stub,
+            index = exp.table_index.unwrap_or_default()
         );
     }
+    jumps.push_str(&render_call_thunks(
+        TPL_CALL_THUNK_X64_GAS,
+        &ctx.options,
+        !resolvers.is_empty(),
+    ));
 
     fill(
         TPL_ASM_X64_GAS,
@@ -962,7 +2923,8 @@ pub fn render_asm_x64_gas(ctx: &VsTemplateContext) -> String {
 }
 
 pub fn render_def(ctx: &VsTemplateContext, is_x64: bool) -> String {
-    let exports = prepare_exports(ctx.exports);
+    let exports = prepare_exports(ctx);
+    let origin_module = origin_forward_module(ctx);
 
     fn needs_quotes(name: &str) -> bool {
         name.chars().any(|c| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'))
@@ -982,8 +2944,10 @@ pub fn render_def(ctx: &VsTemplateContext, is_x64: bool) -> String {
 
     for exp in &exports {
         let export_name = quote_if_needed(&exp.label);
-        let internal = if is_x64 {
-            format!("AheadLibEx_{}", exp.stub)
+        let internal = if let Some(target) = exp.forward_to(origin_module.as_deref()) {
+            quote_if_needed(&target)
+        } else if is_x64 || exp.is_typed_hook() {
+            exp.internal_symbol()
         } else {
             format!("_AheadLibEx_{}", exp.stub)
         };
@@ -1002,33 +2966,845 @@ pub fn render_def(ctx: &VsTemplateContext, is_x64: bool) -> String {
     out
 }
 
+const CMAKE_MINIMAL_DEPS: &str = r#"# Minimal dependencies: kernel32 only, no C runtime, DllMain is the entry point.
+target_link_libraries(${PROJECT_NAME} PRIVATE kernel32)
+if(MSVC)
+  string(REPLACE "/RTC1" "" CMAKE_C_FLAGS_DEBUG "${CMAKE_C_FLAGS_DEBUG}")
+  target_compile_options(${PROJECT_NAME} PRIVATE /GS- /Zl)
+  target_link_options(${PROJECT_NAME} PRIVATE /NODEFAULTLIB /ENTRY:DllMain)
+else()
+  if(CMAKE_SIZEOF_VOID_P EQUAL 8)
+    set(AHEADLIBEX_ENTRY "DllMain")
+  else()
+    set(AHEADLIBEX_ENTRY "_DllMain@12")
+  endif()
+  target_compile_options(${PROJECT_NAME} PRIVATE -fno-stack-protector -fno-stack-check)
+  target_link_options(${PROJECT_NAME} PRIVATE -nostdlib "-Wl,-e,${AHEADLIBEX_ENTRY}")
+endif()
+"#;
+
 pub fn render_cmake_lists(ctx: &VsTemplateContext, is_x64: bool) -> String {
     let cmake_project_name = sanitize_build_target_name(&format!("AheadLibEx_{}", ctx.base_name));
 
     let (c_src, asm_masm, asm_gas) = if is_x64 {
         (
-            format!("{}_x64.c", ctx.base_name),
+            proxy_source_name(ctx, true),
             format!("{}_x64_jump.asm", ctx.base_name),
             format!("{}_x64_jump.S", ctx.base_name),
         )
     } else {
         (
-            format!("{}_x86.c", ctx.base_name),
+            proxy_source_name(ctx, false),
             format!("{}_x86_jump.asm", ctx.base_name),
             format!("{}_x86_jump.S", ctx.base_name),
         )
     };
+    let (asm_masm, asm_gas) = if needs_jump_table(ctx) {
+        (asm_masm, asm_gas)
+    } else {
+        (String::new(), String::new())
+    };
 
     fill(
         TPL_CMAKE_LISTS,
         &[
             ("CMAKE_PROJECT_NAME", cmake_project_name),
+            ("LANGUAGES", if ctx.options.cpp { "CXX" } else { "C" }.to_string()),
             ("BASE", ctx.base_name.to_string()),
             ("OUTPUT_NAME", ctx.base_name.to_string()),
             ("C_SRC", c_src),
+            (
+                "PAYLOAD_SRC",
+                if needs_payload_file(ctx) { payload_file_name(ctx) } else { String::new() },
+            ),
             ("ASM_MASM_SRC", asm_masm),
             ("ASM_GAS_SRC", asm_gas),
             ("DEF_SRC", format!("{}.def", ctx.base_name)),
+            (
+                "LINK_LIBRARIES",
+                if ctx.options.minimal_deps {
+                    CMAKE_MINIMAL_DEPS
+                } else {
+                    "target_link_libraries(${PROJECT_NAME} PRIVATE user32)\n"
+                }
+                .to_string(),
+            ),
+        ],
+    )
+}
+
+// Contents of a single-quoted Meson string.
+fn escape_meson_string(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+// System libraries the generated sources import from. The origin runtime formats its messages
+// with wsprintf and reports through MessageBox (user32); a forward-only proxy never loads the
+// original itself and only needs user32 for the helpers that use it.
+pub fn system_libraries(ctx: &VsTemplateContext) -> Vec<&'static str> {
+    if ctx.options.minimal_deps {
+        return vec!["kernel32"];
+    }
+    let has_stubs = prepare_exports(ctx).iter().any(|e| e.needs_pointer());
+    let user32 = has_stubs
+        || ctx.options.payload == PayloadEntry::Demo
+        || ctx.options.plugins.is_some()
+        || ctx.options.crash_guard;
+    if user32 {
+        vec!["kernel32", "user32"]
+    } else {
+        vec!["kernel32"]
+    }
+}
+
+pub fn meson_cross_file_name(is_x64: bool) -> &'static str {
+    if is_x64 {
+        "mingw-w64-x86_64.ini"
+    } else {
+        "mingw-w64-i686.ini"
+    }
+}
+
+const MESON_MINIMAL_DEPS: &str = "if cc.get_argument_syntax() == 'msvc'
+  aheadlibex_args = ['/GS-', '/Zl']
+  aheadlibex_link_args = ['/NODEFAULTLIB', '/ENTRY:DllMain']
+else
+  aheadlibex_args = ['-fno-stack-protector', '-fno-stack-check']
+  aheadlibex_link_args = ['-nostdlib', '-Wl,-e,{{ENTRY}}']
+endif
+";
+
+pub fn render_meson_build(ctx: &VsTemplateContext, is_x64: bool) -> String {
+    let project_name = sanitize_build_target_name(&format!("AheadLibEx_{}", ctx.base_name));
+    let arch = if is_x64 { "x64" } else { "x86" };
+    let (asm_masm, asm_gas) = if needs_jump_table(ctx) {
+        (
+            format!("{}_{arch}_jump.asm", ctx.base_name),
+            format!("{}_{arch}_jump.S", ctx.base_name),
+        )
+    } else {
+        (String::new(), String::new())
+    };
+
+    let mut sources = vec![proxy_source_name(ctx, is_x64)];
+    if needs_payload_file(ctx) {
+        sources.push(payload_file_name(ctx));
+    }
+    let sources = sources
+        .iter()
+        .map(|s| format!("'{}'", escape_meson_string(s)))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let libraries = system_libraries(ctx);
+    let mut link = String::new();
+    if ctx.options.minimal_deps {
+        link.push_str("# Minimal dependencies: kernel32 only, no C runtime, DllMain is the entry point.\n");
+    } else if libraries.contains(&"user32") {
+        link.push_str("# kernel32, plus user32 for the wsprintf and MessageBox calls.\n");
+    } else {
+        link.push_str("# Every export is a linker forwarder: nothing beyond kernel32.\n");
+    }
+    let deps = libraries
+        .iter()
+        .map(|lib| format!("cc.find_library('{lib}')"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let _ = writeln!(link, "aheadlibex_deps = [{deps}]");
+    if ctx.options.minimal_deps {
+        link.push_str(&MESON_MINIMAL_DEPS.replace("{{ENTRY}}", if is_x64 { "DllMain" } else { "_DllMain@12" }));
+    } else {
+        link.push_str("aheadlibex_args = []\naheadlibex_link_args = []\n");
+    }
+
+    fill(
+        TPL_MESON_BUILD,
+        &[
+            ("PROJECT_NAME", project_name),
+            ("LANGUAGE", if ctx.options.cpp { "cpp" } else { "c" }.to_string()),
+            // Meson builds GAS sources with the C compiler.
+            (
+                "PROJECT_LANGUAGES",
+                if ctx.options.cpp { "'cpp', 'c'" } else { "'c'" }.to_string(),
+            ),
+            (
+                "DEFAULT_OPTIONS",
+                // Runtime checks and the CRT selection of debug builds need the C runtime.
+                if ctx.options.minimal_deps {
+                    ", default_options: ['buildtype=release', 'b_vscrt=none']"
+                } else {
+                    ""
+                }
+                .to_string(),
+            ),
+            ("CROSS_FILE", meson_cross_file_name(is_x64).to_string()),
+            ("SOURCES", sources),
+            ("ASM_MASM_SRC", escape_meson_string(&asm_masm)),
+            ("ASM_GAS_SRC", escape_meson_string(&asm_gas)),
+            ("LINK_LIBRARIES", link),
+            ("OUTPUT_NAME", escape_meson_string(ctx.base_name)),
+            ("DEF_SRC", escape_meson_string(&format!("{}.def", ctx.base_name))),
+        ],
+    )
+}
+
+pub fn render_meson_cross_file(is_x64: bool) -> String {
+    let (arch, triple, cpu_family, cpu) = if is_x64 {
+        ("x64", "x86_64-w64-mingw32", "x86_64", "x86_64")
+    } else {
+        ("x86", "i686-w64-mingw32", "x86", "i686")
+    };
+    fill(
+        TPL_MESON_CROSS,
+        &[
+            ("ARCH", arch.to_string()),
+            ("CROSS_FILE", meson_cross_file_name(is_x64).to_string()),
+            ("TRIPLE", triple.to_string()),
+            ("CPU_FAMILY", cpu_family.to_string()),
+            ("CPU", cpu.to_string()),
+        ],
+    )
+}
+
+// Rust crate output: the same proxy as the C sources, with global_asm! jump stubs and hand-written
+// kernel32 bindings so the crate has no dependencies. Only the core runtime is ported.
+pub fn check_rust_crate(ctx: &VsTemplateContext) -> anyhow::Result<()> {
+    let options = &ctx.options;
+    let unsupported = [
+        (options.trace, "call tracing"),
+        (options.lazy, "lazy resolution"),
+        (options.deferred_init, "deferred init"),
+        (options.minimal_deps, "minimal dependencies"),
+        (options.crash_guard, "the crash guard"),
+        (options.cpp, "C++ sources"),
+        (!options.host_filter.is_empty(), "a host filter"),
+        (options.plugins.is_some(), "the plugin loader"),
+        (matches!(options.payload, PayloadEntry::FirstCall(_)), "a first-call payload"),
+        (prepare_exports(ctx).iter().any(|e| e.is_typed_hook()), "typed hooks"),
+    ];
+    if let Some((_, what)) = unsupported.iter().find(|(set, _)| *set) {
+        anyhow::bail!("The Rust crate output doesn't support {what}; generate C sources for that");
+    }
+    Ok(())
+}
+
+// Contents of a Rust string literal.
+fn escape_rust_text_literal(s: &str) -> String {
+    s.escape_default().to_string()
+}
+
+// Same, for the format string of format!.
+fn escape_rust_format_literal(s: &str) -> String {
+    escape_rust_text_literal(s).replace('{', "{{").replace('}', "}}")
+}
+
+// NUL-terminated byte string literal for GetProcAddress; empty for ordinal-only exports.
+fn rust_proc_name(exp: &PreparedExport) -> String {
+    if exp.is_noname() {
+        return "b\"\"".to_string();
+    }
+    let escaped = exp
+        .raw_name
+        .bytes()
+        .flat_map(std::ascii::escape_default)
+        .map(char::from)
+        .collect::<String>();
+    format!("b\"{escaped}\\0\"")
+}
+
+// Cargo wants an identifier; the DLL is `<lib name>.dll`.
+pub fn rust_lib_name(ctx: &VsTemplateContext) -> String {
+    let name = sanitize_identifier(ctx.base_name);
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{name}")
+    } else {
+        name
+    }
+}
+
+pub fn render_rust_cargo_toml(ctx: &VsTemplateContext) -> String {
+    let lib_name = rust_lib_name(ctx);
+    let lib_note = if lib_name == ctx.base_name {
+        String::new()
+    } else {
+        format!(
+            "# Cargo only allows identifier characters here: rename the built {lib_name}.dll to {}.\n",
+            ctx.dll_name
+        )
+    };
+    fill(
+        TPL_RUST_CARGO_TOML,
+        &[
+            ("PACKAGE_NAME", format!("aheadlibex_{}", lib_name.to_ascii_lowercase())),
+            ("LIB_NOTE", lib_note),
+            ("LIB_NAME", lib_name),
+        ],
+    )
+}
+
+pub fn render_rust_build_rs(ctx: &VsTemplateContext, is_x64: bool) -> String {
+    let (arch, triple) = if is_x64 { ("x86_64", "x86_64") } else { ("x86", "i686") };
+    fill(
+        TPL_RUST_BUILD_RS,
+        &[
+            ("DLL_NAME", escape_rust_format_literal(ctx.dll_name)),
+            // A plain string literal: it goes through Path::join, and into println! only as an argument.
+            ("DEF_FILE", escape_rust_text_literal(&format!("{}.def", ctx.base_name))),
+            ("TARGET_ARCH", arch.to_string()),
+            ("TARGET_ARCH_TRIPLE", triple.to_string()),
+        ],
+    )
+}
+
+// One global_asm! per stub, with the labels render_def names, plus the DllMain label.
+pub fn render_rust_jump(ctx: &VsTemplateContext, is_x64: bool) -> String {
+    let mut jumps = String::new();
+    for exp in prepare_exports(ctx).iter().filter(|e| e.is_stub()) {
+        let _ = writeln!(jumps, "// {} @{}", exp.label, exp.ordinal);
+        jumps.push_str("global_asm!(\n");
+        if is_x64 {
+            let _ = writeln!(jumps, "    \".globl AheadLibEx_{}\",", exp.stub);
+            let _ = writeln!(jumps, "    \"AheadLibEx_{}:\",", exp.stub);
+            jumps.push_str("    \"    jmp qword ptr [rip + {ptr}]\",\n");
+        } else {
+            // Both spellings, as in the GAS jump table: the .def names the decorated one.
+            let _ = writeln!(jumps, "    \".globl AheadLibEx_{}\",", exp.stub);
+            let _ = writeln!(jumps, "    \".globl _AheadLibEx_{}\",", exp.stub);
+            let _ = writeln!(jumps, "    \"AheadLibEx_{}:\",", exp.stub);
+            let _ = writeln!(jumps, "    \"_AheadLibEx_{}:\",", exp.stub);
+            jumps.push_str("    \"    jmp dword ptr [{ptr}]\",\n");
+        }
+        let _ = writeln!(jumps, "    ptr = sym crate::pfnAheadLibEx_{},\n);\n", exp.stub);
+    }
+    let entry = if is_x64 { "DllMain" } else { "\\\"_DllMain@12\\\"" };
+    jumps.push_str("// The C runtime's DLL entry point calls DllMain; a plain label keeps it out of the exports.\n");
+    jumps.push_str("global_asm!(\n");
+    let _ = writeln!(jumps, "    \".globl {entry}\",");
+    let _ = writeln!(jumps, "    \"{entry}:\",");
+    jumps.push_str("    \"    jmp {entry}\",\n    entry = sym crate::dll_main,\n);\n");
+
+    fill(
+        TPL_RUST_JUMP,
+        &[
+            ("ARCH", if is_x64 { "x64" } else { "x86" }.to_string()),
+            ("JUMPS", jumps),
+        ],
+    )
+}
+
+// `report_failure` plus the fallback stubs, and how get_address ends after reporting.
+fn render_rust_failure_policy(ctx: &VsTemplateContext, exports: &[PreparedExport], is_x64: bool) -> (String, &'static str) {
+    let debug_string = "    unsafe { sys::OutputDebugStringW(sys::wide(message).as_ptr()) };\n";
+    let mut out = String::new();
+    if matches!(ctx.options.failure_policy, FailurePolicy::Log | FailurePolicy::FailLoad) {
+        out.push_str(&render_rust_missing_exports(exports, is_x64));
+    }
+    let missing = match ctx.options.failure_policy {
+        FailurePolicy::Abort => {
+            out.push_str("fn report_failure(message: &str, _error: u32) {\n");
+            out.push_str("    let (text, caption) = (sys::wide(message), sys::wide(\"AheadLibEx\"));\n");
+            out.push_str("    unsafe { sys::MessageBoxW(null_mut(), text.as_ptr(), caption.as_ptr(), sys::MB_ICONSTOP) };\n}\n");
+            "    unsafe { sys::ExitProcess(0) }\n"
+        }
+        FailurePolicy::Exit => {
+            out.push_str("fn report_failure(message: &str, error: u32) {\n");
+            out.push_str(debug_string);
+            out.push_str("    unsafe { sys::ExitProcess(error) }\n}\n");
+            "    fallback\n"
+        }
+        FailurePolicy::Log => {
+            out.push_str("// Failure policy \"log\": report to the debugger and append to <proxy>.dll.log, then carry on.\n");
+            out.push_str("fn report_failure(message: &str, error: u32) {\n");
+            let _ = writeln!(
+                out,
+                "    let line = format!(\"AheadLibEx [{}] {{message}} (error {{error}})\\r\\n\");",
+                escape_rust_format_literal(ctx.dll_name)
+            );
+            out.push_str("    unsafe { sys::OutputDebugStringW(sys::wide(&line).as_ptr()) };\n\n");
+            out.push_str("    let mut module = null_mut();\n");
+            out.push_str("    let flags = sys::GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS | sys::GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT;\n");
+            out.push_str("    if unsafe { sys::GetModuleHandleExW(flags, report_failure as *const u16, &mut module) } == 0 {\n");
+            out.push_str("        return;\n    }\n");
+            out.push_str("    let mut path = [0u16; 1024];\n");
+            out.push_str("    let length = unsafe { sys::GetModuleFileNameW(module, path.as_mut_ptr(), path.len() as u32) } as usize;\n");
+            out.push_str("    if length == 0 || length >= path.len() {\n        return;\n    }\n");
+            out.push_str("    let log = String::from_utf16_lossy(&path[..length]) + \".log\";\n");
+            out.push_str("    if let Ok(mut file) = std::fs::OpenOptions::new().append(true).create(true).open(log) {\n");
+            out.push_str("        let _ = std::io::Write::write_all(&mut file, line.as_bytes());\n    }\n}\n");
+            "    fallback\n"
+        }
+        FailurePolicy::FailLoad => {
+            out.push_str("fn report_failure(message: &str, _error: u32) {\n");
+            out.push_str(debug_string);
+            out.push_str("}\n");
+            "    MISSING_EXPORTS.fetch_add(1, Ordering::Relaxed);\n    fallback\n"
+        }
+    };
+    (out, missing)
+}
+
+// missing_export_fallback for the Rust crate.
+fn rust_missing_export_fallback(exp: &PreparedExport, policy: FailurePolicy, is_x64: bool) -> String {
+    if matches!(policy, FailurePolicy::Log | FailurePolicy::FailLoad) {
+        format!("{} as *mut c_void", exp.x86_cleanup.fallback(is_x64))
+    } else {
+        "null_mut()".to_string()
+    }
+}
+
+// Rust side of render_missing_exports: the same fallbacks, under the same names, with dummy
+// arguments so rustc emits the matching `ret N`.
+fn render_rust_missing_exports(exports: &[PreparedExport], is_x64: bool) -> String {
+    let mut out = String::new();
+    for cleanup in fallback_cleanups(exports, is_x64) {
+        let (abi, registers, bytes) = match cleanup {
+            X86Cleanup::Caller => {
+                out.push_str("// Stands in for exports the original DLL does not provide.\n");
+                out.push_str("extern \"system\" fn missing_export() -> usize {\n");
+                out.push_str("    unsafe { sys::SetLastError(sys::ERROR_PROC_NOT_FOUND) };\n    0\n}\n\n");
+                continue;
+            }
+            X86Cleanup::Stdcall(bytes) => ("stdcall", 0, bytes),
+            X86Cleanup::Fastcall(bytes) => ("fastcall", 2, bytes),
+            X86Cleanup::Unknown => {
+                out.push_str("// Undecorated exports without a prototype: nothing says how many bytes their callers pushed,\n");
+                out.push_str("// so returning could unbalance the stack. A prototype gives the export a fallback that returns.\n");
+                out.push_str("extern \"system\" fn missing_export_unknown() -> usize {\n");
+                out.push_str("    report_failure(\"Called a missing export whose argument size is unknown.\", sys::ERROR_PROC_NOT_FOUND);\n");
+                out.push_str("    unsafe { sys::ExitProcess(sys::ERROR_PROC_NOT_FOUND) }\n}\n\n");
+                continue;
+            }
+        };
+        let params = vec!["_: usize"; (registers + bytes / 4) as usize].join(", ");
+        let _ = write!(
+            out,
+            "// Pops {bytes} bytes.\nextern \"{abi}\" fn {}({params}) -> usize {{\n",
+            cleanup.fallback(false)
+        );
+        out.push_str("    unsafe { sys::SetLastError(sys::ERROR_PROC_NOT_FOUND) };\n    0\n}\n\n");
+    }
+    out
+}
+
+// payload_main's caller plus whatever starts it, and the DllMain call site.
+fn render_rust_payload_start(payload: &PayloadEntry) -> (String, String) {
+    let mut out = String::new();
+    let run = if *payload == PayloadEntry::Demo {
+        out.push_str("\nfn payload_entry() {\n    payload::payload_main();\n");
+        out.push_str("    let (text, caption) = (sys::wide(\"AheadLibExTest!\"), sys::wide(\"AheadLibEx\"));\n");
+        out.push_str("    unsafe { sys::MessageBoxW(null_mut(), text.as_ptr(), caption.as_ptr(), sys::MB_OK) };\n}\n");
+        "payload_entry()"
+    } else {
+        "payload::payload_main()"
+    };
+    let thread_proc = format!("\nunsafe extern \"system\" fn payload_thread_proc(_context: *mut c_void) -> u32 {{\n    {run};\n    0\n}}\n");
+    let spawn = "    let thread = unsafe { sys::CreateThread(null_mut(), 0, payload_thread_proc, null_mut(), 0, null_mut()) };\n    if !thread.is_null() {\n        unsafe { sys::CloseHandle(thread) };\n    }\n";
+    let attach = match payload {
+        PayloadEntry::None => return (String::new(), String::new()),
+        PayloadEntry::Thread | PayloadEntry::Demo => {
+            out.push_str(&thread_proc);
+            let _ = write!(out, "\nfn start_payload() {{\n{spawn}}}\n");
+            "        start_payload();\n"
+        }
+        PayloadEntry::Sync => {
+            out.push_str("\n// Runs inside DllMain under loader lock: no LoadLibrary, COM or waiting on other threads.\n");
+            let _ = writeln!(out, "fn start_payload() {{\n    {run};\n}}");
+            "        start_payload();\n"
+        }
+        PayloadEntry::HostReady => {
+            let _ = writeln!(out, "\nunsafe extern \"system\" fn payload_apc_proc(_context: usize) {{\n    {run};\n}}");
+            out.push_str(&thread_proc);
+            out.push_str("\n// Static imports (reserved != null) load while the process starts; an APC queued to the\n");
+            out.push_str("// loading thread runs once every static import is initialized, before the host's entry point.\n");
+            out.push_str("// A later LoadLibrary has no such point to wait for, so the payload gets its own thread.\n");
+            out.push_str("fn schedule_payload(reserved: *mut c_void) {\n");
+            out.push_str("    if !reserved.is_null() && unsafe { sys::QueueUserAPC(payload_apc_proc, sys::GetCurrentThread(), 0) } != 0 {\n");
+            out.push_str("        return;\n    }\n");
+            out.push_str(spawn);
+            out.push_str("}\n");
+            "        schedule_payload(reserved);\n"
+        }
+        PayloadEntry::FirstCall(_) => unreachable!("rejected by check_rust_crate"),
+    };
+    (out, attach.to_string())
+}
+
+pub fn render_rust_lib(ctx: &VsTemplateContext, is_x64: bool) -> String {
+    let exports = prepare_exports(ctx);
+    let has_stubs = exports.iter().any(|e| e.needs_pointer());
+    let payload = &ctx.options.payload;
+
+    let mut imports = String::new();
+    if has_stubs || !matches!(payload, PayloadEntry::None | PayloadEntry::Sync) {
+        imports.push_str("use core::ptr::null_mut;\n");
+    }
+    if has_stubs {
+        imports.push_str("use core::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};\n");
+    }
+    let mut modules = String::new();
+    if has_stubs {
+        modules.push_str("mod origin;\n");
+    }
+    if needs_rust_payload_file(ctx) {
+        modules.push_str("mod payload;\n");
+    }
+
+    let mut pointers = String::new();
+    let mut origin_runtime = String::new();
+    let mut attach = String::new();
+    let mut detach = String::new();
+    let origin_module = if has_stubs {
+        pointers.push_str("\n// Resolved from the original by init_forwarders; the stubs in jump.rs jump through these.\n");
+        for exp in exports.iter().filter(|e| e.needs_pointer()) {
+            let _ = writeln!(
+                pointers,
+                "pub static pfnAheadLibEx_{}: AtomicPtr<c_void> = AtomicPtr::new(null_mut()); // {} @{}",
+                exp.stub, exp.label, exp.ordinal
+            );
+        }
+        for exp in exports.iter().filter(|e| e.is_hook()) {
+            let _ = writeln!(
+                pointers,
+                "pub static pfnAheadLibEx_Orig_{}: AtomicPtr<c_void> = AtomicPtr::new(null_mut());",
+                exp.stub
+            );
+        }
+
+        let (failure_policy, missing_export) = render_rust_failure_policy(ctx, &exports, is_x64);
+        let rt = &mut origin_runtime;
+        rt.push_str("\nstatic ORIGIN_MODULE: AtomicPtr<c_void> = AtomicPtr::new(null_mut());\n");
+        rt.push_str("static MISSING_EXPORTS: AtomicUsize = AtomicUsize::new(0);\n\n");
+        rt.push_str(&failure_policy);
+        rt.push_str("\nfn load_original_module(module: sys::HMODULE) -> bool {\n");
+        rt.push_str("    match origin::load_original(module) {\n");
+        rt.push_str("        Ok(handle) => {\n            ORIGIN_MODULE.store(handle, Ordering::Release);\n            true\n        }\n");
+        rt.push_str("        Err(message) => {\n            report_failure(&message, sys::ERROR_MOD_NOT_FOUND);\n");
+        if ctx.options.failure_policy == FailurePolicy::Log {
+            rt.push_str("            // With `log`, a missing original is not fatal: every export gets its missing_export fallback.\n            true\n");
+        } else {
+            rt.push_str("            false\n");
+        }
+        rt.push_str("        }\n    }\n}\n\n");
+        rt.push_str("fn free_origin_module() {\n");
+        rt.push_str("    let handle = ORIGIN_MODULE.swap(null_mut(), Ordering::AcqRel);\n");
+        rt.push_str("    if !handle.is_null() {\n        unsafe { sys::FreeLibrary(handle) };\n    }\n}\n\n");
+        rt.push_str("// `name` is NUL-terminated; an empty name looks the export up by ordinal. `fallback` stands in\n");
+        rt.push_str("// when the original lacks the export and the policy carries on.\n");
+        // `abort` exits instead, so the fallback goes unused.
+        let fallback = if ctx.options.failure_policy == FailurePolicy::Abort { "_fallback" } else { "fallback" };
+        let _ = writeln!(rt, "fn get_address(name: &[u8], ordinal: u16, {fallback}: *mut c_void) -> *mut c_void {{");
+        rt.push_str("    let module = ORIGIN_MODULE.load(Ordering::Acquire);\n");
+        rt.push_str("    let proc_name = if name.is_empty() { usize::from(ordinal) as *const u8 } else { name.as_ptr() };\n");
+        rt.push_str("    let address = if module.is_null() { null_mut() } else { unsafe { sys::GetProcAddress(module, proc_name) } };\n");
+        rt.push_str("    if !address.is_null() {\n        return address;\n    }\n");
+        rt.push_str("    let export = if name.is_empty() {\n        format!(\"#{ordinal}\")\n    } else {\n");
+        rt.push_str("        String::from_utf8_lossy(&name[..name.len() - 1]).into_owned()\n    };\n");
+        rt.push_str("    report_failure(&format!(\"Cannot locate export {export}.\"), sys::ERROR_PROC_NOT_FOUND);\n");
+        rt.push_str(missing_export);
+        rt.push_str("}\n\nfn init_forwarders() -> bool {\n");
+        for exp in exports.iter().filter(|e| e.needs_pointer()) {
+            let _ = writeln!(
+                rt,
+                "    pfnAheadLibEx_{}.store(get_address({}, {}, {}), Ordering::Release);",
+                exp.stub,
+                rust_proc_name(exp),
+                exp.ordinal,
+                rust_missing_export_fallback(exp, ctx.options.failure_policy, is_x64)
+            );
+        }
+        rt.push_str("    MISSING_EXPORTS.load(Ordering::Relaxed) == 0\n}\n");
+
+        let hooks = exports.iter().filter(|e| e.is_hook()).collect::<Vec<_>>();
+        if !hooks.is_empty() {
+            // The detours are the user's code, so they are set in payload.rs, which is never rewritten.
+            rt.push_str("\n// Saves the original of every hooked export, then lets payload.rs point them at detours.\n");
+            rt.push_str("fn install_hooks() {\n");
+            for exp in &hooks {
+                let _ = writeln!(
+                    rt,
+                    "    pfnAheadLibEx_Orig_{name}.store(pfnAheadLibEx_{name}.load(Ordering::Acquire), Ordering::Release);",
+                    name = exp.stub
+                );
+            }
+            rt.push_str("    payload::install_hooks();\n}\n");
+        }
+
+        attach.push_str("        if !load_original_module(module) {\n            return 0;\n        }\n");
+        if ctx.options.failure_policy == FailurePolicy::FailLoad {
+            attach.push_str("        if !init_forwarders() {\n            free_origin_module();\n            return 0;\n        }\n");
+        } else {
+            attach.push_str("        init_forwarders();\n");
+        }
+        if !hooks.is_empty() {
+            attach.push_str("        install_hooks();\n");
+        }
+        detach.push_str(" else if reason == sys::DLL_PROCESS_DETACH {\n        free_origin_module();\n    }");
+        "    ORIGIN_MODULE.load(Ordering::Acquire)\n".to_string()
+    } else {
+        // Every export is a linker forwarder; the loader maps the original for us, and it is
+        // whatever module a forwarded export resolves into.
+        match exports
+            .iter()
+            .find(|e| matches!(e.forward_target, Some(ForwardTarget::Origin(_))))
+        {
+            Some(exp) => format!(
+                "    let flags = sys::GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS | sys::GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT;
+    let mut own = core::ptr::null_mut();
+    let mut origin = core::ptr::null_mut();
+    // @{ordinal} ({label}) is forwarded, so resolving it through the proxy lands in the original.
+    unsafe {{
+        if sys::GetModuleHandleExW(flags, origin_module as *const u16, &mut own) == 0 {{
+            return origin;
+        }}
+        let address = sys::GetProcAddress(own, usize::from({ordinal}u16) as *const u8);
+        if !address.is_null() {{
+            sys::GetModuleHandleExW(flags, address as *const u16, &mut origin);
+        }}
+    }}
+    origin
+",
+                ordinal = exp.ordinal,
+                label = exp.label
+            ),
+            None => "    // No export forwards to the original, so the loader never maps it.\n    core::ptr::null_mut()\n".to_string(),
+        }
+    };
+
+    let (payload_fns, attach_payload) = render_rust_payload_start(payload);
+    attach.push_str(&attach_payload);
+
+    fill(
+        TPL_RUST_LIB,
+        &[
+            ("ARCH", if is_x64 { "x64" } else { "x86" }.to_string()),
+            ("DLL_NAME", ctx.dll_name.to_string()),
+            ("IMPORTS", imports),
+            ("MODULES", modules),
+            ("POINTERS", pointers),
+            ("ORIGIN_RUNTIME", origin_runtime),
+            ("ORIGIN_MODULE", origin_module),
+            ("PAYLOAD", payload_fns),
+            (
+                "RESERVED",
+                if *payload == PayloadEntry::HostReady { "reserved" } else { "_reserved" }.to_string(),
+            ),
+            ("ATTACH", attach),
+            ("DETACH", detach),
+        ],
+    )
+}
+
+fn rust_origin_summary(ctx: &VsTemplateContext, candidate: &OriginLoadModeOwned) -> String {
+    match candidate.as_borrowed() {
+        OriginLoadMode::SystemDir => "the system directory".to_string(),
+        OriginLoadMode::SameDir { original_name } => format!("{original_name} beside the proxy"),
+        OriginLoadMode::CustomPath { path } => path.to_string(),
+        OriginLoadMode::Env { var } => format!(
+            "%{}%",
+            var.map(str::to_string).unwrap_or_else(|| default_origin_env_var(ctx.base_name))
+        ),
+        OriginLoadMode::Config { file } => file
+            .map(str::to_string)
+            .unwrap_or_else(|| default_origin_config_file(ctx.base_name)),
+        OriginLoadMode::Search { .. } => "a System32/Windows/PATH search".to_string(),
+        OriginLoadMode::LoadEx { flags, .. } => format!("LoadLibraryEx with {}", search_flags_expr(flags)),
+        OriginLoadMode::Chain(_) => String::new(),
+    }
+}
+
+// One `candidate_<n>` per location, mirroring the C origin_candidate_<n>, then load_original.
+pub fn render_rust_origin(ctx: &VsTemplateContext) -> String {
+    let candidates = ctx.origin_load_mode.candidate_list();
+    let dll = escape_rust_text_literal(ctx.dll_name);
+    let mut out = String::new();
+    for (idx, candidate) in candidates.iter().enumerate() {
+        let mut uses_module = true;
+        let mut body = String::new();
+        match candidate.as_borrowed() {
+            OriginLoadMode::SystemDir => {
+                uses_module = false;
+                body.push_str("    // System directory\n");
+                let _ = writeln!(body, "    Some(join(known_dir(sys::GetSystemDirectoryW)?, \"{dll}\"))");
+            }
+            OriginLoadMode::SameDir { original_name } => {
+                body.push_str("    // Proxy directory\n");
+                let _ = writeln!(body, "    Some(join(module_dir(module)?, \"{}\"))", escape_rust_text_literal(original_name));
+            }
+            OriginLoadMode::CustomPath { path } => {
+                let _ = writeln!(body, "    resolve(module, &units(\"{}\"))", escape_rust_text_literal(path));
+            }
+            OriginLoadMode::Env { var } => {
+                let var = var
+                    .map(str::to_string)
+                    .unwrap_or_else(|| default_origin_env_var(ctx.base_name));
+                let _ = writeln!(body, "    resolve(module, &env(\"{}\")?)", escape_rust_text_literal(&var));
+            }
+            OriginLoadMode::Config { file } => {
+                let file = file
+                    .map(str::to_string)
+                    .unwrap_or_else(|| default_origin_config_file(ctx.base_name));
+                let reader = if is_json_config(&file) { "read_json_path" } else { "read_ini_path" };
+                let _ = writeln!(body, "    let config = resolve(module, &units(\"{}\"))?;", escape_rust_text_literal(&file));
+                let _ = writeln!(body, "    resolve(module, &{reader}(&config)?)");
+            }
+            OriginLoadMode::Search { name } => {
+                let name = name.map(escape_rust_text_literal).unwrap_or_else(|| dll.clone());
+                let _ = writeln!(body, "    search(module, \"{name}\")");
+            }
+            OriginLoadMode::LoadEx { flags, dirs } => {
+                let _ = writeln!(body, "    // {}", search_flags_expr(flags));
+                // Every directory is registered first so the original's own dependencies resolve too.
+                for dir in dirs {
+                    let _ = writeln!(body, "    add_dll_directory(module, \"{}\");", escape_rust_text_literal(dir));
+                }
+                let mut probes = Vec::new();
+                if flags & (LOAD_LIBRARY_SEARCH_APPLICATION_DIR | LOAD_LIBRARY_SEARCH_DEFAULT_DIRS) != 0 {
+                    probes.push("module_dir(null_mut())".to_string());
+                }
+                for dir in dirs {
+                    probes.push(format!("resolve(module, &units(\"{}\"))", escape_rust_text_literal(dir)));
+                }
+                if flags & (LOAD_LIBRARY_SEARCH_SYSTEM32 | LOAD_LIBRARY_SEARCH_DEFAULT_DIRS) != 0 {
+                    probes.push("known_dir(sys::GetSystemDirectoryW)".to_string());
+                }
+                if probes.is_empty() {
+                    uses_module = !dirs.is_empty();
+                    body.push_str("    None\n");
+                } else {
+                    for (n, probe) in probes.iter().enumerate() {
+                        let call = format!("probe({probe}, \"{dll}\", module)");
+                        if n == 0 {
+                            let _ = writeln!(body, "    {call}");
+                        } else {
+                            let _ = writeln!(body, "        .or_else(|| {call})");
+                        }
+                    }
+                }
+            }
+            OriginLoadMode::Chain(_) => {
+                uses_module = false;
+                body.push_str("    None\n");
+            }
+        }
+        let _ = writeln!(
+            out,
+            "fn candidate_{}({}: HMODULE) -> Option<WidePath> {{\n{body}}}\n",
+            idx + 1,
+            if uses_module { "module" } else { "_module" }
+        );
+    }
+
+    let mut load = String::new();
+    let _ = writeln!(load, "    let candidates: [(Candidate, u32); {}] = [", candidates.len());
+    for (idx, candidate) in candidates.iter().enumerate() {
+        let flags = match candidate {
+            OriginLoadModeOwned::LoadEx { flags, .. } => format!("0x{flags:04X}"),
+            _ => "0".to_string(),
+        };
+        let _ = writeln!(load, "        (candidate_{}, {flags}),", idx + 1);
+    }
+    load.push_str("    ];\n");
+    // Only a single location names the path it tried in the message.
+    let single = candidates.len() == 1;
+    if single {
+        load.push_str("    let mut path = None;\n");
+    }
+    load.push_str("    let mut error = sys::ERROR_MOD_NOT_FOUND;\n");
+    load.push_str("    for (candidate, flags) in candidates {\n");
+    // Directories a candidate registered go away again before the next one is tried.
+    let dll_dirs = candidates
+        .iter()
+        .any(|c| matches!(c, OriginLoadModeOwned::LoadEx { dirs, .. } if !dirs.is_empty()));
+    load.push_str("        let Some(found) = candidate(module) else {\n");
+    if dll_dirs {
+        load.push_str("            remove_dll_directories();\n");
+    }
+    load.push_str("            continue;\n        };\n");
+    if dll_dirs {
+        load.push_str("        let loaded = load(&found, flags, module);\n        remove_dll_directories();\n");
+        load.push_str("        match loaded {\n");
+    } else {
+        load.push_str("        match load(&found, flags, module) {\n");
+    }
+    load.push_str("            Ok(handle) => {\n                trace_loaded(&found);\n                return Ok(handle);\n            }\n");
+    load.push_str("            Err(code) => error = code,\n        }\n");
+    if single {
+        load.push_str("        path = Some(found);\n");
+    }
+    load.push_str("    }\n");
+    if single {
+        let _ = writeln!(
+            load,
+            "    let path = path.map_or_else(|| \"{}\".to_string(), |p| String::from_utf16_lossy(&p));",
+            dll
+        );
+        load.push_str("    Err(format!(\"Cannot locate {path}, AheadLibEx cannot continue.\\nerror code:0x{error:08X}\"))\n");
+    } else {
+        let _ = writeln!(
+            load,
+            "    Err(format!(\"Cannot locate {} in any of {} locations, AheadLibEx cannot continue.\\nerror code:0x{{error:08X}}\"))",
+            escape_rust_format_literal(ctx.dll_name),
+            candidates.len()
+        );
+    }
+
+    let summary = candidates
+        .iter()
+        .map(|c| rust_origin_summary(ctx, c))
+        .collect::<Vec<_>>()
+        .join(", then ");
+    fill(
+        TPL_RUST_ORIGIN,
+        &[
+            ("DLL_NAME", ctx.dll_name.to_string()),
+            ("ORIGIN_SUMMARY", summary),
+            ("CANDIDATES", out),
+            ("LOAD_ORIGINAL", load),
+        ],
+    )
+}
+
+pub fn render_rust_sys() -> String {
+    TPL_RUST_SYS.to_string()
+}
+
+// payload.rs: payload_main when there is a payload, install_hooks when there are hooks.
+pub fn needs_rust_payload_file(ctx: &VsTemplateContext) -> bool {
+    ctx.options.payload != PayloadEntry::None || prepare_exports(ctx).iter().any(|e| e.is_hook())
+}
+
+pub fn render_rust_payload(ctx: &VsTemplateContext) -> String {
+    let mut payload_main = String::new();
+    if ctx.options.payload != PayloadEntry::None {
+        payload_main.push('\n');
+        if ctx.options.payload == PayloadEntry::Sync {
+            payload_main.push_str("// Called from DllMain under loader lock: no LoadLibrary, COM or waiting on other threads.\n");
+        }
+        payload_main.push_str("pub fn payload_main() {\n    // TODO: your patch process begins here.\n}\n");
+    }
+
+    let mut install_hooks = String::new();
+    let hooks = prepare_exports(ctx).into_iter().filter(|e| e.is_hook()).collect::<Vec<_>>();
+    if !hooks.is_empty() {
+        install_hooks.push_str("\n// Runs once the exports are resolved. Hooked exports jump through crate::pfnAheadLibEx_<name>:\n");
+        install_hooks.push_str("// point it at your detour here, and call crate::pfnAheadLibEx_Orig_<name> from the detour\n");
+        install_hooks.push_str("// to reach the original.\npub fn install_hooks() {\n");
+        for exp in &hooks {
+            let _ = writeln!(
+                install_hooks,
+                "    // TODO: crate::pfnAheadLibEx_{name}.store(Detour_{name} as *mut core::ffi::c_void, core::sync::atomic::Ordering::Release);",
+                name = exp.stub
+            );
+        }
+        install_hooks.push_str("}\n");
+    }
+
+    fill(
+        TPL_RUST_PAYLOAD,
+        &[
+            ("DLL_NAME", ctx.dll_name.to_string()),
+            ("PAYLOAD_MAIN", payload_main),
+            ("INSTALL_HOOKS", install_hooks),
         ],
     )
 }
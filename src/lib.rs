@@ -3,7 +3,13 @@ pub mod domain;
 pub mod infrastructure;
 pub mod presentation;
 
+pub use application::batch;
 pub use application::ui_events;
 pub use domain::dll;
+pub use domain::export_diff;
+pub use domain::export_policy;
+pub use domain::host_filter;
+pub use domain::prototype;
+pub use domain::verify;
 pub use infrastructure::templates;
 pub use presentation::gui;
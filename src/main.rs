@@ -1,8 +1,15 @@
 #![cfg_attr(all(windows, not(debug_assertions)), windows_subsystem = "windows")]
 
-use aheadlibex_rs::{gui, ui_events};
+use aheadlibex_rs::export_policy::{
+    self, ExportRule, ExportStrategy, FailurePolicy, PayloadEntry, PluginLoader,
+};
+use aheadlibex_rs::templates::ProxyOptions;
+use aheadlibex_rs::{batch, dll, export_diff, gui, host_filter, prototype, ui_events, verify};
 use anyhow::{bail, Result};
-use std::{env, path::PathBuf};
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
 use ui_events::{generate_cli, OutputTarget};
 
 #[cfg(windows)]
@@ -12,16 +19,64 @@ use windows_sys::Win32::System::Console::{
 
 fn print_usage() {
     println!("AheadLibEx usage:");
-    println!("  aheadlibex-rs.exe <source|vs2022|vs2026|cmake> <dll_path> <output_dir> [options]");
+    println!("  aheadlibex-rs.exe <source|vs2022|vs2026|cmake|meson|rust> <dll_path> <output_dir> [options]");
+    println!("  aheadlibex-rs.exe batch <target[,target...]> <input_dir|glob> <output_dir> [batch options] [options]");
+    println!("  aheadlibex-rs.exe diff <old_dll|old_list> <new_dll|new_list> [--json]");
+    println!("  aheadlibex-rs.exe exports <dll_path> [list_path]");
+    println!("  aheadlibex-rs.exe verify <proxy_dll> <original_dll|list> [--allow-crt] [--origin-name <name.dll>] [--json]");
     println!("Examples:");
     println!("  aheadlibex-rs.exe source  \"C:\\\\path\\\\to\\\\foo.dll\" \"C:\\\\path\\\\to\\\\out\"");
     println!("  aheadlibex-rs.exe vs2022 \"C:\\\\path\\\\to\\\\foo.dll\" \"C:\\\\path\\\\to\\\\out\"");
     println!("  aheadlibex-rs.exe vs2026 \"C:\\\\path\\\\to\\\\foo.dll\" \"C:\\\\path\\\\to\\\\out\"");
     println!("  aheadlibex-rs.exe cmake  \"C:\\\\path\\\\to\\\\foo.dll\" \"C:\\\\path\\\\to\\\\out\"");
+    println!("  aheadlibex-rs.exe meson  \"C:\\\\path\\\\to\\\\foo.dll\" \"C:\\\\path\\\\to\\\\out\"");
+    println!("  aheadlibex-rs.exe rust   \"C:\\\\path\\\\to\\\\foo.dll\" \"C:\\\\path\\\\to\\\\out\"");
     println!("Options:");
     println!("  --origin-mode <system|samedir|custom>   Where to load the original DLL (default: system).");
+    println!("                                         Repeat (or use samedir:<name> / custom:<path>) to try several locations in order.");
+    println!("                                         env[:<VAR>] / config[:<file.ini|file.json>] read the path at load time");
+    println!("                                         (default <STEM>_ORIGIN / <stem>.origin.ini); a sample config is written to the output.");
+    println!("                                         search[:<name>] looks in System32, Windows and PATH, never the proxy's own directory.");
+    println!("                                         loadex[:<flags>][;<dir>...] uses LoadLibraryEx with LOAD_LIBRARY_SEARCH_* flags");
+    println!("                                         (dll-load-dir,application-dir,user-dirs,system32,default-dirs) and AddDllDirectory.");
     println!("  --origin-name <name.dll>               Used when --origin-mode samedir (default: <stem>_orig.dll).");
     println!("  --origin-path <path>                   Used when --origin-mode custom (absolute path, UNC, or relative to proxy DLL dir).");
+    println!("  --forward-only                         Emit every export as a linker forwarder (no stubs, no asm, no runtime loader).");
+    println!("  --export <selector>=<strategy>         Per-export strategy: forward|stub|hook|drop (repeatable; last match wins).");
+    println!("                                         Selector: export name, @ordinal, or wildcard pattern (e.g. Get*).");
+    println!("  --export-rules <file>                  Read export rules from a file, one <selector>=<strategy> per line.");
+    println!("  --hook-proto \"<C declaration>\"        Signature for a hooked export, e.g. \"int WINAPI Foo(int a, LPCSTR b)\" (repeatable).");
+    println!("  --hook-header <file.h>                 Take hooked export signatures from a header.");
+    println!("                                         Without a signature, hooks fall back to name decorations, then to a pointer-swap stub.");
+    println!("  --trace                                Log every call to <proxy>.trace.log (timestamp, thread, caller, export).");
+    println!("  --lazy                                 Resolve each export on its first call instead of in DllMain.");
+    println!("  --deferred-init                        Keep DllMain loader-lock safe: load the original and start the payload on first export use.");
+    println!("  --on-failure <abort|exit|log|fail-load> Missing original DLL/export: MessageBox + exit (default), silent exit code,");
+    println!("                                         log and return ERROR_PROC_NOT_FOUND, or fail DllMain.");
+    println!("  --minimal-deps                         kernel32 only: no C runtime, no user32 import, DllMain as entry point.");
+    println!("  --crash-guard                          Catch faults in the payload and hook callbacks: log them to <proxy>.dll.crash.log");
+    println!("                                         and switch both off while the exports keep forwarding.");
+    println!("  --cpp                                  Emit .cpp sources with extern \"C\" linkage and a namespaced header API.");
+    println!("  --payload <entry>                      How the payload starts: thread (default), sync, first-call:<export>,");
+    println!("                                         host-ready, none, or demo (thread + test MessageBox).");
+    println!("  --host <rule>                          Only run payload/hooks in matching hosts (repeatable):");
+    println!("                                         name:<exe>, path:<full path>, version:<min>-<max>, sha256:<hex>.");
+    println!("  --host-list <file>                     Host rules from a file, one per line.");
+    println!("  --plugins <dir[\\pattern]>              Load plugin DLLs from payload_main, in name order (default pattern: *.dll;");
+    println!("                                         dir is relative to the proxy DLL unless absolute), e.g. plugins\\*.asi.");
+    println!("  --plugin-init <export>                 Call this export of each plugin with an AHEADLIBEX_PLUGIN_CONTEXT; FALSE unloads it.");
+    println!("Batch:");
+    println!("  --recursive                            Also walk subdirectories of <input_dir>.");
+    println!("  --glob <pattern>                       File name pattern (default: *.dll).");
+    println!("  --report <path>                        JSON report path (default: <output_dir>/aheadlibex_batch.json).");
+    println!("Diff / exports:");
+    println!("  diff compares two DLLs or saved export lists; exits with 1 when they differ.");
+    println!("  --json                                 Print the diff as JSON.");
+    println!("  exports prints the export list, or saves it to [list_path] for a later diff.");
+    println!("Verify:");
+    println!("  verify checks a built proxy against the original; exits with 1 on any mismatch.");
+    println!("  --allow-crt                            Accept C runtime imports (e.g. vcruntime140.dll).");
+    println!("  --origin-name <name.dll>               Extra origin DLL name that must not be imported (repeatable).");
     println!("No arguments -> GUI mode (console auto-detached on Windows).");
 }
 
@@ -67,6 +122,16 @@ fn main() -> Result<()> {
     }
 
     ensure_console_for_cli();
+
+    // Dispatched before the banner: their stdout (`--json`, export lists) is meant for other tools.
+    match args[0].to_ascii_lowercase().as_str() {
+        "diff" => return run_diff(&args[1..]),
+        "exports" => return run_exports(&args[1..]),
+        "verify" => return run_verify(&args[1..]),
+        "batch" => return run_batch(&args[1..]),
+        _ => {}
+    }
+
     print_cli_banner();
 
     if args.len() == 1 && matches!(args[0].as_str(), "-h" | "--help" | "help") {
@@ -75,25 +140,22 @@ fn main() -> Result<()> {
     }
 
     if args.len() < 3 {
-        bail!("Usage: AheadLibEx <source|vs2022|vs2026|cmake> <dll_path> <output_dir> [options]");
-    }
-
-    let target = match args[0].to_ascii_lowercase().as_str() {
-        "source" | "src" | "c" => OutputTarget::Source,
-        "vs2022" | "2022" => OutputTarget::Vs2022,
-        "vs2026" | "2026" => OutputTarget::Vs2026,
-        "cmake" | "cml" => OutputTarget::CMake,
-        other => bail!(
-            "Unknown target '{}'. Use source|vs2022|vs2026|cmake.",
-            other
-        ),
+        bail!("Usage: AheadLibEx <source|vs2022|vs2026|cmake|meson|rust> <dll_path> <output_dir> [options]");
+    }
+
+    let Some(target) = OutputTarget::from_name(&args[0]) else {
+        bail!(
+            "Unknown target '{}'. Use source|vs2022|vs2026|cmake|meson|rust.",
+            args[0]
+        );
     };
 
     let dll_path = PathBuf::from(&args[1]);
     let output_dir = PathBuf::from(&args[2]);
 
-    let origin_load_mode = parse_origin_load_mode(&args[3..], &dll_path)?;
-    let written = generate_cli(target, &dll_path, &output_dir, origin_load_mode)?;
+    let (proxy_options, origin_args) = parse_proxy_options(&args[3..])?;
+    let origin_load_mode = parse_origin_load_mode(&origin_args, &dll_path)?;
+    let written = generate_cli(target, &dll_path, &output_dir, origin_load_mode, &proxy_options)?;
     println!("Generated {} file(s):", written.len());
     for path in written {
         println!("{path}");
@@ -102,13 +164,311 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+fn run_batch(args: &[String]) -> Result<()> {
+    if args.len() < 3 {
+        bail!("Usage: AheadLibEx batch <target[,target...]> <input_dir|glob> <output_dir> [options]");
+    }
+
+    let mut options = batch::BatchOptions {
+        targets: Vec::new(),
+        ..Default::default()
+    };
+    for name in args[0].split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let Some(target) = OutputTarget::from_name(name) else {
+            bail!("Unknown target '{}'. Use source|vs2022|vs2026|cmake|meson|rust.", name);
+        };
+        if !options.targets.contains(&target) {
+            options.targets.push(target);
+        }
+    }
+
+    // A wildcard in the last component of the input is treated as the file pattern.
+    let mut input_dir = PathBuf::from(&args[1]);
+    if let Some(name) = input_dir.file_name().map(|s| s.to_string_lossy().to_string()) {
+        if name.contains('*') || name.contains('?') {
+            options.pattern = name;
+            input_dir.pop();
+        }
+    }
+    let output_dir = PathBuf::from(&args[2]);
+
+    let (proxy_options, rest) = parse_proxy_options(&args[3..])?;
+    options.proxy = proxy_options;
+
+    let mut report_path = output_dir.join("aheadlibex_batch.json");
+    let mut origin_args = Vec::new();
+    let mut i = 0;
+    while i < rest.len() {
+        match rest[i].as_str() {
+            "--recursive" | "-r" => options.recursive = true,
+            "--glob" => {
+                let Some(v) = rest.get(i + 1) else {
+                    bail!("Missing value for --glob");
+                };
+                options.pattern = v.to_string();
+                i += 1;
+            }
+            "--report" => {
+                let Some(v) = rest.get(i + 1) else {
+                    bail!("Missing value for --report");
+                };
+                report_path = PathBuf::from(v);
+                i += 1;
+            }
+            other => origin_args.push(other.to_string()),
+        }
+        i += 1;
+    }
+
+    let report = batch::run_batch(&input_dir, &output_dir, &options, |dll_path| {
+        parse_origin_load_mode(&origin_args, dll_path)
+    })?;
+
+    print!("{}", report.to_table());
+    if let Some(parent) = report_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&report_path, report.to_json())?;
+    println!("Report: {}", report_path.display());
+
+    if report.failed() > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn run_diff(args: &[String]) -> Result<()> {
+    let mut json = false;
+    let mut paths = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            other if other.starts_with("--") => {
+                bail!("Unknown option '{}'. Use --help for usage.", other);
+            }
+            other => paths.push(PathBuf::from(other)),
+        }
+    }
+    let [old_path, new_path] = paths.as_slice() else {
+        bail!("Usage: AheadLibEx diff <old_dll|old_list> <new_dll|new_list> [--json]");
+    };
+
+    let old = dll::load_exports(old_path)?;
+    let new = dll::load_exports(new_path)?;
+    let diff = export_diff::diff_exports(&old.exports, &new.exports);
+    if json {
+        print!("{}", diff.to_json());
+    } else {
+        println!("Old: {}", old_path.display());
+        println!("New: {}", new_path.display());
+        if !old.arch.is_empty() && !new.arch.is_empty() && old.arch != new.arch {
+            println!("Architecture: {} -> {}", old.arch, new.arch);
+        }
+        print!("{}", diff.to_text());
+    }
+
+    if !diff.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn run_exports(args: &[String]) -> Result<()> {
+    let (dll_path, list_path) = match args {
+        [dll_path] => (dll_path, None),
+        [dll_path, list_path] => (dll_path, Some(list_path)),
+        _ => bail!("Usage: AheadLibEx exports <dll_path> [list_path]"),
+    };
+
+    let info = dll::read_exports(Path::new(dll_path))?;
+    let list = dll::format_export_list(&info);
+    match list_path {
+        Some(p) => {
+            std::fs::write(p, list)?;
+            println!("Saved {} export(s) to {}", info.exports.len(), p);
+        }
+        None => print!("{list}"),
+    }
+    Ok(())
+}
+
+fn run_verify(args: &[String]) -> Result<()> {
+    let mut json = false;
+    let mut options = verify::VerifyOptions::default();
+    let mut paths = Vec::new();
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--json" => json = true,
+            "--allow-crt" => options.allow_crt = true,
+            "--origin-name" => {
+                let Some(v) = args.get(i + 1) else {
+                    bail!("Missing value for --origin-name");
+                };
+                options.origin_names.push(v.to_string());
+                i += 1;
+            }
+            other if other.starts_with("--") => {
+                bail!("Unknown option '{}'. Use --help for usage.", other);
+            }
+            other => paths.push(PathBuf::from(other)),
+        }
+        i += 1;
+    }
+    let [proxy_path, original_path] = paths.as_slice() else {
+        bail!("Usage: AheadLibEx verify <proxy_dll> <original_dll|list> [--allow-crt] [--json]");
+    };
+
+    let proxy = dll::read_exports(proxy_path)?;
+    let original = dll::load_exports(original_path)?;
+    if let Some(name) = original_path.file_name() {
+        options.origin_names.push(name.to_string_lossy().to_string());
+    }
+    if let Some(stem) = proxy_path.file_stem() {
+        options
+            .origin_names
+            .push(format!("{}_orig.dll", stem.to_string_lossy()));
+    }
+
+    let report = verify::verify_proxy(&proxy, &original, &options);
+    if json {
+        print!("{}", report.to_json());
+    } else {
+        println!("Proxy: {}", proxy_path.display());
+        println!("Original: {}", original_path.display());
+        print!("{}", report.to_text());
+    }
+
+    if !report.is_ok() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+// Pulls the generation options out of `args`; whatever is left is handed to the origin parser.
+fn parse_proxy_options(args: &[String]) -> Result<(ProxyOptions, Vec<String>)> {
+    let mut options = ProxyOptions::default();
+    let mut header_prototypes = Vec::new();
+    let mut plugin_init: Option<String> = None;
+    let mut rest = Vec::new();
+    let mut i = 0;
+    while i < args.len() {
+        let key = args[i].as_str();
+        match key {
+            "--forward-only" => options.default_strategy = ExportStrategy::Forward,
+            "--trace" => options.trace = true,
+            "--lazy" => options.lazy = true,
+            "--deferred-init" => options.deferred_init = true,
+            "--minimal-deps" => options.minimal_deps = true,
+            "--crash-guard" => options.crash_guard = true,
+            "--cpp" => options.cpp = true,
+            "--on-failure" => {
+                let Some(v) = args.get(i + 1) else {
+                    bail!("Missing value for {}", key);
+                };
+                let Some(policy) = FailurePolicy::from_name(v) else {
+                    bail!("Unknown failure policy '{}'. Use abort|exit|log|fail-load.", v);
+                };
+                options.failure_policy = policy;
+                i += 1;
+            }
+            "--payload" => {
+                let Some(v) = args.get(i + 1) else {
+                    bail!("Missing value for {}", key);
+                };
+                options.payload = PayloadEntry::parse(v)?;
+                i += 1;
+            }
+            "--host" => {
+                let Some(v) = args.get(i + 1) else {
+                    bail!("Missing value for {}", key);
+                };
+                options.host_filter.add(v)?;
+                i += 1;
+            }
+            "--host-list" => {
+                let Some(v) = args.get(i + 1) else {
+                    bail!("Missing value for {}", key);
+                };
+                let text = std::fs::read_to_string(v)
+                    .map_err(|e| anyhow::anyhow!("Failed to read host list {}: {e}", v))?;
+                let filter = host_filter::parse_host_list(&text)
+                    .map_err(|e| anyhow::anyhow!("{}: {e}", v))?;
+                options.host_filter.merge(filter);
+                i += 1;
+            }
+            "--plugins" => {
+                let Some(v) = args.get(i + 1) else {
+                    bail!("Missing value for {}", key);
+                };
+                options.plugins = Some(PluginLoader::parse(v)?);
+                i += 1;
+            }
+            "--plugin-init" => {
+                let Some(v) = args.get(i + 1) else {
+                    bail!("Missing value for {}", key);
+                };
+                plugin_init = Some(v.to_string());
+                i += 1;
+            }
+            "--export" => {
+                let Some(v) = args.get(i + 1) else {
+                    bail!("Missing value for {}", key);
+                };
+                options.export_rules.push(ExportRule::parse(v)?);
+                i += 1;
+            }
+            "--export-rules" => {
+                let Some(v) = args.get(i + 1) else {
+                    bail!("Missing value for {}", key);
+                };
+                let text = std::fs::read_to_string(v)
+                    .map_err(|e| anyhow::anyhow!("Failed to read export rules {}: {e}", v))?;
+                let rules = export_policy::parse_rules(&text)
+                    .map_err(|e| anyhow::anyhow!("{}: {e}", v))?;
+                options.export_rules.extend(rules);
+                i += 1;
+            }
+            "--hook-proto" => {
+                let Some(v) = args.get(i + 1) else {
+                    bail!("Missing value for {}", key);
+                };
+                options
+                    .hook_prototypes
+                    .push(prototype::FunctionPrototype::parse_declaration(v)?);
+                i += 1;
+            }
+            "--hook-header" => {
+                let Some(v) = args.get(i + 1) else {
+                    bail!("Missing value for {}", key);
+                };
+                let text = std::fs::read_to_string(v)
+                    .map_err(|e| anyhow::anyhow!("Failed to read header {}: {e}", v))?;
+                header_prototypes.extend(prototype::prototypes_from_header(&text));
+                i += 1;
+            }
+            other => rest.push(other.to_string()),
+        }
+        i += 1;
+    }
+    if let Some(export) = plugin_init {
+        let Some(plugins) = options.plugins.take() else {
+            bail!("--plugin-init needs --plugins <dir>");
+        };
+        options.plugins = Some(plugins.with_init_export(&export)?);
+    }
+    // Manual --hook-proto entries take precedence over header declarations.
+    options.hook_prototypes.extend(header_prototypes);
+    Ok((options, rest))
+}
+
 fn parse_origin_load_mode(
     args: &[String],
-    dll_path: &PathBuf,
+    dll_path: &Path,
 ) -> Result<aheadlibex_rs::templates::OriginLoadModeOwned> {
     use aheadlibex_rs::templates::OriginLoadModeOwned;
 
-    let mut mode: Option<String> = None;
+    let mut modes: Vec<String> = Vec::new();
     let mut origin_name: Option<String> = None;
     let mut origin_path: Option<String> = None;
 
@@ -120,7 +480,7 @@ fn parse_origin_load_mode(
                 let Some(v) = args.get(i + 1) else {
                     bail!("Missing value for {}", key);
                 };
-                mode = Some(v.to_string());
+                modes.push(v.to_string());
                 i += 2;
             }
             "--origin-name" => {
@@ -147,24 +507,28 @@ fn parse_origin_load_mode(
         }
     }
 
-    let mode = mode.unwrap_or_else(|| "system".to_string());
-    match mode.to_ascii_lowercase().as_str() {
-        "system" | "systemdir" | "sys" => Ok(OriginLoadModeOwned::system_dir()),
-        "samedir" | "same" | "local" => {
-            let default_name = dll_path
-                .file_stem()
-                .map(|s| format!("{}_orig.dll", s.to_string_lossy()))
-                .unwrap_or_else(|| "origin_orig.dll".to_string());
-            Ok(OriginLoadModeOwned::same_dir(
-                origin_name.unwrap_or(default_name),
-            ))
-        }
-        "custom" | "path" => {
-            let Some(p) = origin_path else {
-                bail!("--origin-mode custom requires --origin-path <path>");
-            };
-            Ok(OriginLoadModeOwned::custom_path(p))
-        }
-        other => bail!("Unknown --origin-mode '{}'. Use system|samedir|custom.", other),
+    if modes.is_empty() {
+        modes.push("system".to_string());
+    }
+    let default_name = dll_path
+        .file_stem()
+        .map(|s| format!("{}_orig.dll", s.to_string_lossy()))
+        .unwrap_or_else(|| "origin_orig.dll".to_string());
+    let default_name = origin_name.unwrap_or(default_name);
+
+    // Repeated --origin values form a fallback chain, tried in order.
+    let mut candidates = Vec::with_capacity(modes.len());
+    for mode in &modes {
+        let spec = match mode.trim().to_ascii_lowercase().as_str() {
+            "custom" | "path" => {
+                let Some(p) = &origin_path else {
+                    bail!("--origin-mode custom requires --origin-path <path>");
+                };
+                format!("custom:{p}")
+            }
+            _ => mode.clone(),
+        };
+        candidates.push(OriginLoadModeOwned::parse(&spec, &default_name)?);
     }
+    Ok(OriginLoadModeOwned::chain(candidates))
 }
@@ -1,6 +1,8 @@
 use crate::ui_events::{
-    generate, handle_drop, pick_dir, pick_dll, reset, OriginModeChoice, UiLanguageChoice, UiState,
+    compare, generate, handle_drop, load_export_rules, pick_dir, pick_dll, reset, OriginModeChoice,
+    PayloadChoice, UiLanguageChoice, UiState,
 };
+use crate::templates::FailurePolicy;
 use anyhow::Result;
 use eframe::egui::{self, Color32, Frame, Id, Order, RichText, Rounding, Stroke, ViewportCommand};
 
@@ -75,6 +77,26 @@ fn tr(lang: UiLanguageChoice, en: &'static str, zh_hans: &'static str, zh_hant:
     }
 }
 
+fn failure_policy_label(lang: UiLanguageChoice, policy: FailurePolicy) -> &'static str {
+    match policy {
+        FailurePolicy::Abort => tr(lang, "Message + exit", "弹窗并退出", "彈窗並結束"),
+        FailurePolicy::Exit => tr(lang, "Silent exit code", "静默退出（错误码）", "靜默結束（錯誤碼）"),
+        FailurePolicy::Log => tr(lang, "Log and continue", "记录日志并继续", "記錄日誌並繼續"),
+        FailurePolicy::FailLoad => tr(lang, "Fail DllMain", "DllMain 返回失败", "DllMain 傳回失敗"),
+    }
+}
+
+fn payload_label(lang: UiLanguageChoice, payload: PayloadChoice) -> &'static str {
+    match payload {
+        PayloadChoice::Thread => tr(lang, "Thread at attach", "加载时创建线程", "載入時建立執行緒"),
+        PayloadChoice::Sync => tr(lang, "Synchronous at attach", "加载时同步执行", "載入時同步執行"),
+        PayloadChoice::FirstCall => tr(lang, "First call of an export", "首次调用指定导出时", "首次呼叫指定匯出時"),
+        PayloadChoice::HostReady => tr(lang, "After host start-up", "宿主启动完成后", "宿主啟動完成後"),
+        PayloadChoice::None => tr(lang, "None", "无", "無"),
+        PayloadChoice::Demo => tr(lang, "Demo (test MessageBox)", "演示（测试弹窗）", "示範（測試彈窗）"),
+    }
+}
+
 fn setup_fonts(ctx: &egui::Context) {
     let mut fonts = egui::FontDefinitions::default();
     for path in FONT_CANDIDATES {
@@ -211,14 +233,16 @@ impl eframe::App for App {
             .show(ctx, |ui| {
                 ui.vertical(|ui| {
                     ui.horizontal(|ui| {
-                        let total_width = BUTTON_WIDTH * 3.0 + SPACING * 2.0;
+                        let total_width = BUTTON_WIDTH * 4.0 + SPACING * 3.0;
                         let offset = (ui.available_width() - total_width) / 2.0;
                         ui.add_space(offset.max(0.0));
 
                         let any_target = self.state.output_source
                             || self.state.output_vs2022
                             || self.state.output_vs2026
-                            || self.state.output_cmake;
+                            || self.state.output_cmake
+                            || self.state.output_meson
+                            || self.state.output_rust;
 
                         if ui
                             .add_sized(
@@ -240,6 +264,22 @@ impl eframe::App for App {
                             generate(&mut self.state);
                         }
 
+                        if ui
+                            .add_sized(
+                                [BUTTON_WIDTH, CONTROL_HEIGHT],
+                                egui::Button::new(tr(lang, "Compare", "对比", "對比")),
+                            )
+                            .on_hover_text(tr(
+                                lang,
+                                "Diff the input DLL against an older DLL or saved export list",
+                                "将输入 DLL 与旧版 DLL 或已保存的导出列表进行对比",
+                                "將輸入 DLL 與舊版 DLL 或已儲存的匯出列表進行對比",
+                            ))
+                            .clicked()
+                        {
+                            compare(&mut self.state);
+                        }
+
                         if ui
                             .add_sized(
                                 [BUTTON_WIDTH, CONTROL_HEIGHT],
@@ -376,11 +416,15 @@ impl App {
         vs2022: &mut bool,
         vs2026: &mut bool,
         cmake: &mut bool,
+        meson: &mut bool,
+        rust: &mut bool,
     ) {
-        Self::output_checkbox(ui, "Source", source, &mut [vs2022, vs2026, cmake]);
-        Self::output_checkbox(ui, "VS2022", vs2022, &mut [source, vs2026, cmake]);
-        Self::output_checkbox(ui, "VS2026", vs2026, &mut [source, vs2022, cmake]);
-        Self::output_checkbox(ui, "CMake", cmake, &mut [source, vs2022, vs2026]);
+        Self::output_checkbox(ui, "Source", source, &mut [vs2022, vs2026, cmake, meson, rust]);
+        Self::output_checkbox(ui, "VS2022", vs2022, &mut [source, vs2026, cmake, meson, rust]);
+        Self::output_checkbox(ui, "VS2026", vs2026, &mut [source, vs2022, cmake, meson, rust]);
+        Self::output_checkbox(ui, "CMake", cmake, &mut [source, vs2022, vs2026, meson, rust]);
+        Self::output_checkbox(ui, "Meson", meson, &mut [source, vs2022, vs2026, cmake, rust]);
+        Self::output_checkbox(ui, "Rust", rust, &mut [source, vs2022, vs2026, cmake, meson]);
     }
 
     fn left_panel(&mut self, ui: &mut egui::Ui, card_height: f32) {
@@ -401,6 +445,12 @@ impl App {
                     .inner_margin(CARD_PADDING)
                     .show(ui, |ui| {
                         ui.set_height(card_height);
+                        // The settings grow with every generation option; scroll instead of clipping.
+                        egui::ScrollArea::vertical()
+                            .id_source("settings_scroll")
+                            .max_height(card_height)
+                            .auto_shrink([false, true])
+                            .show(ui, |ui| {
                         let input_width = ui.available_width() - BROWSE_BTN_WIDTH - SPACING;
 
                 ui.label(
@@ -473,6 +523,8 @@ impl App {
                         &mut state.output_vs2022,
                         &mut state.output_vs2026,
                         &mut state.output_cmake,
+                        &mut state.output_meson,
+                        &mut state.output_rust,
                     );
                 });
 
@@ -499,6 +551,9 @@ impl App {
                         OriginModeChoice::CustomPath => {
                             tr(lang, "Custom path", "自定义路径", "自訂路徑")
                         }
+                        OriginModeChoice::Chain => {
+                            tr(lang, "Fallback chain", "回退链", "回退鏈")
+                        }
                     })
                     .show_ui(ui, |ui| {
                         ui.selectable_value(
@@ -516,6 +571,11 @@ impl App {
                             OriginModeChoice::CustomPath,
                             tr(lang, "Custom path", "自定义路径", "自訂路徑"),
                         );
+                        ui.selectable_value(
+                            &mut state.origin_mode,
+                            OriginModeChoice::Chain,
+                            tr(lang, "Fallback chain", "回退链", "回退鏈"),
+                        );
                     });
 
                 ui.add_space(6.0);
@@ -544,10 +604,261 @@ impl App {
                             ),
                         );
                     }
+                    OriginModeChoice::Chain => {
+                        egui::TextEdit::multiline(&mut state.origin_chain)
+                            .desired_rows(3)
+                            .desired_width(f32::INFINITY)
+                            .font(egui::TextStyle::Monospace)
+                            .hint_text(tr(
+                                lang,
+                                "One per line, tried in order: env:FOO_ORIGIN, config:foo.origin.ini, samedir:foo_orig.dll, custom:D:\\libs\\foo.dll, system",
+                                "每行一个，按顺序尝试：env:FOO_ORIGIN, config:foo.origin.ini, samedir:foo_orig.dll, custom:D:\\libs\\foo.dll, system",
+                                "每行一個，依序嘗試：env:FOO_ORIGIN, config:foo.origin.ini, samedir:foo_orig.dll, custom:D:\\libs\\foo.dll, system",
+                            ))
+                            .show(ui);
+                    }
                 }
+
+                ui.add_space(SPACING);
+                Self::advanced_options(ui, state, lang);
+                            });
             });
     }
 
+    fn advanced_options(ui: &mut egui::Ui, state: &mut UiState, lang: UiLanguageChoice) {
+        ui.label(
+            RichText::new(tr(lang, "Advanced", "高级选项", "進階選項"))
+                .size(12.0)
+                .color(colors::TEXT_SECONDARY),
+        );
+        ui.add_space(4.0);
+        ui.checkbox(
+            &mut state.forward_only,
+            tr(
+                lang,
+                "Forward-only (linker forwarders, no stubs)",
+                "仅转发（链接器转发，无跳转桩）",
+                "僅轉發（連結器轉發，無跳轉樁）",
+            ),
+        )
+        .on_hover_text(tr(
+            lang,
+            "Every export becomes Foo=orig.Foo; no asm files and no runtime loader are generated",
+            "所有导出都生成为 Foo=orig.Foo，不生成汇编文件和运行时加载代码",
+            "所有匯出都產生為 Foo=orig.Foo，不產生組語檔案與執行時載入程式碼",
+        ));
+        ui.checkbox(
+            &mut state.trace,
+            tr(lang, "Trace calls", "跟踪调用", "追蹤呼叫"),
+        )
+        .on_hover_text(tr(
+            lang,
+            "Stubs log timestamp, thread, caller and export name to <proxy>.trace.log",
+            "跳转桩将时间戳、线程、调用者和导出名写入 <proxy>.trace.log",
+            "跳轉樁將時間戳記、執行緒、呼叫者與匯出名稱寫入 <proxy>.trace.log",
+        ));
+        ui.checkbox(
+            &mut state.lazy,
+            tr(lang, "Lazy resolution", "延迟解析", "延遲解析"),
+        )
+        .on_hover_text(tr(
+            lang,
+            "Resolve each export on its first call; a missing export only matters if it is called",
+            "每个导出在首次调用时才解析，缺失的导出只有被调用时才会报错",
+            "每個匯出在首次呼叫時才解析，缺少的匯出只有被呼叫時才會出錯",
+        ));
+        ui.checkbox(
+            &mut state.deferred_init,
+            tr(
+                lang,
+                "Loader-lock-safe init",
+                "加载器锁安全初始化",
+                "載入器鎖安全初始化",
+            ),
+        )
+        .on_hover_text(tr(
+            lang,
+            "DllMain does no LoadLibrary/CreateThread; the first export call loads the original and starts the payload",
+            "DllMain 中不调用 LoadLibrary/CreateThread，首次调用导出时才加载原始 DLL 并启动 payload",
+            "DllMain 中不呼叫 LoadLibrary/CreateThread，首次呼叫匯出時才載入原始 DLL 並啟動 payload",
+        ));
+        ui.checkbox(
+            &mut state.minimal_deps,
+            tr(
+                lang,
+                "Minimal dependencies (kernel32 only)",
+                "最小依赖（仅 kernel32）",
+                "最小相依性（僅 kernel32）",
+            ),
+        )
+        .on_hover_text(tr(
+            lang,
+            "No C runtime and no user32 import; DllMain becomes the DLL entry point",
+            "不链接 C 运行时、不导入 user32，DllMain 直接作为 DLL 入口点",
+            "不連結 C 執行階段、不匯入 user32，DllMain 直接作為 DLL 進入點",
+        ));
+        ui.checkbox(
+            &mut state.crash_guard,
+            tr(lang, "Crash guard", "崩溃保护", "崩潰保護"),
+        )
+        .on_hover_text(tr(
+            lang,
+            "A fault in the payload or a hook callback is logged to <proxy>.dll.crash.log and switches them off; exports keep forwarding",
+            "payload 或 Hook 回调中的异常会记录到 <proxy>.dll.crash.log 并停用它们，导出仍正常转发",
+            "payload 或 Hook 回呼中的例外會記錄到 <proxy>.dll.crash.log 並停用它們，匯出仍正常轉送",
+        ));
+        ui.checkbox(
+            &mut state.cpp,
+            tr(lang, "C++ sources", "C++ 源文件", "C++ 原始檔"),
+        )
+        .on_hover_text(tr(
+            lang,
+            "Generate .cpp files; the proxy header adds an aheadlibex namespace for payload code",
+            "生成 .cpp 文件；代理头文件额外提供供 payload 使用的 aheadlibex 命名空间",
+            "產生 .cpp 檔案；代理標頭檔額外提供供 payload 使用的 aheadlibex 命名空間",
+        ));
+
+        ui.add_space(6.0);
+        ui.horizontal(|ui| {
+            ui.label(
+                RichText::new(tr(lang, "On failure", "失败时", "失敗時"))
+                    .size(12.0)
+                    .color(colors::TEXT_SECONDARY),
+            );
+            egui::ComboBox::from_id_source("failure_policy")
+                .selected_text(failure_policy_label(lang, state.failure_policy))
+                .show_ui(ui, |ui| {
+                    for policy in FailurePolicy::ALL {
+                        ui.selectable_value(
+                            &mut state.failure_policy,
+                            policy,
+                            failure_policy_label(lang, policy),
+                        );
+                    }
+                });
+        });
+
+        ui.add_space(6.0);
+        ui.horizontal(|ui| {
+            ui.label(
+                RichText::new(tr(lang, "Payload", "Payload 启动", "Payload 啟動"))
+                    .size(12.0)
+                    .color(colors::TEXT_SECONDARY),
+            );
+            egui::ComboBox::from_id_source("payload_entry")
+                .selected_text(payload_label(lang, state.payload))
+                .show_ui(ui, |ui| {
+                    for payload in PayloadChoice::ALL {
+                        ui.selectable_value(&mut state.payload, payload, payload_label(lang, payload));
+                    }
+                });
+        });
+        if state.payload == PayloadChoice::FirstCall {
+            let w = ui.available_width();
+            Self::centered_placeholder_text_edit(
+                ui,
+                [w, PATH_CONTROL_HEIGHT],
+                &mut state.payload_export,
+                tr(lang, "Export name or @ordinal", "导出名或 @序号", "匯出名稱或 @序號"),
+            );
+        }
+
+        ui.add_space(6.0);
+        ui.label(
+            RichText::new(tr(lang, "Host allow-list", "宿主进程白名单", "宿主行程白名單"))
+                .size(12.0)
+                .color(colors::TEXT_SECONDARY),
+        )
+        .on_hover_text(tr(
+            lang,
+            "Payload and hooks only run in matching processes; elsewhere the proxy just forwards",
+            "payload 和 hook 只在匹配的进程中运行，其他进程中代理只做转发",
+            "payload 與 hook 只在符合的行程中執行，其他行程中代理只做轉發",
+        ));
+        egui::TextEdit::multiline(&mut state.host_rules)
+            .desired_rows(2)
+            .desired_width(f32::INFINITY)
+            .font(egui::TextStyle::Monospace)
+            .hint_text(tr(
+                lang,
+                "One per line: name:game.exe, version:1.2-1.4, sha256:<hex>",
+                "每行一条：name:game.exe, version:1.2-1.4, sha256:<hex>",
+                "每行一條：name:game.exe, version:1.2-1.4, sha256:<hex>",
+            ))
+            .show(ui);
+
+        ui.add_space(6.0);
+        ui.label(
+            RichText::new(tr(lang, "Plugin loader", "插件加载器", "外掛載入器"))
+                .size(12.0)
+                .color(colors::TEXT_SECONDARY),
+        )
+        .on_hover_text(tr(
+            lang,
+            "payload_main loads every matching DLL in name order before your code; leave empty to disable",
+            "payload_main 在执行你的代码前按名称顺序加载所有匹配的 DLL；留空则不生成",
+            "payload_main 在執行你的程式碼前依名稱順序載入所有符合的 DLL；留空則不產生",
+        ));
+        let w = ui.available_width();
+        Self::centered_placeholder_text_edit(
+            ui,
+            [w, PATH_CONTROL_HEIGHT],
+            &mut state.plugin_dir,
+            tr(lang, "Directory, e.g. plugins or plugins\\*.asi", "目录，例如 plugins 或 plugins\\*.asi", "目錄，例如 plugins 或 plugins\\*.asi"),
+        );
+        if !state.plugin_dir.trim().is_empty() {
+            Self::centered_placeholder_text_edit(
+                ui,
+                [w, PATH_CONTROL_HEIGHT],
+                &mut state.plugin_init,
+                tr(lang, "Init export (optional)", "初始化导出（可选）", "初始化匯出（選填）"),
+            );
+        }
+
+        ui.add_space(6.0);
+        ui.horizontal(|ui| {
+            ui.label(
+                RichText::new(tr(lang, "Export rules", "导出规则", "匯出規則"))
+                    .size(12.0)
+                    .color(colors::TEXT_SECONDARY),
+            );
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.button(tr(lang, "Load...", "加载...", "載入...")).clicked() {
+                    load_export_rules(state);
+                }
+            });
+        });
+        egui::TextEdit::multiline(&mut state.export_rules)
+            .desired_rows(3)
+            .desired_width(f32::INFINITY)
+            .font(egui::TextStyle::Monospace)
+            .hint_text(tr(
+                lang,
+                "One per line: CreateFileW=hook, @12=drop, Get*=forward",
+                "每行一条：CreateFileW=hook, @12=drop, Get*=forward",
+                "每行一條：CreateFileW=hook, @12=drop, Get*=forward",
+            ))
+            .show(ui);
+
+        ui.add_space(6.0);
+        ui.label(
+            RichText::new(tr(lang, "Hook prototypes", "Hook 函数原型", "Hook 函式原型"))
+                .size(12.0)
+                .color(colors::TEXT_SECONDARY),
+        );
+        egui::TextEdit::multiline(&mut state.hook_prototypes)
+            .desired_rows(2)
+            .desired_width(f32::INFINITY)
+            .font(egui::TextStyle::Monospace)
+            .hint_text(tr(
+                lang,
+                "BOOL WINAPI Foo(LPCWSTR name, DWORD flags);",
+                "BOOL WINAPI Foo(LPCWSTR name, DWORD flags);",
+                "BOOL WINAPI Foo(LPCWSTR name, DWORD flags);",
+            ))
+            .show(ui);
+    }
+
     fn right_panel(&mut self, ui: &mut egui::Ui, card_height: f32) {
         let state = &mut self.state;
         let lang = state.ui_language;
@@ -4,34 +4,37 @@
 
 cmake_minimum_required(VERSION 3.20)
 
-project({{CMAKE_PROJECT_NAME}} LANGUAGES C)
-
-# Toolchain selection:
-# - MSVC / clang-cl: MASM (.asm)
-# - MinGW-w64 (gcc/clang): GAS (.S)
-if(MSVC)
-  enable_language(ASM_MASM)
-else()
-  enable_language(ASM)
-endif()
+project({{CMAKE_PROJECT_NAME}} LANGUAGES {{LANGUAGES}})
 
 set(AHEADLIBEX_BASE "{{BASE}}")
 set(AHEADLIBEX_OUTPUT_NAME "{{OUTPUT_NAME}}")
 set(AHEADLIBEX_C "{{C_SRC}}")
+set(AHEADLIBEX_PAYLOAD "{{PAYLOAD_SRC}}")
 set(AHEADLIBEX_ASM_MASM "{{ASM_MASM_SRC}}")
 set(AHEADLIBEX_ASM_GAS "{{ASM_GAS_SRC}}")
 set(AHEADLIBEX_DEF "{{DEF_SRC}}")
 
-if(MSVC)
-  set(AHEADLIBEX_ASM "${AHEADLIBEX_ASM_MASM}")
-else()
-  set(AHEADLIBEX_ASM "${AHEADLIBEX_ASM_GAS}")
+set(AHEADLIBEX_SOURCES "${CMAKE_CURRENT_LIST_DIR}/${AHEADLIBEX_C}")
+# Your code lives in the payload source, which regeneration never overwrites.
+if(NOT AHEADLIBEX_PAYLOAD STREQUAL "")
+  list(APPEND AHEADLIBEX_SOURCES "${CMAKE_CURRENT_LIST_DIR}/${AHEADLIBEX_PAYLOAD}")
 endif()
 
-add_library(${PROJECT_NAME} SHARED
-  "${CMAKE_CURRENT_LIST_DIR}/${AHEADLIBEX_C}"
-  "${CMAKE_CURRENT_LIST_DIR}/${AHEADLIBEX_ASM}"
-)
+# Toolchain selection (skipped when every export is a linker forwarder):
+# - MSVC / clang-cl: MASM (.asm)
+# - MinGW-w64 (gcc/clang): GAS (.S)
+if(NOT AHEADLIBEX_ASM_MASM STREQUAL "")
+  if(MSVC)
+    enable_language(ASM_MASM)
+    set(AHEADLIBEX_ASM "${AHEADLIBEX_ASM_MASM}")
+  else()
+    enable_language(ASM)
+    set(AHEADLIBEX_ASM "${AHEADLIBEX_ASM_GAS}")
+  endif()
+  list(APPEND AHEADLIBEX_SOURCES "${CMAKE_CURRENT_LIST_DIR}/${AHEADLIBEX_ASM}")
+endif()
+
+add_library(${PROJECT_NAME} SHARED ${AHEADLIBEX_SOURCES})
 
 set_target_properties(${PROJECT_NAME} PROPERTIES
   OUTPUT_NAME "${AHEADLIBEX_OUTPUT_NAME}"
@@ -43,8 +46,7 @@ set_target_properties(${PROJECT_NAME} PROPERTIES
 )
 
 target_include_directories(${PROJECT_NAME} PRIVATE "${CMAKE_CURRENT_LIST_DIR}")
-target_link_libraries(${PROJECT_NAME} PRIVATE user32)
-
+{{LINK_LIBRARIES}}
 # Use .def for exports across toolchains.
 if(MSVC)
   target_link_options(${PROJECT_NAME} PRIVATE "/DEF:${CMAKE_CURRENT_LIST_DIR}/${AHEADLIBEX_DEF}")
@@ -0,0 +1,47 @@
+; Shared thunk: [rsp] = export index pushed by the stub, [rsp+8] = caller return address.
+; Saves the argument registers (xmm0-xmm5, for __vectorcall), passes both to the handler, then
+; tail-jumps to the address it returns. The unwind info counts the stub's push as part of the
+; frame, so exceptions and stack walks through a traced call still reach the caller.
+{{THUNK_NAME}} PROC FRAME
+    .allocstack 8
+    push rcx
+    .pushreg rcx
+    push rdx
+    .pushreg rdx
+    push r8
+    .pushreg r8
+    push r9
+    .pushreg r9
+    push rax
+    .pushreg rax
+    push r10
+    .pushreg r10
+    sub rsp, 128
+    .allocstack 128
+    .endprolog
+    movdqa XMMWORD PTR [rsp+32], xmm0
+    movdqa XMMWORD PTR [rsp+48], xmm1
+    movdqa XMMWORD PTR [rsp+64], xmm2
+    movdqa XMMWORD PTR [rsp+80], xmm3
+    movdqa XMMWORD PTR [rsp+96], xmm4
+    movdqa XMMWORD PTR [rsp+112], xmm5
+    mov rcx, QWORD PTR [rsp+176]
+    mov rdx, QWORD PTR [rsp+184]
+    call {{THUNK_HANDLER}}
+    mov r11, rax
+    movdqa xmm0, XMMWORD PTR [rsp+32]
+    movdqa xmm1, XMMWORD PTR [rsp+48]
+    movdqa xmm2, XMMWORD PTR [rsp+64]
+    movdqa xmm3, XMMWORD PTR [rsp+80]
+    movdqa xmm4, XMMWORD PTR [rsp+96]
+    movdqa xmm5, XMMWORD PTR [rsp+112]
+    add rsp, 128
+    pop r10
+    pop rax
+    pop r9
+    pop r8
+    pop rdx
+    pop rcx
+    add rsp, 8
+    jmp r11
+{{THUNK_NAME}} ENDP
@@ -0,0 +1,13 @@
+; Shared thunk: [esp] = export index pushed by the stub, [esp+4] = caller return address.
+; pushad keeps every register (fastcall/thiscall arguments included); the index slot is
+; overwritten with the target so the final ret jumps there with the caller's frame intact.
+EXTERN _{{THUNK_HANDLER}}:PROC
+{{THUNK_NAME}}:
+    pushad
+    push DWORD PTR [esp+36]
+    push DWORD PTR [esp+36]
+    call _{{THUNK_HANDLER}}
+    add esp, 8
+    mov DWORD PTR [esp+32], eax
+    popad
+    ret
@@ -0,0 +1,228 @@
+// Crash guard: a fault inside the payload or a hook callback is written to <proxy>.dll.crash.log and
+// switches both off for the rest of the process; the exports keep forwarding.
+static volatile LONG g_aheadlibex_guard_tripped;
+
+#ifdef _WIN64
+#define AHEADLIBEX_HEX_PTR "0x%08lX%08lX"
+#define AHEADLIBEX_PTR_ARG(v) (DWORD)((ULONGLONG)(v) >> 32), (DWORD)(ULONGLONG)(v)
+#else
+#define AHEADLIBEX_HEX_PTR "0x%08lX"
+#define AHEADLIBEX_PTR_ARG(v) (DWORD)(v)
+#endif
+
+// Exception code, address, module+offset and the integer registers; no minidump.
+static VOID WINAPI aheadlibex_crash_record(EXCEPTION_POINTERS* info, LPCSTR where)
+{
+    // Each wsprintfA call writes at most 1024 characters.
+    CHAR text[1024 * 4];
+    CHAR module_name[MAX_PATH * 3];
+    WCHAR path[MAX_PATH + 16];
+    const EXCEPTION_RECORD* record = info->ExceptionRecord;
+    const CONTEXT* context = info->ContextRecord;
+    ULONG_PTR address = (ULONG_PTR)record->ExceptionAddress;
+    HMODULE module = NULL;
+    HANDLE file;
+    DWORD written = 0;
+    SYSTEMTIME now;
+    int length;
+    int i;
+
+    lstrcpyA(module_name, "?");
+    if (GetModuleHandleExW(GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS | GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+                           (LPCWSTR)address, &module))
+    {
+        DWORD n = GetModuleFileNameW(module, path, MAX_PATH);
+        LPCWSTR name = path;
+        if (n > 0 && n < MAX_PATH)
+        {
+            for (i = 0; path[i]; ++i)
+            {
+                if (path[i] == L'\\' || path[i] == L'/')
+                {
+                    name = path + i + 1;
+                }
+            }
+            WideCharToMultiByte(CP_UTF8, 0, name, -1, module_name, sizeof(module_name), NULL, NULL);
+        }
+    }
+
+    GetLocalTime(&now);
+    length = wsprintfA(text, "[%04u-%02u-%02u %02u:%02u:%02u] AheadLibEx [{{DLL_NAME}}] crash in %hs, thread %lu\r\n",
+                       now.wYear, now.wMonth, now.wDay, now.wHour, now.wMinute, now.wSecond, where, GetCurrentThreadId());
+    length += wsprintfA(text + length, "  code 0x%08lX at " AHEADLIBEX_HEX_PTR " (%hs+0x%lX)\r\n",
+                        record->ExceptionCode, AHEADLIBEX_PTR_ARG(address), module_name,
+                        (DWORD)(module ? address - (ULONG_PTR)module : address));
+    if (record->ExceptionCode == EXCEPTION_ACCESS_VIOLATION && record->NumberParameters >= 2)
+    {
+        ULONG_PTR kind = record->ExceptionInformation[0];
+        length += wsprintfA(text + length, "  %hs " AHEADLIBEX_HEX_PTR "\r\n",
+                            kind == 0 ? "read of" : kind == 8 ? "execute at" : "write to",
+                            AHEADLIBEX_PTR_ARG(record->ExceptionInformation[1]));
+    }
+#ifdef _WIN64
+    length += wsprintfA(text + length,
+                        "  rax=" AHEADLIBEX_HEX_PTR " rbx=" AHEADLIBEX_HEX_PTR " rcx=" AHEADLIBEX_HEX_PTR " rdx=" AHEADLIBEX_HEX_PTR "\r\n",
+                        AHEADLIBEX_PTR_ARG(context->Rax), AHEADLIBEX_PTR_ARG(context->Rbx),
+                        AHEADLIBEX_PTR_ARG(context->Rcx), AHEADLIBEX_PTR_ARG(context->Rdx));
+    length += wsprintfA(text + length,
+                        "  rsi=" AHEADLIBEX_HEX_PTR " rdi=" AHEADLIBEX_HEX_PTR " rbp=" AHEADLIBEX_HEX_PTR " rsp=" AHEADLIBEX_HEX_PTR "\r\n",
+                        AHEADLIBEX_PTR_ARG(context->Rsi), AHEADLIBEX_PTR_ARG(context->Rdi),
+                        AHEADLIBEX_PTR_ARG(context->Rbp), AHEADLIBEX_PTR_ARG(context->Rsp));
+    length += wsprintfA(text + length,
+                        "  r8 =" AHEADLIBEX_HEX_PTR " r9 =" AHEADLIBEX_HEX_PTR " r10=" AHEADLIBEX_HEX_PTR " r11=" AHEADLIBEX_HEX_PTR "\r\n",
+                        AHEADLIBEX_PTR_ARG(context->R8), AHEADLIBEX_PTR_ARG(context->R9),
+                        AHEADLIBEX_PTR_ARG(context->R10), AHEADLIBEX_PTR_ARG(context->R11));
+    length += wsprintfA(text + length,
+                        "  r12=" AHEADLIBEX_HEX_PTR " r13=" AHEADLIBEX_HEX_PTR " r14=" AHEADLIBEX_HEX_PTR " r15=" AHEADLIBEX_HEX_PTR "\r\n",
+                        AHEADLIBEX_PTR_ARG(context->R12), AHEADLIBEX_PTR_ARG(context->R13),
+                        AHEADLIBEX_PTR_ARG(context->R14), AHEADLIBEX_PTR_ARG(context->R15));
+    length += wsprintfA(text + length, "  rip=" AHEADLIBEX_HEX_PTR " eflags=0x%08lX\r\n",
+                        AHEADLIBEX_PTR_ARG(context->Rip), context->EFlags);
+#else
+    length += wsprintfA(text + length, "  eax=0x%08lX ebx=0x%08lX ecx=0x%08lX edx=0x%08lX\r\n",
+                        context->Eax, context->Ebx, context->Ecx, context->Edx);
+    length += wsprintfA(text + length, "  esi=0x%08lX edi=0x%08lX ebp=0x%08lX esp=0x%08lX\r\n",
+                        context->Esi, context->Edi, context->Ebp, context->Esp);
+    length += wsprintfA(text + length, "  eip=0x%08lX eflags=0x%08lX\r\n", context->Eip, context->EFlags);
+#endif
+    length += wsprintfA(text + length, "  payload and hook callbacks disabled; exports keep forwarding\r\n");
+    OutputDebugStringA(text);
+
+    if (!GetModuleHandleExW(GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS | GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+                            (LPCWSTR)(ULONG_PTR)&aheadlibex_crash_record, &module))
+    {
+        return;
+    }
+    i = (int)GetModuleFileNameW(module, path, MAX_PATH);
+    if (i == 0 || i >= MAX_PATH)
+    {
+        return;
+    }
+    lstrcatW(path, L".crash.log");
+    file = CreateFileW(path, FILE_APPEND_DATA, FILE_SHARE_READ | FILE_SHARE_WRITE, NULL, OPEN_ALWAYS,
+                       FILE_ATTRIBUTE_NORMAL, NULL);
+    if (file != INVALID_HANDLE_VALUE)
+    {
+        WriteFile(file, text, (DWORD)length, &written, NULL);
+        CloseHandle(file);
+    }
+}
+
+#if defined(_MSC_VER)
+static int aheadlibex_crash_filter(EXCEPTION_POINTERS* info, LPCSTR where)
+{
+    aheadlibex_crash_record(info, where);
+    InterlockedExchange(&g_aheadlibex_guard_tripped, 1);
+    return EXCEPTION_EXECUTE_HANDLER;
+}
+
+#define AHEADLIBEX_GUARDED(where, statement)                                             \
+    if (!g_aheadlibex_guard_tripped)                                                     \
+    {                                                                                    \
+        __try                                                                            \
+        {                                                                                \
+            statement;                                                                   \
+        }                                                                                \
+        __except (aheadlibex_crash_filter(GetExceptionInformation(), where))             \
+        {                                                                                \
+        }                                                                                \
+    }
+
+static VOID aheadlibex_guard_shutdown(void)
+{
+}
+#else
+// No __try outside MSVC. The guard installs an unhandled-exception filter, so a fault reaches it only
+// after every handler inside the guarded call has passed on it; the filter records the fault and jumps
+// back to the innermost guarded statement on that thread. Faults outside a guard go to the filter that
+// was installed before.
+typedef struct AHEADLIBEX_GUARD_FRAME
+{
+    void* jump[5];
+    LPCSTR where;
+#ifndef _WIN64
+    // SEH chain at entry; records left by the abandoned calls must not stay on it.
+    struct _EXCEPTION_REGISTRATION_RECORD* seh;
+#endif
+    struct AHEADLIBEX_GUARD_FRAME* parent;
+} AHEADLIBEX_GUARD_FRAME;
+
+static DWORD g_aheadlibex_guard_tls = TLS_OUT_OF_INDEXES;
+static LPTOP_LEVEL_EXCEPTION_FILTER g_aheadlibex_guard_previous;
+static volatile LONG g_aheadlibex_guard_state;
+
+static LONG WINAPI aheadlibex_guard_filter(EXCEPTION_POINTERS* info)
+{
+    AHEADLIBEX_GUARD_FRAME* frame = NULL;
+    if (g_aheadlibex_guard_tls != TLS_OUT_OF_INDEXES)
+    {
+        frame = (AHEADLIBEX_GUARD_FRAME*)TlsGetValue(g_aheadlibex_guard_tls);
+    }
+    if (!frame)
+    {
+        return g_aheadlibex_guard_previous ? g_aheadlibex_guard_previous(info) : EXCEPTION_CONTINUE_SEARCH;
+    }
+    // Popped first, so a fault while recording goes to the outer guard or the previous filter.
+    TlsSetValue(g_aheadlibex_guard_tls, frame->parent);
+    aheadlibex_crash_record(info, frame->where);
+    InterlockedExchange(&g_aheadlibex_guard_tripped, 1);
+#ifndef _WIN64
+    ((NT_TIB*)NtCurrentTeb())->ExceptionList = frame->seh;
+#endif
+    __builtin_longjmp(frame->jump, 1);
+}
+
+static VOID aheadlibex_guard_enter(AHEADLIBEX_GUARD_FRAME* frame, LPCSTR where)
+{
+    if (InterlockedCompareExchange(&g_aheadlibex_guard_state, 1, 0) == 0)
+    {
+        g_aheadlibex_guard_tls = TlsAlloc();
+        g_aheadlibex_guard_previous = SetUnhandledExceptionFilter(aheadlibex_guard_filter);
+        InterlockedExchange(&g_aheadlibex_guard_state, 2);
+    }
+    while (g_aheadlibex_guard_state != 2)
+    {
+        Sleep(0);
+    }
+    frame->where = where;
+#ifndef _WIN64
+    frame->seh = ((NT_TIB*)NtCurrentTeb())->ExceptionList;
+#endif
+    frame->parent = (AHEADLIBEX_GUARD_FRAME*)TlsGetValue(g_aheadlibex_guard_tls);
+    TlsSetValue(g_aheadlibex_guard_tls, frame);
+}
+
+static VOID aheadlibex_guard_leave(AHEADLIBEX_GUARD_FRAME* frame)
+{
+    TlsSetValue(g_aheadlibex_guard_tls, frame->parent);
+}
+
+#define AHEADLIBEX_GUARDED(where, statement)                                             \
+    if (!g_aheadlibex_guard_tripped)                                                     \
+    {                                                                                    \
+        AHEADLIBEX_GUARD_FRAME aheadlibex_frame;                                         \
+        if (__builtin_setjmp(aheadlibex_frame.jump) == 0)                                \
+        {                                                                                \
+            aheadlibex_guard_enter(&aheadlibex_frame, where);                            \
+            statement;                                                                   \
+        }                                                                                \
+        aheadlibex_guard_leave(&aheadlibex_frame);                                       \
+    }
+
+// A filter left behind by an unloaded proxy would crash the next unhandled exception in the process.
+static VOID aheadlibex_guard_shutdown(void)
+{
+    LPTOP_LEVEL_EXCEPTION_FILTER current;
+    if (g_aheadlibex_guard_state != 2)
+    {
+        return;
+    }
+    current = SetUnhandledExceptionFilter(g_aheadlibex_guard_previous);
+    if (current != aheadlibex_guard_filter)
+    {
+        // A filter installed after ours stays in place.
+        SetUnhandledExceptionFilter(current);
+    }
+}
+#endif
+
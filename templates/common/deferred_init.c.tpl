@@ -0,0 +1,29 @@
+
+// Loader-lock-safe start-up: DllMain only records the module handle. The first call into any
+// export loads the original DLL, resolves exports and starts the payload, once per process.
+
+static HMODULE g_aheadlibex_self;
+static INIT_ONCE g_aheadlibex_init_once = INIT_ONCE_STATIC_INIT;
+static DWORD g_aheadlibex_init_error;
+
+static BOOL CALLBACK aheadlibex_init_once_proc(PINIT_ONCE once, PVOID parameter, PVOID* context)
+{
+    UNREFERENCED_PARAMETER(once);
+    UNREFERENCED_PARAMETER(parameter);
+    UNREFERENCED_PARAMETER(context);
+    if (!load_original_module(g_aheadlibex_self))
+    {
+        g_aheadlibex_init_error = ERROR_MOD_NOT_FOUND;
+        return FALSE;
+    }
+    if (!init_forwarders())
+    {
+        g_aheadlibex_init_error = ERROR_PROC_NOT_FOUND;
+        return FALSE;
+    }
+{{DEFERRED_HOOKS}}{{DEFERRED_PAYLOAD}}    return TRUE;
+}
+
+static VOID aheadlibex_ensure_init(void)
+{
+{{INIT_FAILURE}}}
@@ -0,0 +1,46 @@
+// Failure policy "log": report to the debugger and append to <proxy>.dll.log, then carry on.
+static VOID WINAPI report_failure(LPCTSTR message, DWORD error)
+{
+    // wsprintf never writes more than 1024 characters.
+    TCHAR line[1024];
+    TCHAR path[MAX_PATH + 8];
+    CHAR text[1024 * 3];
+    HMODULE self = NULL;
+    HANDLE file;
+    DWORD written = 0;
+    int length;
+
+    wsprintf(line, TEXT("AheadLibEx [{{DLL_NAME}}] %s (error %lu)\r\n"), message, error);
+    OutputDebugString(line);
+
+    if (!GetModuleHandleEx(GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS | GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+                           (LPCTSTR)(ULONG_PTR)&report_failure, &self))
+    {
+        return;
+    }
+    length = (int)GetModuleFileName(self, path, MAX_PATH);
+    if (length == 0 || length >= MAX_PATH)
+    {
+        return;
+    }
+    lstrcat(path, TEXT(".log"));
+
+#ifdef UNICODE
+    length = WideCharToMultiByte(CP_UTF8, 0, line, -1, text, sizeof(text), NULL, NULL) - 1;
+#else
+    lstrcpynA(text, line, sizeof(text));
+    length = lstrlenA(text);
+#endif
+    if (length <= 0)
+    {
+        return;
+    }
+
+    file = CreateFile(path, FILE_APPEND_DATA, FILE_SHARE_READ | FILE_SHARE_WRITE, NULL, OPEN_ALWAYS,
+                      FILE_ATTRIBUTE_NORMAL, NULL);
+    if (file != INVALID_HANDLE_VALUE)
+    {
+        WriteFile(file, text, (DWORD)length, &written, NULL);
+        CloseHandle(file);
+    }
+}
@@ -0,0 +1,26 @@
+// Host allow-list: the payload, detours and typed-hook callbacks only run in matching processes.
+// Everywhere else the proxy is a plain forwarder.
+static BOOL aheadlibex_host_check(const WCHAR* path)
+{
+{{HOST_CHECKS}}    return TRUE;
+}
+
+static BOOL aheadlibex_host_matches(void)
+{
+    origin_path path = { 0 };
+    BOOL matches = origin_module_file(NULL, &path) && aheadlibex_host_check(path.text);
+    origin_path_free(&path);
+    return matches;
+}
+
+static BOOL aheadlibex_host_allowed(void)
+{
+    // 0 = not checked yet, 1 = allowed, 2 = not allowed. Racing first checks reach the same answer.
+    static volatile LONG state;
+    if (state == 0)
+    {
+        InterlockedExchange(&state, aheadlibex_host_matches() ? 1 : 2);
+    }
+    return state == 1;
+}
+
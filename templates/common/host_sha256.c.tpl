@@ -0,0 +1,148 @@
+// SHA-256 of the host executable, computed here so the proxy needs neither bcrypt nor the CRT.
+typedef struct AHEADLIBEX_SHA256
+{
+    DWORD state[8];
+    ULONGLONG length;
+    BYTE block[64];
+    DWORD used;
+} AHEADLIBEX_SHA256;
+
+static const DWORD g_aheadlibex_sha256_k[64] =
+{
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+};
+
+#define AHEADLIBEX_ROR(x, n) (((x) >> (n)) | ((x) << (32 - (n))))
+
+static VOID aheadlibex_sha256_block(AHEADLIBEX_SHA256* ctx)
+{
+    DWORD w[64];
+    DWORD s[8];
+    DWORD t1;
+    DWORD t2;
+    int i;
+
+    for (i = 0; i < 16; ++i)
+    {
+        w[i] = ((DWORD)ctx->block[i * 4] << 24) | ((DWORD)ctx->block[i * 4 + 1] << 16) |
+               ((DWORD)ctx->block[i * 4 + 2] << 8) | ctx->block[i * 4 + 3];
+    }
+    for (i = 16; i < 64; ++i)
+    {
+        t1 = AHEADLIBEX_ROR(w[i - 15], 7) ^ AHEADLIBEX_ROR(w[i - 15], 18) ^ (w[i - 15] >> 3);
+        t2 = AHEADLIBEX_ROR(w[i - 2], 17) ^ AHEADLIBEX_ROR(w[i - 2], 19) ^ (w[i - 2] >> 10);
+        w[i] = w[i - 16] + t1 + w[i - 7] + t2;
+    }
+    for (i = 0; i < 8; ++i)
+    {
+        s[i] = ctx->state[i];
+    }
+    for (i = 0; i < 64; ++i)
+    {
+        t1 = s[7] + (AHEADLIBEX_ROR(s[4], 6) ^ AHEADLIBEX_ROR(s[4], 11) ^ AHEADLIBEX_ROR(s[4], 25)) +
+             ((s[4] & s[5]) ^ (~s[4] & s[6])) + g_aheadlibex_sha256_k[i] + w[i];
+        t2 = (AHEADLIBEX_ROR(s[0], 2) ^ AHEADLIBEX_ROR(s[0], 13) ^ AHEADLIBEX_ROR(s[0], 22)) +
+             ((s[0] & s[1]) ^ (s[0] & s[2]) ^ (s[1] & s[2]));
+        s[7] = s[6];
+        s[6] = s[5];
+        s[5] = s[4];
+        s[4] = s[3] + t1;
+        s[3] = s[2];
+        s[2] = s[1];
+        s[1] = s[0];
+        s[0] = t1 + t2;
+    }
+    for (i = 0; i < 8; ++i)
+    {
+        ctx->state[i] += s[i];
+    }
+}
+
+static VOID aheadlibex_sha256_update(AHEADLIBEX_SHA256* ctx, const BYTE* data, DWORD size)
+{
+    while (size--)
+    {
+        ctx->block[ctx->used++] = *data++;
+        ctx->length += 8;
+        if (ctx->used == 64)
+        {
+            aheadlibex_sha256_block(ctx);
+            ctx->used = 0;
+        }
+    }
+}
+
+static BOOL aheadlibex_host_sha256(const WCHAR* path, BYTE digest[32])
+{
+    static const DWORD init[8] =
+    {
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+    };
+    AHEADLIBEX_SHA256 ctx;
+    // Well under a page, so the frame needs no stack probe.
+    BYTE buffer[2048];
+    BYTE length[8];
+    BYTE pad = 0x80;
+    BYTE zero = 0;
+    ULONGLONG bits;
+    DWORD read = 0;
+    HANDLE file;
+    int i;
+
+    file = CreateFileW(path, GENERIC_READ, FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE, NULL,
+                       OPEN_EXISTING, FILE_ATTRIBUTE_NORMAL, NULL);
+    if (file == INVALID_HANDLE_VALUE)
+    {
+        return FALSE;
+    }
+    for (i = 0; i < 8; ++i)
+    {
+        ctx.state[i] = init[i];
+    }
+    ctx.length = 0;
+    ctx.used = 0;
+    while (ReadFile(file, buffer, sizeof(buffer), &read, NULL) && read)
+    {
+        aheadlibex_sha256_update(&ctx, buffer, read);
+    }
+    CloseHandle(file);
+
+    bits = ctx.length;
+    for (i = 7; i >= 0; --i)
+    {
+        length[i] = (BYTE)bits;
+        bits >>= 8;
+    }
+    aheadlibex_sha256_update(&ctx, &pad, 1);
+    while (ctx.used != 56)
+    {
+        aheadlibex_sha256_update(&ctx, &zero, 1);
+    }
+    aheadlibex_sha256_update(&ctx, length, 8);
+    for (i = 0; i < 32; ++i)
+    {
+        digest[i] = (BYTE)(ctx.state[i / 4] >> (24 - 8 * (i % 4)));
+    }
+    return TRUE;
+}
+
+static BOOL aheadlibex_same_digest(const BYTE* a, const BYTE* b)
+{
+    int i;
+    for (i = 0; i < 32; ++i)
+    {
+        if (a[i] != b[i])
+        {
+            return FALSE;
+        }
+    }
+    return TRUE;
+}
+
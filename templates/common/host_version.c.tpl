@@ -0,0 +1,24 @@
+// FILEVERSION of the host from its own VS_VERSIONINFO resource (already mapped, no version.dll).
+// 0 when the executable has none.
+static ULONGLONG aheadlibex_host_version(void)
+{
+    HMODULE host = GetModuleHandleW(NULL);
+    // VS_VERSION_INFO (1) of type RT_VERSION (16).
+    HRSRC info = FindResourceW(host, MAKEINTRESOURCEW(1), MAKEINTRESOURCEW(16));
+    HGLOBAL data = info ? LoadResource(host, info) : NULL;
+    const BYTE* bytes = data ? (const BYTE*)LockResource(data) : NULL;
+    DWORD size = info ? SizeofResource(host, info) : 0;
+    DWORD i;
+
+    // VS_FIXEDFILEINFO follows the variable-length key; find it by its signature.
+    for (i = 0; bytes && i + sizeof(VS_FIXEDFILEINFO) <= size; i += 4)
+    {
+        const VS_FIXEDFILEINFO* fixed = (const VS_FIXEDFILEINFO*)(bytes + i);
+        if (fixed->dwSignature == 0xFEEF04BD)
+        {
+            return ((ULONGLONG)fixed->dwFileVersionMS << 32) | fixed->dwFileVersionLS;
+        }
+    }
+    return 0;
+}
+
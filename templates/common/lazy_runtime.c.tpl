@@ -0,0 +1,17 @@
+
+// Pointers with a resolver start at their resolver entry. The first call resolves the export,
+// swaps the real address in and continues there; later calls jump straight through.
+{{EXTERN_C}}PVOID __cdecl AheadLibEx_Resolve(ULONG_PTR index, PVOID return_address)
+{
+    const AHEADLIBEX_EXPORT* entry = &g_aheadlibex_exports[index];
+    PVOID address;
+    PVOID current;
+
+    UNREFERENCED_PARAMETER(return_address);
+{{RESOLVE_INIT}}    address = (PVOID)get_address(entry->name ? entry->name : MAKEINTRESOURCEA(entry->ordinal),
+        {{RESOLVE_FALLBACK}});
+    // Leave the slot alone if install_hooks already pointed it at a detour, and continue wherever
+    // the slot leads so this first call reaches the detour too.
+    current = InterlockedCompareExchangePointer(entry->slot, address, (PVOID)entry->resolver);
+    return current == (PVOID)entry->resolver ? address : current;
+}
@@ -0,0 +1,205 @@
+
+// Minimal dependencies: kernel32 only and no C runtime; DllMain is the entry point itself.
+// These replace the few CRT/user32 helpers the proxy would otherwise import.
+#include <stdarg.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#if defined(_MSC_VER)
+#pragma function(memset, memcpy)
+#endif
+
+// The compiler may emit calls to these for zero-initialized arrays and struct copies.
+// volatile keeps the loops from being turned back into memset/memcpy calls.
+void* __cdecl memset(void* dest, int value, size_t count)
+{
+    volatile unsigned char* p = (volatile unsigned char*)dest;
+    while (count--)
+    {
+        *p++ = (unsigned char)value;
+    }
+    return dest;
+}
+
+void* __cdecl memcpy(void* dest, const void* src, size_t count)
+{
+    volatile unsigned char* d = (volatile unsigned char*)dest;
+    const unsigned char* s = (const unsigned char*)src;
+    while (count--)
+    {
+        *d++ = *s++;
+    }
+    return dest;
+}
+
+#ifdef __cplusplus
+}
+#endif
+
+// Not every proxy calls every helper below.
+#if defined(__GNUC__)
+#define AHEADLIBEX_MAYBE_UNUSED __attribute__((unused))
+#else
+#define AHEADLIBEX_MAYBE_UNUSED
+#endif
+
+static unsigned aheadlibex_char_at(const void* text, int wide, int index)
+{
+    return wide ? ((const WCHAR*)text)[index] : ((const unsigned char*)text)[index];
+}
+
+static VOID aheadlibex_put_char(void* buffer, int wide, int* pos, unsigned ch)
+{
+    // Same 1024-character limit as wsprintf.
+    if (*pos >= 1023)
+    {
+        return;
+    }
+    if (wide)
+    {
+        ((WCHAR*)buffer)[*pos] = (WCHAR)ch;
+    }
+    else
+    {
+        ((CHAR*)buffer)[*pos] = (CHAR)ch;
+    }
+    ++*pos;
+}
+
+// wsprintf subset used by the generated code: %s %hs %ls %d %u %lu %x %X with optional 0/width.
+static int aheadlibex_vformat(void* buffer, int wide, const void* format, va_list args)
+{
+    static const char lower[] = "0123456789abcdef";
+    static const char upper[] = "0123456789ABCDEF";
+    int pos = 0;
+    int i = 0;
+    unsigned ch;
+
+    while ((ch = aheadlibex_char_at(format, wide, i++)) != 0)
+    {
+        int width = 0;
+        int zero = 0;
+        unsigned size = 0;
+
+        if (ch != '%')
+        {
+            aheadlibex_put_char(buffer, wide, &pos, ch);
+            continue;
+        }
+        ch = aheadlibex_char_at(format, wide, i++);
+        if (ch == '0')
+        {
+            zero = 1;
+            ch = aheadlibex_char_at(format, wide, i++);
+        }
+        while (ch >= '0' && ch <= '9')
+        {
+            width = width * 10 + (int)(ch - '0');
+            ch = aheadlibex_char_at(format, wide, i++);
+        }
+        if (ch == 'h' || ch == 'l')
+        {
+            size = ch;
+            ch = aheadlibex_char_at(format, wide, i++);
+        }
+
+        if (ch == 's')
+        {
+            // Like wsprintf: %s matches the function's width, %hs is narrow, %ls is wide.
+            int wide_arg = size == 'h' ? 0 : (size == 'l' ? 1 : wide);
+            const void* text = va_arg(args, const void*);
+            int j = 0;
+            unsigned c;
+            if (!text)
+            {
+                text = wide_arg ? (const void*)L"(null)" : (const void*)"(null)";
+            }
+            while ((c = aheadlibex_char_at(text, wide_arg, j++)) != 0)
+            {
+                aheadlibex_put_char(buffer, wide, &pos, c);
+            }
+        }
+        else if (ch == 'd' || ch == 'u' || ch == 'x' || ch == 'X')
+        {
+            ULONG value = va_arg(args, ULONG);
+            ULONG base = (ch == 'x' || ch == 'X') ? 16 : 10;
+            const char* digits = ch == 'x' ? lower : upper;
+            CHAR text[12];
+            int count = 0;
+
+            if (ch == 'd' && (LONG)value < 0)
+            {
+                aheadlibex_put_char(buffer, wide, &pos, '-');
+                value = 0 - value;
+                --width;
+            }
+            do
+            {
+                text[count++] = digits[value % base];
+                value /= base;
+            } while (value);
+            while (width-- > count)
+            {
+                aheadlibex_put_char(buffer, wide, &pos, zero ? '0' : ' ');
+            }
+            while (count)
+            {
+                aheadlibex_put_char(buffer, wide, &pos, (unsigned char)text[--count]);
+            }
+        }
+        else
+        {
+            aheadlibex_put_char(buffer, wide, &pos, ch);
+        }
+    }
+
+    aheadlibex_put_char(buffer, wide, &pos, 0);
+    return pos - 1;
+}
+
+AHEADLIBEX_MAYBE_UNUSED static int __cdecl aheadlibex_wsprintfA(LPSTR buffer, LPCSTR format, ...)
+{
+    va_list args;
+    int n;
+    va_start(args, format);
+    n = aheadlibex_vformat(buffer, 0, format, args);
+    va_end(args);
+    return n;
+}
+
+AHEADLIBEX_MAYBE_UNUSED static int __cdecl aheadlibex_wsprintfW(LPWSTR buffer, LPCWSTR format, ...)
+{
+    va_list args;
+    int n;
+    va_start(args, format);
+    n = aheadlibex_vformat(buffer, 1, format, args);
+    va_end(args);
+    return n;
+}
+
+// user32 is only loaded if a message actually has to be shown.
+AHEADLIBEX_MAYBE_UNUSED static int WINAPI aheadlibex_message_box(HWND owner, LPCTSTR text, LPCTSTR caption, UINT type)
+{
+    typedef int(WINAPI * message_box_t)(HWND, LPCTSTR, LPCTSTR, UINT);
+#ifdef UNICODE
+    static const CHAR name[] = "MessageBoxW";
+#else
+    static const CHAR name[] = "MessageBoxA";
+#endif
+    HMODULE user32 = LoadLibraryA("user32.dll");
+    message_box_t show = user32 ? (message_box_t)(ULONG_PTR)GetProcAddress(user32, name) : NULL;
+    return show ? show(owner, text, caption, type) : 0;
+}
+
+#undef wsprintf
+#undef MessageBox
+#define wsprintfA aheadlibex_wsprintfA
+#define wsprintfW aheadlibex_wsprintfW
+#ifdef UNICODE
+#define wsprintf aheadlibex_wsprintfW
+#else
+#define wsprintf aheadlibex_wsprintfA
+#endif
+#define MessageBox aheadlibex_message_box
@@ -0,0 +1,47 @@
+typedef PVOID (WINAPI* origin_add_dll_directory_fn)(PCWSTR);
+typedef BOOL (WINAPI* origin_remove_dll_directory_fn)(PVOID);
+
+// AddDllDirectory cookies for the candidate being loaded. The directories only have to be there
+// while the original and its imports load; origin_remove_dll_directories drops them again.
+static PVOID g_origin_dll_dirs[{{DLL_DIR_COUNT}}];
+static DWORD g_origin_dll_dir_count;
+
+// Resolves `value` like a custom path and registers it for LOAD_LIBRARY_SEARCH_USER_DIRS.
+// AddDllDirectory is looked up at runtime (Windows 8, or Windows 7 with KB2533623).
+static VOID WINAPI origin_add_dll_directory(HMODULE module, LPCWSTR value)
+{
+    origin_add_dll_directory_fn add_dll_directory = NULL;
+    origin_path dir = { 0 };
+    HMODULE kernel32 = GetModuleHandleW(L"kernel32.dll");
+    if (kernel32)
+    {
+        add_dll_directory = (origin_add_dll_directory_fn)(ULONG_PTR)GetProcAddress(kernel32, "AddDllDirectory");
+    }
+    if (add_dll_directory && origin_resolve_path(module, value, &dir))
+    {
+        PVOID cookie = add_dll_directory(dir.text);
+        if (cookie)
+        {
+            g_origin_dll_dirs[g_origin_dll_dir_count++] = cookie;
+        }
+    }
+    origin_path_free(&dir);
+}
+
+static VOID WINAPI origin_remove_dll_directories(void)
+{
+    origin_remove_dll_directory_fn remove_dll_directory = NULL;
+    HMODULE kernel32 = GetModuleHandleW(L"kernel32.dll");
+    if (kernel32)
+    {
+        remove_dll_directory = (origin_remove_dll_directory_fn)(ULONG_PTR)GetProcAddress(kernel32, "RemoveDllDirectory");
+    }
+    while (g_origin_dll_dir_count > 0)
+    {
+        PVOID cookie = g_origin_dll_dirs[--g_origin_dll_dir_count];
+        if (remove_dll_directory)
+        {
+            remove_dll_directory(cookie);
+        }
+    }
+}
@@ -0,0 +1,26 @@
+// [origin] path=... ; GetPrivateProfileStringW truncates silently, so the buffer grows until it fits.
+static BOOL WINAPI origin_read_ini_path(const origin_path* config_path, origin_path* value)
+{
+    DWORD capacity = MAX_PATH;
+    while (capacity <= 0x10000)
+    {
+        DWORD n;
+        if (!origin_path_reserve(value, capacity))
+        {
+            return FALSE;
+        }
+        n = GetPrivateProfileStringW(L"origin", L"path", L"", value->text, value->capacity, config_path->text);
+        if (n == 0)
+        {
+            return FALSE;
+        }
+        if (n < value->capacity - 1)
+        {
+            value->length = n;
+            return TRUE;
+        }
+        capacity = value->capacity * 2;
+    }
+    return FALSE;
+}
+
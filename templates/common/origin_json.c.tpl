@@ -0,0 +1,119 @@
+// Reads the "path" string of a small flat JSON object (UTF-8). Only the escapes a Windows path
+// needs are decoded; this is not a general JSON parser.
+static BOOL WINAPI origin_read_json_path(const origin_path* config_path, origin_path* value)
+{
+    static const CHAR key[] = "path";
+    origin_path full = { 0 };
+    HANDLE file = INVALID_HANDLE_VALUE;
+    CHAR* text = NULL;
+    const CHAR* p = NULL;
+    DWORD size = 0;
+    DWORD read = 0;
+    DWORD len = 0;
+    int depth = 0;
+    BOOL found = FALSE;
+
+    if (origin_full_path(config_path, &full))
+    {
+        file = CreateFileW(full.text, GENERIC_READ, FILE_SHARE_READ, NULL, OPEN_EXISTING, FILE_ATTRIBUTE_NORMAL, NULL);
+    }
+    origin_path_free(&full);
+    if (file == INVALID_HANDLE_VALUE)
+    {
+        return FALSE;
+    }
+    size = GetFileSize(file, NULL);
+    if (size != INVALID_FILE_SIZE && size < 0x100000)
+    {
+        text = (CHAR*)HeapAlloc(GetProcessHeap(), 0, size + 1);
+    }
+    if (text && ReadFile(file, text, size, &read, NULL))
+    {
+        text[read] = '\0';
+        p = text;
+    }
+    CloseHandle(file);
+
+    // Walks the tokens, so "path" only counts as a key of the top-level object: not inside another
+    // string, not in a nested object.
+    while (p && *p)
+    {
+        if (*p == '{' || *p == '[')
+        {
+            ++depth;
+        }
+        else if (*p == '}' || *p == ']')
+        {
+            --depth;
+        }
+        else if (*p == '"')
+        {
+            const CHAR* start = ++p;
+            DWORD i = 0;
+            BOOL is_path;
+            while (*p && *p != '"')
+            {
+                p += (*p == '\\' && p[1]) ? 2 : 1;
+            }
+            if (!*p)
+            {
+                break;
+            }
+            while (key[i] && start + i < p && start[i] == key[i])
+            {
+                ++i;
+            }
+            is_path = depth == 1 && !key[i] && start + i == p;
+            ++p;
+            while (*p == ' ' || *p == '\t' || *p == '\r' || *p == '\n')
+            {
+                ++p;
+            }
+            // A string followed by ':' is a key.
+            if (is_path && *p == ':')
+            {
+                ++p;
+                break;
+            }
+            continue;
+        }
+        ++p;
+    }
+    if (p && *p)
+    {
+        CHAR* out = (CHAR*)p;
+        while (*p == ' ' || *p == '\t' || *p == '\r' || *p == '\n')
+        {
+            ++p;
+        }
+        // Unescaped in place: the output never runs ahead of the input.
+        if (*p++ == '"')
+        {
+            while (*p && *p != '"')
+            {
+                if (*p == '\\' && p[1])
+                {
+                    ++p;
+                }
+                out[len++] = *p++;
+            }
+            if (*p == '"' && len > 0)
+            {
+                int count = MultiByteToWideChar(CP_UTF8, 0, out, (int)len, NULL, 0);
+                if (count > 0 && origin_path_reserve(value, (DWORD)count + 1))
+                {
+                    MultiByteToWideChar(CP_UTF8, 0, out, (int)len, value->text, count);
+                    value->text[count] = L'\0';
+                    value->length = (DWORD)count;
+                    found = TRUE;
+                }
+            }
+        }
+    }
+    if (text)
+    {
+        HeapFree(GetProcessHeap(), 0, text);
+    }
+    return found;
+}
+
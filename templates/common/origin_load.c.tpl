@@ -0,0 +1,54 @@
+// LoadLibraryExW on the canonical (long-path safe) form; GetLastError is kept for the report.
+// A path that leads back to the proxy yields the proxy's own handle: that reference is dropped
+// and the location counts as not found, so the next candidate gets its turn.
+static HMODULE WINAPI origin_load(HMODULE self, const origin_path* path, DWORD flags)
+{
+    origin_path full = { 0 };
+    HMODULE handle = NULL;
+    DWORD error = ERROR_MOD_NOT_FOUND;
+    if (origin_full_path(path, &full))
+    {
+        handle = LoadLibraryExW(full.text, NULL, flags);
+        error = GetLastError();
+    }
+    if (handle && handle == self)
+    {
+        FreeLibrary(handle);
+        handle = NULL;
+        error = ERROR_MOD_NOT_FOUND;
+    }
+    origin_path_free(&full);
+    SetLastError(handle ? ERROR_SUCCESS : error);
+    return handle;
+}
+
+// "<what><subject>, AheadLibEx cannot continue." plus the error code, without wsprintf's
+// 1024-character cap.
+static VOID WINAPI origin_report(LPCWSTR what, LPCWSTR subject, DWORD error)
+{
+    WCHAR code[16];
+    origin_path text = { 0 };
+    LPCWSTR message = L"Cannot locate the original DLL, AheadLibEx cannot continue.";
+
+    wsprintfW(code, L"0x%08X", error);
+    if (origin_path_set(&text, what) && origin_path_append(&text, subject)
+        && origin_path_append(&text, L", AheadLibEx cannot continue.\nerror code:")
+        && origin_path_append(&text, code))
+    {
+        message = text.text;
+    }
+    report_failure(message, ERROR_MOD_NOT_FOUND);
+    origin_path_free(&text);
+}
+
+static VOID WINAPI origin_trace_loaded(const origin_path* path)
+{
+    origin_path text = { 0 };
+    if (origin_path_set(&text, L"AheadLibEx: original DLL loaded from ") && origin_path_append(&text, path->text)
+        && origin_path_append(&text, L"\n"))
+    {
+        OutputDebugStringW(text.text);
+    }
+    origin_path_free(&text);
+}
+
@@ -0,0 +1,203 @@
+// Heap-backed wide path: no MAX_PATH limit, always NUL-terminated.
+typedef struct origin_path
+{
+    WCHAR* text;
+    DWORD length;
+    DWORD capacity;
+} origin_path;
+
+static VOID WINAPI origin_path_free(origin_path* path)
+{
+    if (path->text)
+    {
+        HeapFree(GetProcessHeap(), 0, path->text);
+    }
+    path->text = NULL;
+    path->length = 0;
+    path->capacity = 0;
+}
+
+// Room for `capacity` characters including the terminator.
+static BOOL WINAPI origin_path_reserve(origin_path* path, DWORD capacity)
+{
+    WCHAR* text;
+    if (capacity <= path->capacity)
+    {
+        return TRUE;
+    }
+    capacity += 64;
+    if (path->text)
+    {
+        text = (WCHAR*)HeapReAlloc(GetProcessHeap(), 0, path->text, capacity * sizeof(WCHAR));
+    }
+    else
+    {
+        text = (WCHAR*)HeapAlloc(GetProcessHeap(), 0, capacity * sizeof(WCHAR));
+        if (text)
+        {
+            text[0] = L'\0';
+        }
+    }
+    if (!text)
+    {
+        return FALSE;
+    }
+    path->text = text;
+    path->capacity = capacity;
+    return TRUE;
+}
+
+static BOOL WINAPI origin_path_append_n(origin_path* path, LPCWSTR text, DWORD count)
+{
+    DWORD i;
+    if (!origin_path_reserve(path, path->length + count + 1))
+    {
+        return FALSE;
+    }
+    for (i = 0; i < count; ++i)
+    {
+        path->text[path->length + i] = text[i];
+    }
+    path->length += count;
+    path->text[path->length] = L'\0';
+    return TRUE;
+}
+
+static BOOL WINAPI origin_path_append(origin_path* path, LPCWSTR text)
+{
+    return origin_path_append_n(path, text, (DWORD)lstrlenW(text));
+}
+
+static BOOL WINAPI origin_path_set(origin_path* path, LPCWSTR text)
+{
+    path->length = 0;
+    return origin_path_append(path, text);
+}
+
+static BOOL WINAPI origin_path_append_ansi(origin_path* path, LPCSTR text)
+{
+    int count = MultiByteToWideChar(CP_ACP, 0, text, -1, NULL, 0);
+    if (count <= 0 || !origin_path_reserve(path, path->length + (DWORD)count))
+    {
+        return FALSE;
+    }
+    MultiByteToWideChar(CP_ACP, 0, text, -1, path->text + path->length, count);
+    path->length += (DWORD)count - 1;
+    return TRUE;
+}
+
+// Appends `name` after a single separator.
+static BOOL WINAPI origin_path_join(origin_path* path, LPCWSTR name)
+{
+    if (path->length > 0 && path->text[path->length - 1] != L'\\' && path->text[path->length - 1] != L'/')
+    {
+        if (!origin_path_append(path, L"\\"))
+        {
+            return FALSE;
+        }
+    }
+    return origin_path_append(path, name);
+}
+
+// Canonical absolute form of `path`. Past MAX_PATH it gets the \\?\ (or \\?\UNC\) prefix;
+// the prefix turns off normalisation, which is why GetFullPathNameW runs first.
+static BOOL WINAPI origin_full_path(const origin_path* path, origin_path* full)
+{
+    DWORD n;
+    if (path->length >= 4 && path->text[0] == L'\\' && path->text[1] == L'\\' && path->text[2] == L'?' && path->text[3] == L'\\')
+    {
+        return origin_path_set(full, path->text);
+    }
+    n = GetFullPathNameW(path->text, 0, NULL, NULL);
+    if (n == 0 || !origin_path_reserve(full, n))
+    {
+        return FALSE;
+    }
+    n = GetFullPathNameW(path->text, full->capacity, full->text, NULL);
+    if (n == 0 || n >= full->capacity)
+    {
+        return FALSE;
+    }
+    full->length = n;
+    if (n < MAX_PATH)
+    {
+        return TRUE;
+    }
+    {
+        origin_path prefixed = { 0 };
+        BOOL unc = full->text[0] == L'\\' && full->text[1] == L'\\';
+        BOOL ok = origin_path_set(&prefixed, unc ? L"\\\\?\\UNC\\" : L"\\\\?\\")
+            && origin_path_append(&prefixed, full->text + (unc ? 2 : 0));
+        origin_path_free(full);
+        *full = prefixed;
+        return ok;
+    }
+}
+
+// Full path of `module` (the host executable for NULL); GetModuleFileNameW truncates silently,
+// so the buffer grows until the result fits.
+static BOOL WINAPI origin_module_file(HMODULE module, origin_path* path)
+{
+    DWORD capacity = MAX_PATH;
+    while (capacity <= 0x10000)
+    {
+        DWORD n;
+        if (!origin_path_reserve(path, capacity))
+        {
+            return FALSE;
+        }
+        n = GetModuleFileNameW(module, path->text, path->capacity);
+        if (n == 0)
+        {
+            return FALSE;
+        }
+        if (n < path->capacity)
+        {
+            path->length = n;
+            return TRUE;
+        }
+        capacity = path->capacity * 2;
+    }
+    return FALSE;
+}
+
+// Directory of `module`, with the trailing separator.
+static BOOL WINAPI origin_module_dir(HMODULE module, origin_path* path)
+{
+    DWORD i;
+    DWORD end = 0;
+    if (!origin_module_file(module, path))
+    {
+        return FALSE;
+    }
+    for (i = 0; i < path->length; ++i)
+    {
+        if (path->text[i] == L'\\' || path->text[i] == L'/')
+        {
+            end = i + 1;
+        }
+    }
+    path->length = end;
+    path->text[end] = L'\0';
+    return TRUE;
+}
+
+typedef UINT (WINAPI* origin_dir_fn)(LPWSTR, UINT);
+
+// GetSystemDirectoryW / GetWindowsDirectoryW, sized by a first call.
+static BOOL WINAPI origin_known_dir(origin_dir_fn get_dir, origin_path* path)
+{
+    UINT n = get_dir(NULL, 0);
+    if (n == 0 || !origin_path_reserve(path, n))
+    {
+        return FALSE;
+    }
+    n = get_dir(path->text, path->capacity);
+    if (n == 0 || n >= path->capacity)
+    {
+        return FALSE;
+    }
+    path->length = n;
+    return TRUE;
+}
+
@@ -0,0 +1,57 @@
+#ifndef LOAD_LIBRARY_SEARCH_DLL_LOAD_DIR
+#define LOAD_LIBRARY_SEARCH_DLL_LOAD_DIR 0x00000100
+#define LOAD_LIBRARY_SEARCH_APPLICATION_DIR 0x00000200
+#define LOAD_LIBRARY_SEARCH_USER_DIRS 0x00000400
+#define LOAD_LIBRARY_SEARCH_SYSTEM32 0x00000800
+#define LOAD_LIBRARY_SEARCH_DEFAULT_DIRS 0x00001000
+#endif
+
+static BOOL WINAPI origin_file_exists(const origin_path* path)
+{
+    origin_path full = { 0 };
+    DWORD attributes = INVALID_FILE_ATTRIBUTES;
+    if (origin_full_path(path, &full))
+    {
+        attributes = GetFileAttributesW(full.text);
+    }
+    origin_path_free(&full);
+    return attributes != INVALID_FILE_ATTRIBUTES && !(attributes & FILE_ATTRIBUTE_DIRECTORY);
+}
+
+// Whether `path` is the proxy itself, asked of the loader rather than by comparing strings: a path
+// it maps to the proxy gives back the proxy's handle. Nothing is loaded; origin_load checks the
+// handle again after loading.
+static BOOL WINAPI origin_is_self(HMODULE self, const origin_path* path)
+{
+    origin_path full = { 0 };
+    HMODULE mapped = NULL;
+    if (origin_full_path(path, &full))
+    {
+        GetModuleHandleExW(GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT, full.text, &mapped);
+    }
+    origin_path_free(&full);
+    return mapped != NULL && mapped == self;
+}
+
+// `<dir>\<name>` if it exists; `dir` is `len` characters, optionally quoted (PATH entries).
+static BOOL WINAPI origin_try_dir(LPCWSTR dir, DWORD len, LPCWSTR name, HMODULE self, origin_path* module_path)
+{
+    if (len >= 2 && dir[0] == L'"' && dir[len - 1] == L'"')
+    {
+        ++dir;
+        len -= 2;
+    }
+    while (len > 0 && (dir[len - 1] == L'\\' || dir[len - 1] == L'/'))
+    {
+        --len;
+    }
+    module_path->length = 0;
+    if (len == 0 || !origin_path_append_n(module_path, dir, len))
+    {
+        return FALSE;
+    }
+    // Never pick up the proxy itself; the search moves on to the next directory instead.
+    return origin_path_join(module_path, name) && origin_file_exists(module_path)
+        && !origin_is_self(self, module_path);
+}
+
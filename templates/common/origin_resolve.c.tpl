@@ -0,0 +1,31 @@
+// Absolute and UNC paths are used as-is; anything else is relative to the proxy DLL directory.
+static BOOL WINAPI origin_resolve_path(HMODULE module, LPCWSTR value, origin_path* module_path)
+{
+    if (!value || !value[0])
+    {
+        return FALSE;
+    }
+    if (value[1] == L':' || value[0] == L'\\' || value[0] == L'/')
+    {
+        return origin_path_set(module_path, value);
+    }
+    return origin_module_dir(module, module_path) && origin_path_append(module_path, value);
+}
+
+// Environment variable, sized by a first call.
+static BOOL WINAPI origin_env(LPCWSTR name, origin_path* value)
+{
+    DWORD n = GetEnvironmentVariableW(name, NULL, 0);
+    if (n == 0 || !origin_path_reserve(value, n))
+    {
+        return FALSE;
+    }
+    n = GetEnvironmentVariableW(name, value->text, value->capacity);
+    if (n == 0 || n >= value->capacity)
+    {
+        return FALSE;
+    }
+    value->length = n;
+    return TRUE;
+}
+
@@ -0,0 +1,48 @@
+static HMODULE g_origin_module_handle;
+
+static VOID WINAPI free_origin_module(void)
+{
+    if (g_origin_module_handle)
+    {
+        FreeLibrary(g_origin_module_handle);
+        g_origin_module_handle = NULL;
+    }
+}
+
+{{FAILURE_POLICY}}
+{{ORIGIN_CANDIDATES}}static BOOL WINAPI load_original_module(HMODULE module)
+{
+{{LOAD_ORIGIN_MODULE}}
+}
+
+static FARPROC WINAPI get_address(PCSTR proc_name, FARPROC fallback)
+{
+    CHAR ordinal_name[16];
+    FARPROC address = g_origin_module_handle ? GetProcAddress(g_origin_module_handle, proc_name) : NULL;
+    if (!address)
+    {
+        // Decorated C++ names easily pass MAX_PATH, so the message is built on the heap.
+        origin_path text = { 0 };
+        LPCWSTR message = L"Cannot locate an export.";
+        if (HIWORD(proc_name) == 0)
+        {
+            wsprintfA(ordinal_name, "#%d", (int)(ULONG_PTR)proc_name);
+            proc_name = ordinal_name;
+        }
+        if (origin_path_set(&text, L"Cannot locate export ") && origin_path_append_ansi(&text, proc_name)
+            && origin_path_append(&text, L"."))
+        {
+            message = text.text;
+        }
+        report_failure(message, ERROR_PROC_NOT_FOUND);
+{{MISSING_EXPORT}}        origin_path_free(&text);
+        address = fallback;
+    }
+    return address;
+}
+
+static BOOL WINAPI init_forwarders(void)
+{
+{{INIT_FORWARDERS}}    return g_missing_export_count == 0;
+}
+{{INSTALL_HOOKS}}
\ No newline at end of file
@@ -0,0 +1,32 @@
+// System directory, Windows directory, then each PATH entry; the proxy itself is skipped.
+static BOOL WINAPI origin_search_path(HMODULE module, LPCWSTR name, origin_path* module_path)
+{
+    origin_path dir = { 0 };
+    BOOL found = FALSE;
+
+    if (origin_known_dir(GetSystemDirectoryW, &dir))
+    {
+        found = origin_try_dir(dir.text, dir.length, name, module, module_path);
+    }
+    if (!found && origin_known_dir(GetWindowsDirectoryW, &dir))
+    {
+        found = origin_try_dir(dir.text, dir.length, name, module, module_path);
+    }
+    if (!found && origin_env(L"PATH", &dir))
+    {
+        LPCWSTR entry = dir.text;
+        while (!found && *entry)
+        {
+            LPCWSTR end = entry;
+            while (*end && *end != L';')
+            {
+                ++end;
+            }
+            found = origin_try_dir(entry, (DWORD)(end - entry), name, module, module_path);
+            entry = *end ? end + 1 : end;
+        }
+    }
+    origin_path_free(&dir);
+    return found;
+}
+
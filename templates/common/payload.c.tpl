@@ -0,0 +1,24 @@
+// Payload for {{DLL_NAME}}, generated once by AheadLibEx (Rust)
+// Author: i1tao
+// Blog: https://www.winsec.cn
+// Github: https://github.com/i1tao/AheadLibEx
+
+// This file is yours: regenerating the proxy rewrites {{GENERATED_SRC}}, never this file.
+// {{GENERATED_SRC}} declares every function it expects from here. When hooks are added later, write
+// their callbacks here too, or move this file away and regenerate to get fresh stubs.
+
+// The generated code is written against the wide API; TCHAR is always WCHAR.
+#ifndef UNICODE
+#define UNICODE
+#endif
+#ifndef _UNICODE
+#define _UNICODE
+#endif
+#include <windows.h>
+#include "{{PROXY_HEADER}}"
+
+#ifdef UNREFERENCED_PARAMETER
+#undef UNREFERENCED_PARAMETER
+#endif
+#define UNREFERENCED_PARAMETER(P) (void)(P)
+{{PAYLOAD_NOTES}}{{PAYLOAD_MAIN}}{{INSTALL_HOOKS}}{{HOOK_CALLBACKS}}
\ No newline at end of file
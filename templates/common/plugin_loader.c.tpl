@@ -0,0 +1,152 @@
+{{PLUGIN_CONTEXT}}// a + b + c on the process heap; NULL when out of memory.
+static LPWSTR WINAPI plugin_concat(LPCWSTR a, LPCWSTR b, LPCWSTR c)
+{
+    int na = lstrlenW(a);
+    int nb = lstrlenW(b);
+    LPWSTR text = (LPWSTR)HeapAlloc(GetProcessHeap(), 0, (na + nb + lstrlenW(c) + 1) * sizeof(WCHAR));
+    if (text)
+    {
+        lstrcpyW(text, a);
+        lstrcpyW(text + na, b);
+        lstrcpyW(text + na + nb, c);
+    }
+    return text;
+}
+
+static VOID WINAPI plugin_free(LPVOID block)
+{
+    if (block)
+    {
+        HeapFree(GetProcessHeap(), 0, block);
+    }
+}
+
+// One OutputDebugString line per plugin, so a failing plugin never takes the host down.
+static VOID WINAPI plugin_report(LPCWSTR what, LPCWSTR path, DWORD error)
+{
+    WCHAR code[32];
+    LPWSTR text;
+    if (error != ERROR_SUCCESS)
+    {
+        wsprintfW(code, L" (error %lu)\n", error);
+    }
+    else
+    {
+        lstrcpyW(code, L"\n");
+    }
+    text = plugin_concat(what, path, code);
+    if (text)
+    {
+        OutputDebugStringW(text);
+        plugin_free(text);
+    }
+}
+
+// Loads every {{PLUGIN_PATTERN}} in {{PLUGIN_DIR}}, in case-insensitive name order. Runs from payload_main,
+// outside loader lock.
+static VOID WINAPI load_plugins(void)
+{
+    static const WCHAR plugin_dir[] = L"{{PLUGIN_DIR}}";
+    HMODULE self = NULL;
+    origin_path dir = { 0 };
+    origin_path base = { 0 };
+    origin_path query = { 0 };
+    BOOL listed;
+    LPWSTR* names = NULL;
+    DWORD count = 0;
+    DWORD capacity = 0;
+    DWORD i;
+    WIN32_FIND_DATAW data;
+    HANDLE find = INVALID_HANDLE_VALUE;
+
+    GetModuleHandleExW(GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS | GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+        (LPCWSTR)(ULONG_PTR)load_plugins, &self);
+    if (plugin_dir[1] == L':' || plugin_dir[0] == L'\\' || plugin_dir[0] == L'/')
+    {
+        listed = origin_path_set(&dir, plugin_dir);
+    }
+    else
+    {
+        listed = origin_module_dir(self, &dir) && origin_path_append(&dir, plugin_dir);
+    }
+    listed = listed && origin_full_path(&dir, &base);
+    if (listed && base.length > 0 && (base.text[base.length - 1] == L'\\' || base.text[base.length - 1] == L'/'))
+    {
+        base.text[--base.length] = L'\0';
+    }
+    listed = listed && origin_path_set(&query, base.text) && origin_path_join(&query, L"{{PLUGIN_PATTERN}}");
+    if (listed)
+    {
+        find = FindFirstFileW(query.text, &data);
+    }
+    if (find == INVALID_HANDLE_VALUE)
+    {
+        // No directory or no match just means there is nothing to load.
+        DWORD error = GetLastError();
+        if (!listed || (error != ERROR_FILE_NOT_FOUND && error != ERROR_PATH_NOT_FOUND))
+        {
+            plugin_report(L"AheadLibEx: cannot list plugins in ", listed ? query.text : plugin_dir, error);
+        }
+    }
+    else
+    {
+        do
+        {
+            if (data.dwFileAttributes & FILE_ATTRIBUTE_DIRECTORY)
+            {
+                continue;
+            }
+            if (count == capacity)
+            {
+                DWORD grown_capacity = capacity ? capacity * 2 : 16;
+                LPWSTR* grown = names ? (LPWSTR*)HeapReAlloc(GetProcessHeap(), 0, names, grown_capacity * sizeof(LPWSTR))
+                                      : (LPWSTR*)HeapAlloc(GetProcessHeap(), 0, grown_capacity * sizeof(LPWSTR));
+                if (!grown)
+                {
+                    break;
+                }
+                names = grown;
+                capacity = grown_capacity;
+            }
+            names[count] = plugin_concat(base.text, L"\\", data.cFileName);
+            if (names[count])
+            {
+                ++count;
+            }
+        } while (FindNextFileW(find, &data));
+        FindClose(find);
+    }
+
+    // FindFirstFile order depends on the file system; sorting keeps the load order stable.
+    for (i = 1; i < count; ++i)
+    {
+        LPWSTR name = names[i];
+        DWORD j = i;
+        while (j > 0 && lstrcmpiW(names[j - 1], name) > 0)
+        {
+            names[j] = names[j - 1];
+            --j;
+        }
+        names[j] = name;
+    }
+
+    for (i = 0; i < count; ++i)
+    {
+        LPCWSTR path = names[i];
+        // Altered search path: the plugin's own dependencies resolve from its directory first.
+        HMODULE plugin = LoadLibraryExW(path, NULL, LOAD_WITH_ALTERED_SEARCH_PATH);
+        if (!plugin)
+        {
+            plugin_report(L"AheadLibEx: plugin failed to load: ", path, GetLastError());
+        }
+        else
+        {
+{{PLUGIN_INIT}}        }
+        plugin_free(names[i]);
+    }
+    plugin_free(names);
+    origin_path_free(&query);
+    origin_path_free(&base);
+    origin_path_free(&dir);
+}
+
@@ -0,0 +1,24 @@
+// generated by AheadLibEx (Rust)
+// Author: i1tao
+// Blog: https://www.winsec.cn
+// Github: https://github.com/i1tao/AheadLibEx
+
+// Proxy API for {{DLL_NAME}}: the original module and its resolved exports, for payload code.
+// Regenerated together with the proxy sources; don't edit.
+#ifndef AHEADLIBEX_{{GUARD}}_PROXY_H
+#define AHEADLIBEX_{{GUARD}}_PROXY_H
+
+#include <windows.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// The original {{DLL_NAME}}; NULL until it has been loaded.
+HMODULE WINAPI AheadLibEx_OriginModule(void);
+{{POINTERS}}{{PROTOTYPES}}{{ENTRY_POINTS}}
+#ifdef __cplusplus
+}
+#endif
+{{CPP_API}}
+#endif
@@ -5,96 +5,33 @@
 
 // Auto proxy for {{DLL_NAME}}
 
+// The generated code is written against the wide API; TCHAR is always WCHAR.
+#ifndef UNICODE
+#define UNICODE
+#endif
+#ifndef _UNICODE
+#define _UNICODE
+#endif
 #include <windows.h>
+#include "{{PROXY_HEADER}}"
 
 #ifdef UNREFERENCED_PARAMETER
 #undef UNREFERENCED_PARAMETER
 #endif
 #define UNREFERENCED_PARAMETER(P) (void)(P)
-
+{{MINIMAL_RUNTIME}}
 {{EXPORT_PRAGMAS}}
 
-#ifdef __cplusplus
-#define AHEADLIB_EXTERN extern "C"
-#else
-#define AHEADLIB_EXTERN
-#endif
-
 {{FORWARD_DECLS}}
 
-#undef AHEADLIB_EXTERN
-
-static HMODULE g_origin_module_handle;
-
-static VOID WINAPI free_origin_module(void)
-{
-    if (g_origin_module_handle)
-    {
-        FreeLibrary(g_origin_module_handle);
-        g_origin_module_handle = NULL;
-    }
-}
-
-static BOOL WINAPI load_original_module(HMODULE module)
-{
-{{LOAD_ORIGIN_MODULE}}
-}
-
-static FARPROC WINAPI get_address(PCSTR proc_name)
-{
-    CHAR ordinal_name[64];
-    TCHAR message[MAX_PATH];
-    FARPROC address = GetProcAddress(g_origin_module_handle, proc_name);
-    if (!address)
-    {
-        if (HIWORD(proc_name) == 0)
-        {
-            wsprintfA(ordinal_name, "#%d", proc_name);
-            proc_name = ordinal_name;
-        }
-        wsprintf(message, TEXT("Cannot locate export %hs."), proc_name);
-        MessageBox(NULL, message, TEXT("AheadLibEx"), MB_ICONSTOP);
-
-        ExitProcess(0);
-    }
-    return address;
-}
-
-static VOID WINAPI init_forwarders(void)
-{
-{{INIT_FORWARDERS}}
-}
-
-DWORD WINAPI patch_thread_proc(LPVOID context)
-{
-    UNREFERENCED_PARAMETER(context);
-    // TODO: Put custom patch logic here when the target process matches.
-    MessageBox(NULL, TEXT("AheadLibExTest!"), TEXT("AheadLibEx"), MB_OK);
-    return 0;
-}
-
-BOOL APIENTRY DllMain(HMODULE module, DWORD reason, PVOID reserved)
+{{PATH_HELPERS}}{{CRASH_GUARD}}{{HOST_FILTER}}{{PAYLOAD}}{{ORIGIN_RUNTIME}}{{EXPORT_RUNTIME}}{{HOOK_FUNCTIONS}}
+{{EXTERN_C}}BOOL APIENTRY DllMain(HMODULE module, DWORD reason, PVOID reserved)
 {
     UNREFERENCED_PARAMETER(reserved);
     if (reason == DLL_PROCESS_ATTACH)
     {
         DisableThreadLibraryCalls(module);
-        if (!load_original_module(module))
-        {
-            return FALSE;
-        }
-        init_forwarders();
-
-        // TODO: your patch process begins here.
-        HANDLE thread = CreateThread(NULL, 0, patch_thread_proc, NULL, 0, NULL);
-        if (thread)
-        {
-            CloseHandle(thread);
-        }
-    }
-    else if (reason == DLL_PROCESS_DETACH)
-    {
-        free_origin_module();
-    }
-    return TRUE;
+{{ATTACH_INIT}}
+{{ATTACH_PAYLOAD}}    }
+{{DETACH_CLEANUP}}    return TRUE;
 }
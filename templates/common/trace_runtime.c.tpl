@@ -0,0 +1,282 @@
+
+// Call tracing: every stub records into a lock-free ring buffer, a background thread
+// flushes it to <proxy>.trace.log next to the proxy DLL.
+#if defined(_MSC_VER)
+#include <intrin.h>
+#define AHEADLIBEX_RETURN_ADDRESS() _ReturnAddress()
+#else
+#define AHEADLIBEX_RETURN_ADDRESS() __builtin_return_address(0)
+#endif
+
+#if defined(_WIN64)
+#define AHEADLIBEX_HIGH_PART(v) ((ULONG)((ULONG64)(v) >> 32))
+#else
+#define AHEADLIBEX_HIGH_PART(v) 0UL
+#endif
+
+#define AHEADLIBEX_TRACE_CAPACITY 8192
+#define AHEADLIBEX_TRACE_FLUSH_MS 200
+
+typedef struct AHEADLIBEX_TRACE_RECORD
+{
+    // 0 while a writer fills the record, ticket + 1 once it is complete.
+    volatile LONG sequence;
+    DWORD thread_id;
+    ULONG_PTR index;
+    PVOID return_address;
+    LARGE_INTEGER timestamp;
+} AHEADLIBEX_TRACE_RECORD;
+
+typedef struct AHEADLIBEX_TRACE_BUFFER
+{
+    CHAR data[4096];
+    DWORD length;
+} AHEADLIBEX_TRACE_BUFFER;
+
+static AHEADLIBEX_TRACE_RECORD g_trace_ring[AHEADLIBEX_TRACE_CAPACITY];
+static volatile LONG g_trace_head;
+static LONG g_trace_tail;
+static LONG g_trace_dropped;
+static volatile LONG g_trace_flushing;
+static volatile LONG g_trace_flusher_started;
+static HANDLE g_trace_file = INVALID_HANDLE_VALUE;
+// Only touched by the flusher that holds g_trace_flushing; static keeps frames under a page.
+static AHEADLIBEX_TRACE_BUFFER g_trace_buffer;
+
+static VOID trace_write(AHEADLIBEX_TRACE_BUFFER* buffer)
+{
+    DWORD written = 0;
+    if (buffer->length && g_trace_file != INVALID_HANDLE_VALUE)
+    {
+        WriteFile(g_trace_file, buffer->data, buffer->length, &written, NULL);
+    }
+    buffer->length = 0;
+}
+
+static VOID trace_append(AHEADLIBEX_TRACE_BUFFER* buffer, PCSTR text)
+{
+    while (*text)
+    {
+        if (buffer->length == sizeof(buffer->data))
+        {
+            trace_write(buffer);
+        }
+        buffer->data[buffer->length++] = *text++;
+    }
+}
+
+// 64-bit values are handled as two 32-bit halves so x86 builds never need _aulldiv/_aullshr.
+static ULONG trace_divmod10(ULONG* high, ULONG* low)
+{
+    ULONG rest = *high % 10;
+    ULONG middle = (rest << 16) | (*low >> 16);
+    ULONG bottom;
+
+    *high /= 10;
+    rest = middle % 10;
+    middle /= 10;
+    bottom = (rest << 16) | (*low & 0xFFFF);
+    *low = (middle << 16) | (bottom / 10);
+    return bottom % 10;
+}
+
+static VOID trace_append_dec(AHEADLIBEX_TRACE_BUFFER* buffer, ULONG high, ULONG low)
+{
+    CHAR text[24];
+    int pos = sizeof(text) - 1;
+
+    text[pos] = '\0';
+    do
+    {
+        text[--pos] = (CHAR)('0' + trace_divmod10(&high, &low));
+    } while (high || low);
+    trace_append(buffer, text + pos);
+}
+
+static VOID trace_append_hex(AHEADLIBEX_TRACE_BUFFER* buffer, ULONG high, ULONG low, int digits)
+{
+    static const CHAR hex[] = "0123456789ABCDEF";
+    CHAR text[17];
+    int i;
+
+    for (i = 0; i < digits; ++i)
+    {
+        int shift = (digits - 1 - i) * 4;
+        ULONG part = shift >= 32 ? high >> (shift - 32) : low >> shift;
+        text[i] = hex[part & 0xF];
+    }
+    text[digits] = '\0';
+    trace_append(buffer, text);
+}
+
+static VOID trace_open(void)
+{
+    static const WCHAR suffix[] = L".trace.log";
+    WCHAR path[MAX_PATH + 16];
+    HMODULE self = NULL;
+    LARGE_INTEGER frequency;
+    DWORD n;
+    DWORD dot;
+    DWORD i;
+
+    if (g_trace_file != INVALID_HANDLE_VALUE)
+    {
+        return;
+    }
+    if (!GetModuleHandleExW(GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS | GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+                            (LPCWSTR)(ULONG_PTR)&trace_open, &self))
+    {
+        return;
+    }
+    n = GetModuleFileNameW(self, path, MAX_PATH);
+    if (n == 0 || n >= MAX_PATH)
+    {
+        return;
+    }
+
+    // foo.dll -> foo.trace.log
+    dot = n;
+    for (i = n; i > 0; --i)
+    {
+        if (path[i - 1] == L'.')
+        {
+            dot = i - 1;
+            break;
+        }
+        if (path[i - 1] == L'\\' || path[i - 1] == L'/')
+        {
+            break;
+        }
+    }
+    for (i = 0; i < sizeof(suffix) / sizeof(suffix[0]); ++i)
+    {
+        path[dot + i] = suffix[i];
+    }
+
+    g_trace_file = CreateFileW(path, GENERIC_WRITE, FILE_SHARE_READ, NULL, CREATE_ALWAYS, FILE_ATTRIBUTE_NORMAL, NULL);
+    if (g_trace_file == INVALID_HANDLE_VALUE)
+    {
+        return;
+    }
+
+    QueryPerformanceFrequency(&frequency);
+    trace_append(&g_trace_buffer, "# AheadLibEx call trace for {{DLL_NAME}}\r\n# qpc_frequency ");
+    trace_append_dec(&g_trace_buffer, (ULONG)frequency.HighPart, frequency.LowPart);
+    trace_append(&g_trace_buffer, "\r\n# timestamp\tthread\tcaller\tordinal\tname\r\n");
+    trace_write(&g_trace_buffer);
+}
+
+VOID AheadLibEx_TraceFlush(void)
+{
+    AHEADLIBEX_TRACE_BUFFER* buffer = &g_trace_buffer;
+    LONG head;
+
+    if (InterlockedCompareExchange(&g_trace_flushing, 1, 0) != 0)
+    {
+        return;
+    }
+
+    trace_open();
+    head = g_trace_head;
+    // Writers lapped the reader; the oldest records are gone.
+    if (head - g_trace_tail > AHEADLIBEX_TRACE_CAPACITY)
+    {
+        g_trace_dropped += head - g_trace_tail - AHEADLIBEX_TRACE_CAPACITY;
+        g_trace_tail = head - AHEADLIBEX_TRACE_CAPACITY;
+    }
+
+    while (g_trace_tail != head)
+    {
+        AHEADLIBEX_TRACE_RECORD* slot = &g_trace_ring[g_trace_tail & (AHEADLIBEX_TRACE_CAPACITY - 1)];
+        LONG expected = g_trace_tail + 1;
+        LONG sequence = slot->sequence;
+        DWORD thread_id;
+        ULONG_PTR index;
+        PVOID return_address;
+        LARGE_INTEGER timestamp;
+        const AHEADLIBEX_EXPORT* entry;
+
+        if (sequence == 0)
+        {
+            // Still being written; pick it up on the next flush.
+            break;
+        }
+
+        thread_id = slot->thread_id;
+        index = slot->index;
+        return_address = slot->return_address;
+        timestamp = slot->timestamp;
+        MemoryBarrier();
+        if (sequence != expected || slot->sequence != expected)
+        {
+            g_trace_dropped++;
+            g_trace_tail++;
+            continue;
+        }
+
+        entry = &g_aheadlibex_exports[index];
+        trace_append_dec(buffer, (ULONG)timestamp.HighPart, timestamp.LowPart);
+        trace_append(buffer, "\t");
+        trace_append_dec(buffer, 0, thread_id);
+        trace_append(buffer, "\t0x");
+        trace_append_hex(buffer, AHEADLIBEX_HIGH_PART(return_address), (ULONG)(ULONG_PTR)return_address,
+                         (int)sizeof(PVOID) * 2);
+        trace_append(buffer, "\t#");
+        trace_append_dec(buffer, 0, entry->ordinal);
+        trace_append(buffer, "\t");
+        trace_append(buffer, entry->name ? entry->name : "(noname)");
+        trace_append(buffer, "\r\n");
+        g_trace_tail++;
+    }
+
+    if (g_trace_dropped)
+    {
+        trace_append(buffer, "# dropped ");
+        trace_append_dec(buffer, 0, (ULONG)g_trace_dropped);
+        trace_append(buffer, " record(s)\r\n");
+        g_trace_dropped = 0;
+    }
+    trace_write(buffer);
+    InterlockedExchange(&g_trace_flushing, 0);
+}
+
+static DWORD WINAPI trace_flusher_proc(LPVOID context)
+{
+    UNREFERENCED_PARAMETER(context);
+    for (;;)
+    {
+        Sleep(AHEADLIBEX_TRACE_FLUSH_MS);
+        AheadLibEx_TraceFlush();
+    }
+}
+
+// Called by the trace thunk with the export index and the caller's return address;
+// returns the address the thunk jumps to.
+{{EXTERN_C}}PVOID __cdecl AheadLibEx_TraceEnter(ULONG_PTR index, PVOID return_address)
+{
+    LONG ticket = InterlockedIncrement(&g_trace_head) - 1;
+    AHEADLIBEX_TRACE_RECORD* slot = &g_trace_ring[ticket & (AHEADLIBEX_TRACE_CAPACITY - 1)];
+
+    InterlockedExchange(&slot->sequence, 0);
+    slot->thread_id = GetCurrentThreadId();
+    slot->index = index;
+    slot->return_address = return_address;
+    QueryPerformanceCounter(&slot->timestamp);
+    InterlockedExchange(&slot->sequence, ticket + 1);
+
+    if (!g_trace_flusher_started && InterlockedCompareExchange(&g_trace_flusher_started, 1, 0) == 0)
+    {
+        // The flusher never exits, so keep the proxy mapped for the life of the process.
+        HMODULE self = NULL;
+        HANDLE thread;
+        GetModuleHandleExW(GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS | GET_MODULE_HANDLE_EX_FLAG_PIN,
+                           (LPCWSTR)(ULONG_PTR)&AheadLibEx_TraceEnter, &self);
+        thread = CreateThread(NULL, 0, trace_flusher_proc, NULL, 0, NULL);
+        if (thread)
+        {
+            CloseHandle(thread);
+        }
+    }
+
+    return *g_aheadlibex_exports[index].slot;
+}
@@ -0,0 +1,41 @@
+# Generated by AheadLibEx (Rust)
+# Author: i1tao
+# GitHub: https://github.com/i1tao/AheadLibEx
+#
+# MSVC / clang-cl: meson setup build --vsenv
+# MinGW-w64:       meson setup build --cross-file {{CROSS_FILE}}
+# Then:            meson compile -C build
+
+project('{{PROJECT_NAME}}', {{PROJECT_LANGUAGES}}, meson_version: '>=1.3.0'{{DEFAULT_OPTIONS}})
+
+cc = meson.get_compiler('{{LANGUAGE}}')
+
+# The payload source, when there is one, holds your code; regeneration never overwrites it.
+aheadlibex_sources = files({{SOURCES}})
+
+# Toolchain selection (skipped when every export is a linker forwarder):
+# - MSVC / clang-cl: MASM (.asm)
+# - MinGW-w64 (gcc/clang): GAS (.S)
+aheadlibex_asm_masm = '{{ASM_MASM_SRC}}'
+aheadlibex_asm_gas = '{{ASM_GAS_SRC}}'
+if aheadlibex_asm_masm != ''
+  if cc.get_argument_syntax() == 'msvc'
+    add_languages('masm', native: false)
+    aheadlibex_sources += files(aheadlibex_asm_masm)
+  else
+    aheadlibex_sources += files(aheadlibex_asm_gas)
+  endif
+endif
+
+{{LINK_LIBRARIES}}
+shared_library(
+  '{{OUTPUT_NAME}}',
+  aheadlibex_sources,
+  name_prefix: '',
+  name_suffix: 'dll',
+  # Use .def for exports across toolchains.
+  vs_module_defs: '{{DEF_SRC}}',
+  {{LANGUAGE}}_args: aheadlibex_args,
+  link_args: aheadlibex_link_args,
+  dependencies: aheadlibex_deps,
+)
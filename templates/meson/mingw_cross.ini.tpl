@@ -0,0 +1,18 @@
+# Generated by AheadLibEx (Rust)
+# Author: i1tao
+# GitHub: https://github.com/i1tao/AheadLibEx
+#
+# MinGW-w64 cross build of the {{ARCH}} proxy: meson setup build --cross-file {{CROSS_FILE}}
+
+[binaries]
+c = '{{TRIPLE}}-gcc'
+cpp = '{{TRIPLE}}-g++'
+ar = '{{TRIPLE}}-ar'
+strip = '{{TRIPLE}}-strip'
+windres = '{{TRIPLE}}-windres'
+
+[host_machine]
+system = 'windows'
+cpu_family = '{{CPU_FAMILY}}'
+cpu = '{{CPU}}'
+endian = 'little'
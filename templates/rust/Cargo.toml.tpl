@@ -0,0 +1,22 @@
+# Generated by AheadLibEx (Rust)
+# Author: i1tao
+# GitHub: https://github.com/i1tao/AheadLibEx
+
+[package]
+name = "{{PACKAGE_NAME}}"
+version = "0.1.0"
+edition = "2021"
+build = "build.rs"
+
+[lib]
+{{LIB_NOTE}}name = "{{LIB_NAME}}"
+crate-type = ["cdylib"]
+
+# Standalone, even when generated inside another workspace.
+[workspace]
+
+[profile.dev]
+panic = "abort"
+
+[profile.release]
+panic = "abort"
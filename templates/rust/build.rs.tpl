@@ -0,0 +1,26 @@
+// generated by AheadLibEx (Rust)
+// Author: i1tao
+// Blog: https://www.winsec.cn
+// Github: https://github.com/i1tao/AheadLibEx
+
+// Hands the .def to the linker, so the DLL exports exactly what the original does.
+use std::env;
+use std::path::Path;
+
+const DEF_FILE: &str = "{{DEF_FILE}}";
+
+fn main() {
+    let def = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join(DEF_FILE);
+    println!("cargo:rerun-if-changed={DEF_FILE}");
+
+    let os = env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+    let arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default();
+    if os != "windows" || arch != "{{TARGET_ARCH}}" {
+        panic!("{{DLL_NAME}} is built for Windows on {{TARGET_ARCH}}; build with --target {{TARGET_ARCH_TRIPLE}}-pc-windows-gnu or -msvc");
+    }
+    match env::var("CARGO_CFG_TARGET_ENV").as_deref() {
+        Ok("msvc") => println!("cargo:rustc-cdylib-link-arg=/DEF:{}", def.display()),
+        // GNU ld takes a .def file as a plain input.
+        _ => println!("cargo:rustc-cdylib-link-arg={}", def.display()),
+    }
+}
@@ -0,0 +1,10 @@
+// generated by AheadLibEx (Rust) - {{ARCH}} jump table
+// Author: i1tao
+// Blog: https://www.winsec.cn
+// Github: https://github.com/i1tao/AheadLibEx
+
+//! Export stubs named in the .def: each jumps through its pfnAheadLibEx_<name> pointer.
+
+use core::arch::global_asm;
+
+{{JUMPS}}
@@ -0,0 +1,24 @@
+// generated by AheadLibEx (Rust) - only {{ARCH}}
+// Author: i1tao
+// Blog: https://www.winsec.cn
+// Github: https://github.com/i1tao/AheadLibEx
+
+//! Auto proxy for {{DLL_NAME}}. Regenerated together with the .def; your code goes in src/payload.rs.
+#![allow(non_upper_case_globals, non_snake_case)]
+
+use core::ffi::c_void;
+{{IMPORTS}}
+mod jump;
+{{MODULES}}mod sys;
+{{POINTERS}}{{ORIGIN_RUNTIME}}
+/// The original {{DLL_NAME}}; null until it has been loaded.
+pub fn origin_module() -> sys::HMODULE {
+{{ORIGIN_MODULE}}}
+{{PAYLOAD}}
+// Called through the DllMain label in jump.rs, so rustc doesn't add it to the exports.
+extern "system" fn dll_main(module: sys::HMODULE, reason: u32, {{RESERVED}}: *mut c_void) -> sys::BOOL {
+    if reason == sys::DLL_PROCESS_ATTACH {
+        unsafe { sys::DisableThreadLibraryCalls(module) };
+{{ATTACH}}    }{{DETACH}}
+    1
+}
@@ -0,0 +1,326 @@
+// generated by AheadLibEx (Rust)
+// Author: i1tao
+// Blog: https://www.winsec.cn
+// Github: https://github.com/i1tao/AheadLibEx
+
+//! Finds and loads the original {{DLL_NAME}}: {{ORIGIN_SUMMARY}}.
+#![allow(dead_code)]
+
+use crate::sys::{self, HMODULE};
+use core::ffi::{c_void, CStr};
+use core::ptr::null_mut;
+use std::sync::Mutex;
+
+/// A wide path without the terminating NUL.
+type WidePath = Vec<u16>;
+
+/// One place the original may be; None if it can't be built (unset variable, missing config, ...).
+type Candidate = fn(HMODULE) -> Option<WidePath>;
+
+fn units(text: &str) -> WidePath {
+    text.encode_utf16().collect()
+}
+
+fn nul(path: &[u16]) -> Vec<u16> {
+    path.iter().copied().chain(Some(0)).collect()
+}
+
+fn is_separator(unit: u16) -> bool {
+    unit == u16::from(b'\\') || unit == u16::from(b'/')
+}
+
+// Full path of `module` (the host executable for null); GetModuleFileNameW truncates silently,
+// so the buffer grows until the result fits.
+fn module_file(module: HMODULE) -> Option<WidePath> {
+    let mut capacity = 260usize;
+    while capacity <= 0x10000 {
+        let mut buffer = vec![0u16; capacity];
+        let n = unsafe { sys::GetModuleFileNameW(module, buffer.as_mut_ptr(), capacity as u32) } as usize;
+        if n == 0 {
+            return None;
+        }
+        if n < capacity {
+            buffer.truncate(n);
+            return Some(buffer);
+        }
+        capacity *= 2;
+    }
+    None
+}
+
+// Directory of `module`, with the trailing separator.
+fn module_dir(module: HMODULE) -> Option<WidePath> {
+    let mut path = module_file(module)?;
+    let end = path.iter().rposition(|&c| is_separator(c)).map_or(0, |i| i + 1);
+    path.truncate(end);
+    Some(path)
+}
+
+// GetSystemDirectoryW / GetWindowsDirectoryW, sized by a first call.
+fn known_dir(get_dir: sys::KnownDirFn) -> Option<WidePath> {
+    let n = unsafe { get_dir(null_mut(), 0) } as usize;
+    if n == 0 {
+        return None;
+    }
+    let mut buffer = vec![0u16; n];
+    let len = unsafe { get_dir(buffer.as_mut_ptr(), n as u32) } as usize;
+    if len == 0 || len >= n {
+        return None;
+    }
+    buffer.truncate(len);
+    Some(buffer)
+}
+
+fn join(mut dir: WidePath, name: &str) -> WidePath {
+    if dir.last().is_some_and(|&c| !is_separator(c)) {
+        dir.push(u16::from(b'\\'));
+    }
+    dir.extend(name.encode_utf16());
+    dir
+}
+
+// Absolute and UNC paths are used as-is; anything else is relative to the proxy DLL directory.
+fn resolve(module: HMODULE, value: &[u16]) -> Option<WidePath> {
+    let first = *value.first()?;
+    if value.get(1) == Some(&u16::from(b':')) || is_separator(first) {
+        return Some(value.to_vec());
+    }
+    let mut path = module_dir(module)?;
+    path.extend_from_slice(value);
+    Some(path)
+}
+
+// Environment variable, sized by a first call.
+fn env(name: &str) -> Option<WidePath> {
+    let name = nul(&units(name));
+    let n = unsafe { sys::GetEnvironmentVariableW(name.as_ptr(), null_mut(), 0) } as usize;
+    if n == 0 {
+        return None;
+    }
+    let mut buffer = vec![0u16; n];
+    let len = unsafe { sys::GetEnvironmentVariableW(name.as_ptr(), buffer.as_mut_ptr(), n as u32) } as usize;
+    if len == 0 || len >= n {
+        return None;
+    }
+    buffer.truncate(len);
+    Some(buffer)
+}
+
+fn file_exists(path: &[u16]) -> bool {
+    let attributes = unsafe { sys::GetFileAttributesW(nul(path).as_ptr()) };
+    attributes != sys::INVALID_FILE_ATTRIBUTES && attributes & sys::FILE_ATTRIBUTE_DIRECTORY == 0
+}
+
+// GetFullPathNameW, sized by a first call.
+fn full_path(path: &[u16]) -> Option<WidePath> {
+    let path = nul(path);
+    let n = unsafe { sys::GetFullPathNameW(path.as_ptr(), 0, null_mut(), null_mut()) } as usize;
+    if n == 0 {
+        return None;
+    }
+    let mut buffer = vec![0u16; n];
+    let len = unsafe { sys::GetFullPathNameW(path.as_ptr(), n as u32, buffer.as_mut_ptr(), null_mut()) } as usize;
+    if len == 0 || len >= n {
+        return None;
+    }
+    buffer.truncate(len);
+    Some(buffer)
+}
+
+// Whether `path` is the proxy itself, asked of the loader rather than by comparing strings: a path
+// it maps to the proxy gives back the proxy's handle. Nothing is loaded; `load` checks the handle
+// again after loading.
+fn is_self(module: HMODULE, path: &[u16]) -> bool {
+    let Some(full) = full_path(path) else {
+        return false;
+    };
+    let mut mapped = null_mut();
+    let flags = sys::GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT;
+    unsafe { sys::GetModuleHandleExW(flags, nul(&full).as_ptr(), &mut mapped) };
+    !mapped.is_null() && mapped == module
+}
+
+// `<dir>\<name>` if it exists; `dir` may be quoted (PATH entries).
+fn try_dir(dir: &[u16], name: &str, module: HMODULE) -> Option<WidePath> {
+    let quote = u16::from(b'"');
+    let mut dir = dir;
+    if dir.len() >= 2 && dir[0] == quote && dir[dir.len() - 1] == quote {
+        dir = &dir[1..dir.len() - 1];
+    }
+    while dir.last().is_some_and(|&c| is_separator(c)) {
+        dir = &dir[..dir.len() - 1];
+    }
+    if dir.is_empty() {
+        return None;
+    }
+    let path = join(dir.to_vec(), name);
+    // Never pick up the proxy itself; the search moves on to the next directory instead.
+    (file_exists(&path) && !is_self(module, &path)).then_some(path)
+}
+
+// try_dir for a directory that may not be available.
+fn probe(dir: Option<WidePath>, name: &str, module: HMODULE) -> Option<WidePath> {
+    try_dir(&dir?, name, module)
+}
+
+// System directory, Windows directory, then each PATH entry; the proxy itself is skipped.
+fn search(module: HMODULE, name: &str) -> Option<WidePath> {
+    let from_known = |get_dir: sys::KnownDirFn| known_dir(get_dir).and_then(|dir| try_dir(&dir, name, module));
+    from_known(sys::GetSystemDirectoryW)
+        .or_else(|| from_known(sys::GetWindowsDirectoryW))
+        .or_else(|| {
+            let path = env("PATH")?;
+            path.split(|&c| c == u16::from(b';')).find_map(|entry| try_dir(entry, name, module))
+        })
+}
+
+// AddDllDirectory cookies for the candidate being loaded. The directories only have to be there
+// while the original and its imports load; remove_dll_directories drops them again.
+static DLL_DIRECTORIES: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+
+// A kernel32 export that older systems may lack; null if it is missing.
+fn kernel32_proc(name: &CStr) -> *mut c_void {
+    let kernel32 = unsafe { sys::GetModuleHandleW(sys::wide("kernel32.dll").as_ptr()) };
+    if kernel32.is_null() {
+        return null_mut();
+    }
+    unsafe { sys::GetProcAddress(kernel32, name.as_ptr().cast()) }
+}
+
+// Resolves `value` like a custom path and registers it for LOAD_LIBRARY_SEARCH_USER_DIRS.
+// AddDllDirectory is looked up at runtime (Windows 8, or Windows 7 with KB2533623).
+fn add_dll_directory(module: HMODULE, value: &str) {
+    let address = kernel32_proc(c"AddDllDirectory");
+    if address.is_null() {
+        return;
+    }
+    let add: sys::AddDllDirectoryFn = unsafe { core::mem::transmute(address) };
+    if let Some(dir) = resolve(module, &units(value)) {
+        let cookie = unsafe { add(nul(&dir).as_ptr()) };
+        if !cookie.is_null() {
+            if let Ok(mut cookies) = DLL_DIRECTORIES.lock() {
+                cookies.push(cookie as usize);
+            }
+        }
+    }
+}
+
+fn remove_dll_directories() {
+    let Ok(mut cookies) = DLL_DIRECTORIES.lock() else {
+        return;
+    };
+    let address = kernel32_proc(c"RemoveDllDirectory");
+    while let Some(cookie) = cookies.pop() {
+        if !address.is_null() {
+            let remove: sys::RemoveDllDirectoryFn = unsafe { core::mem::transmute(address) };
+            unsafe { remove(cookie as *mut c_void) };
+        }
+    }
+}
+
+// [origin] path=... ; GetPrivateProfileStringW truncates silently, so the buffer grows until it fits.
+fn read_ini_path(config: &[u16]) -> Option<WidePath> {
+    let (section, key, empty, file) = (sys::wide("origin"), sys::wide("path"), sys::wide(""), nul(config));
+    let mut capacity = 260usize;
+    while capacity <= 0x10000 {
+        let mut buffer = vec![0u16; capacity];
+        let n = unsafe {
+            sys::GetPrivateProfileStringW(
+                section.as_ptr(),
+                key.as_ptr(),
+                empty.as_ptr(),
+                buffer.as_mut_ptr(),
+                capacity as u32,
+                file.as_ptr(),
+            )
+        } as usize;
+        if n == 0 {
+            return None;
+        }
+        if n < capacity - 1 {
+            buffer.truncate(n);
+            return Some(buffer);
+        }
+        capacity *= 2;
+    }
+    None
+}
+
+const JSON_SPACE: [char; 4] = [' ', '\t', '\r', '\n'];
+
+// Splits `text`, which starts just after an opening quote, into the string's raw contents (escapes
+// kept) and what follows the closing quote; None if the string is never closed.
+fn json_string(text: &str) -> Option<(&str, &str)> {
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' if !escaped => return Some((&text[..i], &text[i + 1..])),
+            '\\' if !escaped => escaped = true,
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+// Reads the "path" string of a small flat JSON object (UTF-8). Only the escapes a Windows path
+// needs are decoded; this is not a general JSON parser.
+fn read_json_path(config: &[u16]) -> Option<WidePath> {
+    let bytes = std::fs::read(String::from_utf16_lossy(config)).ok()?;
+    let text = String::from_utf8_lossy(&bytes);
+    // Walks the tokens, so "path" only counts as a key of the top-level object: not inside another
+    // string, not in a nested object.
+    let mut rest: &str = &text;
+    let mut depth = 0;
+    let raw = loop {
+        let c = rest.chars().next()?;
+        rest = &rest[c.len_utf8()..];
+        match c {
+            '{' | '[' => depth += 1,
+            '}' | ']' => depth -= 1,
+            '"' => {
+                let (key, after) = json_string(rest)?;
+                rest = after.trim_start_matches(JSON_SPACE);
+                // A string followed by ':' is a key.
+                if depth == 1 && key == "path" {
+                    if let Some(value) = rest.strip_prefix(':') {
+                        break json_string(value.trim_start_matches(JSON_SPACE).strip_prefix('"')?)?.0;
+                    }
+                }
+            }
+            _ => {}
+        }
+    };
+    let mut value = String::new();
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        value.push(if c == '\\' { chars.next()? } else { c });
+    }
+    (!value.is_empty()).then(|| units(&value))
+}
+
+// LoadLibraryExW, with the error left for the caller either way. A path that leads back to the
+// proxy yields the proxy's own handle: that reference is dropped and the location counts as not
+// found, so the next candidate gets its turn.
+fn load(path: &[u16], flags: u32, module: HMODULE) -> Result<HMODULE, u32> {
+    let handle = unsafe { sys::LoadLibraryExW(nul(path).as_ptr(), null_mut(), flags) };
+    if handle.is_null() {
+        Err(unsafe { sys::GetLastError() })
+    } else if handle == module {
+        unsafe { sys::FreeLibrary(handle) };
+        Err(sys::ERROR_MOD_NOT_FOUND)
+    } else {
+        Ok(handle)
+    }
+}
+
+fn trace_loaded(path: &[u16]) {
+    let mut text = units("AheadLibEx: original DLL loaded from ");
+    text.extend_from_slice(path);
+    text.push(u16::from(b'\n'));
+    unsafe { sys::OutputDebugStringW(nul(&text).as_ptr()) };
+}
+
+{{CANDIDATES}}/// Tries every location in order and loads the first one that exists; the error is the message
+/// for the failure policy.
+pub fn load_original(module: HMODULE) -> Result<HMODULE, String> {
+{{LOAD_ORIGINAL}}}
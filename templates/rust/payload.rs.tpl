@@ -0,0 +1,8 @@
+// Payload for {{DLL_NAME}}, generated once by AheadLibEx (Rust)
+// Author: i1tao
+// Blog: https://www.winsec.cn
+// Github: https://github.com/i1tao/AheadLibEx
+
+//! This file is yours: regenerating the proxy rewrites the other sources, never this file.
+//! `crate::origin_module()` and the `crate::pfnAheadLibEx_<name>` pointers reach the original.
+{{PAYLOAD_MAIN}}{{INSTALL_HOOKS}}
\ No newline at end of file
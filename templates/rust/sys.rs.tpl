@@ -0,0 +1,80 @@
+// generated by AheadLibEx (Rust)
+// Author: i1tao
+// Blog: https://www.winsec.cn
+// Github: https://github.com/i1tao/AheadLibEx
+
+//! The kernel32/user32 imports the proxy needs, declared by hand so the crate has no dependencies.
+#![allow(non_camel_case_types, non_snake_case, clippy::upper_case_acronyms, dead_code)]
+
+use core::ffi::c_void;
+
+pub type HMODULE = *mut c_void;
+pub type HANDLE = *mut c_void;
+pub type BOOL = i32;
+
+pub const DLL_PROCESS_DETACH: u32 = 0;
+pub const DLL_PROCESS_ATTACH: u32 = 1;
+pub const ERROR_SUCCESS: u32 = 0;
+pub const ERROR_MOD_NOT_FOUND: u32 = 126;
+pub const ERROR_PROC_NOT_FOUND: u32 = 127;
+pub const INVALID_FILE_ATTRIBUTES: u32 = u32::MAX;
+pub const FILE_ATTRIBUTE_DIRECTORY: u32 = 0x10;
+pub const GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT: u32 = 0x2;
+pub const GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS: u32 = 0x4;
+pub const MB_OK: u32 = 0;
+pub const MB_ICONSTOP: u32 = 0x10;
+
+pub type ThreadProc = unsafe extern "system" fn(*mut c_void) -> u32;
+pub type ApcProc = unsafe extern "system" fn(usize);
+pub type KnownDirFn = unsafe extern "system" fn(*mut u16, u32) -> u32;
+pub type AddDllDirectoryFn = unsafe extern "system" fn(*const u16) -> *mut c_void;
+pub type RemoveDllDirectoryFn = unsafe extern "system" fn(*mut c_void) -> BOOL;
+
+#[link(name = "kernel32")]
+extern "system" {
+    pub fn CloseHandle(handle: HANDLE) -> BOOL;
+    pub fn CreateThread(
+        attributes: *mut c_void,
+        stack_size: usize,
+        start: ThreadProc,
+        parameter: *mut c_void,
+        flags: u32,
+        thread_id: *mut u32,
+    ) -> HANDLE;
+    pub fn DisableThreadLibraryCalls(module: HMODULE) -> BOOL;
+    pub fn ExitProcess(code: u32) -> !;
+    pub fn FreeLibrary(module: HMODULE) -> BOOL;
+    pub fn GetCurrentThread() -> HANDLE;
+    pub fn GetEnvironmentVariableW(name: *const u16, buffer: *mut u16, size: u32) -> u32;
+    pub fn GetFileAttributesW(path: *const u16) -> u32;
+    pub fn GetFullPathNameW(path: *const u16, size: u32, buffer: *mut u16, file_part: *mut *mut u16) -> u32;
+    pub fn GetLastError() -> u32;
+    pub fn GetModuleFileNameW(module: HMODULE, buffer: *mut u16, size: u32) -> u32;
+    pub fn GetModuleHandleExW(flags: u32, name: *const u16, module: *mut HMODULE) -> BOOL;
+    pub fn GetModuleHandleW(name: *const u16) -> HMODULE;
+    pub fn GetPrivateProfileStringW(
+        section: *const u16,
+        key: *const u16,
+        default: *const u16,
+        buffer: *mut u16,
+        size: u32,
+        file: *const u16,
+    ) -> u32;
+    pub fn GetProcAddress(module: HMODULE, name: *const u8) -> *mut c_void;
+    pub fn GetSystemDirectoryW(buffer: *mut u16, size: u32) -> u32;
+    pub fn GetWindowsDirectoryW(buffer: *mut u16, size: u32) -> u32;
+    pub fn LoadLibraryExW(name: *const u16, file: HANDLE, flags: u32) -> HMODULE;
+    pub fn OutputDebugStringW(text: *const u16);
+    pub fn QueueUserAPC(proc_: ApcProc, thread: HANDLE, data: usize) -> u32;
+    pub fn SetLastError(code: u32);
+}
+
+#[link(name = "user32")]
+extern "system" {
+    pub fn MessageBoxW(window: *mut c_void, text: *const u16, caption: *const u16, kind: u32) -> i32;
+}
+
+/// UTF-16 copy of `text`, NUL-terminated for the W functions.
+pub fn wide(text: &str) -> Vec<u16> {
+    text.encode_utf16().chain(Some(0)).collect()
+}
@@ -0,0 +1,205 @@
+use std::fs;
+
+use aheadlibex_rs::batch::{collect_dlls, run_batch, wildcard_match, BatchOptions};
+use aheadlibex_rs::dll::read_exports;
+use aheadlibex_rs::export_policy::parse_rules;
+use aheadlibex_rs::templates::{OriginLoadModeOwned, ProxyOptions};
+use aheadlibex_rs::ui_events::OutputTarget;
+
+#[test]
+fn wildcard_matches_case_insensitively() {
+    assert!(wildcard_match("*.dll", "Foo.DLL"));
+    assert!(wildcard_match("plugin_??.dll", "plugin_01.dll"));
+    assert!(wildcard_match("*a*b*.dll", "xxaYYbzz.dll"));
+    assert!(!wildcard_match("*.dll", "foo.dll.bak"));
+    assert!(!wildcard_match("plugin_?.dll", "plugin_10.dll"));
+}
+
+#[test]
+fn batch_continues_past_failures() {
+    let root = std::env::temp_dir().join(format!("aheadlibex_batch_{}", std::process::id()));
+    let input = root.join("in");
+    let output = root.join("out");
+    fs::create_dir_all(input.join("sub")).unwrap();
+    fs::write(input.join("a.dll"), b"not a pe").unwrap();
+    fs::write(input.join("sub").join("b.dll"), b"not a pe either").unwrap();
+    fs::write(input.join("readme.txt"), b"skip me").unwrap();
+
+    let flat = BatchOptions::default();
+    assert_eq!(collect_dlls(&input, &flat).unwrap().len(), 1);
+
+    let options = BatchOptions {
+        targets: vec![OutputTarget::Source, OutputTarget::CMake],
+        recursive: true,
+        ..Default::default()
+    };
+    let report = run_batch(&input, &output, &options, |_| Ok(OriginLoadModeOwned::system_dir()))
+        .unwrap();
+
+    assert_eq!(report.entries.len(), 4);
+    assert_eq!(report.failed(), 4);
+    assert!(report.entries[0].output_dir.ends_with("a/source"));
+    assert!(report.entries[3].output_dir.ends_with("sub/b/cmake"));
+    assert!(report.to_table().contains("Total: 4, succeeded: 0, failed: 4"));
+    assert!(report.to_json().contains(r#""failed": 4"#));
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+// A minimal x64 DLL: one executable section holding the export directory and a `ret` for every export.
+fn tiny_dll(names: &[&str]) -> Vec<u8> {
+    fn put(buf: &mut [u8], at: usize, bytes: &[u8]) {
+        buf[at..at + bytes.len()].copy_from_slice(bytes);
+    }
+    const SECTION_RVA: u32 = 0x1000;
+    const CODE_RVA: u32 = 0x1800;
+
+    let n = names.len() as u32;
+    let functions = SECTION_RVA + 40;
+    let name_ptrs = functions + 4 * n;
+    let ordinals = name_ptrs + 4 * n;
+    let mut strings = ordinals + 2 * n;
+    let mut dir = vec![0u8; (strings - SECTION_RVA) as usize];
+    put(&mut dir, 16, &1u32.to_le_bytes());
+    put(&mut dir, 20, &n.to_le_bytes());
+    put(&mut dir, 24, &n.to_le_bytes());
+    put(&mut dir, 28, &functions.to_le_bytes());
+    put(&mut dir, 32, &name_ptrs.to_le_bytes());
+    put(&mut dir, 36, &ordinals.to_le_bytes());
+    for (i, name) in names.iter().enumerate() {
+        let i = i as u32;
+        put(&mut dir, (functions - SECTION_RVA + 4 * i) as usize, &CODE_RVA.to_le_bytes());
+        put(&mut dir, (name_ptrs - SECTION_RVA + 4 * i) as usize, &strings.to_le_bytes());
+        put(&mut dir, (ordinals - SECTION_RVA + 2 * i) as usize, &(i as u16).to_le_bytes());
+        dir.extend_from_slice(name.as_bytes());
+        dir.push(0);
+        strings += name.len() as u32 + 1;
+    }
+
+    let mut image = vec![0u8; 0x1000];
+    put(&mut image, 0, b"MZ");
+    put(&mut image, 0x3c, &0x40u32.to_le_bytes());
+    put(&mut image, 0x40, b"PE\0\0");
+    // COFF header: AMD64, one section, PE32+ optional header, DLL | executable | large address aware.
+    put(&mut image, 0x44, &0x8664u16.to_le_bytes());
+    put(&mut image, 0x46, &1u16.to_le_bytes());
+    put(&mut image, 0x54, &240u16.to_le_bytes());
+    put(&mut image, 0x56, &0x2022u16.to_le_bytes());
+    let opt = 0x58;
+    put(&mut image, opt, &0x20bu16.to_le_bytes());
+    put(&mut image, opt + 24, &0x1_8000_0000u64.to_le_bytes());
+    put(&mut image, opt + 32, &0x1000u32.to_le_bytes());
+    put(&mut image, opt + 36, &0x200u32.to_le_bytes());
+    put(&mut image, opt + 48, &6u16.to_le_bytes());
+    put(&mut image, opt + 56, &0x2000u32.to_le_bytes());
+    put(&mut image, opt + 60, &0x200u32.to_le_bytes());
+    put(&mut image, opt + 68, &2u16.to_le_bytes());
+    put(&mut image, opt + 108, &16u32.to_le_bytes());
+    put(&mut image, opt + 112, &SECTION_RVA.to_le_bytes());
+    put(&mut image, opt + 116, &(dir.len() as u32).to_le_bytes());
+    let section = opt + 240;
+    put(&mut image, section, b".text");
+    put(&mut image, section + 8, &0x1000u32.to_le_bytes());
+    put(&mut image, section + 12, &SECTION_RVA.to_le_bytes());
+    put(&mut image, section + 16, &0xe00u32.to_le_bytes());
+    put(&mut image, section + 20, &0x200u32.to_le_bytes());
+    put(&mut image, section + 36, &0x6000_0020u32.to_le_bytes());
+    put(&mut image, 0x200, &dir);
+    image[0x200 + (CODE_RVA - SECTION_RVA) as usize] = 0xc3;
+    image
+}
+
+#[test]
+fn batch_generates_every_dll() {
+    let root = std::env::temp_dir().join(format!("aheadlibex_batch_ok_{}", std::process::id()));
+    let input = root.join("in");
+    let output = root.join("out");
+    fs::create_dir_all(input.join("sub")).unwrap();
+    fs::write(input.join("a.dll"), tiny_dll(&["Alpha", "Beta"])).unwrap();
+    fs::write(input.join("sub").join("b.dll"), tiny_dll(&["Gamma"])).unwrap();
+    assert_eq!(read_exports(&input.join("a.dll")).unwrap().exports.len(), 2);
+
+    // A link back to the input root must not send the walk round in circles.
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(&input, input.join("sub").join("loop")).unwrap();
+
+    let options = BatchOptions {
+        recursive: true,
+        ..Default::default()
+    };
+    assert_eq!(collect_dlls(&input, &options).unwrap().len(), 2);
+    let report = run_batch(&input, &output, &options, |_| Ok(OriginLoadModeOwned::system_dir()))
+        .unwrap();
+
+    assert_eq!(report.failed(), 0, "{}", report.to_table());
+    assert_eq!(report.succeeded(), 2);
+    assert!(output.join("a").join("a_x64.c").is_file());
+    assert!(output.join("sub").join("b").join("b.def").is_file());
+    assert!(report.to_json().contains(r#""ok": true"#));
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn regenerating_keeps_the_edited_origin_config_and_payload() {
+    let root = std::env::temp_dir().join(format!("aheadlibex_batch_config_{}", std::process::id()));
+    let input = root.join("in");
+    let output = root.join("out");
+    fs::create_dir_all(&input).unwrap();
+    fs::write(input.join("a.dll"), tiny_dll(&["Alpha"])).unwrap();
+
+    let options = BatchOptions::default();
+    let config = |_: &std::path::Path| Ok(OriginLoadModeOwned::config(None));
+    let report = run_batch(&input, &output, &options, config).unwrap();
+    assert_eq!(report.failed(), 0, "{}", report.to_table());
+    let sample = output.join("a").join("a.origin.ini");
+    assert!(fs::read_to_string(&sample).unwrap().contains("[origin]"));
+    let payload = output.join("a").join("a_payload.c");
+    assert!(fs::read_to_string(&payload).unwrap().contains("payload_main"));
+
+    fs::write(&sample, "[origin]\npath=D:\\real\\a.dll\n").unwrap();
+    fs::write(&payload, "// edited\n").unwrap();
+    let report = run_batch(&input, &output, &options, config).unwrap();
+    assert_eq!(report.failed(), 0, "{}", report.to_table());
+    assert_eq!(fs::read_to_string(&sample).unwrap(), "[origin]\npath=D:\\real\\a.dll\n");
+    assert_eq!(fs::read_to_string(&payload).unwrap(), "// edited\n");
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn regenerating_with_hooks_keeps_the_edited_detours() {
+    let root = std::env::temp_dir().join(format!("aheadlibex_batch_hooks_{}", std::process::id()));
+    let input = root.join("in");
+    let output = root.join("out");
+    fs::create_dir_all(&input).unwrap();
+    fs::write(input.join("a.dll"), tiny_dll(&["Alpha", "Beta"])).unwrap();
+
+    let options = BatchOptions {
+        proxy: ProxyOptions {
+            export_rules: parse_rules("Alpha=hook").unwrap(),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let system = |_: &std::path::Path| Ok(OriginLoadModeOwned::system_dir());
+    let report = run_batch(&input, &output, &options, system).unwrap();
+    assert_eq!(report.failed(), 0, "{}", report.to_table());
+    let payload = output.join("a").join("a_payload.c");
+    let stub = fs::read_to_string(&payload).unwrap();
+    assert!(stub.contains("VOID WINAPI install_hooks(void)\n{\n    // TODO: pfnAheadLibEx_Alpha = (PVOID)Detour_Alpha;\n}\n"));
+
+    // The detours live in the payload file; the generated source only saves the originals and calls it.
+    let edited = stub.replace("// TODO: pfnAheadLibEx_Alpha", "pfnAheadLibEx_Alpha");
+    fs::write(&payload, &edited).unwrap();
+    let report = run_batch(&input, &output, &options, system).unwrap();
+    assert_eq!(report.failed(), 0, "{}", report.to_table());
+    assert_eq!(fs::read_to_string(&payload).unwrap(), edited);
+    let generated = fs::read_to_string(output.join("a").join("a_x64.c")).unwrap();
+    assert!(generated.contains("    pfnAheadLibEx_Orig_Alpha = pfnAheadLibEx_Alpha;\n    install_hooks();\n"));
+    assert!(!generated.contains("Detour_Alpha"));
+    let header = fs::read_to_string(output.join("a").join("a_proxy.h")).unwrap();
+    assert!(header.contains("VOID WINAPI install_hooks(void);"));
+
+    let _ = fs::remove_dir_all(&root);
+}
@@ -0,0 +1,219 @@
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use aheadlibex_rs::dll::{format_export_list, DllExports, ExportEntry};
+
+fn entry(name: &str, ordinal: u16) -> ExportEntry {
+    ExportEntry {
+        name: name.to_string(),
+        ordinal,
+        forwarder: None,
+        is_data: false,
+    }
+}
+
+fn write_list(path: &Path, exports: Vec<ExportEntry>) {
+    let info = DllExports {
+        arch: "x64".to_string(),
+        exports,
+        imports: Vec::new(),
+    };
+    fs::write(path, format_export_list(&info)).unwrap();
+}
+
+fn run(args: &[&Path]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_aheadlibex-rs")).args(args).output().unwrap()
+}
+
+// A PE32+ DLL with one executable section holding the export table and nothing else: enough for
+// the export reader, not for the loader.
+fn write_tiny_dll(path: &Path, names: &[&str]) {
+    fn put(image: &mut [u8], at: usize, bytes: &[u8]) {
+        image[at..at + bytes.len()].copy_from_slice(bytes);
+    }
+    fn u16le(v: u16) -> [u8; 2] {
+        v.to_le_bytes()
+    }
+    fn u32le(v: u32) -> [u8; 4] {
+        v.to_le_bytes()
+    }
+    const RVA: u32 = 0x1000;
+    const RAW: usize = 0x200;
+    let mut image = vec![0u8; RAW + 0x200];
+    put(&mut image, 0, b"MZ");
+    put(&mut image, 0x3C, &u32le(0x40));
+    put(&mut image, 0x40, b"PE\0\0");
+    // COFF header: x64, one section, DLL.
+    let coff = 0x44;
+    put(&mut image, coff, &u16le(0x8664));
+    put(&mut image, coff + 2, &u16le(1));
+    put(&mut image, coff + 16, &u16le(0xF0));
+    put(&mut image, coff + 18, &u16le(0x2022));
+    // Optional header.
+    let opt = coff + 20;
+    put(&mut image, opt, &u16le(0x20B));
+    put(&mut image, opt + 24, &0x1_8000_0000u64.to_le_bytes());
+    put(&mut image, opt + 32, &u32le(0x1000));
+    put(&mut image, opt + 36, &u32le(0x200));
+    put(&mut image, opt + 48, &u16le(6));
+    put(&mut image, opt + 56, &u32le(0x2000));
+    put(&mut image, opt + 60, &u32le(0x200));
+    put(&mut image, opt + 68, &u16le(2));
+    put(&mut image, opt + 108, &u32le(16));
+    // Section header.
+    let section = opt + 0xF0;
+    put(&mut image, section, b".text\0\0\0");
+    put(&mut image, section + 8, &u32le(0x1000));
+    put(&mut image, section + 12, &u32le(RVA));
+    put(&mut image, section + 16, &u32le(0x200));
+    put(&mut image, section + 20, &u32le(RAW as u32));
+    put(&mut image, section + 36, &u32le(0x6000_0020));
+
+    // Export directory, then the address, name and ordinal tables, then the strings.
+    let count = names.len() as u32;
+    let functions = RVA + 40;
+    let name_table = functions + 4 * count;
+    let ordinal_table = name_table + 4 * count;
+    let mut strings = ordinal_table + 2 * count;
+    let at = |rva: u32| RAW + (rva - RVA) as usize;
+    let dll_name = strings;
+    put(&mut image, at(strings), b"tiny.dll\0");
+    strings += 9;
+    for (i, name) in names.iter().enumerate() {
+        let i = i as u32;
+        // Code addresses past the export table, so none of them reads as a forwarder.
+        put(&mut image, at(functions + 4 * i), &u32le(RVA + 0x800 + i));
+        put(&mut image, at(name_table + 4 * i), &u32le(strings));
+        put(&mut image, at(ordinal_table + 2 * i), &u16le(i as u16));
+        put(&mut image, at(strings), name.as_bytes());
+        strings += name.len() as u32 + 1;
+    }
+    put(&mut image, at(RVA + 12), &u32le(dll_name));
+    put(&mut image, at(RVA + 16), &u32le(1));
+    put(&mut image, at(RVA + 20), &u32le(count));
+    put(&mut image, at(RVA + 24), &u32le(count));
+    put(&mut image, at(RVA + 28), &u32le(functions));
+    put(&mut image, at(RVA + 32), &u32le(name_table));
+    put(&mut image, at(RVA + 36), &u32le(ordinal_table));
+    put(&mut image, opt + 112, &u32le(RVA));
+    put(&mut image, opt + 116, &u32le(strings - RVA));
+    fs::write(path, image).unwrap();
+}
+
+// Syntax only: enough to show stdout is one JSON value with nothing around it.
+fn skip_space(s: &[u8], i: &mut usize) {
+    while s.get(*i).is_some_and(|c| c.is_ascii_whitespace()) {
+        *i += 1;
+    }
+}
+
+fn json_value(s: &[u8], i: &mut usize) -> bool {
+    skip_space(s, i);
+    match s.get(*i) {
+        Some(b'{') | Some(b'[') => {
+            let close = if s[*i] == b'{' { b'}' } else { b']' };
+            let object = close == b'}';
+            *i += 1;
+            skip_space(s, i);
+            if s.get(*i) == Some(&close) {
+                *i += 1;
+                return true;
+            }
+            loop {
+                if object {
+                    skip_space(s, i);
+                    if s.get(*i) != Some(&b'"') || !json_value(s, i) {
+                        return false;
+                    }
+                    skip_space(s, i);
+                    if s.get(*i) != Some(&b':') {
+                        return false;
+                    }
+                    *i += 1;
+                }
+                if !json_value(s, i) {
+                    return false;
+                }
+                skip_space(s, i);
+                match s.get(*i) {
+                    Some(b',') => *i += 1,
+                    Some(c) if *c == close => {
+                        *i += 1;
+                        return true;
+                    }
+                    _ => return false,
+                }
+            }
+        }
+        Some(b'"') => {
+            *i += 1;
+            while let Some(&c) = s.get(*i) {
+                *i += if c == b'\\' { 2 } else { 1 };
+                if c == b'"' {
+                    return true;
+                }
+            }
+            false
+        }
+        Some(_) => {
+            let start = *i;
+            while s.get(*i).is_some_and(|c| c.is_ascii_alphanumeric() || b"+-.".contains(c)) {
+                *i += 1;
+            }
+            let token = &s[start..*i];
+            matches!(token, b"true" | b"false" | b"null")
+                || (!token.is_empty() && std::str::from_utf8(token).unwrap().parse::<f64>().is_ok())
+        }
+        None => false,
+    }
+}
+
+fn is_json(text: &str) -> bool {
+    let mut i = 0;
+    let ok = json_value(text.as_bytes(), &mut i);
+    skip_space(text.as_bytes(), &mut i);
+    ok && i == text.len()
+}
+
+#[test]
+fn diff_json_is_the_only_stdout() {
+    let root = std::env::temp_dir().join(format!("aheadlibex_cli_diff_{}", std::process::id()));
+    fs::create_dir_all(&root).unwrap();
+    let (old, new) = (root.join("old.txt"), root.join("new.txt"));
+    write_list(&old, vec![entry("Foo", 1), entry("Gone", 2)]);
+    write_list(&new, vec![entry("Foo", 1), entry("Fresh", 3)]);
+
+    let output = run(&[Path::new("diff"), &old, &new, Path::new("--json")]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(is_json(&stdout), "{stdout}");
+    assert!(stdout.contains("\"Fresh\""));
+    assert!(!is_json("AheadLibEx (Rust)\n{}"));
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn verify_json_is_the_only_stdout() {
+    let root = std::env::temp_dir().join(format!("aheadlibex_cli_verify_{}", std::process::id()));
+    fs::create_dir_all(&root).unwrap();
+    let (proxy, original) = (root.join("tiny.dll"), root.join("original.txt"));
+    write_tiny_dll(&proxy, &["Foo", "Bar"]);
+
+    write_list(&original, vec![entry("Foo", 1), entry("Bar", 2)]);
+    let output = run(&[Path::new("verify"), &proxy, &original, Path::new("--json")]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(output.status.code(), Some(0), "{stdout}");
+    assert!(is_json(&stdout), "{stdout}");
+    assert!(stdout.contains("\"ok\": true"));
+
+    write_list(&original, vec![entry("Foo", 1), entry("Bar", 2), entry("Baz", 3)]);
+    let output = run(&[Path::new("verify"), &proxy, &original, Path::new("--json")]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(is_json(&stdout), "{stdout}");
+    assert!(stdout.contains("\"export\": \"Baz\""));
+
+    let _ = fs::remove_dir_all(&root);
+}
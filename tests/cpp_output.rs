@@ -0,0 +1,125 @@
+use aheadlibex_rs::dll::ExportEntry;
+use aheadlibex_rs::export_policy::parse_rules;
+use aheadlibex_rs::prototype::prototypes_from_header;
+use aheadlibex_rs::templates::{
+    payload_file_name, proxy_source_name, render_c, render_c_x64, render_cmake_lists, render_filters_2026,
+    render_payload_c, render_proxy_h, render_vcxproj, OriginLoadMode, ProxyOptions, VsGuids, VsTemplateContext,
+};
+
+fn entry(name: &str, ordinal: u16) -> ExportEntry {
+    ExportEntry {
+        name: name.to_string(),
+        ordinal,
+        forwarder: None,
+        is_data: false,
+    }
+}
+
+fn ctx<'a>(exports: &'a [ExportEntry], options: ProxyOptions) -> VsTemplateContext<'a> {
+    VsTemplateContext {
+        project_name: "Foo",
+        dll_name: "Foo.dll",
+        base_name: "Foo",
+        origin_load_mode: OriginLoadMode::SystemDir,
+        exports,
+        guids: VsGuids {
+            solution: "{S}",
+            project: "{P}",
+            filter_source: "{FS}",
+            filter_header: "{FH}",
+            filter_resource: "{FR}",
+        },
+        options,
+    }
+}
+
+fn cpp() -> ProxyOptions {
+    ProxyOptions {
+        cpp: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn sources_give_asm_symbols_c_linkage() {
+    let exports = vec![entry("Add", 1), entry("Open", 2), entry("#3", 3)];
+    let mut options = cpp();
+    options.lazy = true;
+    options.export_rules = parse_rules("Add=hook").unwrap();
+    options.hook_prototypes = prototypes_from_header("int WINAPI Add(int a, int b);");
+    let ctx = ctx(&exports, options);
+    assert_eq!(proxy_source_name(&ctx, true), "Foo_x64.cpp");
+    assert_eq!(payload_file_name(&ctx), "Foo_payload.cpp");
+
+    let c = render_c_x64(&ctx);
+    assert!(c.contains("extern \"C\"\n{\nvoid AheadLibEx_Resolve_Add(void);\n"));
+    assert!(!c.contains("#ifdef __cplusplus"));
+    assert!(c.contains("static constexpr AHEADLIBEX_EXPORT g_aheadlibex_exports[] ="));
+    assert!(c.contains("    void (*resolver)(void);"));
+    assert!(c.contains("    { nullptr, 3, &pfnAheadLibEx_Unnamed3, AheadLibEx_Resolve_Unnamed3 },"));
+    assert!(c.contains("extern \"C\" PVOID __cdecl AheadLibEx_Resolve(ULONG_PTR index, PVOID return_address)"));
+    assert!(c.contains("extern \"C\" int WINAPI AheadLibEx_Hook_Add(int a, int b)"));
+    assert!(c.contains("extern \"C\" BOOL APIENTRY DllMain(HMODULE module, DWORD reason, PVOID reserved)"));
+    assert!(!c.contains("{{"));
+
+    // The C flavor is unchanged.
+    let mut options = cpp();
+    options.cpp = false;
+    options.trace = true;
+    let c = render_c(&crate::ctx(&exports, options));
+    assert!(c.contains("#ifdef __cplusplus\nextern \"C\" {\n#endif\n"));
+    assert!(c.contains("static const AHEADLIBEX_EXPORT g_aheadlibex_exports[] ="));
+    assert!(c.contains("\nPVOID __cdecl AheadLibEx_TraceEnter("));
+    assert!(c.contains("\nBOOL APIENTRY DllMain("));
+    assert!(c.contains("    pfnAheadLibEx_Add = get_address(\"Add\", NULL);"));
+}
+
+#[test]
+fn header_adds_a_namespaced_api() {
+    let mut exports = vec![entry("Add", 1), entry("delete", 2), entry("Baz", 3), entry("#4", 4), entry("Fwd", 5)];
+    exports[4].forwarder = Some("kernel32.Sleep".to_string());
+    let mut options = cpp();
+    options.export_rules = parse_rules("Add=hook\nBaz=hook").unwrap();
+    options.hook_prototypes = prototypes_from_header("int WINAPI Add(int a, int b);");
+    let ctx = ctx(&exports, options);
+
+    let header = render_proxy_h(&ctx);
+    assert!(header.contains("#ifdef __cplusplus\nnamespace aheadlibex\n{\n"));
+    assert!(header.contains("inline HMODULE origin_module() { return AheadLibEx_OriginModule(); }"));
+    assert!(header.contains(
+        "inline AheadLibEx_Add_t Add() { return reinterpret_cast<AheadLibEx_Add_t>(pfnAheadLibEx_Add); }"
+    ));
+    assert!(header.contains("inline PVOID delete_() { return pfnAheadLibEx_delete; }"));
+    assert!(header.contains("inline PVOID Baz() { return pfnAheadLibEx_Orig_Baz; }"));
+    assert!(header.contains("inline PVOID Unnamed4() { return pfnAheadLibEx_Unnamed4; }"));
+    assert!(!header.contains(" Fwd()"));
+    assert!(header.contains("constexpr export_info exports[] =\n{\n    { \"Add\", 1, nullptr },\n"));
+    assert!(header.contains("    { nullptr, 4, nullptr },\n"));
+    assert!(header.contains("    { \"Fwd\", 5, \"kernel32.Sleep\" },\n"));
+
+    let user = render_payload_c(&ctx, true);
+    assert!(user.contains("regenerating the proxy rewrites Foo_x64.cpp, never this file."));
+    assert!(user.contains("aheadlibex::original::<name>()"));
+
+    let mut options = cpp();
+    options.cpp = false;
+    assert!(!render_proxy_h(&crate::ctx(&exports, options)).contains("namespace"));
+}
+
+#[test]
+fn projects_build_the_cpp_sources() {
+    let exports = vec![entry("Bar", 1)];
+    let ctx = ctx(&exports, cpp());
+
+    assert!(render_vcxproj(&ctx, true)
+        .contains("    <ClCompile Include=\"Foo_x64.cpp\" />\n    <ClCompile Include=\"Foo_payload.cpp\" />\n"));
+    assert!(render_filters_2026(&ctx, false).contains("<ClCompile Include=\"Foo_x86.cpp\">"));
+    let cmake = render_cmake_lists(&ctx, true);
+    assert!(cmake.contains("project(AheadLibEx_Foo LANGUAGES CXX)"));
+    assert!(cmake.contains("set(AHEADLIBEX_C \"Foo_x64.cpp\")"));
+    assert!(cmake.contains("set(AHEADLIBEX_PAYLOAD \"Foo_payload.cpp\")"));
+
+    let mut options = cpp();
+    options.minimal_deps = true;
+    assert!(options.check_exports(&exports).is_err());
+}
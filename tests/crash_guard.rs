@@ -0,0 +1,109 @@
+use aheadlibex_rs::dll::ExportEntry;
+use aheadlibex_rs::export_policy::{parse_rules, PayloadEntry};
+use aheadlibex_rs::prototype::prototypes_from_header;
+use aheadlibex_rs::templates::{
+    render_c, render_c_x64, OriginLoadMode, ProxyOptions, VsGuids, VsTemplateContext,
+};
+
+fn entry(name: &str, ordinal: u16) -> ExportEntry {
+    ExportEntry {
+        name: name.to_string(),
+        ordinal,
+        forwarder: None,
+        is_data: false,
+    }
+}
+
+fn ctx<'a>(exports: &'a [ExportEntry], options: ProxyOptions) -> VsTemplateContext<'a> {
+    VsTemplateContext {
+        project_name: "Foo",
+        dll_name: "Foo.dll",
+        base_name: "Foo",
+        origin_load_mode: OriginLoadMode::SystemDir,
+        exports,
+        guids: VsGuids {
+            solution: "{S}",
+            project: "{P}",
+            filter_source: "{FS}",
+            filter_header: "{FH}",
+            filter_resource: "{FR}",
+        },
+        options,
+    }
+}
+
+fn guarded() -> ProxyOptions {
+    ProxyOptions {
+        crash_guard: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn payload_and_hook_callbacks_run_guarded() {
+    let exports = vec![entry("Add", 1), entry("Reset", 2)];
+    let mut options = guarded();
+    options.export_rules = parse_rules("*=hook").unwrap();
+    options.hook_prototypes = prototypes_from_header("int WINAPI Add(int a, int b); void Reset(void);");
+    let ctx = ctx(&exports, options);
+
+    let c = render_c_x64(&ctx);
+    assert!(c.contains("static VOID WINAPI aheadlibex_crash_record(EXCEPTION_POINTERS* info, LPCSTR where)"));
+    assert!(c.contains("AheadLibEx [Foo.dll] crash in %hs"));
+    assert!(c.contains("__except (aheadlibex_crash_filter(GetExceptionInformation(), where))"));
+    // MinGW: only faults nothing inside the guarded call handled, resumed on the guard's own frame.
+    assert!(c.contains("SetUnhandledExceptionFilter(aheadlibex_guard_filter)"));
+    assert!(c.contains("__builtin_longjmp(frame->jump, 1);"));
+    assert!(!c.contains("AddVectoredExceptionHandler") && !c.contains("ContextRecord->Rsp"));
+    assert!(c.contains(r#"    AHEADLIBEX_GUARDED("payload_main", payload_main());"#));
+    assert!(c.contains(r#"    AHEADLIBEX_GUARDED("before_Add", before_Add(&a, &b));"#));
+    assert!(c.contains("    int result = ((AheadLibEx_Add_t)pfnAheadLibEx_Add)(a, b);"));
+    assert!(c.contains(r#"    AHEADLIBEX_GUARDED("after_Add", result = after_Add(result, a, b));"#));
+    assert!(c.contains(r#"    AHEADLIBEX_GUARDED("after_Reset", after_Reset());"#));
+    assert!(!c.contains("    return after_Add("));
+    assert!(c.contains("        aheadlibex_guard_shutdown();\n        free_origin_module();\n"));
+    // The guard is defined before anything that uses it.
+    assert!(c.find("#define AHEADLIBEX_GUARDED").unwrap() < c.find("AHEADLIBEX_GUARDED(\"payload_main\"").unwrap());
+
+    let c_x86 = render_c(&ctx);
+    assert!(c_x86.contains("  eip=0x%08lX eflags=0x%08lX"));
+    assert!(c_x86.contains("((NT_TIB*)NtCurrentTeb())->ExceptionList = frame->seh;"));
+    assert!(c_x86.contains(r#"AHEADLIBEX_GUARDED("payload_main", payload_main());"#));
+}
+
+#[test]
+fn guard_is_left_out_when_there_is_nothing_to_guard() {
+    let exports = vec![entry("Bar", 1)];
+
+    let c = render_c_x64(&ctx(&exports, ProxyOptions::default()));
+    assert!(!c.contains("AHEADLIBEX_GUARDED"));
+    assert!(c.contains("    payload_main();\n"));
+
+    let mut options = guarded();
+    options.payload = PayloadEntry::None;
+    let c = render_c_x64(&ctx(&exports, options));
+    assert!(!c.contains("aheadlibex_crash_record"));
+    assert!(!c.contains("aheadlibex_guard_shutdown"));
+
+    // Forward-only proxies have no detach branch of their own; the guard adds one.
+    let mut options = guarded();
+    options.default_strategy = ProxyOptions::forward_only().default_strategy;
+    let c = render_c(&VsTemplateContext {
+        origin_load_mode: OriginLoadMode::SameDir {
+            original_name: "Foo_orig.dll",
+        },
+        ..ctx(&exports, options)
+    });
+    assert!(c.contains("    else if (reason == DLL_PROCESS_DETACH)\n    {\n        aheadlibex_guard_shutdown();\n    }\n"));
+    assert!(!c.contains("free_origin_module"));
+}
+
+#[test]
+fn crash_guard_needs_the_c_runtime() {
+    let exports = vec![entry("Bar", 1)];
+    let mut options = guarded();
+    assert!(options.check_exports(&exports).is_ok());
+
+    options.minimal_deps = true;
+    assert!(options.check_exports(&exports).is_err());
+}
@@ -0,0 +1,110 @@
+use aheadlibex_rs::dll::ExportEntry;
+use aheadlibex_rs::export_policy::parse_rules;
+use aheadlibex_rs::templates::{
+    needs_jump_table, render_asm_x64, render_c, render_c_x64, FailurePolicy, OriginLoadMode,
+    ProxyOptions, VsGuids, VsTemplateContext,
+};
+
+fn entry(name: &str, ordinal: u16) -> ExportEntry {
+    ExportEntry {
+        name: name.to_string(),
+        ordinal,
+        forwarder: None,
+        is_data: false,
+    }
+}
+
+fn ctx<'a>(exports: &'a [ExportEntry], options: ProxyOptions) -> VsTemplateContext<'a> {
+    VsTemplateContext {
+        project_name: "Foo",
+        dll_name: "Foo.dll",
+        base_name: "Foo",
+        origin_load_mode: OriginLoadMode::SystemDir,
+        exports,
+        guids: VsGuids {
+            solution: "{S}",
+            project: "{P}",
+            filter_source: "{FS}",
+            filter_header: "{FH}",
+            filter_resource: "{FR}",
+        },
+        options,
+    }
+}
+
+#[test]
+fn deferred_init_keeps_dllmain_to_bookkeeping() {
+    let exports = vec![entry("Open", 1), entry("Hooked", 2)];
+    let eager = ctx(
+        &exports,
+        ProxyOptions {
+            deferred_init: true,
+            export_rules: parse_rules("Hooked=hook").unwrap(),
+            ..Default::default()
+        },
+    );
+
+    let c = render_c_x64(&eager);
+    let dllmain = &c[c.find("BOOL APIENTRY DllMain").unwrap()..];
+    assert!(dllmain.contains("        g_aheadlibex_self = module;"));
+    assert!(!dllmain.contains("load_original_module"));
+    assert!(!dllmain.contains("start_payload();"));
+    assert!(c.contains("static INIT_ONCE g_aheadlibex_init_once = INIT_ONCE_STATIC_INIT;"));
+    assert!(c.contains("        return FALSE;\n    }\n    start_hooks();\n    start_payload();"));
+    assert!(c.contains("    aheadlibex_ensure_init();\n    address = (PVOID)get_address("));
+    // Eager resolution still happens, inside the one-time init.
+    assert!(c.contains("pfnAheadLibEx_Open = get_address(\"Open\", NULL);"));
+    assert!(c.contains("PVOID pfnAheadLibEx_Open = (PVOID)AheadLibEx_Resolve_Open;"));
+    assert!(render_c(&eager).contains("aheadlibex_ensure_init"));
+    assert!(needs_jump_table(&eager));
+    assert!(render_asm_x64(&eager).contains("AheadLibEx_ResolveThunk PROC"));
+
+    let lazy = ctx(
+        &exports,
+        ProxyOptions {
+            deferred_init: true,
+            lazy: true,
+            ..Default::default()
+        },
+    );
+    let c = render_c_x64(&lazy);
+    assert!(c.contains("aheadlibex_ensure_init();"));
+    assert!(!c.contains("get_address(\"Open\""));
+
+    let plain = render_c_x64(&ctx(&exports, ProxyOptions::default()));
+    assert!(plain.contains("        init_forwarders();\n\n        start_payload();"));
+    assert!(!plain.contains("INIT_ONCE"));
+    assert!(!plain.contains("{{"));
+}
+
+#[test]
+fn deferred_init_failure_follows_failure_policy() {
+    let exports = vec![entry("Open", 1)];
+    let render = |failure_policy| {
+        render_c_x64(&ctx(
+            &exports,
+            ProxyOptions {
+                deferred_init: true,
+                failure_policy,
+                ..Default::default()
+            },
+        ))
+    };
+
+    let fail = render(FailurePolicy::FailLoad);
+    assert!(fail.contains("        g_aheadlibex_init_error = ERROR_PROC_NOT_FOUND;\n        return FALSE;"));
+    assert!(fail.contains("        // Too late to fail the load; exit with the reason instead.\n        ExitProcess(g_aheadlibex_init_error);"));
+    assert!(!fail.contains("ExitProcess(0)"));
+
+    let abort = render(FailurePolicy::Abort);
+    assert!(abort.contains("        g_aheadlibex_init_error = ERROR_MOD_NOT_FOUND;"));
+    assert!(abort.contains("report_failure has shown why"));
+    assert!(abort.contains("        ExitProcess(g_aheadlibex_init_error);"));
+
+    // Missing exports fall back instead of ending the process.
+    let log = render(FailurePolicy::Log);
+    let ensure = &log[log.find("static VOID aheadlibex_ensure_init").unwrap()..];
+    let ensure = &ensure[..ensure.find("\n}\n").unwrap()];
+    assert!(ensure.contains("    InitOnceExecuteOnce(&g_aheadlibex_init_once, aheadlibex_init_once_proc, NULL, NULL);"));
+    assert!(!ensure.contains("ExitProcess"));
+}
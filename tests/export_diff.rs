@@ -0,0 +1,89 @@
+use aheadlibex_rs::dll::{format_export_list, parse_export_list, DllExports, ExportEntry};
+use aheadlibex_rs::export_diff::{diff_exports, ExportChange};
+
+fn entry(name: &str, ordinal: u16, forwarder: Option<&str>, is_data: bool) -> ExportEntry {
+    ExportEntry {
+        name: name.to_string(),
+        ordinal,
+        forwarder: forwarder.map(|s| s.to_string()),
+        is_data,
+    }
+}
+
+#[test]
+fn diff_reports_every_change_kind() {
+    let old = vec![
+        entry("Keep", 1, None, false),
+        entry("Gone", 2, None, false),
+        entry("OldName", 3, None, false),
+        entry("Moved", 4, None, false),
+        entry("Fwd", 5, Some("NTDLL!RtlFoo"), false),
+        entry("gTable", 6, None, false),
+    ];
+    let new = vec![
+        entry("Keep", 1, None, false),
+        entry("NewName", 3, None, false),
+        entry("Moved", 14, None, false),
+        entry("Fwd", 5, Some("NTDLL!RtlBar"), false),
+        entry("gTable", 6, None, true),
+        entry("Fresh", 20, None, false),
+    ];
+
+    let diff = diff_exports(&old, &new);
+    assert!(diff.changes.contains(&ExportChange::Removed {
+        name: "Gone".into(),
+        ordinal: 2
+    }));
+    assert!(diff.changes.contains(&ExportChange::Added {
+        name: "Fresh".into(),
+        ordinal: 20
+    }));
+    assert!(diff.changes.contains(&ExportChange::Renamed {
+        ordinal: 3,
+        old_name: "OldName".into(),
+        new_name: "NewName".into()
+    }));
+    assert!(diff.changes.contains(&ExportChange::OrdinalChanged {
+        name: "Moved".into(),
+        old_ordinal: 4,
+        new_ordinal: 14
+    }));
+    assert!(diff.changes.contains(&ExportChange::ForwarderChanged {
+        name: "Fwd".into(),
+        old_forwarder: Some("NTDLL!RtlFoo".into()),
+        new_forwarder: Some("NTDLL!RtlBar".into())
+    }));
+    assert!(diff.changes.contains(&ExportChange::KindChanged {
+        name: "gTable".into(),
+        old_is_data: false,
+        new_is_data: true
+    }));
+    assert_eq!(diff.changes.len(), 6);
+
+    let json = diff.to_json();
+    assert!(json.contains(r#""identical": false"#));
+    assert!(json.contains(r#"{"kind": "renamed", "ordinal": 3, "old_name": "OldName", "new_name": "NewName"}"#));
+    assert!(diff.to_text().contains("~ kind       gTable code -> data"));
+
+    assert!(diff_exports(&old, &old).is_empty());
+}
+
+#[test]
+fn export_list_round_trips() {
+    let info = DllExports {
+        arch: "x86".to_string(),
+        exports: vec![
+            entry("Foo", 1, None, false),
+            entry("#7", 7, None, false),
+            entry("gData", 8, None, true),
+            entry("Bar", 9, Some("KERNEL32!#12"), false),
+        ],
+        imports: Vec::new(),
+    };
+
+    let text = format_export_list(&info);
+    let parsed = parse_export_list(&text).unwrap();
+    assert_eq!(parsed.arch, "x86");
+    assert!(diff_exports(&info.exports, &parsed.exports).is_empty());
+    assert!(parse_export_list("x\tFoo\n").is_err());
+}
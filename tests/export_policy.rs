@@ -0,0 +1,158 @@
+use aheadlibex_rs::dll::ExportEntry;
+use aheadlibex_rs::export_policy::{
+    parse_rules, wildcard_match, ExportRule, ExportSelector, ExportStrategy, PayloadEntry,
+};
+use aheadlibex_rs::templates::{
+    render_asm_x64, render_c_x64, render_def, OriginLoadMode, ProxyOptions, VsGuids,
+    VsTemplateContext,
+};
+
+fn entry(name: &str, ordinal: u16) -> ExportEntry {
+    ExportEntry {
+        name: name.to_string(),
+        ordinal,
+        forwarder: None,
+        is_data: false,
+    }
+}
+
+#[test]
+fn rules_parse_selectors_and_last_match_wins() {
+    let rules = parse_rules(
+        "# comment\n; another\nGet* = forward\n@3=drop\nGetVersion=hook\n",
+    )
+    .unwrap();
+    assert_eq!(rules.len(), 3);
+    assert_eq!(rules[0].selector, ExportSelector::Pattern("Get*".to_string()));
+    assert_eq!(rules[1].selector, ExportSelector::Ordinal(3));
+    assert_eq!(
+        ExportRule::parse("#7=stub").unwrap().selector,
+        ExportSelector::Ordinal(7)
+    );
+    assert!(parse_rules("Foo=bogus").is_err());
+
+    // `#N=` is an ordinal rule; `#` followed by a space, or on a line without `=`, is a comment.
+    let hashed = parse_rules("#7=drop\n#  8=drop\n#no rule here\n# Foo=hook\n").unwrap();
+    assert_eq!(hashed.len(), 1);
+    assert_eq!(hashed[0].selector, ExportSelector::Ordinal(7));
+    assert_eq!(hashed[0].strategy, ExportStrategy::Drop);
+
+    let options = ProxyOptions {
+        export_rules: rules,
+        ..Default::default()
+    };
+    assert_eq!(options.strategy_for(&entry("GetName", 1)), ExportStrategy::Forward);
+    assert_eq!(options.strategy_for(&entry("GetVersion", 2)), ExportStrategy::Hook);
+    assert_eq!(options.strategy_for(&entry("#3", 3)), ExportStrategy::Drop);
+    assert_eq!(options.strategy_for(&entry("Other", 4)), ExportStrategy::Stub);
+}
+
+#[test]
+fn patterns_match_case_sensitively_like_names() {
+    let options = ProxyOptions {
+        export_rules: parse_rules("Get*=hook\nset?=drop\nOpen=forward").unwrap(),
+        ..Default::default()
+    };
+    assert_eq!(options.strategy_for(&entry("GetName", 1)), ExportStrategy::Hook);
+    assert_eq!(options.strategy_for(&entry("getName", 2)), ExportStrategy::Stub);
+    assert_eq!(options.strategy_for(&entry("setX", 3)), ExportStrategy::Drop);
+    assert_eq!(options.strategy_for(&entry("SetX", 4)), ExportStrategy::Stub);
+    assert_eq!(options.strategy_for(&entry("open", 5)), ExportStrategy::Stub);
+    assert!(!wildcard_match("*.DLL", "foo.dll"));
+}
+
+#[test]
+fn renderers_honor_per_export_strategy() {
+    let exports = vec![
+        entry("Plain", 1),
+        entry("Fwd", 2),
+        entry("Hooked", 3),
+        entry("Gone", 4),
+    ];
+    let ctx = VsTemplateContext {
+        project_name: "Foo",
+        dll_name: "Foo.dll",
+        base_name: "Foo",
+        origin_load_mode: OriginLoadMode::SameDir {
+            original_name: "Foo_orig.dll",
+        },
+        exports: &exports,
+        guids: VsGuids {
+            solution: "{S}",
+            project: "{P}",
+            filter_source: "{FS}",
+            filter_header: "{FH}",
+            filter_resource: "{FR}",
+        },
+        options: ProxyOptions {
+            export_rules: parse_rules("Fwd=forward\nHooked=hook\nGone=drop").unwrap(),
+            ..Default::default()
+        },
+    };
+
+    let c = render_c_x64(&ctx);
+    assert!(c.contains(r#"/EXPORT:\"Fwd=Foo_orig.Fwd,@2\""#));
+    assert!(c.contains("PVOID pfnAheadLibEx_Hooked = NULL;"));
+    assert!(c.contains("PVOID pfnAheadLibEx_Orig_Hooked = NULL;"));
+    assert!(c.contains("pfnAheadLibEx_Orig_Hooked = pfnAheadLibEx_Hooked;"));
+    assert!(c.contains("        start_hooks();"));
+    assert!(c.contains("    pfnAheadLibEx_Orig_Hooked = pfnAheadLibEx_Hooked;\n    install_hooks();\n}\n"));
+    assert!(!c.contains("Detour_"));
+    assert!(!c.contains("pfnAheadLibEx_Fwd"));
+    assert!(!c.contains("Gone"));
+
+    let asm = render_asm_x64(&ctx);
+    assert!(asm.contains("AheadLibEx_Plain PROC"));
+    assert!(asm.contains("AheadLibEx_Hooked PROC"));
+    assert!(!asm.contains("Fwd"));
+
+    let def = render_def(&ctx, true);
+    assert!(def.contains("Fwd=Foo_orig.Fwd @2"));
+    assert!(def.contains("Hooked=AheadLibEx_Hooked @3"));
+    assert!(!def.contains("Gone"));
+}
+
+#[test]
+fn data_exports_are_always_forwarded() {
+    let mut exports = vec![entry("Plain", 1), entry("gData", 2), entry("gTable", 3), entry("gGone", 4)];
+    for e in &mut exports[1..] {
+        e.is_data = true;
+    }
+    let ctx = VsTemplateContext {
+        project_name: "Foo",
+        dll_name: "Foo.dll",
+        base_name: "Foo",
+        origin_load_mode: OriginLoadMode::SameDir {
+            original_name: "Foo_orig.dll",
+        },
+        exports: &exports,
+        guids: VsGuids {
+            solution: "{S}",
+            project: "{P}",
+            filter_source: "{FS}",
+            filter_header: "{FH}",
+            filter_resource: "{FR}",
+        },
+        options: ProxyOptions {
+            export_rules: parse_rules("gTable=hook\ngGone=drop").unwrap(),
+            ..Default::default()
+        },
+    };
+
+    let def = render_def(&ctx, true);
+    assert!(def.contains("    Plain=AheadLibEx_Plain @1\n"));
+    assert!(def.contains("    gData=Foo_orig.gData @2\n"));
+    assert!(def.contains("    gTable=Foo_orig.gTable @3\n"));
+    assert!(!def.contains("gGone"));
+    let c = render_c_x64(&ctx);
+    assert!(!c.contains("pfnAheadLibEx_gData"));
+    assert!(!c.contains("pfnAheadLibEx_gTable"));
+    assert!(!render_asm_x64(&ctx).contains("gTable"));
+
+    let options = ProxyOptions {
+        payload: PayloadEntry::parse("first-call:gData").unwrap(),
+        ..Default::default()
+    };
+    let err = options.check_exports(&exports).unwrap_err();
+    assert!(err.to_string().contains("is a DATA export"));
+}
@@ -0,0 +1,126 @@
+use aheadlibex_rs::dll::ExportEntry;
+use aheadlibex_rs::prototype::FunctionPrototype;
+use aheadlibex_rs::templates::{
+    render_c, render_c_x64, FailurePolicy, OriginLoadMode, ProxyOptions, VsGuids, VsTemplateContext,
+};
+
+fn render(policy: FailurePolicy) -> String {
+    let options = ProxyOptions {
+        failure_policy: policy,
+        ..Default::default()
+    };
+    render_exports(&["Open"], options, true)
+}
+
+fn render_exports(names: &[&str], options: ProxyOptions, is_x64: bool) -> String {
+    let exports = names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| ExportEntry {
+            name: name.to_string(),
+            ordinal: i as u16 + 1,
+            forwarder: None,
+            is_data: false,
+        })
+        .collect::<Vec<_>>();
+    let ctx = VsTemplateContext {
+        project_name: "Foo",
+        dll_name: "Foo.dll",
+        base_name: "Foo",
+        origin_load_mode: OriginLoadMode::SameDir {
+            original_name: "Foo_orig.dll",
+        },
+        exports: &exports,
+        guids: VsGuids {
+            solution: "{S}",
+            project: "{P}",
+            filter_source: "{FS}",
+            filter_header: "{FH}",
+            filter_resource: "{FR}",
+        },
+        options,
+    };
+    if is_x64 {
+        render_c_x64(&ctx)
+    } else {
+        render_c(&ctx)
+    }
+}
+
+#[test]
+fn failure_policy_controls_missing_dll_and_export_handling() {
+    assert_eq!(FailurePolicy::from_name("fail-load"), Some(FailurePolicy::FailLoad));
+    assert_eq!(FailurePolicy::from_name("LOG"), Some(FailurePolicy::Log));
+    assert!(FailurePolicy::from_name("ignore").is_none());
+
+    let abort = render(FailurePolicy::Abort);
+    assert!(abort.contains("MessageBox(NULL, message, TEXT(\"AheadLibEx\"), MB_ICONSTOP);"));
+    assert!(abort.contains("report_failure(message, ERROR_PROC_NOT_FOUND);\n\n        ExitProcess(0);"));
+    assert!(abort.contains("report_failure(message, ERROR_MOD_NOT_FOUND);"));
+    assert!(!abort.contains("missing_export(void)"));
+
+    let exit = render(FailurePolicy::Exit);
+    assert!(exit.contains("    ExitProcess(error);"));
+    assert!(!exit.contains("MessageBox(NULL, message"));
+
+    let log = render(FailurePolicy::Log);
+    assert!(log.contains("static ULONG_PTR WINAPI missing_export(void)"));
+    assert!(log.contains("        address = fallback;"));
+    assert!(log.contains("    pfnAheadLibEx_Open = get_address(\"Open\", (FARPROC)missing_export);"));
+    assert!(log.contains("lstrcat(path, TEXT(\".log\"));"));
+    assert!(log.contains("OutputDebugString(line);"));
+    // A missing original is logged and the proxy keeps loading.
+    assert!(log.contains("    return TRUE;\n}"));
+    assert!(!log.contains("MessageBox(NULL, message"));
+
+    let fail = render(FailurePolicy::FailLoad);
+    assert!(fail.contains("InterlockedIncrement(&g_missing_export_count);"));
+    assert!(fail.contains("        if (!init_forwarders())\n        {\n            free_origin_module();\n            return FALSE;\n        }"));
+    assert!(fail.contains("    return g_origin_module_handle != NULL;"));
+}
+
+#[test]
+fn x86_missing_export_fallbacks_pop_what_the_caller_pushed() {
+    let options = ProxyOptions {
+        failure_policy: FailurePolicy::Log,
+        hook_prototypes: vec![
+            FunctionPrototype::parse_declaration("int __cdecl Printf(const char* format, int count)").unwrap(),
+            FunctionPrototype::parse_declaration("BOOL WINAPI Close(HANDLE handle, ULONGLONG when)").unwrap(),
+        ],
+        ..Default::default()
+    };
+    let names = ["_Sum@12", "@Fast@16", "?Scale@@YGHHN@Z", "Printf", "Plain", "Close"];
+    let c = render_exports(&names, options, false);
+    // `ret 12`: three dummy arguments in a WINAPI function.
+    assert!(c.contains(
+        "static ULONG_PTR WINAPI missing_export_stdcall_12(ULONG_PTR a1, ULONG_PTR a2, ULONG_PTR a3)\n"
+    ));
+    assert!(c.contains("pfnAheadLibEx__Sum_12 = get_address(\"_Sum@12\", (FARPROC)missing_export_stdcall_12);"));
+    // `@Fast@16`: two arguments in ECX/EDX, `ret 8` for the rest.
+    assert!(c.contains(
+        "static ULONG_PTR __fastcall missing_export_fastcall_8(ULONG_PTR a1, ULONG_PTR a2, ULONG_PTR a3, ULONG_PTR a4)\n"
+    ));
+    // Demangled `int __stdcall Scale(int, double)` pops 12 bytes too.
+    assert!(c.contains("get_address(\"?Scale@@YGHHN@Z\", (FARPROC)missing_export_stdcall_12);"));
+    // cdecl: the caller cleans up, so the shared fallback will do.
+    assert!(c.contains("get_address(\"Printf\", (FARPROC)missing_export);"));
+    assert!(c.contains("static ULONG_PTR WINAPI missing_export(void)"));
+    // A 64-bit argument takes two stack slots.
+    assert!(c.contains("get_address(\"Close\", (FARPROC)missing_export_stdcall_12);"));
+    // Nothing says what `Plain` pops, so its fallback ends the process instead of returning.
+    assert!(c.contains("get_address(\"Plain\", (FARPROC)missing_export_unknown);"));
+    assert!(c.contains("    ExitProcess(ERROR_PROC_NOT_FOUND);\n}"));
+    assert_eq!(c.matches("missing_export_stdcall_12(ULONG_PTR").count(), 1);
+
+    // Resolved on first call, the fallback comes from a table in export order.
+    let options = ProxyOptions {
+        failure_policy: FailurePolicy::FailLoad,
+        lazy: true,
+        ..Default::default()
+    };
+    let lazy = render_exports(&["_Sum@12", "Plain"], options, false);
+    assert!(lazy.contains(
+        "static const FARPROC g_aheadlibex_fallbacks[] =\n{\n    (FARPROC)missing_export_stdcall_12,\n    (FARPROC)missing_export_unknown,\n};"
+    ));
+    assert!(lazy.contains("MAKEINTRESOURCEA(entry->ordinal),\n        g_aheadlibex_fallbacks[index]);"));
+}
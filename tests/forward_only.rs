@@ -0,0 +1,112 @@
+use aheadlibex_rs::dll::ExportEntry;
+use aheadlibex_rs::templates::{
+    check_origin_forwarders, needs_jump_table, render_c, render_c_x64, render_cmake_lists, render_def, render_vcxproj,
+    OriginLoadMode, ProxyOptions, VsGuids, VsTemplateContext,
+};
+
+fn forward_ctx<'a>(exports: &'a [ExportEntry], mode: OriginLoadMode<'a>) -> VsTemplateContext<'a> {
+    let guids = VsGuids {
+        solution: "{S}",
+        project: "{P}",
+        filter_source: "{FS}",
+        filter_header: "{FH}",
+        filter_resource: "{FR}",
+    };
+    VsTemplateContext {
+        project_name: "Foo",
+        dll_name: "Foo.dll",
+        base_name: "Foo",
+        origin_load_mode: mode,
+        exports,
+        guids,
+        options: ProxyOptions::forward_only(),
+    }
+}
+
+#[test]
+fn forward_only_emits_linker_forwarders_without_runtime() {
+    let exports = vec![
+        ExportEntry {
+            name: "Foo".to_string(),
+            ordinal: 1,
+            forwarder: None,
+            is_data: false,
+        },
+        ExportEntry {
+            name: "#5".to_string(),
+            ordinal: 5,
+            forwarder: None,
+            is_data: false,
+        },
+    ];
+    let ctx = forward_ctx(
+        &exports,
+        OriginLoadMode::SameDir {
+            original_name: "Foo_orig.dll",
+        },
+    );
+
+    assert!(!needs_jump_table(&ctx));
+
+    let c = render_c_x64(&ctx);
+    assert!(c.contains(r#"/EXPORT:\"Foo=Foo_orig.Foo,@1\""#));
+    assert!(c.contains(r#"Foo_orig.#5,@5,NONAME"#));
+    assert!(!c.contains("load_original_module"));
+    assert!(!c.contains("get_address"));
+    assert!(!c.contains("pfnAheadLibEx_"));
+
+    let def = render_def(&ctx, true);
+    assert!(def.contains("Foo=Foo_orig.Foo @1"));
+
+    let vcxproj = render_vcxproj(&ctx, true);
+    assert!(!vcxproj.contains("<MASM Include="));
+
+    let cmake = render_cmake_lists(&ctx, true);
+    assert!(cmake.contains("set(AHEADLIBEX_ASM_MASM \"\")"));
+}
+
+#[test]
+fn forwarders_need_a_named_original() {
+    let exports = vec![ExportEntry {
+        name: "Bar".to_string(),
+        ordinal: 2,
+        forwarder: None,
+        is_data: false,
+    }];
+
+    // A bare "Foo.Bar" would resolve back to the proxy itself, and env/config/search/loadex only
+    // find the original at load time.
+    for mode in [
+        OriginLoadMode::SystemDir,
+        OriginLoadMode::Env { var: None },
+    ] {
+        let err = check_origin_forwarders(&forward_ctx(&exports, mode)).unwrap_err();
+        assert!(err.to_string().contains("samedir or custom"), "{err}");
+    }
+    let dotted = forward_ctx(
+        &exports,
+        OriginLoadMode::SameDir {
+            original_name: "Foo.orig.dll",
+        },
+    );
+    assert!(check_origin_forwarders(&dotted).is_err());
+
+    // A custom path forwards to its file name.
+    let custom = forward_ctx(
+        &exports,
+        OriginLoadMode::CustomPath {
+            path: "Foo_orig.dll",
+        },
+    );
+    check_origin_forwarders(&custom).unwrap();
+    assert!(render_def(&custom, false).contains("Bar=Foo_orig.Bar @2"));
+
+    // Without forward-only or forward rules nothing names the original.
+    let ctx = VsTemplateContext {
+        options: ProxyOptions::default(),
+        ..forward_ctx(&exports, OriginLoadMode::SystemDir)
+    };
+    check_origin_forwarders(&ctx).unwrap();
+    let c = render_c(&ctx);
+    assert!(!c.contains(".Bar,@2"), "{c}");
+}
@@ -0,0 +1,103 @@
+use aheadlibex_rs::dll::ExportEntry;
+use aheadlibex_rs::export_policy::parse_rules;
+use aheadlibex_rs::prototype::{prototypes_from_header, FunctionPrototype};
+use aheadlibex_rs::templates::{
+    needs_jump_table, render_c, render_c_x64, render_def, render_proxy_h, OriginLoadMode,
+    ProxyOptions, VsGuids, VsTemplateContext,
+};
+
+#[test]
+fn prototypes_come_from_declarations_headers_and_decorations() {
+    let proto = FunctionPrototype::parse_declaration(
+        "WINBASEAPI HANDLE WINAPI CreateFileW(_In_ LPCWSTR lpFileName, _In_ DWORD dwDesiredAccess, _In_reads_(n) const char* data[]);",
+    )
+    .unwrap();
+    assert_eq!(proto.name, "CreateFileW");
+    assert_eq!(proto.return_type, "HANDLE");
+    assert_eq!(proto.calling_convention, "WINAPI");
+    let params: Vec<_> = proto.params.iter().map(|p| (p.ty.as_str(), p.name.as_str())).collect();
+    assert_eq!(
+        params,
+        [
+            ("LPCWSTR", "lpFileName"),
+            ("DWORD", "dwDesiredAccess"),
+            ("const char * *", "data")
+        ]
+    );
+    assert!(FunctionPrototype::parse_declaration("int printf(const char *fmt, ...)").is_err());
+
+    let header = "/* api */\n#pragma once\nint __cdecl Add(int, int); // two ints\nvoid Reset(void);\n";
+    let found = prototypes_from_header(header);
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0].params[1].name, "a2");
+    assert!(found[1].is_void() && found[1].params.is_empty());
+
+    let mangled = FunctionPrototype::from_decorated("?Func@@YAHPEBDH@Z").unwrap();
+    assert_eq!(mangled.calling_convention, "__cdecl");
+    assert_eq!(mangled.return_type, "int");
+    assert_eq!(mangled.params[0].ty, "const char *");
+    assert_eq!(mangled.params[1].ty, "int");
+
+    let stdcall = FunctionPrototype::from_decorated("_Foo@8").unwrap();
+    assert_eq!(stdcall.calling_convention, "__stdcall");
+    assert_eq!(stdcall.params.len(), 2);
+    assert!(FunctionPrototype::from_decorated("Plain").is_none());
+}
+
+#[test]
+fn typed_hooks_are_exported_from_c() {
+    let exports = vec![
+        ExportEntry {
+            name: "Add".to_string(),
+            ordinal: 1,
+            forwarder: None,
+            is_data: false,
+        },
+        ExportEntry {
+            name: "Reset".to_string(),
+            ordinal: 2,
+            forwarder: None,
+            is_data: false,
+        },
+    ];
+    let ctx = VsTemplateContext {
+        project_name: "Foo",
+        dll_name: "Foo.dll",
+        base_name: "Foo",
+        origin_load_mode: OriginLoadMode::SystemDir,
+        exports: &exports,
+        guids: VsGuids {
+            solution: "{S}",
+            project: "{P}",
+            filter_source: "{FS}",
+            filter_header: "{FH}",
+            filter_resource: "{FR}",
+        },
+        options: ProxyOptions {
+            export_rules: parse_rules("*=hook").unwrap(),
+            hook_prototypes: prototypes_from_header("int WINAPI Add(int a, int b); void Reset(void);"),
+            ..Default::default()
+        },
+    };
+
+    assert!(!needs_jump_table(&ctx));
+
+    let c = render_c_x64(&ctx);
+    assert!(c.contains(r#"/EXPORT:\"Add=AheadLibEx_Hook_Add,@1\""#));
+    let header = render_proxy_h(&ctx);
+    assert!(header.contains("typedef int (WINAPI *AheadLibEx_Add_t)(int a, int b);"));
+    assert!(header.contains("void before_Add(int *a, int *b);"));
+    assert!(header.contains("int after_Add(int result, int a, int b);"));
+    assert!(c.contains("int WINAPI AheadLibEx_Hook_Add(int a, int b)"));
+    assert!(c.contains("    before_Add(&a, &b);"));
+    assert!(c.contains("    int result = ((AheadLibEx_Add_t)pfnAheadLibEx_Add)(a, b);"));
+    assert!(c.contains("    return after_Add(result, a, b);"));
+    assert!(header.contains("void after_Reset(void);"));
+    assert!(c.contains("pfnAheadLibEx_Reset = get_address(\"Reset\", NULL);"));
+
+    let c_x86 = render_c(&ctx);
+    assert!(!c_x86.contains("alternatename:AheadLibEx_Hook_Add"));
+
+    let def = render_def(&ctx, false);
+    assert!(def.contains("Add=AheadLibEx_Hook_Add @1"));
+}
@@ -0,0 +1,150 @@
+use aheadlibex_rs::dll::ExportEntry;
+use aheadlibex_rs::export_policy::{parse_rules, PayloadEntry};
+use aheadlibex_rs::host_filter::{parse_host_list, HostFilter, VersionRange};
+use aheadlibex_rs::templates::{
+    render_c, render_c_x64, OriginLoadMode, ProxyOptions, VsGuids, VsTemplateContext,
+};
+
+fn entry(name: &str, ordinal: u16) -> ExportEntry {
+    ExportEntry {
+        name: name.to_string(),
+        ordinal,
+        forwarder: None,
+        is_data: false,
+    }
+}
+
+fn ctx<'a>(exports: &'a [ExportEntry], options: ProxyOptions) -> VsTemplateContext<'a> {
+    VsTemplateContext {
+        project_name: "Foo",
+        dll_name: "Foo.dll",
+        base_name: "Foo",
+        origin_load_mode: OriginLoadMode::SystemDir,
+        exports,
+        guids: VsGuids {
+            solution: "{S}",
+            project: "{P}",
+            filter_source: "{FS}",
+            filter_header: "{FH}",
+            filter_resource: "{FR}",
+        },
+        options,
+    }
+}
+
+#[test]
+fn host_rules_parse_by_kind() {
+    let filter = parse_host_list(
+        "# hosts\ngame.exe\nname:Launcher.exe\nC:\\Games\\game.exe\npath:D:/x.exe\nversion:1.2-1.4.5\nsha256:E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855\n",
+    )
+    .unwrap();
+    assert_eq!(filter.names, vec!["game.exe", "Launcher.exe"]);
+    assert_eq!(filter.paths, vec!["C:\\Games\\game.exe", "D:/x.exe"]);
+    assert_eq!(
+        filter.versions,
+        vec![VersionRange {
+            min: 0x0001_0002_0000_0000,
+            max: 0x0001_0004_0005_FFFF,
+        }]
+    );
+    assert_eq!(filter.sha256[0][0], 0xe3);
+    assert_eq!(filter.sha256[0][31], 0x55);
+
+    assert_eq!(
+        VersionRange::parse("2").unwrap(),
+        VersionRange {
+            min: 0x0002_0000_0000_0000,
+            max: 0x0002_FFFF_FFFF_FFFF,
+        }
+    );
+    assert_eq!(VersionRange::parse("-1.0").unwrap().min, 0);
+    assert_eq!(VersionRange::parse("3.1-").unwrap().max, u64::MAX);
+    assert!(VersionRange::parse("2.0-1.0").is_err());
+    assert!(VersionRange::parse("1.x").is_err());
+    assert!(parse_host_list("sha256:abcd").is_err());
+    assert!(parse_host_list("version:").is_err());
+}
+
+#[test]
+fn filter_gates_payload_hooks_and_typed_hooks() {
+    let exports = vec![entry("Open", 1), entry("Hooked", 2), entry("_Typed@8", 3)];
+    let mut host_filter = HostFilter::default();
+    host_filter.add("game.exe").unwrap();
+    host_filter.add("version:1.2-1.4").unwrap();
+    let options = ProxyOptions {
+        host_filter,
+        export_rules: parse_rules("Hooked=hook\n_Typed@8=hook").unwrap(),
+        ..Default::default()
+    };
+    let c = render_c(&ctx(&exports, options));
+
+    assert!(c.contains("static BOOL aheadlibex_host_allowed(void)"));
+    assert!(c.contains("    if (lstrcmpiW(name, L\"game.exe\") != 0)"));
+    assert!(c.contains("(version >= 0x0001000200000000ULL && version <= 0x00010004FFFFFFFFULL)"));
+    assert!(c.contains("static ULONGLONG aheadlibex_host_version(void)"));
+    assert!(!c.contains("aheadlibex_host_sha256"));
+    assert!(c.contains("static VOID start_payload(void)\n{\n    HANDLE thread;\n\n    if (!aheadlibex_host_allowed())\n    {\n        return;\n    }\n"));
+    assert!(c.contains("static VOID WINAPI start_hooks(void)\n{\n    if (!aheadlibex_host_allowed())"));
+    assert!(c.contains("    if (!aheadlibex_host_allowed())\n    {\n        return ((AheadLibEx__Typed_8_t)pfnAheadLibEx__Typed_8)(a1, a2);\n    }\n    before__Typed_8(&a1, &a2);"));
+    // The check comes before any helper that uses it.
+    assert!(c.find("aheadlibex_host_allowed(void)").unwrap() < c.find("static VOID start_payload").unwrap());
+    assert!(!c.contains("{{"));
+    // Long host paths: the growing buffer from the origin helpers, not MAX_PATH.
+    assert!(c.contains("    BOOL matches = origin_module_file(NULL, &path) && aheadlibex_host_check(path.text);"));
+    assert!(!c.contains("WCHAR path[MAX_PATH]"));
+    assert_eq!(c.matches("static BOOL WINAPI origin_module_file(").count(), 1);
+    assert!(c.find("origin_module_file(HMODULE").unwrap() < c.find("aheadlibex_host_matches(void)").unwrap());
+
+    // Forward-only proxies have no origin runtime but still get the path helpers.
+    let mut host_filter = HostFilter::default();
+    host_filter.add("game.exe").unwrap();
+    let forwarded = render_c_x64(&VsTemplateContext {
+        origin_load_mode: OriginLoadMode::SameDir {
+            original_name: "Foo_orig.dll",
+        },
+        ..ctx(
+            &[entry("Open", 1)],
+            ProxyOptions {
+                host_filter,
+                export_rules: parse_rules("Open=forward").unwrap(),
+                ..Default::default()
+            },
+        )
+    });
+    assert!(!forwarded.contains("load_original_module"));
+    assert!(forwarded.contains("static BOOL WINAPI origin_module_file(HMODULE module, origin_path* path)"));
+}
+
+#[test]
+fn no_filter_or_nothing_to_gate_emits_no_check() {
+    let exports = vec![entry("Open", 1)];
+    let plain = render_c_x64(&ctx(&exports, ProxyOptions::default()));
+    assert!(!plain.contains("aheadlibex_host"));
+
+    let mut host_filter = HostFilter::default();
+    host_filter
+        .add("sha256:e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+        .unwrap();
+    let idle = render_c_x64(&ctx(
+        &exports,
+        ProxyOptions {
+            host_filter: host_filter.clone(),
+            payload: PayloadEntry::None,
+            ..Default::default()
+        },
+    ));
+    assert!(!idle.contains("aheadlibex_host"));
+
+    let hashed = render_c_x64(&ctx(
+        &exports,
+        ProxyOptions {
+            host_filter,
+            payload: PayloadEntry::Sync,
+            ..Default::default()
+        },
+    ));
+    assert!(hashed.contains("aheadlibex_host_sha256(path, digest)"));
+    assert!(hashed.contains("{ 0xe3, 0xb0, 0xc4, 0x42,"));
+    assert!(!hashed.contains("aheadlibex_host_version"));
+    assert!(hashed.contains("static VOID start_payload(void)\n{\n    if (!aheadlibex_host_allowed())"));
+}
@@ -0,0 +1,80 @@
+use aheadlibex_rs::dll::ExportEntry;
+use aheadlibex_rs::export_policy::parse_rules;
+use aheadlibex_rs::prototype::prototypes_from_header;
+use aheadlibex_rs::templates::{
+    needs_jump_table, render_asm_x64, render_asm_x64_gas, render_asm_x86, render_asm_x86_gas,
+    render_c_x64, OriginLoadMode, ProxyOptions, VsGuids, VsTemplateContext,
+};
+
+fn entry(name: &str, ordinal: u16) -> ExportEntry {
+    ExportEntry {
+        name: name.to_string(),
+        ordinal,
+        forwarder: None,
+        is_data: false,
+    }
+}
+
+#[test]
+fn lazy_mode_starts_pointers_at_resolver_entries() {
+    let exports = vec![entry("Open", 1), entry("#2", 2), entry("Add", 3)];
+    let ctx = VsTemplateContext {
+        project_name: "Foo",
+        dll_name: "Foo.dll",
+        base_name: "Foo",
+        origin_load_mode: OriginLoadMode::SystemDir,
+        exports: &exports,
+        guids: VsGuids {
+            solution: "{S}",
+            project: "{P}",
+            filter_source: "{FS}",
+            filter_header: "{FH}",
+            filter_resource: "{FR}",
+        },
+        options: ProxyOptions {
+            lazy: true,
+            export_rules: parse_rules("Add=hook").unwrap(),
+            hook_prototypes: prototypes_from_header("int WINAPI Add(int a, int b);"),
+            ..Default::default()
+        },
+    };
+
+    assert!(needs_jump_table(&ctx));
+
+    let c = render_c_x64(&ctx);
+    assert!(c.contains("void AheadLibEx_Resolve_Open(void);"));
+    assert!(c.contains("PVOID pfnAheadLibEx_Open = (PVOID)AheadLibEx_Resolve_Open;"));
+    assert!(c.contains("    { NULL, 2, &pfnAheadLibEx_Unnamed2, (PVOID)AheadLibEx_Resolve_Unnamed2 },"));
+    assert!(c.contains("PVOID __cdecl AheadLibEx_Resolve(ULONG_PTR index, PVOID return_address)"));
+    assert!(c.contains("current = InterlockedCompareExchangePointer(entry->slot, address, (PVOID)entry->resolver);"));
+    // A detour installed during init already owns the slot; the first call has to go there too.
+    assert!(c.contains("return current == (PVOID)entry->resolver ? address : current;"));
+    assert!(!c.contains("get_address(\"Open\""));
+    assert!(!c.contains("AheadLibEx_TraceEnter"));
+
+    let asm = render_asm_x64(&ctx);
+    assert!(asm.contains("EXTERN AheadLibEx_Resolve:PROC"));
+    assert!(asm.contains("AheadLibEx_Open PROC\n    jmp pfnAheadLibEx_Open"));
+    assert!(asm.contains("AheadLibEx_Resolve_Add PROC\n    push 2\n    jmp AheadLibEx_ResolveThunk"));
+    assert!(asm.contains("AheadLibEx_ResolveThunk PROC"));
+    assert!(asm.contains("call AheadLibEx_Resolve\n"));
+    // Typed hooks are C functions; only their resolver entry lives in asm.
+    assert!(!asm.contains("AheadLibEx_Add PROC"));
+
+    let asm_x86 = render_asm_x86(&ctx);
+    assert!(asm_x86.contains("_AheadLibEx_Resolve_Open:\n    push 0\n    jmp AheadLibEx_ResolveThunk"));
+    assert!(asm_x86.contains("call _AheadLibEx_Resolve\n"));
+    assert!(render_asm_x86_gas(&ctx).contains("    .globl _AheadLibEx_Resolve_Unnamed2"));
+    assert!(render_asm_x64_gas(&ctx).contains("    .extern AheadLibEx_Resolve\n"));
+
+    let traced = VsTemplateContext {
+        options: ProxyOptions {
+            trace: true,
+            ..ctx.options.clone()
+        },
+        ..ctx
+    };
+    let asm = render_asm_x64(&traced);
+    assert!(asm.contains("AheadLibEx_TraceThunk PROC"));
+    assert!(asm.contains("AheadLibEx_ResolveThunk PROC"));
+}
@@ -0,0 +1,92 @@
+use aheadlibex_rs::dll::ExportEntry;
+use aheadlibex_rs::export_policy::PayloadEntry;
+use aheadlibex_rs::templates::{
+    meson_cross_file_name, render_meson_build, render_meson_cross_file, system_libraries, OriginLoadMode, ProxyOptions,
+    VsGuids, VsTemplateContext,
+};
+
+fn entry(name: &str, ordinal: u16) -> ExportEntry {
+    ExportEntry {
+        name: name.to_string(),
+        ordinal,
+        forwarder: None,
+        is_data: false,
+    }
+}
+
+fn ctx(exports: &[ExportEntry], options: ProxyOptions) -> VsTemplateContext<'_> {
+    VsTemplateContext {
+        project_name: "Foo",
+        dll_name: "Foo.dll",
+        base_name: "Foo",
+        origin_load_mode: OriginLoadMode::SystemDir,
+        exports,
+        guids: VsGuids {
+            solution: "",
+            project: "",
+            filter_source: "",
+            filter_header: "",
+            filter_resource: "",
+        },
+        options,
+    }
+}
+
+#[test]
+fn meson_build_picks_jump_table_per_toolchain() {
+    let exports = vec![entry("Add", 1)];
+    let ctx = ctx(&exports, ProxyOptions::default());
+
+    let build = render_meson_build(&ctx, true);
+    assert!(build.contains("project('AheadLibEx_Foo', 'c', meson_version: '>=1.3.0')\n"));
+    assert!(build.contains("aheadlibex_sources = files('Foo_x64.c', 'Foo_payload.c')\n"));
+    assert!(build.contains("aheadlibex_asm_masm = 'Foo_x64_jump.asm'\naheadlibex_asm_gas = 'Foo_x64_jump.S'\n"));
+    assert!(build.contains("    add_languages('masm', native: false)\n"));
+    assert!(build.contains("  vs_module_defs: 'Foo.def',\n  c_args: aheadlibex_args,\n"));
+    assert!(build.contains("[cc.find_library('kernel32'), cc.find_library('user32')]"));
+    assert!(build.contains("--cross-file mingw-w64-x86_64.ini"));
+    assert!(!build.contains("{{"));
+
+    let cross = render_meson_cross_file(true);
+    assert_eq!(meson_cross_file_name(true), "mingw-w64-x86_64.ini");
+    assert!(cross.contains("c = 'x86_64-w64-mingw32-gcc'\n"));
+    assert!(cross.contains("cpu_family = 'x86_64'\ncpu = 'x86_64'\n"));
+
+    let cpp = ProxyOptions {
+        cpp: true,
+        ..Default::default()
+    };
+    let build = render_meson_build(&crate::ctx(&exports, cpp), false);
+    assert!(build.contains("project('AheadLibEx_Foo', 'cpp', 'c', meson_version: '>=1.3.0')\n"));
+    assert!(build.contains("cc = meson.get_compiler('cpp')\n"));
+    assert!(build.contains("files('Foo_x86.cpp', 'Foo_payload.cpp')"));
+    assert!(build.contains("  cpp_args: aheadlibex_args,\n"));
+}
+
+#[test]
+fn meson_build_links_only_what_the_proxy_calls() {
+    let mut exports = vec![entry("Fwd", 1)];
+    exports[0].forwarder = Some("kernel32.Sleep".to_string());
+    let mut options = ProxyOptions::forward_only();
+    options.payload = PayloadEntry::None;
+    let forward_only = ctx(&exports, options);
+    assert_eq!(system_libraries(&forward_only), ["kernel32"]);
+
+    let build = render_meson_build(&forward_only, true);
+    assert!(build.contains("aheadlibex_asm_masm = ''\naheadlibex_asm_gas = ''\n"));
+    assert!(build.contains("aheadlibex_deps = [cc.find_library('kernel32')]\n"));
+
+    let exports = vec![entry("Add", 1)];
+    let minimal = ProxyOptions {
+        minimal_deps: true,
+        ..Default::default()
+    };
+    let minimal = ctx(&exports, minimal);
+    assert_eq!(system_libraries(&minimal), ["kernel32"]);
+
+    let build = render_meson_build(&minimal, false);
+    assert!(build.contains("default_options: ['buildtype=release', 'b_vscrt=none'])\n"));
+    assert!(build.contains("  aheadlibex_link_args = ['/NODEFAULTLIB', '/ENTRY:DllMain']\n"));
+    assert!(build.contains("  aheadlibex_link_args = ['-nostdlib', '-Wl,-e,_DllMain@12']\n"));
+    assert!(render_meson_build(&minimal, true).contains("'-Wl,-e,DllMain'"));
+}
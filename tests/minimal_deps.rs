@@ -0,0 +1,62 @@
+use aheadlibex_rs::dll::ExportEntry;
+use aheadlibex_rs::templates::{
+    render_cmake_lists, render_c_x64, render_vcxproj, OriginLoadMode, ProxyOptions, VsGuids,
+    VsTemplateContext,
+};
+
+fn ctx(exports: &[ExportEntry], minimal_deps: bool) -> VsTemplateContext<'_> {
+    VsTemplateContext {
+        project_name: "Foo",
+        dll_name: "Foo.dll",
+        base_name: "Foo",
+        origin_load_mode: OriginLoadMode::SystemDir,
+        exports,
+        guids: VsGuids {
+            solution: "{S}",
+            project: "{P}",
+            filter_source: "{FS}",
+            filter_header: "{FH}",
+            filter_resource: "{FR}",
+        },
+        options: ProxyOptions {
+            minimal_deps,
+            ..Default::default()
+        },
+    }
+}
+
+#[test]
+fn minimal_deps_drops_crt_and_user32() {
+    let exports = vec![ExportEntry {
+        name: "Open".to_string(),
+        ordinal: 1,
+        forwarder: None,
+        is_data: false,
+    }];
+    let minimal = ctx(&exports, true);
+
+    let c = render_c_x64(&minimal);
+    assert!(c.contains("#pragma function(memset, memcpy)"));
+    assert!(c.contains("#define wsprintf aheadlibex_wsprintfW"));
+    assert!(c.contains("#define MessageBox aheadlibex_message_box"));
+    assert!(c.contains("LoadLibraryA(\"user32.dll\")"));
+    // The shims have to come before the first wsprintf/MessageBox use.
+    assert!(c.find("#define MessageBox").unwrap() < c.find("static BOOL WINAPI load_original_module").unwrap());
+
+    let vcxproj = render_vcxproj(&minimal, true);
+    assert_eq!(vcxproj.matches("<EntryPointSymbol>DllMain</EntryPointSymbol>").count(), 2);
+    assert!(vcxproj.contains("<IgnoreAllDefaultLibraries>true</IgnoreAllDefaultLibraries>"));
+    assert!(vcxproj.contains("<BufferSecurityCheck>false</BufferSecurityCheck>"));
+    assert!(!vcxproj.contains("<SDLCheck>true</SDLCheck>"));
+
+    let cmake = render_cmake_lists(&minimal, false);
+    assert!(cmake.contains("target_link_libraries(${PROJECT_NAME} PRIVATE kernel32)"));
+    assert!(cmake.contains("/NODEFAULTLIB /ENTRY:DllMain"));
+    assert!(cmake.contains("set(AHEADLIBEX_ENTRY \"_DllMain@12\")"));
+    assert!(!cmake.contains("user32"));
+
+    let normal = ctx(&exports, false);
+    assert!(!render_c_x64(&normal).contains("aheadlibex_wsprintfW"));
+    assert!(!render_vcxproj(&normal, true).contains("EntryPointSymbol"));
+    assert!(render_cmake_lists(&normal, true).contains("target_link_libraries(${PROJECT_NAME} PRIVATE user32)"));
+}
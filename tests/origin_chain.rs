@@ -0,0 +1,151 @@
+use aheadlibex_rs::dll::ExportEntry;
+use aheadlibex_rs::templates::{
+    render_c, render_origin_config_sample, OriginLoadMode, OriginLoadModeOwned, ProxyOptions,
+    VsGuids, VsTemplateContext,
+};
+
+fn dummy_ctx<'a>(exports: &'a [ExportEntry], mode: OriginLoadMode<'a>) -> VsTemplateContext<'a> {
+    let guids = VsGuids {
+        solution: "{S}",
+        project: "{P}",
+        filter_source: "{FS}",
+        filter_header: "{FH}",
+        filter_resource: "{FR}",
+    };
+    VsTemplateContext {
+        project_name: "Foo",
+        dll_name: "Foo.dll",
+        base_name: "Foo",
+        origin_load_mode: mode,
+        exports,
+        guids,
+        options: ProxyOptions::default(),
+    }
+}
+
+fn exports() -> Vec<ExportEntry> {
+    vec![ExportEntry {
+        name: "Bar".to_string(),
+        ordinal: 1,
+        forwarder: None,
+        is_data: false,
+    }]
+}
+
+#[test]
+fn parse_accepts_inline_arguments() {
+    assert_eq!(
+        OriginLoadModeOwned::parse("samedir", "Foo_orig.dll").unwrap(),
+        OriginLoadModeOwned::same_dir("Foo_orig.dll".to_string())
+    );
+    assert_eq!(
+        OriginLoadModeOwned::parse("samedir:Other.dll", "Foo_orig.dll").unwrap(),
+        OriginLoadModeOwned::same_dir("Other.dll".to_string())
+    );
+    assert_eq!(
+        OriginLoadModeOwned::parse(r"custom:C:\libs\Foo.dll", "Foo_orig.dll").unwrap(),
+        OriginLoadModeOwned::custom_path(r"C:\libs\Foo.dll".to_string())
+    );
+    assert!(OriginLoadModeOwned::parse("custom", "Foo_orig.dll").is_err());
+    assert!(OriginLoadModeOwned::parse("nowhere", "Foo_orig.dll").is_err());
+}
+
+#[test]
+fn single_candidate_chain_collapses() {
+    let chain = OriginLoadModeOwned::chain(vec![OriginLoadModeOwned::system_dir()]);
+    assert_eq!(chain, OriginLoadModeOwned::system_dir());
+}
+
+#[test]
+fn chain_tries_candidates_in_order_and_logs_the_winner() {
+    let exports = exports();
+    let mode = OriginLoadModeOwned::chain(vec![
+        OriginLoadModeOwned::same_dir("Foo_orig.dll".to_string()),
+        OriginLoadModeOwned::custom_path(r"D:\libs\Foo.dll".to_string()),
+        OriginLoadModeOwned::system_dir(),
+    ]);
+    let ctx = dummy_ctx(&exports, mode.as_borrowed());
+
+    let c = render_c(&ctx);
+    let first = c.find("origin_candidate_1(module, &module_path)").unwrap();
+    let second = c.find("origin_candidate_2(module, &module_path)").unwrap();
+    let third = c.find("origin_candidate_3(module, &module_path)").unwrap();
+    assert!(first < second && second < third);
+    assert!(c.contains(r#"origin_path_join(module_path, L"Foo_orig.dll")"#));
+    assert!(c.contains(r#"origin_cfg[] = L"D:\\libs\\Foo.dll";"#));
+    assert!(c.contains("origin_known_dir(GetSystemDirectoryW, module_path)"));
+    assert!(c.contains("origin_trace_loaded(&module_path);"));
+    assert!(c.contains(r#"origin_report(L"Cannot locate Foo.dll in any of 3 locations", L"", error);"#));
+}
+
+#[test]
+fn system_only_chain_skips_path_resolution() {
+    let exports = exports();
+    let mode = OriginLoadModeOwned::Chain(vec![
+        OriginLoadModeOwned::system_dir(),
+        OriginLoadModeOwned::system_dir(),
+    ]);
+    let ctx = dummy_ctx(&exports, mode.as_borrowed());
+
+    let c = render_c(&ctx);
+    assert!(c.contains("origin_candidate_2("));
+    assert!(!c.contains("origin_resolve_path("));
+}
+
+#[test]
+fn runtime_sources_fall_back_to_system_directory() {
+    let chain = OriginLoadModeOwned::chain(vec![
+        OriginLoadModeOwned::parse("env", "Foo_orig.dll").unwrap(),
+        OriginLoadModeOwned::parse("config", "Foo_orig.dll").unwrap(),
+    ]);
+    assert_eq!(
+        chain,
+        OriginLoadModeOwned::Chain(vec![
+            OriginLoadModeOwned::env(None),
+            OriginLoadModeOwned::config(None),
+            OriginLoadModeOwned::system_dir(),
+        ])
+    );
+
+    let exports = exports();
+    let ctx = dummy_ctx(&exports, chain.as_borrowed());
+    let c = render_c(&ctx);
+    assert!(c.contains(r#"origin_env(L"FOO_ORIGIN", &value)"#));
+    assert!(c.contains(r#"origin_resolve_path(module, L"Foo.origin.ini", &config_path)"#));
+    assert!(c.contains("&& origin_read_ini_path(&config_path, &value)"));
+    assert!(c.contains("origin_candidate_3(module, &module_path)"));
+    assert!(!c.contains("origin_read_json_path"));
+
+    let (name, sample) = render_origin_config_sample(&ctx).unwrap();
+    assert_eq!(name, "Foo.origin.ini");
+    assert!(sample.contains("[origin]\r\npath=C:\\Windows\\System32\\Foo.dll\r\n"));
+}
+
+#[test]
+fn json_config_uses_json_reader_and_sample() {
+    let mode = OriginLoadModeOwned::chain(vec![
+        OriginLoadModeOwned::config(Some("cfg\\origin.json".to_string())),
+        OriginLoadModeOwned::same_dir("Foo_orig.dll".to_string()),
+    ]);
+    let exports = exports();
+    let ctx = dummy_ctx(&exports, mode.as_borrowed());
+
+    let c = render_c(&ctx);
+    assert!(c.contains("static BOOL WINAPI origin_read_json_path("));
+    assert!(c.contains("&& origin_read_json_path(&config_path, &value)"));
+    // "path" counts only as a top-level key, not as text inside some other string.
+    assert!(c.contains("            is_path = depth == 1 && !key[i] && start + i == p;"));
+    assert!(c.contains("            if (is_path && *p == ':')"));
+    assert!(!c.contains("origin_read_ini_path"));
+
+    let (name, sample) = render_origin_config_sample(&ctx).unwrap();
+    assert_eq!(name, "origin.json");
+    assert_eq!(sample, "{\n  \"path\": \"Foo_orig.dll\"\n}\n");
+}
+
+#[test]
+fn compiled_in_modes_have_no_config_sample() {
+    let exports = exports();
+    let ctx = dummy_ctx(&exports, OriginLoadMode::SystemDir);
+    assert!(render_origin_config_sample(&ctx).is_none());
+}
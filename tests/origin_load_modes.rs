@@ -1,5 +1,7 @@
 use aheadlibex_rs::dll::ExportEntry;
-use aheadlibex_rs::templates::{render_c, render_c_x64, OriginLoadMode, VsGuids, VsTemplateContext};
+use aheadlibex_rs::templates::{
+    render_c, render_c_x64, OriginLoadMode, ProxyOptions, VsGuids, VsTemplateContext,
+};
 
 fn dummy_ctx<'a>(exports: &'a [ExportEntry], mode: OriginLoadMode<'a>) -> VsTemplateContext<'a> {
     let guids = VsGuids {
@@ -16,6 +18,7 @@ fn dummy_ctx<'a>(exports: &'a [ExportEntry], mode: OriginLoadMode<'a>) -> VsTemp
         origin_load_mode: mode,
         exports,
         guids,
+        options: ProxyOptions::default(),
     }
 }
 
@@ -25,14 +28,15 @@ fn system_dir_mode_uses_system_directory() {
         name: "Bar".to_string(),
         ordinal: 1,
         forwarder: None,
+        is_data: false,
     }];
     let ctx = dummy_ctx(&exports, OriginLoadMode::SystemDir);
 
     let c = render_c(&ctx);
     assert!(c.contains("load_original_module(HMODULE module)"));
     assert!(c.contains("load_original_module(module)"));
-    assert!(c.contains("GetSystemDirectory("));
-    assert!(c.contains(r#"TEXT("Foo.dll")"#));
+    assert!(c.contains("GetSystemDirectoryW"));
+    assert!(c.contains(r#"L"Foo.dll""#));
 }
 
 #[test]
@@ -41,6 +45,7 @@ fn same_dir_mode_uses_proxy_directory_and_original_name() {
         name: "Bar".to_string(),
         ordinal: 1,
         forwarder: None,
+        is_data: false,
     }];
     let ctx = dummy_ctx(
         &exports,
@@ -50,8 +55,8 @@ fn same_dir_mode_uses_proxy_directory_and_original_name() {
     );
 
     let c = render_c(&ctx);
-    assert!(c.contains("GetModuleFileName("));
-    assert!(c.contains(r#"TEXT("Foo_orig.dll")"#));
+    assert!(c.contains("GetModuleFileNameW("));
+    assert!(c.contains(r#"L"Foo_orig.dll""#));
 }
 
 #[test]
@@ -60,6 +65,7 @@ fn custom_path_mode_embeds_origin_cfg() {
         name: "Bar".to_string(),
         ordinal: 1,
         forwarder: None,
+        is_data: false,
     }];
     let ctx = dummy_ctx(
         &exports,
@@ -69,6 +75,72 @@ fn custom_path_mode_embeds_origin_cfg() {
     );
 
     let c = render_c_x64(&ctx);
-    assert!(c.contains(r#"origin_cfg[] = TEXT("C:\\path\\to\\Foo.dll")"#));
+    assert!(c.contains(r#"origin_cfg[] = L"C:\\path\\to\\Foo.dll";"#));
 }
 
+
+#[test]
+fn loader_uses_wide_heap_buffers() {
+    let exports = vec![ExportEntry {
+        name: "Bar".to_string(),
+        ordinal: 1,
+        forwarder: None,
+        is_data: false,
+    }];
+    let ctx = dummy_ctx(&exports, OriginLoadMode::SystemDir);
+
+    let c = render_c(&ctx);
+    assert!(c.contains("#ifndef UNICODE\n#define UNICODE\n#endif"));
+    let loader = &c[c.find("static BOOL WINAPI load_original_module").unwrap()..];
+    let loader = &loader[..loader.find("static FARPROC WINAPI get_address").unwrap()];
+    assert!(loader.contains("origin_path module_path = { 0 };"));
+    assert!(!loader.contains("MAX_PATH"));
+    assert!(!loader.contains("wsprintf"));
+    assert!(c.contains("LoadLibraryExW(full.text, NULL, flags)"));
+    // The export-miss message is heap built too, so long decorated names can't overflow it.
+    assert!(c.contains("origin_path_append_ansi(&text, proc_name)"));
+    assert!(!c.contains("%hs"));
+}
+
+#[test]
+fn deep_custom_path_is_embedded_whole_and_gets_long_path_prefix() {
+    let exports = vec![ExportEntry {
+        name: "Bar".to_string(),
+        ordinal: 1,
+        forwarder: None,
+        is_data: false,
+    }];
+    let deep = format!(r"C:\{}\Foo.dll", vec!["very_long_directory_name"; 20].join(r"\"));
+    assert!(deep.len() > 400);
+    let ctx = dummy_ctx(&exports, OriginLoadMode::CustomPath { path: &deep });
+
+    let c = render_c_x64(&ctx);
+    let escaped = deep.replace('\\', r"\\");
+    assert!(c.contains(&format!(r#"origin_cfg[] = L"{escaped}";"#)));
+    assert!(c.contains("if (n < MAX_PATH)"));
+    assert!(c.contains(r#"L"\\\\?\\UNC\\" : L"\\\\?\\""#));
+    assert!(c.contains("GetFullPathNameW(path->text, 0, NULL, NULL)"));
+}
+
+#[test]
+fn non_ascii_custom_path_is_escaped_for_any_source_charset() {
+    let exports = vec![ExportEntry {
+        name: "Bar".to_string(),
+        ordinal: 1,
+        forwarder: None,
+        is_data: false,
+    }];
+    let ctx = dummy_ctx(
+        &exports,
+        OriginLoadMode::CustomPath {
+            path: "D:\\游戏\\Café\\𝒳\\Foo.dll",
+        },
+    );
+
+    let c = render_c(&ctx);
+    assert!(c.contains(
+        r#"origin_cfg[] = L"D:\\\u6E38\u620F\\Caf\u00E9\\\U0001D4B3\\Foo.dll";"#
+    ));
+    let loader = &c[c.find("static const WCHAR origin_cfg").unwrap()..];
+    assert!(loader.lines().next().unwrap().is_ascii());
+}
@@ -0,0 +1,131 @@
+use aheadlibex_rs::dll::ExportEntry;
+use aheadlibex_rs::templates::{
+    check_origin_forwarders, parse_search_flags, render_c, render_def, OriginLoadModeOwned, ProxyOptions, VsGuids,
+    VsTemplateContext, LOAD_LIBRARY_SEARCH_DEFAULT_DIRS, LOAD_LIBRARY_SEARCH_DLL_LOAD_DIR,
+    LOAD_LIBRARY_SEARCH_SYSTEM32, LOAD_LIBRARY_SEARCH_USER_DIRS,
+};
+
+fn render(mode: &OriginLoadModeOwned, options: ProxyOptions) -> (String, String) {
+    let exports = vec![ExportEntry {
+        name: "Bar".to_string(),
+        ordinal: 1,
+        forwarder: None,
+        is_data: false,
+    }];
+    let ctx = VsTemplateContext {
+        project_name: "Foo",
+        dll_name: "Foo.dll",
+        base_name: "Foo",
+        origin_load_mode: mode.as_borrowed(),
+        exports: &exports,
+        guids: VsGuids {
+            solution: "{S}",
+            project: "{P}",
+            filter_source: "{FS}",
+            filter_header: "{FH}",
+            filter_resource: "{FR}",
+        },
+        options,
+    };
+    (render_c(&ctx), render_def(&ctx, false))
+}
+
+#[test]
+fn search_flags_parse_and_default() {
+    assert_eq!(
+        parse_search_flags("").unwrap(),
+        LOAD_LIBRARY_SEARCH_DLL_LOAD_DIR | LOAD_LIBRARY_SEARCH_DEFAULT_DIRS
+    );
+    assert_eq!(
+        parse_search_flags("System32, user-dirs").unwrap(),
+        LOAD_LIBRARY_SEARCH_SYSTEM32 | LOAD_LIBRARY_SEARCH_USER_DIRS
+    );
+    assert!(parse_search_flags("cwd").is_err());
+
+    // Directories only take part with user-dirs, so they switch it on.
+    assert_eq!(
+        OriginLoadModeOwned::parse(r"loadex:system32;C:\Vendor\bin", "Foo_orig.dll").unwrap(),
+        OriginLoadModeOwned::LoadEx {
+            flags: LOAD_LIBRARY_SEARCH_SYSTEM32 | LOAD_LIBRARY_SEARCH_USER_DIRS,
+            dirs: vec![r"C:\Vendor\bin".to_string()],
+        }
+    );
+}
+
+#[test]
+fn bare_name_search_skips_proxy_directory() {
+    let mode = OriginLoadModeOwned::parse("search", "Foo_orig.dll").unwrap();
+    let (c, _) = render(&mode, ProxyOptions::default());
+    assert!(c.contains("static BOOL WINAPI origin_search_path("));
+    assert!(c.contains(r#"return origin_search_path(module, L"Foo.dll", module_path);"#));
+    assert!(c.contains(r#"origin_env(L"PATH", &dir)"#));
+    // The proxy is recognised by its module handle, not by its directory's name.
+    assert!(c.contains("found = origin_try_dir(dir.text, dir.length, name, module, module_path);"));
+    assert!(c.contains("GetModuleHandleExW(GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT, full.text, &mapped);"));
+    assert!(!c.contains("origin_own_dir"));
+    assert!(c.contains("g_origin_module_handle = origin_load(module, &module_path, 0);"));
+    assert!(c.contains("    if (handle && handle == self)\n    {\n        FreeLibrary(handle);"));
+    assert!(!c.contains("AddDllDirectory"));
+}
+
+#[test]
+fn load_ex_registers_directories_and_passes_flags() {
+    let mode = OriginLoadModeOwned::parse(r"loadex:dll-load-dir,system32;vendor", "Foo_orig.dll").unwrap();
+    let (c, _) = render(&mode, ProxyOptions::default());
+    assert!(c.contains(r#"origin_add_dll_directory(module, L"vendor");"#));
+    assert!(c.contains("GetProcAddress(kernel32, \"AddDllDirectory\")"));
+    assert!(c.contains(
+        "origin_load(module, &module_path, LOAD_LIBRARY_SEARCH_DLL_LOAD_DIR | LOAD_LIBRARY_SEARCH_USER_DIRS | LOAD_LIBRARY_SEARCH_SYSTEM32);\n        error = GetLastError();\n    }\n    origin_remove_dll_directories();"
+    ));
+    // The cookies are kept so the directories can be dropped once the load is over.
+    assert!(c.contains("static PVOID g_origin_dll_dirs[1];"));
+    assert!(c.contains("            g_origin_dll_dirs[g_origin_dll_dir_count++] = cookie;"));
+    assert!(c.contains("GetProcAddress(kernel32, \"RemoveDllDirectory\")"));
+    let registered = c.find(r#"origin_add_dll_directory(module, L"vendor");"#).unwrap();
+    let probed = c.find(r#"origin_resolve_path(module, L"vendor", &dir)"#).unwrap();
+    assert!(registered < probed);
+    // No application-dir flag, so the host executable's directory is not probed.
+    assert!(!c.contains("origin_module_dir(NULL, &dir)"));
+}
+
+#[test]
+fn forwarders_never_use_searched_locations() {
+    let exports = vec![ExportEntry {
+        name: "Bar".to_string(),
+        ordinal: 1,
+        forwarder: None,
+        is_data: false,
+    }];
+    let check = |mode: &OriginLoadModeOwned| {
+        check_origin_forwarders(&VsTemplateContext {
+            project_name: "Foo",
+            dll_name: "Foo.dll",
+            base_name: "Foo",
+            origin_load_mode: mode.as_borrowed(),
+            exports: &exports,
+            guids: VsGuids {
+                solution: "{S}",
+                project: "{P}",
+                filter_source: "{FS}",
+                filter_header: "{FH}",
+                filter_resource: "{FR}",
+            },
+            options: ProxyOptions::forward_only(),
+        })
+    };
+
+    // A forwarder can't search, so a chain that searches first is rejected rather than skipped.
+    let searched_first = OriginLoadModeOwned::chain(vec![
+        OriginLoadModeOwned::search(None),
+        OriginLoadModeOwned::same_dir("Foo_orig.dll".to_string()),
+    ]);
+    assert!(check(&searched_first).unwrap_err().to_string().contains("search"));
+
+    let same_dir_first = OriginLoadModeOwned::chain(vec![
+        OriginLoadModeOwned::same_dir("Foo_orig.dll".to_string()),
+        OriginLoadModeOwned::search(None),
+    ]);
+    check(&same_dir_first).unwrap();
+    let (_, def) = render(&same_dir_first, ProxyOptions::forward_only());
+    assert!(def.contains("Foo_orig.Bar"));
+}
@@ -0,0 +1,162 @@
+use aheadlibex_rs::dll::ExportEntry;
+use aheadlibex_rs::export_policy::{parse_rules, ExportSelector, PayloadEntry};
+use aheadlibex_rs::templates::{
+    needs_jump_table, render_asm_x86, render_c, render_c_x64, OriginLoadMode, ProxyOptions,
+    VsGuids, VsTemplateContext,
+};
+
+fn entry(name: &str, ordinal: u16) -> ExportEntry {
+    ExportEntry {
+        name: name.to_string(),
+        ordinal,
+        forwarder: None,
+        is_data: false,
+    }
+}
+
+fn ctx<'a>(exports: &'a [ExportEntry], options: ProxyOptions) -> VsTemplateContext<'a> {
+    VsTemplateContext {
+        project_name: "Foo",
+        dll_name: "Foo.dll",
+        base_name: "Foo",
+        origin_load_mode: OriginLoadMode::SystemDir,
+        exports,
+        guids: VsGuids {
+            solution: "{S}",
+            project: "{P}",
+            filter_source: "{FS}",
+            filter_header: "{FH}",
+            filter_resource: "{FR}",
+        },
+        options,
+    }
+}
+
+fn with_payload(payload: PayloadEntry) -> ProxyOptions {
+    ProxyOptions {
+        payload,
+        ..Default::default()
+    }
+}
+
+fn dllmain(c: &str) -> &str {
+    &c[c.find("BOOL APIENTRY DllMain").unwrap()..]
+}
+
+#[test]
+fn parses_payload_entries() {
+    assert_eq!(PayloadEntry::parse("thread").unwrap(), PayloadEntry::Thread);
+    assert_eq!(PayloadEntry::parse("Host-Ready").unwrap(), PayloadEntry::HostReady);
+    assert_eq!(
+        PayloadEntry::parse("first-call:@7").unwrap(),
+        PayloadEntry::FirstCall(ExportSelector::Ordinal(7))
+    );
+    assert_eq!(
+        PayloadEntry::parse("first-call:Open").unwrap().name(),
+        "first-call:Open"
+    );
+    assert!(PayloadEntry::parse("first-call").is_err());
+    assert!(PayloadEntry::parse("first-call:Get*").is_err());
+    assert!(PayloadEntry::parse("later").is_err());
+}
+
+#[test]
+fn message_box_only_in_demo_mode() {
+    let exports = vec![entry("Open", 1)];
+    for payload in [PayloadEntry::Thread, PayloadEntry::Sync, PayloadEntry::HostReady] {
+        let c = render_c_x64(&ctx(&exports, with_payload(payload)));
+        assert!(c.contains("    payload_main();\n"));
+        assert!(!c.contains("AheadLibExTest!"));
+        assert!(!c.contains("{{"));
+    }
+
+    let demo = render_c(&ctx(&exports, with_payload(PayloadEntry::Demo)));
+    assert!(demo.contains("MessageBox(NULL, TEXT(\"AheadLibExTest!\")"));
+    assert!(dllmain(&demo).contains("        start_payload();"));
+
+    let none = render_c_x64(&ctx(&exports, with_payload(PayloadEntry::None)));
+    assert!(!none.contains("payload_main"));
+    assert!(!none.contains("start_payload"));
+    assert!(!none.contains("CreateThread"));
+}
+
+#[test]
+fn attach_strategies_shape_dllmain() {
+    let exports = vec![entry("Open", 1)];
+
+    let thread = render_c_x64(&ctx(&exports, with_payload(PayloadEntry::Thread)));
+    assert!(thread.contains("CreateThread(NULL, 0, payload_thread_proc, NULL, 0, NULL)"));
+    assert!(dllmain(&thread).contains("        start_payload();"));
+
+    let sync = render_c_x64(&ctx(&exports, with_payload(PayloadEntry::Sync)));
+    assert!(sync.contains("static VOID start_payload(void)\n{\n    payload_main();\n}"));
+    assert!(!sync.contains("CreateThread"));
+
+    let host = render_c_x64(&ctx(&exports, with_payload(PayloadEntry::HostReady)));
+    assert!(host.contains("QueueUserAPC(payload_apc_proc, GetCurrentThread(), 0)"));
+    assert!(dllmain(&host).contains("        schedule_payload(reserved);"));
+    assert!(!host.contains("start_payload"));
+
+    // Deferred init starts the payload from the one-time init instead of DllMain.
+    let deferred = render_c_x64(&ctx(
+        &exports,
+        ProxyOptions {
+            deferred_init: true,
+            payload: PayloadEntry::Sync,
+            ..Default::default()
+        },
+    ));
+    assert!(!dllmain(&deferred).contains("start_payload();"));
+    assert!(deferred.contains("if (!init_forwarders())\n    {\n        g_aheadlibex_init_error = ERROR_PROC_NOT_FOUND;\n        return FALSE;\n    }\n    start_payload();"));
+}
+
+#[test]
+fn first_call_routes_the_export_through_a_resolver() {
+    let exports = vec![entry("Open", 1), entry("Close", 2), entry("Fwd", 3)];
+    let options = ProxyOptions {
+        payload: PayloadEntry::parse("first-call:Close").unwrap(),
+        export_rules: parse_rules("Fwd=forward").unwrap(),
+        ..Default::default()
+    };
+    assert!(options.check_exports(&exports).is_ok());
+    let first = VsTemplateContext {
+        origin_load_mode: OriginLoadMode::SameDir {
+            original_name: "Foo_orig.dll",
+        },
+        ..ctx(&exports, options)
+    };
+
+    let c = render_c_x64(&first);
+    assert!(c.contains("PVOID pfnAheadLibEx_Close = (PVOID)AheadLibEx_Resolve_Close;"));
+    assert!(c.contains("PVOID pfnAheadLibEx_Open = NULL;"));
+    assert!(c.contains("pfnAheadLibEx_Open = get_address(\"Open\", NULL);"));
+    assert!(!c.contains("pfnAheadLibEx_Close = get_address"));
+    assert!(c.contains("{ \"Close\", 2, &pfnAheadLibEx_Close, (PVOID)AheadLibEx_Resolve_Close },"));
+    assert!(c.contains("{ \"Open\", 1, &pfnAheadLibEx_Open, NULL },"));
+    assert!(c.contains("    if (index == 1)\n    {\n        start_payload();\n    }"));
+    assert!(c.contains("InterlockedCompareExchange(&g_payload_started, 1, 0)"));
+    assert!(!dllmain(&c).contains("start_payload();"));
+
+    assert!(needs_jump_table(&first));
+    let asm = render_asm_x86(&first);
+    assert!(asm.contains("_AheadLibEx_Resolve_Close:"));
+    assert!(!asm.contains("_AheadLibEx_Resolve_Open:"));
+    assert!(asm.contains("AheadLibEx_ResolveThunk"));
+}
+
+#[test]
+fn first_call_export_must_reach_the_proxy() {
+    let exports = vec![entry("Open", 1), entry("Fwd", 2)];
+    let missing = with_payload(PayloadEntry::parse("first-call:Nope").unwrap());
+    assert!(missing.check_exports(&exports).is_err());
+
+    let forwarded = ProxyOptions {
+        payload: PayloadEntry::parse("first-call:Fwd").unwrap(),
+        export_rules: parse_rules("Fwd=forward").unwrap(),
+        ..Default::default()
+    };
+    assert!(forwarded.check_exports(&exports).is_err());
+
+    let by_ordinal = with_payload(PayloadEntry::parse("first-call:@1").unwrap());
+    assert!(by_ordinal.check_exports(&exports).is_ok());
+}
@@ -0,0 +1,103 @@
+use aheadlibex_rs::dll::ExportEntry;
+use aheadlibex_rs::export_policy::{parse_rules, PayloadEntry};
+use aheadlibex_rs::prototype::prototypes_from_header;
+use aheadlibex_rs::templates::{
+    needs_payload_file, payload_file_name, render_c_x64, render_cmake_lists, render_filters,
+    render_payload_c, render_proxy_h, render_vcxproj_2026, OriginLoadMode, ProxyOptions, VsGuids,
+    VsTemplateContext,
+};
+
+fn entry(name: &str, ordinal: u16) -> ExportEntry {
+    ExportEntry {
+        name: name.to_string(),
+        ordinal,
+        forwarder: None,
+        is_data: false,
+    }
+}
+
+fn ctx<'a>(exports: &'a [ExportEntry], options: ProxyOptions) -> VsTemplateContext<'a> {
+    VsTemplateContext {
+        project_name: "Foo",
+        dll_name: "Foo.dll",
+        base_name: "Foo",
+        origin_load_mode: OriginLoadMode::SystemDir,
+        exports,
+        guids: VsGuids {
+            solution: "{S}",
+            project: "{P}",
+            filter_source: "{FS}",
+            filter_header: "{FH}",
+            filter_resource: "{FR}",
+        },
+        options,
+    }
+}
+
+fn hooked() -> ProxyOptions {
+    ProxyOptions {
+        export_rules: parse_rules("Add=hook").unwrap(),
+        hook_prototypes: prototypes_from_header("int WINAPI Add(int a, int b);"),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn user_code_moves_to_the_payload_file() {
+    let exports = vec![entry("Add", 1), entry("Bar", 2)];
+    let ctx = ctx(&exports, hooked());
+    assert!(needs_payload_file(&ctx));
+    assert_eq!(payload_file_name(&ctx), "Foo_payload.c");
+
+    let header = render_proxy_h(&ctx);
+    assert!(header.contains("VOID WINAPI payload_main(void);"));
+    assert!(header.contains("void before_Add(int *a, int *b);\nint after_Add(int result, int a, int b);\n"));
+
+    let c = render_c_x64(&ctx);
+    assert!(c.contains("#include \"Foo_proxy.h\""));
+    assert!(c.contains("    before_Add(&a, &b);"));
+    assert!(!c.contains("TODO"));
+
+    let user = render_payload_c(&ctx, true);
+    assert!(user.contains("regenerating the proxy rewrites Foo_x64.c, never this file."));
+    assert!(user.contains("#include <windows.h>\n#include \"Foo_proxy.h\"\n"));
+    assert!(user.contains("VOID WINAPI payload_main(void)\n{\n    // TODO: your patch process begins here.\n}\n"));
+    assert!(user.contains("void before_Add(int *a, int *b)\n{\n    UNREFERENCED_PARAMETER(a);"));
+    assert!(user.contains("int after_Add(int result, int a, int b)\n{"));
+    assert!(user.contains("    return result;\n}\n"));
+    assert!(!user.contains("static "));
+    assert!(!user.contains("{{"));
+}
+
+#[test]
+fn projects_compile_the_payload_file() {
+    let exports = vec![entry("Add", 1)];
+    let ctx = ctx(&exports, hooked());
+
+    assert!(render_vcxproj_2026(&ctx, true)
+        .contains("    <ClCompile Include=\"Foo_x64.c\" />\n    <ClCompile Include=\"Foo_payload.c\" />\n"));
+    assert!(render_filters(&ctx, false).contains("<ClCompile Include=\"Foo_payload.c\">"));
+    assert!(render_cmake_lists(&ctx, true).contains("set(AHEADLIBEX_PAYLOAD \"Foo_payload.c\")"));
+}
+
+#[test]
+fn nothing_to_keep_without_payload_or_typed_hooks() {
+    let exports = vec![entry("Bar", 1)];
+    let options = ProxyOptions {
+        payload: PayloadEntry::None,
+        ..Default::default()
+    };
+    let ctx = ctx(&exports, options);
+    assert!(!needs_payload_file(&ctx));
+    assert!(!render_vcxproj_2026(&ctx, true).contains("_payload.c"));
+    assert!(render_cmake_lists(&ctx, true).contains("set(AHEADLIBEX_PAYLOAD \"\")"));
+
+    // Hooks alone still need their callbacks.
+    let mut options = hooked();
+    options.payload = PayloadEntry::None;
+    let exports = vec![entry("Add", 1)];
+    let user = render_payload_c(&crate::ctx(&exports, options), false);
+    assert!(user.contains("Foo_x86.c"));
+    assert!(!user.contains("payload_main"));
+    assert!(user.contains("// Hook for Add\nvoid before_Add"));
+}
@@ -0,0 +1,124 @@
+use aheadlibex_rs::dll::ExportEntry;
+use aheadlibex_rs::export_policy::{PayloadEntry, PluginLoader};
+use aheadlibex_rs::templates::{
+    render_c, render_c_x64, OriginLoadMode, ProxyOptions, VsGuids, VsTemplateContext,
+};
+
+fn entry(name: &str, ordinal: u16) -> ExportEntry {
+    ExportEntry {
+        name: name.to_string(),
+        ordinal,
+        forwarder: None,
+        is_data: false,
+    }
+}
+
+fn ctx<'a>(exports: &'a [ExportEntry], options: ProxyOptions) -> VsTemplateContext<'a> {
+    VsTemplateContext {
+        project_name: "Foo",
+        dll_name: "Foo.dll",
+        base_name: "Foo",
+        origin_load_mode: OriginLoadMode::SameDir {
+            original_name: "Foo_orig.dll",
+        },
+        exports,
+        guids: VsGuids {
+            solution: "{S}",
+            project: "{P}",
+            filter_source: "{FS}",
+            filter_header: "{FH}",
+            filter_resource: "{FR}",
+        },
+        options,
+    }
+}
+
+fn with_plugins(spec: &str, init: Option<&str>) -> ProxyOptions {
+    let mut plugins = PluginLoader::parse(spec).unwrap();
+    if let Some(init) = init {
+        plugins = plugins.with_init_export(init).unwrap();
+    }
+    ProxyOptions {
+        plugins: Some(plugins),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn parses_plugin_directories_and_patterns() {
+    let plugins = PluginLoader::parse("plugins").unwrap();
+    assert_eq!(plugins.dir, "plugins");
+    assert_eq!(plugins.pattern, "*.dll");
+    assert_eq!(plugins.init_export, None);
+
+    let plugins = PluginLoader::parse(r"mods\scripts\*.asi").unwrap();
+    assert_eq!(plugins.dir, r"mods\scripts");
+    assert_eq!(plugins.pattern, "*.asi");
+
+    let plugins = PluginLoader::parse(r"C:\Game\plugins\").unwrap();
+    assert_eq!(plugins.dir, r"C:\Game\plugins");
+    assert_eq!(PluginLoader::parse("*.asi").unwrap().dir, ".");
+
+    assert!(PluginLoader::parse(r"mods*\x.dll").is_err());
+    assert!(PluginLoader::parse("plugins").unwrap().with_init_export("@3").is_err());
+}
+
+#[test]
+fn payload_main_loads_plugins_before_user_code() {
+    let exports = vec![entry("Bar", 1)];
+    let ctx = ctx(&exports, with_plugins(r"mods\*.asi", Some("PluginInit")));
+
+    let c = render_c_x64(&ctx);
+    assert!(c.contains("static VOID WINAPI payload_entry(void)\n{\n    load_plugins();\n    payload_main();\n}"));
+    assert!(c.contains(r#"static const WCHAR plugin_dir[] = L"mods";"#));
+    assert!(c.contains(r#"origin_path_join(&query, L"*.asi")"#));
+    // The loader resolves its directory with the shared path helpers rather than its own copies.
+    assert!(c.contains("origin_module_dir(self, &dir)"));
+    assert!(c.contains("static BOOL WINAPI origin_full_path"));
+    assert!(!c.contains("plugin_full_path") && !c.contains("plugin_module_dir"));
+    assert!(c.contains("lstrcmpiW(names[j - 1], name) > 0"));
+    assert!(c.contains("LoadLibraryExW(path, NULL, LOAD_WITH_ALTERED_SEARCH_PATH)"));
+    assert!(c.contains("typedef struct AHEADLIBEX_PLUGIN_CONTEXT"));
+    assert!(c.contains(r#"GetProcAddress(plugin, "PluginInit")"#));
+    // The loader comes before g_origin_module_handle is declared, so it goes through the accessor.
+    assert!(c.contains("context.origin_module = AheadLibEx_OriginModule();"));
+    assert!(c.contains("FreeLibrary(plugin);"));
+    // The loader lives before the payload entry, which calls it ahead of the user's payload_main.
+    assert!(c.find("static VOID WINAPI load_plugins").unwrap() < c.find("payload_entry(void)").unwrap());
+    assert!(c.contains("    payload_entry();\n"));
+}
+
+#[test]
+fn forward_only_and_init_less_loaders() {
+    let exports = vec![entry("Bar", 1)];
+    let mut options = with_plugins("plugins", Some("PluginInit"));
+    options.default_strategy = ProxyOptions::forward_only().default_strategy;
+    let c = render_c(&ctx(&exports, options));
+    assert!(c.contains("context.origin_module = AheadLibEx_OriginModule();"));
+    assert!(!c.contains("g_origin_module_handle"));
+    // No origin loader here, so the path helpers come in for the plugin loader alone.
+    assert!(c.contains("static BOOL WINAPI origin_module_dir"));
+
+    let c = render_c(&ctx(&exports, with_plugins("plugins", None)));
+    assert!(c.contains("load_plugins();"));
+    assert!(!c.contains("AHEADLIBEX_PLUGIN_CONTEXT"));
+    assert!(c.contains(r#"plugin_report(L"AheadLibEx: plugin loaded: ", path, ERROR_SUCCESS);"#));
+
+    let c = render_c(&ctx(&exports, ProxyOptions::default()));
+    assert!(!c.contains("load_plugins"));
+}
+
+#[test]
+fn plugins_need_a_payload_outside_loader_lock() {
+    let exports = vec![entry("Bar", 1)];
+    let mut options = with_plugins("plugins", None);
+    assert!(options.check_exports(&exports).is_ok());
+
+    options.payload = PayloadEntry::None;
+    assert!(options.check_exports(&exports).is_err());
+
+    options.payload = PayloadEntry::Sync;
+    assert!(options.check_exports(&exports).is_err());
+    options.deferred_init = true;
+    assert!(options.check_exports(&exports).is_ok());
+}
@@ -0,0 +1,96 @@
+use aheadlibex_rs::dll::ExportEntry;
+use aheadlibex_rs::export_policy::{parse_rules, PayloadEntry};
+use aheadlibex_rs::prototype::prototypes_from_header;
+use aheadlibex_rs::templates::{
+    proxy_header_name, render_c, render_c_x64, render_filters_2026, render_proxy_h, render_vcxproj,
+    OriginLoadMode, ProxyOptions, VsGuids, VsTemplateContext,
+};
+
+fn entry(name: &str, ordinal: u16) -> ExportEntry {
+    ExportEntry {
+        name: name.to_string(),
+        ordinal,
+        forwarder: None,
+        is_data: false,
+    }
+}
+
+fn ctx<'a>(exports: &'a [ExportEntry], options: ProxyOptions) -> VsTemplateContext<'a> {
+    VsTemplateContext {
+        project_name: "Foo",
+        dll_name: "Foo.dll",
+        base_name: "Foo",
+        origin_load_mode: OriginLoadMode::SameDir {
+            original_name: "Foo_orig.dll",
+        },
+        exports,
+        guids: VsGuids {
+            solution: "{S}",
+            project: "{P}",
+            filter_source: "{FS}",
+            filter_header: "{FH}",
+            filter_resource: "{FR}",
+        },
+        options,
+    }
+}
+
+#[test]
+fn header_exposes_pointers_origin_and_signatures() {
+    let exports = vec![entry("Add", 1), entry("Get-Value", 2), entry("Baz", 3), entry("#4", 4)];
+    let options = ProxyOptions {
+        export_rules: parse_rules("Add=hook\nBaz=hook").unwrap(),
+        hook_prototypes: prototypes_from_header("int WINAPI Add(int a, int b); DWORD WINAPI Bar(LPCWSTR p);"),
+        ..Default::default()
+    };
+    let ctx = ctx(&exports, options);
+    assert_eq!(proxy_header_name(&ctx), "Foo_proxy.h");
+
+    let header = render_proxy_h(&ctx);
+    assert!(header.contains("#ifndef AHEADLIBEX_FOO_PROXY_H\n"));
+    assert!(header.contains("#ifdef __cplusplus\nextern \"C\" {\n#endif\n"));
+    assert!(header.contains("HMODULE WINAPI AheadLibEx_OriginModule(void);"));
+    assert!(header.contains("extern PVOID pfnAheadLibEx_Add; // Add @1\n"));
+    assert!(header.contains("extern PVOID pfnAheadLibEx_Get_Value; // Get-Value @2\n"));
+    assert!(header.contains("extern PVOID pfnAheadLibEx_Unnamed4; // Noname4 @4\n"));
+    assert!(header.contains("extern PVOID pfnAheadLibEx_Orig_Baz;"));
+    assert!(!header.contains("pfnAheadLibEx_Orig_Add"));
+    assert!(header.contains("typedef int (WINAPI *AheadLibEx_Add_t)(int a, int b);"));
+    assert!(!header.contains("AheadLibEx_Baz_t"));
+    assert!(header.contains("VOID WINAPI payload_main(void);\nVOID WINAPI install_hooks(void);\nvoid before_Add(int *a, int *b);\n"));
+    assert!(!header.contains("{{"));
+
+    let c = render_c_x64(&ctx);
+    assert!(c.contains("#include <windows.h>\n#include \"Foo_proxy.h\"\n"));
+    assert!(c.contains("PVOID pfnAheadLibEx_Get_Value = NULL;"));
+    assert!(c.contains("HMODULE WINAPI AheadLibEx_OriginModule(void)\n{\n    return g_origin_module_handle;\n}"));
+    assert!(!c.contains("typedef int (WINAPI *AheadLibEx_Add_t)"));
+}
+
+#[test]
+fn forward_only_header_has_just_the_origin_accessor() {
+    let exports = vec![entry("Bar", 1)];
+    let mut options = ProxyOptions::forward_only();
+    options.payload = PayloadEntry::None;
+    let ctx = ctx(&exports, options);
+
+    let header = render_proxy_h(&ctx);
+    assert!(header.contains("HMODULE WINAPI AheadLibEx_OriginModule(void);"));
+    assert!(!header.contains("pfnAheadLibEx_"));
+    assert!(!header.contains("payload_main"));
+
+    let c = render_c(&ctx);
+    assert!(c.contains("(LPCWSTR)(ULONG_PTR)AheadLibEx_OriginModule, &self))"));
+    assert!(c.contains("        address = GetProcAddress(self, MAKEINTRESOURCEA(1));\n"));
+    assert!(c.contains("    return origin;\n}"));
+}
+
+#[test]
+fn projects_list_the_header() {
+    let exports = vec![entry("Bar", 1)];
+    let ctx = ctx(&exports, ProxyOptions::default());
+
+    assert!(render_vcxproj(&ctx, true).contains("    <ClInclude Include=\"Foo_proxy.h\" />\n"));
+    assert!(render_filters_2026(&ctx, false)
+        .contains("    <ClInclude Include=\"Foo_proxy.h\">\n      <Filter>Header Files</Filter>\n    </ClInclude>\n"));
+}
@@ -0,0 +1,89 @@
+use aheadlibex_rs::dll::ExportEntry;
+use aheadlibex_rs::export_policy::parse_rules;
+use aheadlibex_rs::templates::{
+    needs_jump_table, render_c, render_c_x64, render_def, OriginLoadMode, ProxyOptions, VsGuids,
+    VsTemplateContext,
+};
+
+fn export(name: &str, ordinal: u16, forwarder: Option<&str>) -> ExportEntry {
+    ExportEntry {
+        name: name.to_string(),
+        ordinal,
+        forwarder: forwarder.map(str::to_string),
+        is_data: false,
+    }
+}
+
+fn ctx<'a>(exports: &'a [ExportEntry], options: ProxyOptions) -> VsTemplateContext<'a> {
+    VsTemplateContext {
+        project_name: "Foo",
+        dll_name: "Foo.dll",
+        base_name: "Foo",
+        origin_load_mode: OriginLoadMode::SameDir {
+            original_name: "Foo_orig.dll",
+        },
+        exports,
+        guids: VsGuids {
+            solution: "{S}",
+            project: "{P}",
+            filter_source: "{FS}",
+            filter_header: "{FH}",
+            filter_resource: "{FR}",
+        },
+        options,
+    }
+}
+
+#[test]
+fn forwarded_exports_point_at_their_final_target() {
+    let exports = vec![
+        export("Bar", 1, None),
+        export("HeapAlloc", 2, Some("NTDLL!RtlAllocateHeap")),
+        export("#7", 7, Some("api-ms-win-core-misc-l1-1-0!#123")),
+    ];
+    let ctx = ctx(&exports, ProxyOptions::default());
+
+    assert!(needs_jump_table(&ctx));
+    let c = render_c_x64(&ctx);
+    assert!(c.contains(r#"/EXPORT:\"HeapAlloc=NTDLL.RtlAllocateHeap,@2\""#));
+    assert!(c.contains(r#"/EXPORT:\"Noname7=api-ms-win-core-misc-l1-1-0.#123,@7,NONAME\""#));
+    assert!(c.contains("pfnAheadLibEx_Bar"));
+    assert!(!c.contains("pfnAheadLibEx_HeapAlloc"));
+    assert!(!c.contains("pfnAheadLibEx_Unnamed7"));
+
+    let def = render_def(&ctx, true);
+    assert!(def.contains("    HeapAlloc=NTDLL.RtlAllocateHeap @2\n"));
+    assert!(def.contains("    Noname7=\"api-ms-win-core-misc-l1-1-0.#123\" @7 NONAME\n"));
+}
+
+#[test]
+fn forward_rules_skip_the_original_and_hooks_keep_their_stub() {
+    let exports = vec![
+        export("Bar", 1, Some("NTDLL!RtlBar")),
+        export("Baz", 2, Some("NTDLL!RtlBaz")),
+    ];
+    let mut options = ProxyOptions::forward_only();
+    options.export_rules = parse_rules("Baz=hook").unwrap();
+    let ctx = ctx(&exports, options);
+
+    let c = render_c(&ctx);
+    assert!(c.contains(r#"/EXPORT:\"Bar=NTDLL.RtlBar,@1\""#));
+    assert!(!c.contains("Foo_orig.Bar"));
+    // The hook resolves through the original, whose own forward then applies.
+    assert!(c.contains("pfnAheadLibEx_Baz"));
+}
+
+#[test]
+fn forward_into_the_original_itself_targets_its_location() {
+    let exports = vec![
+        export("Bar", 1, Some("foo!Baz")),
+        export("Baz", 2, None),
+    ];
+    let ctx = ctx(&exports, ProxyOptions::forward_only());
+
+    assert!(!needs_jump_table(&ctx));
+    let def = render_def(&ctx, false);
+    assert!(def.contains("    Bar=Foo_orig.Baz @1\n"));
+    assert!(def.contains("    Baz=Foo_orig.Baz @2\n"));
+    assert!(!def.contains("foo.Baz"));
+}
@@ -0,0 +1,265 @@
+use std::fs;
+use std::process::Command;
+
+use aheadlibex_rs::dll::ExportEntry;
+use aheadlibex_rs::export_policy::{parse_rules, FailurePolicy, PayloadEntry};
+use aheadlibex_rs::prototype::prototypes_from_header;
+use aheadlibex_rs::templates::{
+    check_rust_crate, needs_rust_payload_file, render_rust_build_rs, render_rust_cargo_toml, render_rust_jump,
+    render_rust_lib, render_rust_origin, render_rust_payload, render_rust_sys, OriginLoadMode, OriginLoadModeOwned, ProxyOptions, VsGuids, VsTemplateContext,
+};
+
+fn entry(name: &str, ordinal: u16) -> ExportEntry {
+    ExportEntry {
+        name: name.to_string(),
+        ordinal,
+        forwarder: None,
+        is_data: false,
+    }
+}
+
+fn ctx<'a>(exports: &'a [ExportEntry], mode: OriginLoadMode<'a>, options: ProxyOptions) -> VsTemplateContext<'a> {
+    VsTemplateContext {
+        project_name: "Foo",
+        dll_name: "Foo.dll",
+        base_name: "Foo",
+        origin_load_mode: mode,
+        exports,
+        guids: VsGuids {
+            solution: "",
+            project: "",
+            filter_source: "",
+            filter_header: "",
+            filter_resource: "",
+        },
+        options,
+    }
+}
+
+#[test]
+fn stubs_jump_through_atomic_pointers() {
+    let mut exports = vec![entry("Add", 1), entry("Baz", 2), entry("#3", 3), entry("Fwd", 4)];
+    exports[3].forwarder = Some("kernel32.Sleep".to_string());
+    let options = ProxyOptions {
+        export_rules: parse_rules("Baz=hook").unwrap(),
+        failure_policy: FailurePolicy::FailLoad,
+        payload: PayloadEntry::HostReady,
+        ..Default::default()
+    };
+    let ctx = ctx(&exports, OriginLoadMode::SystemDir, options);
+    check_rust_crate(&ctx).unwrap();
+
+    let lib = render_rust_lib(&ctx, true);
+    assert!(lib.contains("mod jump;\nmod origin;\nmod payload;\nmod sys;\n"));
+    assert!(lib.contains("pub static pfnAheadLibEx_Add: AtomicPtr<c_void> = AtomicPtr::new(null_mut()); // Add @1\n"));
+    assert!(lib.contains("pub static pfnAheadLibEx_Orig_Baz: AtomicPtr<c_void>"));
+    assert!(!lib.contains("pfnAheadLibEx_Fwd"));
+    assert!(lib.contains("    pfnAheadLibEx_Add.store(get_address(b\"Add\\0\", 1, missing_export as *mut c_void), Ordering::Release);"));
+    assert!(lib.contains("    pfnAheadLibEx_Unnamed3.store(get_address(b\"\", 3, missing_export as *mut c_void), Ordering::Release);"));
+    assert!(lib.contains("    MISSING_EXPORTS.fetch_add(1, Ordering::Relaxed);\n    fallback\n"));
+    assert!(lib.contains("        if !init_forwarders() {\n            free_origin_module();\n            return 0;\n        }\n        install_hooks();\n        schedule_payload(reserved);\n"));
+    assert!(!lib.contains("{{"));
+    // lib.rs is rewritten on every generation, so the detours are set in payload.rs.
+    assert!(lib.contains("    pfnAheadLibEx_Orig_Baz.store(pfnAheadLibEx_Baz.load(Ordering::Acquire), Ordering::Release);\n    payload::install_hooks();\n}\n"));
+    assert!(!lib.contains("TODO"));
+    let payload = render_rust_payload(&ctx);
+    assert!(payload.contains("pub fn payload_main() {"));
+    assert!(payload.contains("pub fn install_hooks() {\n    // TODO: crate::pfnAheadLibEx_Baz.store(Detour_Baz as *mut core::ffi::c_void,"));
+
+    let jump = render_rust_jump(&ctx, true);
+    assert!(jump.contains(
+        "global_asm!(\n    \".globl AheadLibEx_Add\",\n    \"AheadLibEx_Add:\",\n    \"    jmp qword ptr [rip + {ptr}]\",\n    ptr = sym crate::pfnAheadLibEx_Add,\n);\n"
+    ));
+    assert!(jump.contains("    \"DllMain:\",\n    \"    jmp {entry}\",\n    entry = sym crate::dll_main,\n"));
+    assert!(!jump.contains("AheadLibEx_Fwd"));
+
+    let jump = render_rust_jump(&ctx, false);
+    assert!(jump.contains("    \"_AheadLibEx_Baz:\",\n    \"    jmp dword ptr [{ptr}]\",\n"));
+    assert!(jump.contains("    \"\\\"_DllMain@12\\\":\",\n"));
+    assert!(render_rust_build_rs(&ctx, false).contains("arch != \"x86\""));
+
+    // Braces in the name stay literal: the .def name is never part of a format string.
+    let ctx = VsTemplateContext {
+        dll_name: "foo{1}.dll",
+        base_name: "foo{1}",
+        ..ctx
+    };
+    let build = render_rust_build_rs(&ctx, true);
+    assert!(build.contains("const DEF_FILE: &str = \"foo{1}.def\";\n"));
+    assert!(build.contains(".join(DEF_FILE);\n    println!(\"cargo:rerun-if-changed={DEF_FILE}\");\n"));
+    assert!(build.contains("panic!(\"foo{{1}}.dll is built for Windows"));
+}
+
+#[test]
+fn origin_module_tries_each_candidate() {
+    let exports = vec![entry("Add", 1)];
+    let chain = OriginLoadModeOwned::chain(vec![
+        OriginLoadModeOwned::env(None),
+        OriginLoadModeOwned::load_ex(0x1000, vec!["libs".to_string()]),
+        OriginLoadModeOwned::same_dir("Foo_orig.dll".to_string()),
+    ]);
+    let ctx = ctx(&exports, chain.as_borrowed(), ProxyOptions::default());
+
+    let origin = render_rust_origin(&ctx);
+    assert!(origin.contains("fn candidate_1(module: HMODULE) -> Option<WidePath> {\n    resolve(module, &env(\"FOO_ORIGIN\")?)\n}"));
+    assert!(origin.contains("    add_dll_directory(module, \"libs\");\n"));
+    assert!(origin.contains("        .or_else(|| probe(resolve(module, &units(\"libs\")), \"Foo.dll\", module))\n"));
+    assert!(origin.contains("    Some(join(module_dir(module)?, \"Foo_orig.dll\"))\n"));
+    assert!(origin.contains("        (candidate_2, 0x1400),\n"));
+    // The registered directories only last while a candidate loads.
+    assert!(origin.contains("        let loaded = load(&found, flags, module);\n        remove_dll_directories();\n"));
+    assert!(origin.contains("Cannot locate Foo.dll in any of 3 locations"));
+
+    let ctx = crate::ctx(&exports, OriginLoadMode::SystemDir, ProxyOptions::default());
+    let origin = render_rust_origin(&ctx);
+    assert!(origin.contains("fn candidate_1(_module: HMODULE)"));
+    assert!(origin.contains("        match load(&found, flags, module) {\n"));
+    assert!(!origin.contains("        remove_dll_directories();\n"));
+    assert!(origin.contains("Err(format!(\"Cannot locate {path}, AheadLibEx cannot continue."));
+
+    // Forward-only proxies leave loading to the loader.
+    let mut options = ProxyOptions::forward_only();
+    options.payload = PayloadEntry::None;
+    let lib = render_rust_lib(&crate::ctx(&exports, OriginLoadMode::SystemDir, options), true);
+    assert!(lib.contains("        let address = sys::GetProcAddress(own, usize::from(1u16) as *const u8);\n"));
+    assert!(!lib.contains("mod origin;"));
+    assert!(!lib.contains("use core::ptr::null_mut;"));
+}
+
+#[test]
+fn hooks_get_a_payload_file_without_a_payload() {
+    let exports = vec![entry("Add", 1), entry("Baz", 2)];
+    let options = ProxyOptions {
+        export_rules: parse_rules("Baz=hook").unwrap(),
+        payload: PayloadEntry::None,
+        ..Default::default()
+    };
+    let ctx = ctx(&exports, OriginLoadMode::SystemDir, options);
+    assert!(needs_rust_payload_file(&ctx));
+    assert!(render_rust_lib(&ctx, true).contains("mod payload;\n"));
+    let payload = render_rust_payload(&ctx);
+    assert!(payload.contains("pub fn install_hooks()"));
+    assert!(!payload.contains("payload_main"));
+
+    let options = ProxyOptions {
+        payload: PayloadEntry::None,
+        ..Default::default()
+    };
+    let ctx = crate::ctx(&exports, OriginLoadMode::SystemDir, options);
+    assert!(!needs_rust_payload_file(&ctx));
+    assert!(!render_rust_lib(&ctx, true).contains("mod payload;"));
+}
+
+#[test]
+fn x86_missing_exports_pop_what_the_caller_pushed() {
+    let exports = vec![entry("_Sum@12", 1), entry("@Fast@12", 2), entry("Add", 3)];
+    let options = ProxyOptions {
+        failure_policy: FailurePolicy::Log,
+        ..Default::default()
+    };
+    let ctx = ctx(&exports, OriginLoadMode::SystemDir, options);
+
+    let lib = render_rust_lib(&ctx, false);
+    assert!(lib.contains("// Pops 12 bytes.\nextern \"stdcall\" fn missing_export_stdcall_12(_: usize, _: usize, _: usize) -> usize {"));
+    assert!(lib.contains("// Pops 4 bytes.\nextern \"fastcall\" fn missing_export_fastcall_4(_: usize, _: usize, _: usize) -> usize {"));
+    assert!(lib.contains("    unsafe { sys::ExitProcess(sys::ERROR_PROC_NOT_FOUND) }\n}\n"));
+    assert!(lib.contains("get_address(b\"_Sum@12\\0\", 1, missing_export_stdcall_12 as *mut c_void)"));
+    assert!(lib.contains("get_address(b\"Add\\0\", 3, missing_export_unknown as *mut c_void)"));
+    assert!(!lib.contains("fn missing_export()"));
+
+    let lib = render_rust_lib(&ctx, true);
+    assert!(lib.contains("extern \"system\" fn missing_export() -> usize {"));
+    assert!(!lib.contains("missing_export_stdcall_12"));
+
+    let ctx = VsTemplateContext {
+        options: ProxyOptions {
+            failure_policy: FailurePolicy::Abort,
+            ..Default::default()
+        },
+        ..ctx
+    };
+    let lib = render_rust_lib(&ctx, false);
+    assert!(lib.contains("fn get_address(name: &[u8], ordinal: u16, _fallback: *mut c_void) -> *mut c_void {"));
+    assert!(lib.contains("get_address(b\"Add\\0\", 3, null_mut())"));
+    assert!(!lib.contains("fn missing_export"));
+}
+
+#[test]
+fn unsupported_options_are_rejected() {
+    let exports = vec![entry("Add", 1)];
+    for options in [
+        ProxyOptions {
+            lazy: true,
+            ..Default::default()
+        },
+        ProxyOptions {
+            cpp: true,
+            ..Default::default()
+        },
+        ProxyOptions {
+            payload: PayloadEntry::parse("first-call:Add").unwrap(),
+            ..Default::default()
+        },
+        ProxyOptions {
+            export_rules: parse_rules("Add=hook").unwrap(),
+            hook_prototypes: prototypes_from_header("int WINAPI Add(int a, int b);"),
+            ..Default::default()
+        },
+    ] {
+        assert!(check_rust_crate(&ctx(&exports, OriginLoadMode::SystemDir, options)).is_err());
+    }
+
+    let exports = vec![entry("Add", 1)];
+    let ctx = VsTemplateContext {
+        dll_name: "foo-bar.dll",
+        base_name: "foo-bar",
+        ..ctx(&exports, OriginLoadMode::SystemDir, ProxyOptions::default())
+    };
+    let cargo = render_rust_cargo_toml(&ctx);
+    assert!(cargo.contains("name = \"aheadlibex_foo_bar\"\n"));
+    assert!(cargo.contains("# Cargo only allows identifier characters here: rename the built foo_bar.dll to foo-bar.dll.\nname = \"foo_bar\"\ncrate-type = [\"cdylib\"]\n"));
+}
+
+// Only the x64 crate: rustc rejects the x86 fallbacks' stdcall/fastcall ABIs on an x86_64 host, so
+// the x86 output is checked by the asserts above alone. Nothing here links against Windows.
+#[test]
+fn generated_x64_crate_type_checks() {
+    let root = std::env::temp_dir().join(format!("aheadlibex_rust_crate_{}", std::process::id()));
+    let exports = vec![entry("Add", 1), entry("_Sum@12", 2), entry("Baz", 3), entry("#4", 4)];
+    let chain = OriginLoadModeOwned::chain(vec![
+        OriginLoadModeOwned::search(None),
+        OriginLoadModeOwned::load_ex(0x1000, vec!["libs".to_string()]),
+        OriginLoadModeOwned::config(Some("Foo.json".to_string())),
+    ]);
+    let policies = [FailurePolicy::Abort, FailurePolicy::Log, FailurePolicy::FailLoad, FailurePolicy::Exit];
+    for (n, policy) in policies.into_iter().enumerate() {
+        for (name, payload) in [("thread", PayloadEntry::Thread), ("none", PayloadEntry::None)] {
+            let options = ProxyOptions {
+                export_rules: parse_rules("Baz=hook").unwrap(),
+                failure_policy: policy,
+                payload,
+                ..Default::default()
+            };
+            let ctx = ctx(&exports, chain.as_borrowed(), options);
+            let dir = root.join(format!("{n}_{name}"));
+            let src = dir.join("src");
+            fs::create_dir_all(&src).unwrap();
+            fs::write(src.join("lib.rs"), render_rust_lib(&ctx, true)).unwrap();
+            fs::write(src.join("jump.rs"), render_rust_jump(&ctx, true)).unwrap();
+            fs::write(src.join("origin.rs"), render_rust_origin(&ctx)).unwrap();
+            fs::write(src.join("sys.rs"), render_rust_sys()).unwrap();
+            if needs_rust_payload_file(&ctx) {
+                fs::write(src.join("payload.rs"), render_rust_payload(&ctx)).unwrap();
+            }
+
+            let output = Command::new("rustc")
+                .args(["--edition", "2021", "--crate-type", "cdylib", "--emit=metadata", "-D", "warnings", "-o"])
+                .arg(dir.join("out.rmeta"))
+                .arg(src.join("lib.rs"))
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "{policy:?}/{name}: {}", String::from_utf8_lossy(&output.stderr));
+        }
+    }
+    let _ = fs::remove_dir_all(&root);
+}
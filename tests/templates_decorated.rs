@@ -1,7 +1,7 @@
 use aheadlibex_rs::dll::ExportEntry;
 use aheadlibex_rs::templates::{
     render_asm_x64, render_asm_x64_gas, render_asm_x86, render_c, render_c_x64, render_cmake_lists,
-    render_def, render_vcxproj, OriginLoadMode, VsGuids, VsTemplateContext,
+    render_def, render_vcxproj, OriginLoadMode, ProxyOptions, VsGuids, VsTemplateContext,
 };
 
 fn dummy_ctx<'a>(exports: &'a [ExportEntry]) -> VsTemplateContext<'a> {
@@ -19,6 +19,7 @@ fn dummy_ctx<'a>(exports: &'a [ExportEntry]) -> VsTemplateContext<'a> {
         origin_load_mode: OriginLoadMode::SystemDir,
         exports,
         guids,
+        options: ProxyOptions::default(),
     }
 }
 
@@ -29,21 +30,25 @@ fn decorated_names_are_preserved_in_exports() {
             name: "?Func@@YAXH@Z".to_string(),
             ordinal: 1,
             forwarder: None,
+            is_data: false,
         },
         ExportEntry {
             name: "@Func@8".to_string(),
             ordinal: 2,
             forwarder: None,
+            is_data: false,
         },
         ExportEntry {
             name: "??0Class@@QAE@XZ".to_string(),
             ordinal: 3,
             forwarder: None,
+            is_data: false,
         },
         ExportEntry {
             name: "#345".to_string(),
             ordinal: 345,
             forwarder: None,
+            is_data: false,
         },
     ];
 
@@ -85,6 +90,7 @@ fn asm_uses_sanitized_stub_names() {
         name: "?Decorated@Name@@@".to_string(),
         ordinal: 7,
         forwarder: None,
+        is_data: false,
     }];
     let ctx = dummy_ctx(&exports);
 
@@ -103,6 +109,7 @@ fn cmake_template_references_expected_files() {
         name: "Foo".to_string(),
         ordinal: 1,
         forwarder: None,
+        is_data: false,
     }];
     let ctx = dummy_ctx(&exports);
 
@@ -121,6 +128,7 @@ fn win32_vcxproj_enables_masm_safeseh() {
         name: "Foo".to_string(),
         ordinal: 1,
         forwarder: None,
+        is_data: false,
     }];
     let ctx = dummy_ctx(&exports);
 
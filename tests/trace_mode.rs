@@ -0,0 +1,77 @@
+use aheadlibex_rs::dll::ExportEntry;
+use aheadlibex_rs::export_policy::parse_rules;
+use aheadlibex_rs::templates::{
+    render_asm_x64, render_asm_x64_gas, render_asm_x86, render_asm_x86_gas, render_c,
+    render_c_x64, OriginLoadMode, ProxyOptions, VsGuids, VsTemplateContext,
+};
+
+fn entry(name: &str, ordinal: u16) -> ExportEntry {
+    ExportEntry {
+        name: name.to_string(),
+        ordinal,
+        forwarder: None,
+        is_data: false,
+    }
+}
+
+#[test]
+fn trace_mode_routes_stubs_through_the_thunk() {
+    let exports = vec![entry("Open", 1), entry("Skip", 2), entry("#3", 3), entry("Close", 4)];
+    let ctx = VsTemplateContext {
+        project_name: "Foo",
+        dll_name: "Foo.dll",
+        base_name: "Foo",
+        origin_load_mode: OriginLoadMode::SameDir {
+            original_name: "Foo_orig.dll",
+        },
+        exports: &exports,
+        guids: VsGuids {
+            solution: "{S}",
+            project: "{P}",
+            filter_source: "{FS}",
+            filter_header: "{FH}",
+            filter_resource: "{FR}",
+        },
+        options: ProxyOptions {
+            trace: true,
+            export_rules: parse_rules("Skip=forward").unwrap(),
+            ..Default::default()
+        },
+    };
+
+    let c = render_c_x64(&ctx);
+    assert!(c.contains("static const AHEADLIBEX_EXPORT g_aheadlibex_exports[] ="));
+    assert!(c.contains("    { \"Open\", 1, &pfnAheadLibEx_Open, NULL },"));
+    assert!(c.contains("    { NULL, 3, &pfnAheadLibEx_Unnamed3, NULL },"));
+    assert!(c.contains("    { \"Close\", 4, &pfnAheadLibEx_Close, NULL },"));
+    assert!(!c.contains("&pfnAheadLibEx_Skip"));
+    assert!(c.contains("PVOID __cdecl AheadLibEx_TraceEnter(ULONG_PTR index, PVOID return_address)"));
+    assert!(c.contains(".trace.log"));
+    assert!(c.contains("AheadLibEx_TraceFlush();"));
+    assert!(render_c(&ctx).contains("g_aheadlibex_exports"));
+
+    let asm = render_asm_x64(&ctx);
+    assert!(asm.contains("EXTERN AheadLibEx_TraceEnter:PROC"));
+    assert!(asm.contains("AheadLibEx_Close PROC\n    push 2\n    jmp AheadLibEx_TraceThunk"));
+    assert!(asm.contains("AheadLibEx_TraceThunk PROC FRAME\n    .allocstack 8\n    push rcx\n    .pushreg rcx\n"));
+    assert!(asm.contains("    .allocstack 128\n    .endprolog\n"));
+    assert!(asm.contains("    movdqa XMMWORD PTR [rsp+112], xmm5\n    mov rcx, QWORD PTR [rsp+176]\n"));
+    assert!(!asm.contains("pfnAheadLibEx_"));
+
+    let asm_x86 = render_asm_x86(&ctx);
+    assert!(asm_x86.contains("EXTERN _AheadLibEx_TraceEnter:PROC"));
+    assert!(asm_x86.contains("_AheadLibEx_Open:\n    push 0\n    jmp AheadLibEx_TraceThunk"));
+
+    let gas = render_asm_x64_gas(&ctx);
+    assert!(gas.contains("    .extern AheadLibEx_TraceEnter"));
+    assert!(gas.contains("AheadLibEx_TraceThunk:\n    .seh_proc AheadLibEx_TraceThunk\n    .seh_stackalloc 8\n"));
+    assert!(gas.contains("    movdqa xmm5, XMMWORD PTR [rsp+112]\n    add rsp, 128\n"));
+    assert!(render_asm_x86_gas(&ctx).contains("call _AheadLibEx_TraceEnter"));
+
+    let plain = VsTemplateContext {
+        options: ProxyOptions::default(),
+        ..ctx
+    };
+    assert!(!render_c_x64(&plain).contains("AheadLibEx_TraceEnter"));
+    assert!(!render_asm_x64(&plain).contains("AheadLibEx_TraceThunk"));
+}
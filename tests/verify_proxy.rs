@@ -0,0 +1,112 @@
+use aheadlibex_rs::dll::{DllExports, ExportEntry};
+use aheadlibex_rs::verify::{verify_proxy, VerifyOptions};
+
+fn entry(name: &str, ordinal: u16, is_data: bool) -> ExportEntry {
+    ExportEntry {
+        name: name.to_string(),
+        ordinal,
+        forwarder: None,
+        is_data,
+    }
+}
+
+fn dll(exports: Vec<ExportEntry>, imports: &[&str]) -> DllExports {
+    DllExports {
+        arch: "x64".to_string(),
+        exports,
+        imports: imports.iter().map(|s| s.to_string()).collect(),
+    }
+}
+
+#[test]
+fn matching_proxy_passes() {
+    let original = dll(
+        vec![entry("Foo", 1, false), entry("#2", 2, false), entry("gData", 3, true)],
+        &["KERNEL32.dll"],
+    );
+    let mut forwarded = entry("gData", 3, false);
+    forwarded.forwarder = Some("foo_orig!gData".to_string());
+    let proxy = dll(
+        vec![entry("Foo", 1, false), entry("#2", 2, false), forwarded],
+        &["KERNEL32.dll"],
+    );
+
+    let report = verify_proxy(&proxy, &original, &VerifyOptions::default());
+    assert!(report.is_ok(), "{}", report.to_text());
+    assert_eq!(report.checked, 3);
+}
+
+#[test]
+fn mismatches_and_bad_imports_are_reported() {
+    let original = dll(
+        vec![
+            entry("Foo", 1, false),
+            entry("#2", 2, false),
+            entry("gData", 3, true),
+            entry("Missing", 4, false),
+        ],
+        &[],
+    );
+    let proxy = dll(
+        vec![
+            entry("Foo", 5, false),
+            entry("Named2", 2, false),
+            entry("gData", 3, false),
+            entry("Extra", 9, false),
+        ],
+        &["KERNEL32.dll", "VCRUNTIME140.dll", "foo_orig.dll"],
+    );
+    let options = VerifyOptions {
+        allow_crt: false,
+        origin_names: vec!["foo_orig.dll".to_string()],
+    };
+
+    let report = verify_proxy(&proxy, &original, &options);
+    let kinds: Vec<&str> = report.issues.iter().map(|i| i.kind).collect();
+    assert!(kinds.contains(&"ordinal"));
+    assert!(kinds.contains(&"noname"));
+    assert!(kinds.contains(&"data"));
+    assert!(kinds.contains(&"missing"));
+    assert!(kinds.contains(&"extra"));
+    assert_eq!(kinds.iter().filter(|k| **k == "import").count(), 2);
+    assert!(report.to_json().contains(r#""ok": false"#));
+
+    let relaxed = verify_proxy(
+        &proxy,
+        &original,
+        &VerifyOptions {
+            allow_crt: true,
+            origin_names: Vec::new(),
+        },
+    );
+    assert!(!relaxed.issues.iter().any(|i| i.kind == "import"));
+}
+
+#[test]
+fn noname_mismatches_are_reported_once() {
+    // Named in the original, NONAME in the proxy.
+    let original = dll(vec![entry("Foo", 1, false)], &[]);
+    let proxy = dll(vec![entry("#1", 1, false)], &[]);
+    let report = verify_proxy(&proxy, &original, &VerifyOptions::default());
+    assert_eq!(report.issues.len(), 1, "{}", report.to_text());
+    assert_eq!(report.issues[0].kind, "noname");
+    assert_eq!(report.issues[0].detail, "original is named but the proxy exports it NONAME");
+
+    // NONAME in the original, named in the proxy.
+    let original = dll(vec![entry("#2", 2, false)], &[]);
+    let proxy = dll(vec![entry("Named2", 2, false)], &[]);
+    let report = verify_proxy(&proxy, &original, &VerifyOptions::default());
+    assert_eq!(report.issues.len(), 1, "{}", report.to_text());
+    assert_eq!(report.issues[0].kind, "noname");
+    assert!(report.issues[0].detail.contains("'Named2'"));
+
+    // A named proxy export at a missing export's ordinal is a different export, not a stand-in.
+    let original = dll(vec![entry("Foo", 1, false)], &[]);
+    let proxy = dll(vec![entry("Bar", 1, false)], &[]);
+    let kinds: Vec<&str> = verify_proxy(&proxy, &original, &VerifyOptions::default())
+        .issues
+        .iter()
+        .map(|i| i.kind)
+        .collect();
+    assert_eq!(kinds, ["missing", "extra"]);
+}